@@ -1,15 +1,6 @@
 use std::{fs::File, io::Read};
 
-use livre::{
-    content::operators::{text::TextOperator, Operator},
-    extraction::Extract,
-    InMemoryDocument,
-};
-use winnow::{
-    ascii::multispace0,
-    combinator::{iterator, preceded},
-    BStr,
-};
+use livre::{extraction::Extract, InMemoryDocument};
 
 fn read_document(path: &str) -> InMemoryDocument {
     let mut file = File::open(path).unwrap();
@@ -20,26 +11,14 @@ fn read_document(path: &str) -> InMemoryDocument {
 }
 
 fn main() {
-    let paths = &["tests/resources/letter.pdf"];
+    let paths = &["tests/resources/letter.pdf", "tests/resources/text.pdf"];
 
     for path in paths {
         let doc = read_document(path);
 
-        for page in doc.pages().unwrap().iter() {
-            let content = page.build_content(&doc).unwrap();
-            let mut stream = BStr::new(&content);
-
-            let mut it = iterator(&mut stream, preceded(multispace0, Operator::extract));
-
-            for operator in &mut it {
-                match operator {
-                    Operator::BeginText(_) => println!(),
-                    Operator::Text(TextOperator::TextShowingOperator(v)) => print!("{v}"),
-                    _ => {}
-                }
-            }
-
-            it.finish().unwrap();
+        for page in doc.pages_iter() {
+            let page = page.unwrap();
+            println!("{}", page.extract_text(&doc).unwrap());
         }
     }
 }
@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use livre::{extraction::Extract, InMemoryDocument};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(doc) = InMemoryDocument::extract(&mut data.as_ref()) else {
+        return;
+    };
+
+    // Walk the pages as well, since their content is only parsed on demand.
+    for page in doc.pages_iter() {
+        let Ok(page) = page else {
+            return;
+        };
+        let _ = page.operators(&doc);
+    }
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use livre::extraction::{Extract, Object};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Object::extract(&mut data.as_ref());
+});
@@ -0,0 +1,108 @@
+use std::{fs, io::Write, path::Path};
+
+use livre::{
+    extraction::{Map, Object, Reference},
+    InMemoryDocument,
+};
+
+use super::{decode, get, resolve};
+
+/// List the embedded files of the document, and save them to `directory` if supplied.
+///
+/// Embedded files are listed in the `EmbeddedFiles` name tree of the catalog's `Names`
+/// dictionary.
+pub fn attachments(
+    document: &InMemoryDocument,
+    directory: Option<&Path>,
+    output: &mut impl Write,
+) -> crate::Result {
+    let root = document.history.latest().trailer.root.id;
+    let Object::Dictionary(catalog) = resolve(document, &Object::Indirect(Reference::from(root)))?
+    else {
+        return Err("the catalog is not a dictionary".into());
+    };
+
+    let mut files = Vec::new();
+    if let Object::Dictionary(names) = get(document, &catalog, "Names")? {
+        if let Object::Dictionary(tree) = get(document, &names, "EmbeddedFiles")? {
+            collect(document, &tree, &mut files, 0)?;
+        }
+    }
+
+    if files.is_empty() {
+        writeln!(output, "No embedded files")?;
+    }
+
+    for (key, spec) in files {
+        let name = match get(document, &spec, "UF")? {
+            Object::String(name) => decode(&name),
+            _ => match get(document, &spec, "F")? {
+                Object::String(name) => decode(&name),
+                _ => key,
+            },
+        };
+
+        let content = match get(document, &spec, "EF")? {
+            Object::Dictionary(ef) => match get(document, &ef, "F")? {
+                Object::Stream(stream) => Some(stream.content),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let Some(content) = content else {
+            writeln!(output, "{name}  (no content)")?;
+            continue;
+        };
+        writeln!(output, "{name}  {} bytes", content.len())?;
+
+        if let Some(directory) = directory {
+            // Only keep the file name, such that files cannot be written outside of the
+            // directory.
+            let file_name = Path::new(&name)
+                .file_name()
+                .ok_or_else(|| format!("invalid file name: {name}"))?;
+            fs::write(directory.join(file_name), content)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Maximum depth of the name tree, which guards against cycles.
+const MAX_DEPTH: usize = 32;
+
+/// Collect the file specifications of a name tree node and its descendants, along with their
+/// keys.
+fn collect(
+    document: &InMemoryDocument,
+    node: &Map<Object>,
+    files: &mut Vec<(String, Map<Object>)>,
+    depth: usize,
+) -> crate::Result {
+    if depth > MAX_DEPTH {
+        return Err("the name tree of embedded files is too deep".into());
+    }
+
+    if let Object::Array(names) = get(document, node, "Names")? {
+        for pair in names.chunks_exact(2) {
+            let key = match &pair[0] {
+                Object::String(key) => decode(key),
+                _ => String::new(),
+            };
+            if let Object::Dictionary(spec) = resolve(document, &pair[1])? {
+                files.push((key, spec));
+            }
+        }
+    }
+
+    if let Object::Array(kids) = get(document, node, "Kids")? {
+        for kid in &kids {
+            if let Object::Dictionary(kid) = resolve(document, kid)? {
+                collect(document, &kid, files, depth + 1)?;
+            }
+        }
+    }
+
+    Ok(())
+}
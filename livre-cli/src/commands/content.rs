@@ -0,0 +1,37 @@
+use std::io::Write;
+
+use livre::{
+    content::operators::{write_content, FormatOptions},
+    InMemoryDocument,
+};
+
+/// Write the operators of the page at `page` (starting from 1) as indented content-stream
+/// text, optionally along with their position in the decoded content.
+pub fn content(
+    document: &InMemoryDocument,
+    page: usize,
+    offsets: bool,
+    output: &mut impl Write,
+) -> crate::Result {
+    let page = page
+        .checked_sub(1)
+        .map(|index| document.page(index))
+        .transpose()
+        .map_err(livre::Error::from)?
+        .flatten()
+        .ok_or_else(|| format!("page {page} does not exist"))?;
+
+    let (content, operators) = page
+        .spanned_operators(document)
+        .map_err(livre::Error::from)?;
+
+    let options = if offsets {
+        FormatOptions::default().with_offsets()
+    } else {
+        FormatOptions::default()
+    };
+
+    write_content(&content, &operators, options, output)?;
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use std::io::Write;
+
+use livre::{
+    extraction::{Object, OptRef, Reference},
+    follow_refs::Builder,
+    InMemoryDocument,
+};
+
+use super::{get, resolve};
+
+/// List the fonts of each page's resources, with their type, base font and encoding.
+pub fn fonts(document: &InMemoryDocument, output: &mut impl Write) -> crate::Result {
+    for (i, page) in document.pages_iter().enumerate() {
+        let page = page.map_err(livre::Error::from)?;
+
+        let resources = match &page.inheritable_props.resources {
+            Some(OptRef::Direct(resources)) => Some(resources.clone()),
+            Some(OptRef::Ref(reference)) => document
+                .resolve_reference(*reference)
+                .map_err(livre::Error::from)?,
+            None => None,
+        };
+
+        let mut fonts: Vec<_> = resources
+            .map(|r| r.font)
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        fonts.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+
+        writeln!(output, "Page {}", i + 1)?;
+
+        for (name, reference) in fonts {
+            let Reference { id, .. } = reference;
+            write!(
+                output,
+                "  {:<8}{:>5} {} R  ",
+                String::from(name),
+                id.object,
+                id.generation
+            )?;
+
+            let font = resolve(document, &Object::Indirect(id.into()))?;
+            let Object::Dictionary(font) = font else {
+                writeln!(output, "unreadable")?;
+                continue;
+            };
+
+            let subtype = name_or(get(document, &font, "Subtype")?, "?");
+            let base_font = name_or(get(document, &font, "BaseFont")?, "?");
+            let encoding = match get(document, &font, "Encoding")? {
+                Object::Dictionary(encoding) => {
+                    name_or(get(document, &encoding, "BaseEncoding")?, "custom")
+                }
+                encoding => name_or(encoding, "builtin"),
+            };
+            let to_unicode = if font.contains_key(&"ToUnicode".into()) {
+                ", ToUnicode"
+            } else {
+                ""
+            };
+
+            writeln!(output, "{subtype} {base_font} ({encoding}{to_unicode})")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The name held by `object`, or `default` if it is not a name.
+fn name_or(object: Object, default: &str) -> String {
+    match object {
+        Object::Name(name) => String::from(name),
+        _ => default.to_string(),
+    }
+}
@@ -0,0 +1,60 @@
+use std::io::Write;
+
+use livre::{extraction::Object, follow_refs::Builder, serialization::Serialize, InMemoryDocument};
+
+use super::decode;
+
+/// Show the trailer of the latest revision, the catalog, the page count and the metadata of
+/// the document information dictionary.
+pub fn info(document: &InMemoryDocument, output: &mut impl Write) -> crate::Result {
+    let trailer = &document.history.latest().trailer;
+
+    writeln!(output, "Revisions:    {}", document.history.len())?;
+    writeln!(output, "Objects:      {}", trailer.size)?;
+    writeln!(output, "Root:         {}", text(&trailer.root))?;
+    if let Some(info) = trailer.info {
+        writeln!(output, "Info:         {}", text(&info))?;
+    }
+    if let Some(id) = &trailer.id {
+        writeln!(output, "ID:           {}", text(id))?;
+    }
+    if let Some(prev) = trailer.prev {
+        writeln!(output, "Prev:         {prev}")?;
+    }
+
+    writeln!(output, "Page layout:  {:?}", document.catalog.page_layout)?;
+    writeln!(output, "Page mode:    {:?}", document.catalog.page_mode)?;
+    writeln!(
+        output,
+        "Pages:        {}",
+        document.page_count().map_err(livre::Error::from)?
+    )?;
+
+    let metadata = match trailer.info {
+        Some(info) => document
+            .resolve_reference(info)
+            .map_err(livre::Error::from)?,
+        None => None,
+    };
+
+    if let Some(Object::Dictionary(metadata)) = metadata {
+        let mut entries: Vec<_> = metadata.into_iter().collect();
+        entries.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+
+        writeln!(output, "\nMetadata:")?;
+        for (key, value) in entries {
+            let value = match value {
+                Object::String(bytes) => decode(&bytes),
+                value => text(&value),
+            };
+            writeln!(output, "  {:<14}{value}", String::from(key))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The PDF syntax of a value.
+fn text(value: &impl Serialize) -> String {
+    String::from_utf8_lossy(&value.to_bytes()).into_owned()
+}
@@ -0,0 +1,68 @@
+//! The subcommands of the `livre` tool. Each one writes its report to the supplied writer.
+
+mod attachments;
+mod content;
+mod fonts;
+mod info;
+mod object;
+mod pages;
+mod render;
+mod svg;
+mod text;
+mod xref;
+
+use std::path::Path;
+
+use livre::{
+    extraction::{Map, Object, PDFString},
+    follow_refs::{Builder, Options},
+    InMemoryDocument,
+};
+
+pub use attachments::attachments;
+pub use content::content;
+pub use fonts::fonts;
+pub use info::info;
+pub use object::object;
+pub use pages::pages;
+pub use render::render;
+pub use svg::svg;
+pub use text::text;
+pub use xref::xref;
+
+/// Read and load the document at `path`.
+pub fn open(path: &Path, lenient: bool) -> crate::Result<InMemoryDocument> {
+    let input = std::fs::read(path).map_err(|err| format!("{}: {err}", path.display()))?;
+
+    let options = if lenient {
+        Options::lenient()
+    } else {
+        Options::default()
+    };
+
+    Ok(InMemoryDocument::from_bytes_with(&input, options)?)
+}
+
+/// Decode a text string, e.g. from the document information dictionary.
+fn decode(bytes: &[u8]) -> String {
+    PDFString(bytes.to_vec()).decode()
+}
+
+/// Follow `object` if it is a reference. Dangling references resolve to null.
+fn resolve(document: &InMemoryDocument, object: &Object) -> crate::Result<Object> {
+    match object {
+        Object::Indirect(reference) => Ok(document
+            .resolve_reference(*reference)
+            .map_err(livre::Error::from)?
+            .unwrap_or(Object::Null)),
+        object => Ok(object.clone()),
+    }
+}
+
+/// The entry `key` of a dictionary, following references.
+fn get(document: &InMemoryDocument, dict: &Map<Object>, key: &str) -> crate::Result<Object> {
+    match dict.get(&key.into()) {
+        Some(value) => resolve(document, value),
+        None => Ok(Object::Null),
+    }
+}
@@ -0,0 +1,30 @@
+use std::io::Write;
+
+use livre::{extraction::ReferenceId, InMemoryDocument};
+
+use crate::pretty::{write_object, Content};
+
+/// Pretty-print the indirect object `number generation R`.
+pub fn object(
+    document: &InMemoryDocument,
+    number: usize,
+    generation: u16,
+    decode: bool,
+    output: &mut impl Write,
+) -> crate::Result {
+    let object = document
+        .object(ReferenceId::new(number, generation))?
+        .ok_or_else(|| format!("object {number} {generation} R does not exist"))?;
+
+    let content = if decode {
+        Content::Decoded
+    } else {
+        Content::Summary
+    };
+
+    writeln!(output, "{number} {generation} obj")?;
+    write_object(&object, content, output)?;
+    writeln!(output, "endobj")?;
+
+    Ok(())
+}
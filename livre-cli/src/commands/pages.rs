@@ -0,0 +1,49 @@
+use std::io::Write;
+
+use livre::{extraction::Rectangle, InMemoryDocument};
+
+/// List the pages, with their boxes, rotation, content streams and number of operators.
+pub fn pages(document: &InMemoryDocument, output: &mut impl Write) -> crate::Result {
+    for (i, page) in document.pages_iter().enumerate() {
+        let page = page.map_err(livre::Error::from)?;
+        let props = &page.inheritable_props;
+
+        writeln!(output, "Page {}", i + 1)?;
+        if let Some(media_box) = props.media_box {
+            writeln!(output, "  MediaBox:  {}", rectangle(media_box))?;
+        }
+        if let Some(crop_box) = props.crop_box {
+            writeln!(output, "  CropBox:   {}", rectangle(crop_box))?;
+        }
+        writeln!(
+            output,
+            "  Rotate:    {}",
+            props.rotate.unwrap_or_default().degrees()
+        )?;
+
+        let contents: Vec<String> = page
+            .contents
+            .iter()
+            .map(|reference| format!("{} {} R", reference.id.object, reference.id.generation))
+            .collect();
+        writeln!(output, "  Contents:  [{}]", contents.join(", "))?;
+
+        match page.operators(document) {
+            Ok(operators) => writeln!(output, "  Operators: {}", operators.len())?,
+            Err(err) => writeln!(output, "  Operators: {}", livre::Error::from(err))?,
+        }
+    }
+
+    Ok(())
+}
+
+fn rectangle(
+    Rectangle {
+        xmin,
+        ymin,
+        xmax,
+        ymax,
+    }: Rectangle,
+) -> String {
+    format!("[{xmin} {ymin} {xmax} {ymax}]")
+}
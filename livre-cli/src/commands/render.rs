@@ -0,0 +1,29 @@
+use std::io::Write;
+
+use livre::InMemoryDocument;
+
+/// Write the page at `page` (starting from 1), rasterized at `dpi` dots per inch, as a PNG
+/// image.
+pub fn render(
+    document: &InMemoryDocument,
+    page: usize,
+    dpi: f32,
+    output: &mut impl Write,
+) -> crate::Result {
+    if !(dpi > 0.0 && dpi <= 2400.0) {
+        return Err(format!("invalid resolution {dpi}, expected up to 2400 dpi").into());
+    }
+
+    let page = page
+        .checked_sub(1)
+        .map(|index| document.page(index))
+        .transpose()
+        .map_err(livre::Error::from)?
+        .flatten()
+        .ok_or_else(|| format!("page {page} does not exist"))?;
+
+    let pixmap = page.render(document, dpi).map_err(livre::Error::from)?;
+    output.write_all(&pixmap.to_png())?;
+
+    Ok(())
+}
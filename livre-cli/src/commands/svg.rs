@@ -0,0 +1,19 @@
+use std::io::Write;
+
+use livre::InMemoryDocument;
+
+/// Write an SVG preview of the page at `page` (starting from 1).
+pub fn svg(document: &InMemoryDocument, page: usize, output: &mut impl Write) -> crate::Result {
+    let page = page
+        .checked_sub(1)
+        .map(|index| document.page(index))
+        .transpose()
+        .map_err(livre::Error::from)?
+        .flatten()
+        .ok_or_else(|| format!("page {page} does not exist"))?;
+
+    let svg = page.to_svg(document).map_err(livre::Error::from)?;
+    output.write_all(svg.as_bytes())?;
+
+    Ok(())
+}
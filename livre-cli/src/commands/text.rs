@@ -0,0 +1,43 @@
+use std::io::Write;
+
+use livre::{structure::Page, InMemoryDocument};
+
+use crate::layout;
+
+/// Write the text of the document, or of the page at `page` (starting from 1). Pages are
+/// separated by a form feed.
+pub fn text(
+    document: &InMemoryDocument,
+    page: Option<usize>,
+    layout: bool,
+    output: &mut impl Write,
+) -> crate::Result {
+    let pages: Vec<Page> = match page {
+        Some(number) => {
+            let page = number
+                .checked_sub(1)
+                .map(|index| document.page(index))
+                .transpose()
+                .map_err(livre::Error::from)?
+                .flatten()
+                .ok_or_else(|| format!("page {number} does not exist"))?;
+            vec![page]
+        }
+        None => document.pages().map_err(livre::Error::from)?,
+    };
+
+    for (i, page) in pages.iter().enumerate() {
+        if i > 0 {
+            write!(output, "\x0c")?;
+        }
+
+        let text_page = page.text_page(document).map_err(livre::Error::from)?;
+        if layout {
+            writeln!(output, "{}", layout::render(&text_page))?;
+        } else {
+            writeln!(output, "{text_page}")?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use std::io::Write;
+
+use livre::{structure::RefLocation, InMemoryDocument};
+
+/// Dump the merged cross-reference table, one entry per line, by object number.
+pub fn xref(document: &InMemoryDocument, output: &mut impl Write) -> crate::Result {
+    let mut entries: Vec<_> = document.builder.xrefs.iter().collect();
+    entries.sort_by_key(|(id, _)| id.object);
+
+    for (id, location) in entries {
+        write!(output, "{:>6} {:>5}  ", id.object, id.generation)?;
+        match location {
+            RefLocation::Plain(offset) => writeln!(output, "offset {offset}")?,
+            RefLocation::Compressed { stream_id, index } => {
+                writeln!(output, "in object stream {stream_id}, index {index}")?
+            }
+            RefLocation::Free => writeln!(output, "free")?,
+        }
+    }
+
+    Ok(())
+}
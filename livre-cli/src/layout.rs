@@ -0,0 +1,222 @@
+//! Plain text that keeps the physical layout of the page, such that columns and tables line
+//! up in a monospace font.
+//!
+//! Lines of text that share a baseline, across blocks, are written on the same row. Words are
+//! placed on a grid of characters, whose cell is as wide as the median character of the page.
+//! Lines that do not run from left to right are written at the end of the page.
+
+use livre::{
+    content::layout::{TextLine, TextPage},
+    extraction::Rectangle,
+    structure::RotationAngle,
+};
+
+/// Vertical gap, relative to the row height, above which an empty line is inserted.
+const PARAGRAPH_GAP: f32 = 1.5;
+
+/// Widest grid, in characters, whatever the size of the page.
+const MAX_COLUMNS: usize = 1000;
+
+/// Render the page as plain text, on a grid of characters.
+pub fn render(page: &TextPage) -> String {
+    let (upright, rotated): (Vec<&TextLine>, Vec<&TextLine>) = page
+        .blocks
+        .iter()
+        .flat_map(|block| &block.lines)
+        .partition(|line| line.rotation == RotationAngle::Zero);
+
+    let cell = cell_width(&upright);
+    let left = upright
+        .iter()
+        .map(|line| line.bbox.xmin)
+        .filter(|xmin| xmin.is_finite())
+        .fold(f32::INFINITY, f32::min);
+
+    // Words are kept within the page, such that a stray glyph cannot widen the grid at will.
+    let Rectangle { xmin, xmax, .. } = page.viewport.bbox;
+    let columns = ((xmax - xmin) / cell).ceil();
+    let columns = if columns.is_finite() {
+        (columns.max(0.0) as usize).min(MAX_COLUMNS)
+    } else {
+        MAX_COLUMNS
+    };
+
+    let mut output = Vec::new();
+    let mut previous: Option<(f32, f32)> = None;
+
+    for row in rows(upright) {
+        let top = row
+            .iter()
+            .map(|l| l.bbox.ymin)
+            .fold(f32::INFINITY, f32::min);
+        let bottom = row
+            .iter()
+            .map(|l| l.bbox.ymax)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        if let Some((previous_bottom, height)) = previous {
+            if top - previous_bottom > PARAGRAPH_GAP * height {
+                output.push(String::new());
+            }
+        }
+        previous = Some((bottom, bottom - top));
+
+        output.push(render_row(&row, left, cell, columns));
+    }
+
+    for line in rotated {
+        output.push(line.to_string());
+    }
+
+    output.join("\n")
+}
+
+/// Median width of a character, across the words of the page.
+fn cell_width(lines: &[&TextLine]) -> f32 {
+    let mut widths: Vec<f32> = lines
+        .iter()
+        .flat_map(|line| &line.words)
+        .filter(|word| !word.text.is_empty())
+        .map(|word| (word.bbox.xmax - word.bbox.xmin) / word.text.chars().count() as f32)
+        .filter(|width| *width > 0.0)
+        .collect();
+
+    if widths.is_empty() {
+        return 1.0;
+    }
+
+    widths.sort_by(f32::total_cmp);
+    widths[widths.len() / 2]
+}
+
+/// Group the lines whose vertical centres lie within one another, from top to bottom.
+fn rows(mut lines: Vec<&TextLine>) -> Vec<Vec<&TextLine>> {
+    let centre = |line: &TextLine| (line.bbox.ymin + line.bbox.ymax) / 2.0;
+    lines.sort_by(|a, b| centre(a).total_cmp(&centre(b)));
+
+    let mut rows: Vec<Vec<&TextLine>> = Vec::new();
+
+    for line in lines {
+        match rows.last_mut() {
+            Some(row)
+                if row
+                    .iter()
+                    .any(|other| (other.bbox.ymin..=other.bbox.ymax).contains(&centre(line))) =>
+            {
+                row.push(line)
+            }
+            _ => rows.push(vec![line]),
+        }
+    }
+
+    for row in &mut rows {
+        row.sort_by(|a, b| a.bbox.xmin.total_cmp(&b.bbox.xmin));
+    }
+
+    rows
+}
+
+/// Place the words of a row on the grid, keeping at least one space between them. Words are
+/// placed no further than `columns`, and words whose position is not finite follow the
+/// previous one.
+fn render_row(row: &[&TextLine], left: f32, cell: f32, columns: usize) -> String {
+    let mut text = String::new();
+    let mut column = 0;
+
+    for word in row.iter().flat_map(|line| &line.words) {
+        let target = ((word.bbox.xmin - left) / cell).round();
+        let target = if target.is_finite() {
+            (target.max(0.0) as usize).min(columns)
+        } else {
+            column
+        };
+        let spaces = if column == 0 && text.is_empty() {
+            target
+        } else {
+            target.saturating_sub(column).max(1)
+        };
+
+        text.extend(std::iter::repeat_n(' ', spaces));
+        text.push_str(&word.text);
+        column += spaces + word.text.chars().count();
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use livre::content::layout::{TextBlock, Viewport, Word};
+    use rstest::rstest;
+
+    use super::*;
+
+    fn line(words: &[(&str, f32)], y: f32) -> TextLine {
+        let words: Vec<Word> = words
+            .iter()
+            .map(|&(text, x)| Word {
+                text: text.to_string(),
+                bbox: Rectangle::from((x, y, x + 10.0 * text.len() as f32, y + 10.0)),
+                font_size: 10.0,
+            })
+            .collect();
+        let xmax = words.last().map_or(0.0, |word| word.bbox.xmax);
+
+        TextLine {
+            bbox: Rectangle::from((words[0].bbox.xmin, y, xmax, y + 10.0)),
+            words,
+            rotation: RotationAngle::Zero,
+        }
+    }
+
+    fn page(lines: Vec<TextLine>) -> TextPage {
+        TextPage {
+            viewport: Viewport {
+                bbox: Rectangle::from((0.0, 0.0, 612.0, 792.0)),
+                rotation: RotationAngle::Zero,
+            },
+            blocks: lines
+                .into_iter()
+                .map(|line| TextBlock {
+                    bbox: line.bbox,
+                    lines: vec![line],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn columns() {
+        // Two columns, read as two separate blocks each, are rendered side by side.
+        let page = page(vec![
+            line(&[("Name", 50.0)], 100.0),
+            line(&[("Alice", 50.0)], 112.0),
+            line(&[("Age", 150.0)], 100.0),
+            line(&[("42", 150.0)], 112.0),
+        ]);
+
+        assert_eq!(render(&page), "Name      Age\nAlice     42");
+    }
+
+    #[test]
+    fn paragraphs() {
+        let page = page(vec![
+            line(&[("First", 50.0)], 100.0),
+            line(&[("Second", 50.0), ("line", 120.0)], 150.0),
+        ]);
+
+        assert_eq!(render(&page), "First\n\nSecond line");
+    }
+
+    #[rstest]
+    #[case::far_right(1e30, "Left        Right")]
+    #[case::infinite(f32::INFINITY, "Left Right")]
+    #[case::not_a_number(f32::NAN, "Left Right")]
+    fn extreme_positions(#[case] x: f32, #[case] expected: &str) {
+        // A page 120 units wide, i.e. 12 cells of 10 units.
+        let mut page = page(vec![line(&[("Left", 0.0), ("Right", x)], 100.0)]);
+        page.viewport.bbox = Rectangle::from((0.0, 0.0, 120.0, 792.0));
+
+        assert_eq!(render(&page), expected);
+    }
+}
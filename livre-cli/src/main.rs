@@ -0,0 +1,160 @@
+//! The `livre` command: inspect PDF documents and extract their content.
+//!
+//! Every command reads the whole document in memory, as an
+//! [`InMemoryDocument`](livre::InMemoryDocument), and writes to the standard output.
+
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand};
+
+mod commands;
+mod layout;
+mod pretty;
+
+/// Errors are reported to the user as-is.
+pub type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Inspect PDF documents and extract their content.
+#[derive(Debug, Parser)]
+#[command(name = "livre", version)]
+struct Cli {
+    /// Recover from common producer bugs, such as wrong cross-reference offsets.
+    #[arg(long, global = true)]
+    lenient: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Show the trailer, the catalog, the page count and the metadata.
+    Info { path: PathBuf },
+    /// Dump the cross-reference table.
+    Xref { path: PathBuf },
+    /// Pretty-print an indirect object.
+    Object {
+        path: PathBuf,
+        /// Object number.
+        number: usize,
+        /// Generation number.
+        #[arg(default_value_t = 0)]
+        generation: u16,
+        /// Write the decoded content of a stream, instead of its length.
+        #[arg(long)]
+        decode: bool,
+    },
+    /// List the pages, with their boxes, rotation and content streams.
+    Pages { path: PathBuf },
+    /// Pretty-print the content stream of a page, one operator per line.
+    Content {
+        path: PathBuf,
+        /// The page, starting from 1.
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+        /// Prefix each operator with its position in the decoded content.
+        #[arg(long)]
+        offsets: bool,
+    },
+    /// Extract the text, in reading order.
+    Text {
+        path: PathBuf,
+        /// Only extract the text of this page, starting from 1.
+        #[arg(long)]
+        page: Option<usize>,
+        /// Keep the physical layout of the page, such that columns and tables line up.
+        #[arg(long)]
+        layout: bool,
+    },
+    /// Write a vector preview of a page, as SVG.
+    Svg {
+        path: PathBuf,
+        /// The page, starting from 1.
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+    },
+    /// Rasterize a page, and write it as a PNG image.
+    Render {
+        path: PathBuf,
+        /// The page, starting from 1.
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+        /// The resolution, in dots per inch.
+        #[arg(long, default_value_t = 72.0)]
+        dpi: f32,
+    },
+    /// List the fonts used by each page.
+    Fonts { path: PathBuf },
+    /// List the embedded files.
+    Attachments {
+        path: PathBuf,
+        /// Save the embedded files to this directory.
+        #[arg(long)]
+        save: Option<PathBuf>,
+    },
+}
+
+fn run(cli: Cli, output: &mut impl Write) -> Result {
+    let lenient = cli.lenient;
+
+    match cli.command {
+        Command::Info { path } => commands::info(&commands::open(&path, lenient)?, output),
+        Command::Xref { path } => commands::xref(&commands::open(&path, lenient)?, output),
+        Command::Object {
+            path,
+            number,
+            generation,
+            decode,
+        } => commands::object(
+            &commands::open(&path, lenient)?,
+            number,
+            generation,
+            decode,
+            output,
+        ),
+        Command::Pages { path } => commands::pages(&commands::open(&path, lenient)?, output),
+        Command::Content {
+            path,
+            page,
+            offsets,
+        } => commands::content(&commands::open(&path, lenient)?, page, offsets, output),
+        Command::Text { path, page, layout } => {
+            commands::text(&commands::open(&path, lenient)?, page, layout, output)
+        }
+        Command::Svg { path, page } => {
+            commands::svg(&commands::open(&path, lenient)?, page, output)
+        }
+        Command::Render { path, page, dpi } => {
+            commands::render(&commands::open(&path, lenient)?, page, dpi, output)
+        }
+        Command::Fonts { path } => commands::fonts(&commands::open(&path, lenient)?, output),
+        Command::Attachments { path, save } => {
+            commands::attachments(&commands::open(&path, lenient)?, save.as_deref(), output)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let mut output = io::stdout().lock();
+
+    match run(cli, &mut output) {
+        Ok(()) => ExitCode::SUCCESS,
+        // The output was closed early, e.g. piped into `head`.
+        Err(err)
+            if err
+                .downcast_ref::<io::Error>()
+                .is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Pretty-printing of PDF objects, in PDF syntax.
+//!
+//! Dictionaries are written one entry per line, with their keys in order, and arrays are kept
+//! on a single line unless they hold dictionaries. Streams are written as their dictionary,
+//! followed by a summary of their content or, on demand, the decoded content itself.
+
+use std::io::{self, Write};
+
+use livre::{
+    extraction::{Map, Object, Stream},
+    serialization::Serialize,
+};
+
+const INDENT: &str = "  ";
+
+/// How to write the content of streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Content {
+    /// Only write the length of the decoded content and the filters.
+    Summary,
+    /// Write the decoded content, between the `stream` and `endstream` keywords. Streams
+    /// that Livre cannot decode are written as is.
+    Decoded,
+}
+
+/// Write `object`, followed by a line break.
+pub fn write_object(object: &Object, content: Content, writer: &mut impl Write) -> io::Result<()> {
+    write(object, content, 0, writer)?;
+    writeln!(writer)
+}
+
+fn write(
+    object: &Object,
+    content: Content,
+    depth: usize,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    match object {
+        Object::Array(array) if array.iter().any(is_nested) => {
+            writeln!(writer, "[")?;
+            for item in array {
+                indent(depth + 1, writer)?;
+                write(item, content, depth + 1, writer)?;
+                writeln!(writer)?;
+            }
+            indent(depth, writer)?;
+            write!(writer, "]")
+        }
+        Object::Array(array) => {
+            write!(writer, "[")?;
+            for (i, item) in array.iter().enumerate() {
+                if i > 0 {
+                    write!(writer, " ")?;
+                }
+                write(item, content, depth, writer)?;
+            }
+            write!(writer, "]")
+        }
+        Object::Dictionary(map) => write_dict(map, content, depth, writer),
+        Object::Stream(stream) => write_stream(stream, content, depth, writer),
+        scalar => writer.write_all(&scalar.to_bytes()),
+    }
+}
+
+/// Whether the object spans several lines.
+fn is_nested(object: &Object) -> bool {
+    matches!(object, Object::Dictionary(_) | Object::Stream(_))
+}
+
+fn indent(depth: usize, writer: &mut impl Write) -> io::Result<()> {
+    write!(writer, "{}", INDENT.repeat(depth))
+}
+
+fn write_dict(
+    map: &Map<Object>,
+    content: Content,
+    depth: usize,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    if map.is_empty() {
+        return write!(writer, "<<>>");
+    }
+
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+
+    writeln!(writer, "<<")?;
+    for (key, value) in entries {
+        indent(depth + 1, writer)?;
+        key.serialize(writer)?;
+        write!(writer, " ")?;
+        write(value, content, depth + 1, writer)?;
+        writeln!(writer)?;
+    }
+    indent(depth, writer)?;
+    write!(writer, ">>")
+}
+
+fn write_stream(
+    stream: &Stream<Map<Object>>,
+    content: Content,
+    depth: usize,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    write_dict(&stream.structured, content, depth, writer)?;
+
+    match content {
+        // Livre could not decode the stream, whose filters are listed in its dictionary.
+        Content::Summary if is_encoded(stream) => {
+            write!(writer, "\n% {} bytes, still encoded", stream.content.len())
+        }
+        Content::Summary => {
+            let filters: Vec<_> = stream.filters.iter().map(|f| format!("{f:?}")).collect();
+            write!(writer, "\n% {} bytes decoded", stream.content.len())?;
+            if !filters.is_empty() {
+                write!(writer, ", filters: {}", filters.join(", "))?;
+            }
+            Ok(())
+        }
+        Content::Decoded => {
+            writeln!(writer, "\nstream")?;
+            writer.write_all(&stream.content)?;
+            if !stream.content.ends_with(b"\n") {
+                writeln!(writer)?;
+            }
+            write!(writer, "endstream")
+        }
+    }
+}
+
+/// Whether the stream was kept encoded, see [`InMemoryDocument::object`].
+///
+/// [`InMemoryDocument::object`]: livre::InMemoryDocument::object
+fn is_encoded(stream: &Stream<Map<Object>>) -> bool {
+    stream.filters.is_empty() && stream.structured.contains_key(&"Filter".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use livre::extraction::{extract, Name};
+    use rstest::rstest;
+
+    use super::*;
+
+    fn pretty(object: &Object, content: Content) -> String {
+        let mut output = Vec::new();
+        write_object(object, content, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[rstest]
+    #[case(b"42", "42\n")]
+    #[case(b"(Hello)", "(Hello)\n")]
+    #[case(b"[1 /Name 2 0 R]", "[1 /Name 2 0 R]\n")]
+    #[case(b"<<>>", "<<>>\n")]
+    #[case(
+        b"<</Type /Page /Kids [<</A 1>>] /Parent 1 0 R>>",
+        indoc! {"
+            <<
+              /Kids [
+                <<
+                  /A 1
+                >>
+              ]
+              /Parent 1 0 R
+              /Type /Page
+            >>
+        "}
+    )]
+    fn objects(#[case] input: &[u8], #[case] expected: &str) {
+        let object: Object = extract(&mut input.as_ref()).unwrap();
+        assert_eq!(pretty(&object, Content::Summary), expected);
+    }
+
+    #[rstest]
+    #[case(Content::Summary, "<<\n  /Length 5\n>>\n% 5 bytes decoded\n")]
+    #[case(Content::Decoded, "<<\n  /Length 5\n>>\nstream\nhello\nendstream\n")]
+    fn streams(#[case] content: Content, #[case] expected: &str) {
+        let stream = Stream {
+            structured: [(Name::from("Length"), Object::Integer(5))]
+                .into_iter()
+                .collect(),
+            filters: Vec::new(),
+            content: b"hello".to_vec(),
+        };
+
+        assert_eq!(pretty(&Object::Stream(stream), content), expected);
+    }
+
+    #[test]
+    fn encoded_stream() {
+        let stream = Stream {
+            structured: [(Name::from("Filter"), Object::Name("DCTDecode".into()))]
+                .into_iter()
+                .collect(),
+            filters: Vec::new(),
+            content: vec![0xff, 0xd8],
+        };
+
+        assert_eq!(
+            pretty(&Object::Stream(stream), Content::Summary),
+            "<<\n  /Filter /DCTDecode\n>>\n% 2 bytes, still encoded\n"
+        );
+    }
+}
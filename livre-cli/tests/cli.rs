@@ -0,0 +1,66 @@
+use std::process::{Command, Output};
+
+use rstest::rstest;
+
+const LETTER: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../tests/resources/letter.pdf");
+const TEXT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../tests/resources/text.pdf");
+
+fn livre(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_livre"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(args: &[&str]) -> String {
+    let output = livre(args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[rstest]
+#[case(&["info", LETTER], "Pages:        1")]
+#[case(&["info", LETTER], "Author        hamad")]
+#[case(&["xref", LETTER], "     1     0  offset 17")]
+#[case(&["object", LETTER, "1"], "  /Type /Catalog")]
+#[case(&["object", TEXT, "5", "0"], "% 58776 bytes decoded, filters: FlateDecode")]
+#[case(&["object", TEXT, "5", "0", "--decode"], ".75 0 0 .75 0 0 cm")]
+#[case(&["object", LETTER, "5"], "% 19502 bytes, still encoded")]
+#[case(&["pages", TEXT], "  MediaBox:  [0 0 612 792]")]
+#[case(&["content", LETTER], "  BT\n    /F1 9.96 Tf\n")]
+#[case(&["content", LETTER, "--offsets"], "20..27          /GS6 gs\n")]
+#[case(&["svg", LETTER], r#"<svg xmlns="http://www.w3.org/2000/svg""#)]
+#[case(&["svg", TEXT], ">L</text>")]
+#[case(&["fonts", LETTER], "Type0 SymbolMT (Identity-H, ToUnicode)")]
+#[case(&["attachments", LETTER], "No embedded files")]
+#[case(&["text", TEXT], "Longtemps, je me suis couché de bonne heure.")]
+#[case(&["text", LETTER, "--page", "1", "--layout"], "Créteil, le 1 janvier 2010")]
+fn commands(#[case] args: &[&str], #[case] expected: &str) {
+    let output = stdout(args);
+    assert!(output.contains(expected), "{output}");
+}
+
+#[rstest]
+#[case(&["text", LETTER, "--page", "2"], "error: page 2 does not exist")]
+#[case(&["svg", LETTER, "--page", "0"], "error: page 0 does not exist")]
+#[case(&["render", LETTER, "--dpi", "0"], "error: invalid resolution 0")]
+#[case(&["object", LETTER, "1000"], "error: object 1000 0 R does not exist")]
+#[case(&["info", "missing.pdf"], "error: missing.pdf: ")]
+fn errors(#[case] args: &[&str], #[case] expected: &str) {
+    let output = livre(args);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with(expected));
+}
+
+#[test]
+fn render() {
+    let output = livre(&["render", TEXT, "--dpi", "36"]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.starts_with(b"\x89PNG\r\n\x1a\n"));
+}
@@ -9,6 +9,7 @@ use syn::{parse_quote, GenericParam, Generics};
 mod build_from_raw_dict;
 mod extract;
 mod from_raw_dict;
+mod serialize_dict;
 mod utilities;
 
 #[proc_macro_derive(BuildFromRawDict, attributes(livre))]
@@ -21,6 +22,11 @@ pub fn derive_from_raw_dict(input: proc_macro::TokenStream) -> proc_macro::Token
     from_raw_dict::derive(input)
 }
 
+#[proc_macro_derive(SerializeDict, attributes(livre))]
+pub fn derive_serialize_dict(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    serialize_dict::derive(input)
+}
+
 #[proc_macro_derive(Extract, attributes(livre))]
 pub fn derive_extract(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     extract::derive(input)
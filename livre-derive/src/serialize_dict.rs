@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DataStruct, DeriveInput, Fields, GenericParam, Generics,
+};
+
+use crate::utilities::serialization;
+
+fn add_serialization_trait_bounds(mut generics: Generics, flattened: HashSet<String>) -> Generics {
+    for param in &mut generics.params {
+        if let GenericParam::Type(ref mut type_param) = *param {
+            if flattened.contains(&type_param.ident.to_string()) {
+                type_param
+                    .bounds
+                    .push(parse_quote!(crate::serialization::SerializeDict));
+            } else {
+                type_param
+                    .bounds
+                    .push(parse_quote!(crate::serialization::Serialize));
+            }
+        }
+    }
+    generics
+}
+
+pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    // Parse the input tokens into a syntax tree.
+    let input = parse_macro_input!(input as DeriveInput);
+
+    // Used in the quasi-quotation below as `#name`.
+    let name = input.ident;
+
+    let (serialization, flattened) = generate_serialization(&input.data);
+
+    // Add a bound `T: Serialize` to every type parameter T.
+    let generics = add_serialization_trait_bounds(input.generics, flattened);
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let expanded = quote! {
+        impl #impl_generics crate::serialization::SerializeDict for #name #ty_generics #where_clause {
+            fn serialize_entries<W>(&self, dict: &mut crate::serialization::DictWriter<'_, W>) -> ::std::io::Result<()>
+            where
+                W: ::std::io::Write,
+            {
+                #serialization
+                Ok(())
+            }
+        }
+    };
+
+    // Hand the output tokens back to the compiler.
+    proc_macro::TokenStream::from(expanded)
+}
+
+fn generate_serialization(data: &Data) -> (TokenStream, HashSet<String>) {
+    let mut set = HashSet::new();
+    match data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => {
+            let field_by_field = fields
+                .named
+                .iter()
+                .map(|f| serialization::serialize_field(f, &mut set));
+
+            let serialization = quote! {
+                #(
+                    #field_by_field
+                )*
+            };
+
+            (serialization, set)
+        }
+        _ => unimplemented!(),
+    }
+}
@@ -1,3 +1,4 @@
 pub mod attr;
 pub mod extraction;
 pub mod option;
+pub mod serialization;
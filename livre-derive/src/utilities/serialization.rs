@@ -0,0 +1,83 @@
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Field, Type};
+
+use super::attr::{parse_attributes, Attributes, DefaultValue};
+
+/// Generate the code that writes a single field, the inverse of
+/// [`extract_field`](super::extraction::extract_field):
+///
+/// - flattened fields write their own entries
+/// - `from` conversions are turned around, using [`Into`] instead
+/// - fields equal to their default value, as well as missing optional fields, are skipped
+pub fn serialize_field(f: &Field, set: &mut HashSet<String>) -> TokenStream {
+    let name = &f.ident;
+    let ty = &f.ty;
+
+    let Attributes {
+        flatten,
+        from,
+        field_str,
+        is_opt,
+        default,
+    } = parse_attributes(f).unwrap();
+
+    if flatten {
+        let path = match ty {
+            Type::Path(p) => &p.path,
+            _ => unimplemented!(),
+        };
+        set.insert(path.get_ident().unwrap().to_string());
+
+        return quote! {
+            crate::serialization::SerializeDict::serialize_entries(&self.#name, dict)?;
+        };
+    }
+
+    let value = if let Some(from_ty) = &from {
+        quote! {
+            let value: #from_ty = ::core::clone::Clone::clone(&self.#name).into();
+            let value = &value;
+        }
+    } else {
+        quote! {
+            let value = &self.#name;
+        }
+    };
+
+    let from_ty = from.as_ref().unwrap_or(ty);
+
+    let entry = if let Some(default) = default {
+        let default = match default {
+            DefaultValue::None => quote! {::core::default::Default::default()},
+            DefaultValue::Lit(lit) => quote! {#lit},
+            DefaultValue::Closure(closure) => quote! {(#closure)()},
+        };
+
+        quote! {
+            let default: #from_ty = #default;
+            if *value != default {
+                dict.entry(#field_str.as_bytes(), value)?;
+            }
+        }
+    } else if is_opt {
+        quote! {
+            if let Some(value) = value {
+                dict.entry(#field_str.as_bytes(), value)?;
+            }
+        }
+    } else {
+        quote! {
+            dict.entry(#field_str.as_bytes(), value)?;
+        }
+    };
+
+    quote! {
+        {
+            #value
+            #entry
+        }
+    }
+}
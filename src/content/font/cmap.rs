@@ -0,0 +1,244 @@
+//! `ToUnicode` CMaps, which map character codes to Unicode text.
+//!
+//! From the specification:
+//!
+//! > The CMap file shall contain `begincodespacerange` and `endcodespacerange` operators
+//! > that are consistent with the encoding that the font uses. In particular, for a simple
+//! > font, the codespace shall be one byte long.
+//! >
+//! > It shall use the `beginbfchar`, `endbfchar`, `beginbfrange`, and `endbfrange` operators
+//! > to define the mapping from character codes to Unicode character sequences expressed in
+//! > UTF-16BE encoding.
+//!
+//! Livre only reads the `bfchar` and `bfrange` sections, and ignores the rest of the CMap
+//! program.
+
+use std::collections::HashMap;
+
+use winnow::{
+    combinator::{alt, iterator, peek, preceded},
+    dispatch,
+    error::ErrMode,
+    token::any,
+    BStr, ModalResult, Parser,
+};
+
+use crate::extraction::{
+    multicomment0, take_till_delimiter, Brackets, DoubleAngles, Extract, HexadecimalString, Name,
+    Parentheses,
+};
+
+/// Lexical token of a CMap program. Livre only needs to tell codes and arrays of codes from
+/// keywords.
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'de> {
+    Code(Vec<u8>),
+    Array(Vec<Vec<u8>>),
+    Keyword(&'de [u8]),
+    Other,
+}
+
+fn token<'de>(input: &mut &'de BStr) -> ModalResult<Token<'de>> {
+    preceded(
+        multicomment0,
+        dispatch! {peek(any);
+            b'<' => alt((
+                DoubleAngles::recognize.value(Token::Other),
+                HexadecimalString::extract.map(|HexadecimalString(code)| Token::Code(code)),
+            )),
+            b'[' => alt((
+                Vec::<HexadecimalString>::extract
+                    .map(|codes| Token::Array(codes.into_iter().map(|code| code.0).collect())),
+                Brackets::recognize.value(Token::Other),
+            )),
+            b'(' => Parentheses::recognize.value(Token::Other),
+            b'/' => Name::recognize.value(Token::Other),
+            // PostScript procedures
+            b'{' | b'}' => any.value(Token::Other),
+            _ => take_till_delimiter(1..).map(Token::Keyword).map_err(ErrMode::Backtrack),
+        },
+    )
+    .parse_next(input)
+}
+
+/// Big-endian code.
+fn code(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0, |res: u32, &byte| (res << 8) | byte as u32)
+}
+
+/// Destinations are UTF-16BE strings.
+fn utf16(bytes: &[u8]) -> String {
+    let units = bytes.chunks(2).map(|pair| {
+        pair.iter()
+            .fold(0, |res: u16, &byte| (res << 8) | byte as u16)
+    });
+
+    char::decode_utf16(units)
+        .map(|r| r.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Destination of a `bfrange` entry.
+#[derive(Debug, Clone, PartialEq)]
+enum Destination {
+    /// The first code of the range maps to this string, subsequent codes incrementing its last
+    /// byte.
+    Start(Vec<u8>),
+    /// One string per code in the range.
+    Array(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Range {
+    low: u32,
+    high: u32,
+    destination: Destination,
+}
+
+impl Range {
+    fn get(&self, code: u32) -> Option<String> {
+        if !(self.low..=self.high).contains(&code) {
+            return None;
+        }
+
+        let offset = code - self.low;
+
+        match &self.destination {
+            Destination::Start(start) => {
+                let mut bytes = start.clone();
+                let last = bytes.last_mut()?;
+                // The specification forbids ranges that overflow the last byte.
+                *last = last.checked_add(u8::try_from(offset).ok()?)?;
+                Some(utf16(&bytes))
+            }
+            Destination::Array(strings) => strings.get(offset as usize).cloned(),
+        }
+    }
+}
+
+/// A `ToUnicode` CMap.
+///
+/// Ranges are kept as such rather than expanded, such that a hostile CMap cannot allocate
+/// one string per possible code.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ToUnicode {
+    chars: HashMap<u32, String>,
+    ranges: Vec<Range>,
+}
+
+impl ToUnicode {
+    /// Parse the content of the `ToUnicode` stream. Parsing is lenient: it stops at the first
+    /// token it does not recognize, keeping the mappings read so far.
+    pub fn parse(content: &[u8]) -> Self {
+        let mut input = BStr::new(content);
+        let mut it = iterator(&mut input, token);
+        let mut tokens = &mut it;
+
+        let mut cmap = Self::default();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                Token::Keyword(b"beginbfchar") => {
+                    // The section ends with the first token that is not a code.
+                    while let Some(Token::Code(src)) = tokens.next() {
+                        let Some(Token::Code(dst)) = tokens.next() else {
+                            break;
+                        };
+                        cmap.chars.insert(code(&src), utf16(&dst));
+                    }
+                }
+                Token::Keyword(b"beginbfrange") => {
+                    while let Some(Token::Code(low)) = tokens.next() {
+                        let Some(Token::Code(high)) = tokens.next() else {
+                            break;
+                        };
+                        let destination = match tokens.next() {
+                            Some(Token::Code(start)) => Destination::Start(start),
+                            Some(Token::Array(strings)) => {
+                                Destination::Array(strings.iter().map(|s| utf16(s)).collect())
+                            }
+                            _ => break,
+                        };
+
+                        cmap.ranges.push(Range {
+                            low: code(&low),
+                            high: code(&high),
+                            destination,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        cmap
+    }
+
+    /// The Unicode text for a character code, if the CMap defines it.
+    pub fn get(&self, code: u32) -> Option<String> {
+        self.chars
+            .get(&code)
+            .cloned()
+            .or_else(|| self.ranges.iter().find_map(|range| range.get(code)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rstest::rstest;
+
+    use super::*;
+
+    const CMAP: &[u8] = indoc! {b"
+        /CIDInit /ProcSet findresource begin
+        12 dict begin
+        begincmap
+        /CIDSystemInfo
+        << /Registry (Adobe)
+        /Ordering (UCS)
+        /Supplement 0
+        >> def
+        /CMapName /Adobe-Identity-UCS def
+        /CMapType 2 def
+        1 begincodespacerange
+        <0000> <FFFF>
+        endcodespacerange
+        % Single characters
+        2 beginbfchar
+        <0003> <0020>
+        <00B2> <00660069>
+        endbfchar
+        2 beginbfrange
+        <0024> <0026> <0041>
+        <0044> <0045> [<0061> <D835DC9C>]
+        endbfrange
+        endcmap
+        CMapName currentdict /CMap defineresource pop
+        end
+        end
+    "};
+
+    #[rstest]
+    #[case(0x03, Some(" "))]
+    #[case(0xB2, Some("fi"))]
+    #[case(0x24, Some("A"))]
+    #[case(0x26, Some("C"))]
+    #[case(0x27, None)]
+    #[case(0x44, Some("a"))]
+    #[case(0x45, Some("𝒜"))]
+    #[case(0x46, None)]
+    fn to_unicode(#[case] code: u32, #[case] expected: Option<&str>) {
+        let cmap = ToUnicode::parse(CMAP);
+        assert_eq!(cmap.get(code).as_deref(), expected);
+    }
+
+    #[test]
+    fn overflowing_range() {
+        let cmap = ToUnicode::parse(b"beginbfrange <00> <FF> <00FE> endbfrange");
+        assert_eq!(cmap.get(0x01).as_deref(), Some("\u{FF}"));
+        assert_eq!(cmap.get(0x02), None);
+    }
+}
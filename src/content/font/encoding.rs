@@ -0,0 +1,412 @@
+//! Character encodings of simple fonts. See Annex D of the PDF specification.
+//!
+//! A simple font maps single-byte character codes to glyph names, using a base encoding
+//! optionally amended by a `Differences` array. Livre maps these glyph names to Unicode, which
+//! is a best-effort process: it covers the names used by the standard Latin encodings, as well
+//! as the `uniXXXX` and `uXXXX` conventions.
+
+use std::collections::HashMap;
+
+use winnow::{combinator::alt, BStr, ModalResult, Parser};
+
+use crate::{
+    extraction::{extract, Extract, Name, Object},
+    follow_refs::{Build, BuildFromRawDict, Builder},
+};
+
+/// Glyph names of the `WinAnsiEncoding`, starting from code `0x20`. Undefined codes are left
+/// empty.
+#[rustfmt::skip]
+const WIN_ANSI_NAMES: [&str; 224] = [
+    // 0x20
+    "space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand", "quotesingle",
+    "parenleft", "parenright", "asterisk", "plus", "comma", "hyphen", "period", "slash",
+    // 0x30
+    "zero", "one", "two", "three", "four", "five", "six", "seven",
+    "eight", "nine", "colon", "semicolon", "less", "equal", "greater", "question",
+    // 0x40
+    "at", "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O",
+    // 0x50
+    "P", "Q", "R", "S", "T", "U", "V", "W",
+    "X", "Y", "Z", "bracketleft", "backslash", "bracketright", "asciicircum", "underscore",
+    // 0x60
+    "grave", "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o",
+    // 0x70
+    "p", "q", "r", "s", "t", "u", "v", "w",
+    "x", "y", "z", "braceleft", "bar", "braceright", "asciitilde", "",
+    // 0x80
+    "Euro", "", "quotesinglbase", "florin", "quotedblbase", "ellipsis", "dagger", "daggerdbl",
+    "circumflex", "perthousand", "Scaron", "guilsinglleft", "OE", "", "Zcaron", "",
+    // 0x90
+    "", "quoteleft", "quoteright", "quotedblleft", "quotedblright", "bullet", "endash", "emdash",
+    "tilde", "trademark", "scaron", "guilsinglright", "oe", "", "zcaron", "Ydieresis",
+    // 0xA0
+    "space", "exclamdown", "cent", "sterling", "currency", "yen", "brokenbar", "section",
+    "dieresis", "copyright", "ordfeminine", "guillemotleft", "logicalnot", "hyphen", "registered",
+    "macron",
+    // 0xB0
+    "degree", "plusminus", "twosuperior", "threesuperior", "acute", "mu", "paragraph",
+    "periodcentered", "cedilla", "onesuperior", "ordmasculine", "guillemotright", "onequarter",
+    "onehalf", "threequarters", "questiondown",
+    // 0xC0
+    "Agrave", "Aacute", "Acircumflex", "Atilde", "Adieresis", "Aring", "AE", "Ccedilla",
+    "Egrave", "Eacute", "Ecircumflex", "Edieresis", "Igrave", "Iacute", "Icircumflex", "Idieresis",
+    // 0xD0
+    "Eth", "Ntilde", "Ograve", "Oacute", "Ocircumflex", "Otilde", "Odieresis", "multiply",
+    "Oslash", "Ugrave", "Uacute", "Ucircumflex", "Udieresis", "Yacute", "Thorn", "germandbls",
+    // 0xE0
+    "agrave", "aacute", "acircumflex", "atilde", "adieresis", "aring", "ae", "ccedilla",
+    "egrave", "eacute", "ecircumflex", "edieresis", "igrave", "iacute", "icircumflex", "idieresis",
+    // 0xF0
+    "eth", "ntilde", "ograve", "oacute", "ocircumflex", "otilde", "odieresis", "divide",
+    "oslash", "ugrave", "uacute", "ucircumflex", "udieresis", "yacute", "thorn", "ydieresis",
+];
+
+/// Characters of the `WinAnsiEncoding` between `0x80` and `0x9F`, where it departs from
+/// Latin-1.
+#[rustfmt::skip]
+const WIN_ANSI_80: [Option<char>; 32] = [
+    Some('€'), None, Some('‚'), Some('ƒ'), Some('„'), Some('…'), Some('†'), Some('‡'),
+    Some('ˆ'), Some('‰'), Some('Š'), Some('‹'), Some('Œ'), None, Some('Ž'), None,
+    None, Some('‘'), Some('’'), Some('“'), Some('”'), Some('•'), Some('–'), Some('—'),
+    Some('˜'), Some('™'), Some('š'), Some('›'), Some('œ'), None, Some('ž'), Some('Ÿ'),
+];
+
+/// Characters of the `MacRomanEncoding`, starting from code `0x80`.
+const MAC_ROMAN_80: &str = "ÄÅÇÉÑÖÜáàâäãåçéèêëíìîïñóòôöõúùûü†°¢£§•¶ß®©™´¨≠ÆØ∞±≤≥¥µ∂∑∏π∫ªºΩæø\
+                            ¿¡¬√ƒ≈∆«»… ÀÃÕŒœ–—“”‘’÷◊ÿŸ⁄¤‹›ﬁﬂ‡·‚„‰ÂÊÁËÈÍÎÏÌÓÔ\u{F8FF}ÒÚÛÙıˆ˜¯˘˙˚¸˝˛ˇ";
+
+/// Characters of the `StandardEncoding` above `0x7F`. Every other code is undefined.
+const STANDARD_A0: &[(u8, char)] = &[
+    (0xA1, '¡'),
+    (0xA2, '¢'),
+    (0xA3, '£'),
+    (0xA4, '⁄'),
+    (0xA5, '¥'),
+    (0xA6, 'ƒ'),
+    (0xA7, '§'),
+    (0xA8, '¤'),
+    (0xA9, '\''),
+    (0xAA, '“'),
+    (0xAB, '«'),
+    (0xAC, '‹'),
+    (0xAD, '›'),
+    (0xAE, 'ﬁ'),
+    (0xAF, 'ﬂ'),
+    (0xB1, '–'),
+    (0xB2, '†'),
+    (0xB3, '‡'),
+    (0xB4, '·'),
+    (0xB6, '¶'),
+    (0xB7, '•'),
+    (0xB8, '‚'),
+    (0xB9, '„'),
+    (0xBA, '”'),
+    (0xBB, '»'),
+    (0xBC, '…'),
+    (0xBD, '‰'),
+    (0xBF, '¿'),
+    (0xC1, '`'),
+    (0xC2, '´'),
+    (0xC3, 'ˆ'),
+    (0xC4, '˜'),
+    (0xC5, '¯'),
+    (0xC6, '˘'),
+    (0xC7, '˙'),
+    (0xC8, '¨'),
+    (0xCA, '˚'),
+    (0xCB, '¸'),
+    (0xCD, '˝'),
+    (0xCE, '˛'),
+    (0xCF, 'ˇ'),
+    (0xD0, '—'),
+    (0xE1, 'Æ'),
+    (0xE3, 'ª'),
+    (0xE8, 'Ł'),
+    (0xE9, 'Ø'),
+    (0xEA, 'Œ'),
+    (0xEB, 'º'),
+    (0xF1, 'æ'),
+    (0xF5, 'ı'),
+    (0xF8, 'ł'),
+    (0xF9, 'ø'),
+    (0xFA, 'œ'),
+    (0xFB, 'ß'),
+];
+
+/// Glyph names that do not appear in the `WinAnsiEncoding`, but are common enough in
+/// `Differences` arrays.
+const EXTRA_NAMES: &[(&str, &str)] = &[
+    ("fi", "fi"),
+    ("fl", "fl"),
+    ("ff", "ff"),
+    ("ffi", "ffi"),
+    ("ffl", "ffl"),
+    ("dotlessi", "ı"),
+    ("Lslash", "Ł"),
+    ("lslash", "ł"),
+    ("minus", "−"),
+    ("fraction", "⁄"),
+    ("breve", "˘"),
+    ("dotaccent", "˙"),
+    ("ring", "˚"),
+    ("hungarumlaut", "˝"),
+    ("ogonek", "˛"),
+    ("caron", "ˇ"),
+    ("nbspace", "\u{A0}"),
+    ("sfthyphen", "\u{AD}"),
+];
+
+/// Base encoding of a simple font.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BaseEncoding {
+    /// Adobe standard Latin-text encoding, the default for most fonts.
+    #[default]
+    Standard,
+    WinAnsi,
+    MacRoman,
+}
+
+impl BaseEncoding {
+    fn from_name(Name(name): &Name) -> Option<Self> {
+        match name.as_slice() {
+            b"StandardEncoding" => Some(Self::Standard),
+            b"WinAnsiEncoding" => Some(Self::WinAnsi),
+            b"MacRomanEncoding" => Some(Self::MacRoman),
+            _ => None,
+        }
+    }
+
+    /// The character mapped to a code, if any.
+    pub fn decode(self, code: u8) -> Option<char> {
+        match (self, code) {
+            (_, ..=0x1F) | (_, 0x7F) => None,
+            (Self::Standard, 0x27) => Some('’'),
+            (Self::Standard, 0x60) => Some('‘'),
+            (_, 0x20..=0x7E) => Some(code as char),
+            (Self::Standard, _) => STANDARD_A0
+                .iter()
+                .find_map(|&(c, char)| (c == code).then_some(char)),
+            (Self::WinAnsi, 0x80..=0x9F) => WIN_ANSI_80[code as usize - 0x80],
+            (Self::WinAnsi, _) => Some(code as char),
+            (Self::MacRoman, _) => MAC_ROMAN_80.chars().nth(code as usize - 0x80),
+        }
+    }
+}
+
+/// Map a glyph name to Unicode text, if possible.
+pub fn glyph_name(name: &[u8]) -> Option<String> {
+    let name = std::str::from_utf8(name).ok()?;
+
+    // Suffixes denote glyph variants, e.g. `a.sc` or `one.oldstyle`.
+    let name = name.split('.').next()?;
+
+    // Ligatures may be described by their components, e.g. `f_f_i`.
+    if name.contains('_') {
+        return name
+            .split('_')
+            .map(|part| glyph_name(part.as_bytes()))
+            .collect();
+    }
+
+    if let Some(index) = WIN_ANSI_NAMES.iter().position(|&n| n == name) {
+        return BaseEncoding::WinAnsi
+            .decode(index as u8 + 0x20)
+            .map(String::from);
+    }
+
+    if let Some((_, text)) = EXTRA_NAMES.iter().find(|(n, _)| *n == name) {
+        return Some(text.to_string());
+    }
+
+    if let Some(hex) = name.strip_prefix("uni") {
+        // A sequence of four-digit UTF-16 code units.
+        if hex.is_empty() || hex.len() % 4 != 0 {
+            return None;
+        }
+        let units = (0..hex.len())
+            .step_by(4)
+            .map(|i| u16::from_str_radix(hex.get(i..i + 4)?, 16).ok())
+            .collect::<Option<Vec<_>>>()?;
+        return char::decode_utf16(units).collect::<Result<_, _>>().ok();
+    }
+
+    if let Some(hex) = name.strip_prefix('u') {
+        if (4..=6).contains(&hex.len()) {
+            let code = u32::from_str_radix(hex, 16).ok()?;
+            return char::from_u32(code).map(String::from);
+        }
+    }
+
+    None
+}
+
+/// The encoding of a simple font: a base encoding, amended by a `Differences` array.
+///
+/// ```raw
+/// /Encoding /WinAnsiEncoding
+/// /Encoding << /BaseEncoding /MacRomanEncoding /Differences [39 /quotesingle 96 /grave] >>
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Encoding {
+    pub base: BaseEncoding,
+    /// Codes overridden by the `Differences` array, with their Unicode text.
+    pub differences: HashMap<u8, String>,
+    /// The glyph names of the `Differences` array, including those without Unicode text.
+    pub names: HashMap<u8, Name>,
+}
+
+impl Encoding {
+    pub fn decode(&self, code: u8) -> Option<String> {
+        self.differences
+            .get(&code)
+            .cloned()
+            .or_else(|| self.base.decode(code).map(String::from))
+    }
+
+    /// The name of the glyph a code stands for, which selects the glyph in a font program.
+    /// Codes of the base encoding are named after their character, which only covers the
+    /// names that Livre knows of.
+    pub fn glyph(&self, code: u8) -> Option<Name> {
+        if let Some(name) = self.names.get(&code) {
+            return Some(name.clone());
+        }
+
+        let char = self.base.decode(code)?;
+        let name = (0x20..=0xFF)
+            .find(|&c| BaseEncoding::WinAnsi.decode(c) == Some(char))
+            .map(|c| WIN_ANSI_NAMES[c as usize - 0x20])
+            .or_else(|| {
+                EXTRA_NAMES
+                    .iter()
+                    .find_map(|&(name, text)| text.chars().eq([char]).then_some(name))
+            })?;
+
+        Some(name.into())
+    }
+}
+
+#[derive(Debug, BuildFromRawDict)]
+struct EncodingDict {
+    base_encoding: Option<Name>,
+    differences: Option<Vec<Object>>,
+}
+
+impl Extract<'_> for Encoding {
+    fn extract(input: &mut &BStr) -> ModalResult<Self> {
+        let name = extract(input)?;
+        Ok(Self {
+            base: BaseEncoding::from_name(&name).unwrap_or_default(),
+            differences: HashMap::new(),
+            names: HashMap::new(),
+        })
+    }
+}
+
+impl Build for Encoding {
+    fn build<B>(input: &mut &BStr, builder: &B) -> ModalResult<Self>
+    where
+        B: Builder,
+    {
+        alt((Self::extract, |i: &mut &BStr| {
+            let EncodingDict {
+                base_encoding,
+                differences,
+            } = builder.build(i)?;
+
+            let base = base_encoding
+                .as_ref()
+                .and_then(BaseEncoding::from_name)
+                .unwrap_or_default();
+
+            // Each code is followed by the names of the glyphs for consecutive codes.
+            let mut map = HashMap::new();
+            let mut names = HashMap::new();
+            let mut code = 0;
+            for item in differences.unwrap_or_default() {
+                match item {
+                    Object::Integer(c) => code = c,
+                    Object::Name(name) => {
+                        if let Ok(c) = u8::try_from(code) {
+                            if let Some(text) = glyph_name(&name.0) {
+                                map.insert(c, text);
+                            }
+                            names.insert(c, name);
+                        }
+                        code += 1;
+                    }
+                    _ => {}
+                }
+            }
+
+            Ok(Self {
+                base,
+                differences: map,
+                names,
+            })
+        }))
+        .parse_next(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(BaseEncoding::WinAnsi, b'A', Some('A'))]
+    #[case(BaseEncoding::WinAnsi, 0x92, Some('’'))]
+    #[case(BaseEncoding::WinAnsi, 0x81, None)]
+    #[case(BaseEncoding::WinAnsi, 0xE9, Some('é'))]
+    #[case(BaseEncoding::Standard, 0x27, Some('’'))]
+    #[case(BaseEncoding::Standard, 0xAE, Some('ﬁ'))]
+    #[case(BaseEncoding::Standard, 0xE9, Some('Ø'))]
+    #[case(BaseEncoding::MacRoman, 0x8E, Some('é'))]
+    #[case(BaseEncoding::MacRoman, 0xD5, Some('’'))]
+    #[case(BaseEncoding::MacRoman, 0xFF, Some('ˇ'))]
+    fn base_encoding(#[case] base: BaseEncoding, #[case] code: u8, #[case] expected: Option<char>) {
+        assert_eq!(base.decode(code), expected);
+    }
+
+    #[rstest]
+    #[case(b"eacute", Some("é"))]
+    #[case(b"quoteright", Some("’"))]
+    #[case(b"fi", Some("fi"))]
+    #[case(b"f_f_i", Some("ffi"))]
+    #[case(b"a.sc", Some("a"))]
+    #[case(b"uni00E9", Some("é"))]
+    #[case(b"uniD835DC9C", Some("𝒜"))]
+    #[case(b"u1D49C", Some("𝒜"))]
+    #[case(b"g123", None)]
+    fn glyph_names(#[case] name: &[u8], #[case] expected: Option<&str>) {
+        assert_eq!(glyph_name(name).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case(b"/WinAnsiEncoding", 0x92, Some("’"))]
+    #[case(b"<</Differences[39/quotesingle 96/grave/a]>>", 0x27, Some("'"))]
+    #[case(b"<</Differences[39/quotesingle 96/grave/a]>>", 0x61, Some("a"))]
+    #[case(b"<</Differences[39/quotesingle 96/grave/a]>>", 0x60, Some("`"))]
+    #[case(
+        b"<</BaseEncoding/WinAnsiEncoding/Differences[128/bullet]>>",
+        0x80,
+        Some("•")
+    )]
+    fn encoding(#[case] input: &[u8], #[case] code: u8, #[case] expected: Option<&str>) {
+        let encoding: Encoding = ().build(&mut input.as_ref()).unwrap();
+        assert_eq!(encoding.decode(code).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case(b"/WinAnsiEncoding", 0x92, Some("quoteright"))]
+    #[case(b"/MacRomanEncoding", 0x8E, Some("eacute"))]
+    #[case(b"/StandardEncoding", 0xCF, Some("caron"))]
+    #[case(b"/StandardEncoding", 0x7F, None)]
+    #[case(b"<</Differences[65/g12]>>", 0x41, Some("g12"))]
+    fn glyph(#[case] input: &[u8], #[case] code: u8, #[case] expected: Option<&str>) {
+        let encoding: Encoding = ().build(&mut input.as_ref()).unwrap();
+        assert_eq!(encoding.glyph(code), expected.map(Name::from));
+    }
+}
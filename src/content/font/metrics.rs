@@ -0,0 +1,104 @@
+//! Approximate glyph widths for the standard fonts, which PDF writers may use without a
+//! `Widths` array.
+//!
+//! From the specification:
+//!
+//! > The PostScript names of 14 fonts, known as the standard 14 fonts, are as follows:
+//! > Times-Roman, Helvetica, Courier, Symbol, Times-Bold, Helvetica-Bold, Courier-Bold,
+//! > ZapfDingbats, Times-Italic, Helvetica-Oblique, Courier-Oblique, Times-BoldItalic,
+//! > Helvetica-BoldOblique, Courier-BoldOblique
+//!
+//! Livre only needs widths to tell glyphs apart from the gaps between words, so it ships the
+//! printable ASCII widths of the regular Helvetica and Times faces, and uses them for their
+//! bold and italic variants as well. Courier is monospaced.
+
+/// Widths of Helvetica, from code `0x20` to `0x7E`, in thousandths of a text space unit.
+#[rustfmt::skip]
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 222, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    222, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Widths of Times-Roman, from code `0x20` to `0x7E`, in thousandths of a text space unit.
+#[rustfmt::skip]
+const TIMES: [u16; 95] = [
+    250, 333, 408, 500, 500, 833, 778, 333, 333, 333, 500, 564, 250, 333, 250, 278,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 278, 278, 564, 564, 564, 444,
+    921, 722, 667, 667, 722, 611, 556, 722, 722, 333, 389, 722, 611, 889, 722, 722,
+    556, 722, 667, 556, 611, 722, 722, 944, 722, 722, 611, 333, 278, 333, 469, 500,
+    333, 444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500, 278, 778, 500, 500,
+    500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444, 480, 200, 480, 541,
+];
+
+/// Families of standard fonts, which share their metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Family {
+    Helvetica,
+    Times,
+    Courier,
+}
+
+impl Family {
+    /// Guess the family from the `BaseFont` name, falling back to Helvetica. Common
+    /// substitutes, such as Arial for Helvetica, are recognized as well.
+    pub(super) fn from_base_font(name: &[u8]) -> Self {
+        let name = String::from_utf8_lossy(name).to_lowercase();
+
+        if name.contains("courier") || name.contains("mono") {
+            Self::Courier
+        } else if name.contains("times") || (name.contains("serif") && !name.contains("sans")) {
+            Self::Times
+        } else {
+            Self::Helvetica
+        }
+    }
+
+    /// Width of a glyph, in thousandths of a text space unit.
+    pub(super) fn width(self, code: u8) -> f32 {
+        let table = match self {
+            Self::Helvetica => &HELVETICA,
+            Self::Times => &TIMES,
+            Self::Courier => return 600.0,
+        };
+
+        let width = match code {
+            0x20..=0x7E => table[code as usize - 0x20],
+            // Typical width of a lowercase letter.
+            _ => table[b'n' as usize - 0x20],
+        };
+
+        width as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(b"Helvetica-Bold", Family::Helvetica)]
+    #[case(b"ArialMT", Family::Helvetica)]
+    #[case(b"Times-Roman", Family::Times)]
+    #[case(b"ABCDEF+LiberationSerif", Family::Times)]
+    #[case(b"DejaVuSans", Family::Helvetica)]
+    #[case(b"Courier-Oblique", Family::Courier)]
+    fn family(#[case] name: &[u8], #[case] expected: Family) {
+        assert_eq!(Family::from_base_font(name), expected);
+    }
+
+    #[rstest]
+    #[case(Family::Helvetica, b' ', 278.0)]
+    #[case(Family::Helvetica, b'W', 944.0)]
+    #[case(Family::Helvetica, b'~', 584.0)]
+    #[case(Family::Times, b'm', 778.0)]
+    #[case(Family::Courier, b'i', 600.0)]
+    fn width(#[case] family: Family, #[case] code: u8, #[case] expected: f32) {
+        assert_eq!(family.width(code), expected);
+    }
+}
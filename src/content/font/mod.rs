@@ -0,0 +1,529 @@
+//! Fonts, as far as text extraction is concerned.
+//!
+//! A text-showing operator paints a string of bytes, which only make sense in the light of the
+//! current font. The font defines:
+//!
+//! - how to split the string into character codes: simple fonts use single-byte codes,
+//!   while composite (`Type0`) fonts usually use two-byte codes;
+//! - how wide each glyph is, which governs the position of the next glyph;
+//! - which Unicode text each code stands for, through a `ToUnicode` CMap or an encoding.
+//!
+//! Font programs are only read by the optional `render` feature, to draw glyph outlines.
+//! Composite fonts are assumed to use two-byte codes (e.g. `Identity-H`), and vertical
+//! writing is not supported.
+
+mod cmap;
+mod encoding;
+mod metrics;
+
+use std::collections::HashMap;
+
+use winnow::{BStr, ModalResult};
+
+pub use cmap::ToUnicode;
+pub use encoding::{glyph_name, BaseEncoding, Encoding};
+use metrics::Family;
+
+use crate::{
+    extraction::{extract, Name, Object, RawDict, Reference, ReferenceId, Stream},
+    follow_refs::{Build, BuildFromRawDict, Builder},
+};
+
+#[derive(Debug, BuildFromRawDict)]
+struct FontDescriptor {
+    flags: Option<u32>,
+    ascent: Option<f32>,
+    descent: Option<f32>,
+    missing_width: Option<f32>,
+    #[livre(flatten)]
+    font_file: FontFile,
+}
+
+/// The embedded font program, kept as a reference: it is only read to render glyphs.
+#[derive(Debug, Default)]
+struct FontFile(Option<ReferenceId>);
+
+impl BuildFromRawDict for FontFile {
+    fn build_from_raw_dict<B>(dict: &mut RawDict<'_>, _: &B) -> ModalResult<Self>
+    where
+        B: Builder,
+    {
+        // Type 1 programs (`FontFile`) are not supported.
+        let reference = ["FontFile2", "FontFile3"].into_iter().find_map(|key| {
+            dict.pop(&key.into())
+                .and_then(|value| value.extract::<Reference<()>>().ok())
+        });
+
+        Ok(Self(reference.map(|reference| reference.id)))
+    }
+}
+
+/// The `CIDToGIDMap` stream of a composite TrueType font, absent for the `Identity` mapping.
+#[derive(Debug, Default)]
+struct CIDToGIDMap(Option<ReferenceId>);
+
+impl BuildFromRawDict for CIDToGIDMap {
+    fn build_from_raw_dict<B>(dict: &mut RawDict<'_>, _: &B) -> ModalResult<Self>
+    where
+        B: Builder,
+    {
+        let reference = dict
+            .pop(&"CIDToGIDMap".into())
+            .and_then(|value| value.extract::<Reference<()>>().ok());
+
+        Ok(Self(reference.map(|reference| reference.id)))
+    }
+}
+
+/// Descendant of a composite font.
+#[derive(Debug, BuildFromRawDict)]
+struct CIDFont {
+    w: Option<Vec<Object>>,
+    #[livre(rename = "DW")]
+    dw: Option<f32>,
+    font_descriptor: Option<FontDescriptor>,
+    #[livre(flatten)]
+    cid_to_gid_map: CIDToGIDMap,
+}
+
+#[derive(Debug, BuildFromRawDict)]
+struct FontDict {
+    subtype: Option<Name>,
+    base_font: Option<Name>,
+    first_char: Option<u32>,
+    widths: Option<Vec<f32>>,
+    font_descriptor: Option<FontDescriptor>,
+    font_matrix: Option<[f32; 6]>,
+    descendant_fonts: Option<Vec<CIDFont>>,
+}
+
+fn number(object: &Object) -> Option<f32> {
+    match *object {
+        Object::Integer(i) => Some(i as f32),
+        Object::Real(r) => Some(r),
+        _ => None,
+    }
+}
+
+/// Widths of a composite font, read from the `W` array:
+///
+/// ```raw
+/// /W [120 [400 325 500] 7080 8032 1000]
+/// ```
+///
+/// Ranges are kept as such, like in [`ToUnicode`].
+#[derive(Debug, Clone, PartialEq, Default)]
+struct CIDWidths {
+    individual: HashMap<u32, f32>,
+    ranges: Vec<(u32, u32, f32)>,
+}
+
+impl CIDWidths {
+    fn new(w: &[Object]) -> Self {
+        let mut widths = Self::default();
+        let mut items = w.iter();
+
+        while let Some(&Object::Integer(first)) = items.next() {
+            let Ok(first) = u32::try_from(first) else {
+                break;
+            };
+
+            match items.next() {
+                Some(Object::Array(array)) => {
+                    let iter = (first..).zip(array.iter().filter_map(number));
+                    widths.individual.extend(iter);
+                }
+                Some(&Object::Integer(last)) => {
+                    let (Ok(last), Some(width)) =
+                        (u32::try_from(last), items.next().and_then(number))
+                    else {
+                        break;
+                    };
+                    widths.ranges.push((first, last, width));
+                }
+                _ => break,
+            }
+        }
+
+        widths
+    }
+
+    fn get(&self, code: u32) -> Option<f32> {
+        self.individual.get(&code).copied().or_else(|| {
+            self.ranges
+                .iter()
+                .find_map(|&(first, last, width)| (first..=last).contains(&code).then_some(width))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Widths {
+    /// Simple fonts define the widths of consecutive codes, starting from `FirstChar`.
+    Simple {
+        first_char: u32,
+        widths: Vec<f32>,
+    },
+    Composite(CIDWidths),
+    /// Standard fonts may not define widths at all.
+    Standard(Family),
+}
+
+/// A font resource.
+///
+/// Glyph metrics are expressed in thousandths of a text space unit, like in the font
+/// dictionary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Font {
+    base_font: Option<Name>,
+    composite: bool,
+    widths: Widths,
+    /// Width of the glyphs that are not covered by [`widths`](Self::widths).
+    default_width: f32,
+    ascent: f32,
+    descent: f32,
+    /// Symbolic fonts use glyphs outside of the standard Latin set, see
+    /// [`is_symbolic`](Self::is_symbolic).
+    symbolic: bool,
+    encoding: Option<Encoding>,
+    to_unicode: Option<ToUnicode>,
+    font_file: Option<ReferenceId>,
+    cid_to_gid_map: Option<ReferenceId>,
+}
+
+/// The font used when a font resource is missing or cannot be read: a simple font with
+/// Helvetica's metrics.
+impl Default for Font {
+    fn default() -> Self {
+        Self {
+            base_font: None,
+            composite: false,
+            widths: Widths::Standard(Family::Helvetica),
+            default_width: 0.0,
+            ascent: 800.0,
+            descent: -200.0,
+            symbolic: false,
+            encoding: None,
+            to_unicode: None,
+            font_file: None,
+            cid_to_gid_map: None,
+        }
+    }
+}
+
+impl Font {
+    /// The PostScript name of the font, from the `BaseFont` entry. Embedded subsets are
+    /// prefixed with a six-letter tag, e.g. `ABCDEF+Helvetica`.
+    pub fn base_font(&self) -> Option<&Name> {
+        self.base_font.as_ref()
+    }
+
+    /// Whether the font is a composite font, which uses two-byte character codes.
+    pub fn is_composite(&self) -> bool {
+        self.composite
+    }
+
+    /// Split a string into character codes. An odd trailing byte is dropped from a composite
+    /// font's string.
+    pub fn codes<'a>(&self, bytes: &'a [u8]) -> impl Iterator<Item = u32> + 'a {
+        let size = if self.composite { 2 } else { 1 };
+        bytes
+            .chunks_exact(size)
+            .map(|code| code.iter().fold(0, |res, &byte| (res << 8) | byte as u32))
+    }
+
+    /// The horizontal displacement of a glyph, in thousandths of a text space unit.
+    pub fn width(&self, code: u32) -> f32 {
+        let width = match &self.widths {
+            Widths::Simple { first_char, widths } => code
+                .checked_sub(*first_char)
+                .and_then(|index| widths.get(index as usize).copied()),
+            Widths::Composite(widths) => widths.get(code),
+            Widths::Standard(family) => u8::try_from(code).ok().map(|code| family.width(code)),
+        };
+
+        width.unwrap_or(self.default_width)
+    }
+
+    /// Distance from the baseline to the top of the glyphs, in thousandths of a text space
+    /// unit.
+    pub fn ascent(&self) -> f32 {
+        self.ascent
+    }
+
+    /// Distance from the baseline to the bottom of the glyphs, in thousandths of a text space
+    /// unit. This is a negative number.
+    pub fn descent(&self) -> f32 {
+        self.descent
+    }
+
+    /// The Unicode text a character code stands for, if it can be determined. The `ToUnicode`
+    /// CMap takes precedence over the encoding.
+    pub fn text(&self, code: u32) -> Option<String> {
+        if let Some(text) = self.to_unicode.as_ref().and_then(|cmap| cmap.get(code)) {
+            return Some(text);
+        }
+
+        if self.composite {
+            // Character identifiers say nothing about the actual character.
+            return None;
+        }
+
+        let code = u8::try_from(code).ok()?;
+        match &self.encoding {
+            Some(encoding) => encoding.decode(code),
+            None => Encoding::default().decode(code),
+        }
+    }
+
+    /// The encoding of a simple font, if the font dictionary defines one. Otherwise, glyphs
+    /// are selected by the font program's built-in encoding, while text is decoded with the
+    /// standard encoding.
+    pub fn encoding(&self) -> Option<&Encoding> {
+        self.encoding.as_ref()
+    }
+
+    /// Whether the font uses glyphs outside of the standard Latin set, according to the flags
+    /// of its descriptor. The codes of a symbolic font select glyphs directly, regardless of
+    /// their name.
+    pub fn is_symbolic(&self) -> bool {
+        self.symbolic
+    }
+
+    /// The stream of the embedded font program, if any: `FontFile2` for TrueType fonts, or
+    /// `FontFile3` for CFF and OpenType fonts. Type 1 programs are left out.
+    pub fn font_file(&self) -> Option<ReferenceId> {
+        self.font_file
+    }
+
+    /// The `CIDToGIDMap` stream of a composite TrueType font, which maps CIDs to glyph
+    /// indices, two bytes each. `None` stands for the identity mapping.
+    pub fn cid_to_gid_map(&self) -> Option<ReferenceId> {
+        self.cid_to_gid_map
+    }
+}
+
+impl Build for Font {
+    fn build<B>(input: &mut &BStr, builder: &B) -> ModalResult<Self>
+    where
+        B: Builder,
+    {
+        let mut dict: RawDict = extract(input)?;
+
+        // A font is still usable without these, hence they are read leniently.
+        let to_unicode = dict
+            .pop_and_build::<Option<Stream<()>>, _>(&"ToUnicode".into(), builder)
+            .ok()
+            .flatten()
+            .flatten()
+            .map(|stream| ToUnicode::parse(&stream.content));
+        let encoding = dict
+            .pop_and_build::<Option<Encoding>, _>(&"Encoding".into(), builder)
+            .ok()
+            .flatten()
+            .flatten();
+
+        let FontDict {
+            subtype,
+            base_font,
+            first_char,
+            widths,
+            font_descriptor,
+            font_matrix,
+            descendant_fonts,
+        } = FontDict::build_from_raw_dict(&mut dict, builder)?;
+
+        let composite = subtype.as_ref().is_some_and(|Name(s)| s == b"Type0");
+        let family = Family::from_base_font(base_font.as_ref().map_or(&[], |Name(name)| name));
+
+        let mut cid_to_gid_map = None;
+
+        let (widths, default_width, descriptor) = if composite {
+            let descendant = descendant_fonts.and_then(|fonts| fonts.into_iter().next());
+            let (w, dw, descriptor) = match descendant {
+                Some(CIDFont {
+                    w,
+                    dw,
+                    font_descriptor,
+                    cid_to_gid_map: CIDToGIDMap(map),
+                }) => {
+                    cid_to_gid_map = map;
+                    (w, dw, font_descriptor)
+                }
+                None => (None, None, None),
+            };
+
+            let widths = Widths::Composite(CIDWidths::new(&w.unwrap_or_default()));
+            (widths, dw.unwrap_or(1000.0), descriptor)
+        } else {
+            let missing_width = font_descriptor
+                .as_ref()
+                .and_then(|descriptor| descriptor.missing_width)
+                .unwrap_or(0.0);
+
+            let widths = match widths {
+                Some(widths) => {
+                    // Type 3 glyph widths are expressed in glyph space, which the `FontMatrix`
+                    // maps to text space.
+                    let scale = font_matrix.map_or(1.0, |matrix| matrix[0] * 1000.0);
+                    Widths::Simple {
+                        first_char: first_char.unwrap_or(0),
+                        widths: widths.into_iter().map(|w| w * scale).collect(),
+                    }
+                }
+                None => Widths::Standard(family),
+            };
+
+            (widths, missing_width, font_descriptor)
+        };
+
+        let default = Self::default();
+        let ascent = descriptor
+            .as_ref()
+            .and_then(|descriptor| descriptor.ascent)
+            .filter(|&ascent| ascent > 0.0)
+            .unwrap_or(default.ascent);
+        let descent = descriptor
+            .as_ref()
+            .and_then(|descriptor| descriptor.descent)
+            .filter(|&descent| descent < 0.0)
+            .unwrap_or(default.descent);
+
+        // Bit 3 of the flags.
+        let symbolic = descriptor
+            .as_ref()
+            .and_then(|descriptor| descriptor.flags)
+            .is_some_and(|flags| flags & 0b100 != 0);
+        let font_file = descriptor.and_then(|descriptor| descriptor.font_file.0);
+
+        Ok(Self {
+            base_font,
+            composite,
+            widths,
+            default_width,
+            ascent,
+            descent,
+            symbolic,
+            encoding,
+            to_unicode,
+            font_file,
+            cid_to_gid_map,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::extraction::ReferenceId;
+
+    use super::*;
+
+    #[rstest]
+    #[case(b"<</Type/Font/Subtype/Type1/BaseFont/Helvetica>>", b'W' as u32, 944.0)]
+    #[case(b"<</Type/Font/Subtype/Type1/BaseFont/Times-Bold>>", b'm' as u32, 778.0)]
+    #[case(b"<</Subtype/TrueType/FirstChar 32/Widths[250 333]>>", 33, 333.0)]
+    #[case(b"<</Subtype/TrueType/FirstChar 32/Widths[250 333]>>", 34, 0.0)]
+    #[case(
+        b"<</Subtype/TrueType/FirstChar 32/Widths[250]/FontDescriptor<</MissingWidth 500>>>>",
+        34,
+        500.0
+    )]
+    #[case(
+        b"<</Subtype/Type3/FirstChar 0/Widths[1]/FontMatrix[0.01 0 0 0.01 0 0]>>",
+        0,
+        10.0
+    )]
+    #[case(
+        b"<</Subtype/Type0/DescendantFonts[<</W[3[250 300]10 20 400]>>]>>",
+        4,
+        300.0
+    )]
+    #[case(
+        b"<</Subtype/Type0/DescendantFonts[<</W[3[250 300]10 20 400]>>]>>",
+        15,
+        400.0
+    )]
+    #[case(
+        b"<</Subtype/Type0/DescendantFonts[<</W[3[250 300]10 20 400]/DW 900>>]>>",
+        21,
+        900.0
+    )]
+    #[case(b"<</Subtype/Type0/DescendantFonts[<<>>]>>", 21, 1000.0)]
+    fn width(#[case] input: &[u8], #[case] code: u32, #[case] expected: f32) {
+        let font: Font = ().build(&mut input.as_ref()).unwrap();
+        assert_eq!(font.width(code), expected);
+    }
+
+    #[rstest]
+    #[case(b"<</Subtype/Type1>>", b"AB", vec![0x41, 0x42])]
+    #[case(b"<</Subtype/Type0>>", b"\x00\x41\x01\x42", vec![0x41, 0x0142])]
+    #[case(b"<</Subtype/Type0>>", b"\x00\x41\x01", vec![0x41])]
+    fn codes(#[case] input: &[u8], #[case] bytes: &[u8], #[case] expected: Vec<u32>) {
+        let font: Font = ().build(&mut input.as_ref()).unwrap();
+        assert_eq!(font.codes(bytes).collect::<Vec<_>>(), expected);
+    }
+
+    #[rstest]
+    #[case(b"<</Subtype/Type1>>", 0x27, Some("’"))]
+    #[case(b"<</Subtype/TrueType/Encoding/WinAnsiEncoding>>", 0x27, Some("'"))]
+    #[case(b"<</Subtype/Type0/Encoding/Identity-H>>", 0x41, None)]
+    fn text(#[case] input: &[u8], #[case] code: u32, #[case] expected: Option<&str>) {
+        let font: Font = ().build(&mut input.as_ref()).unwrap();
+        assert_eq!(font.text(code).as_deref(), expected);
+    }
+
+    #[test]
+    fn indirect_to_unicode() {
+        let cmap = b"3 0 obj\n<</Length 35>>\nstream\nbeginbfchar <0001> <0041> endbfchar\nendstream\nendobj";
+        let builder: HashMap<ReferenceId, &BStr> =
+            HashMap::from([(ReferenceId::first(3), BStr::new(cmap))]);
+
+        let input = b"<</Subtype/Type0/Encoding/Identity-H/ToUnicode 3 0 R>>";
+        let font: Font = builder.build(&mut BStr::new(input)).unwrap();
+        assert_eq!(font.text(1).as_deref(), Some("A"));
+    }
+
+    #[rstest]
+    #[case(b"<</Subtype/Type1/BaseFont/Helvetica>>", None, false, None)]
+    #[case(
+        b"<</Subtype/TrueType/FontDescriptor<</Flags 4/FontFile2 5 0 R>>>>",
+        Some(5),
+        true,
+        None
+    )]
+    #[case(
+        b"<</Subtype/Type1/FontDescriptor<</Flags 32/FontFile 5 0 R>>>>",
+        None,
+        false,
+        None
+    )]
+    #[case(
+        b"<</Subtype/Type0/DescendantFonts[<</CIDToGIDMap 7 0 R/FontDescriptor<</FontFile2 6 0 R>>>>]>>",
+        Some(6),
+        false,
+        Some(7)
+    )]
+    #[case(
+        b"<</Subtype/Type0/DescendantFonts[<</CIDToGIDMap/Identity>>]>>",
+        None,
+        false,
+        None
+    )]
+    fn program(
+        #[case] input: &[u8],
+        #[case] font_file: Option<usize>,
+        #[case] symbolic: bool,
+        #[case] cid_to_gid_map: Option<usize>,
+    ) {
+        let font: Font = ().build(&mut input.as_ref()).unwrap();
+
+        assert_eq!(font.font_file(), font_file.map(ReferenceId::first));
+        assert_eq!(font.is_symbolic(), symbolic);
+        assert_eq!(
+            font.cid_to_gid_map(),
+            cid_to_gid_map.map(ReferenceId::first)
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use crate::{
+    extraction::{Name, Object},
+    follow_refs::Builder,
+};
+
+use super::{number, resolve};
+
+/// A colour space, as far as converting colours to RGB is concerned.
+///
+/// CIE-based spaces are approximated by their device counterparts, and so are ICC-based
+/// spaces, according to their number of components. Livre does not evaluate tint transforms:
+/// `Separation` and `DeviceN` colours are painted as shades of grey, full tint being black.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum ColorSpace {
+    #[default]
+    DeviceGray,
+    DeviceRGB,
+    DeviceCMYK,
+    /// CIE L\*a\*b\* colours, with a D50 white point.
+    Lab,
+    /// Colours are indices into a table of colours in the `base` space, each stored as one
+    /// byte per component.
+    Indexed {
+        base: Box<ColorSpace>,
+        lookup: Vec<u8>,
+    },
+    /// `Separation` and `DeviceN` spaces, which hold one tint per colorant.
+    Separation {
+        components: usize,
+    },
+    /// Areas are painted with a pattern, which Livre does not support. They are painted grey.
+    Pattern,
+}
+
+impl ColorSpace {
+    /// The colour space for a name from the `CS` and `cs` operators, or from an image
+    /// dictionary. Names that are not family names stand for an entry of the `ColorSpace`
+    /// resource dictionary, and yield `None`.
+    pub(super) fn from_name(name: &Name) -> Option<Self> {
+        let space = match name.0.as_slice() {
+            b"DeviceGray" | b"G" | b"CalGray" => Self::DeviceGray,
+            b"DeviceRGB" | b"RGB" | b"CalRGB" => Self::DeviceRGB,
+            b"DeviceCMYK" | b"CMYK" => Self::DeviceCMYK,
+            b"Lab" => Self::Lab,
+            b"Pattern" => Self::Pattern,
+            _ => return None,
+        };
+
+        Some(space)
+    }
+
+    /// Read a colour space from its definition: a family name, or an array that starts with
+    /// the family name followed by its parameters. Unknown spaces fall back to `DeviceRGB`.
+    pub(super) fn from_object<B>(object: &Object, builder: &B) -> Self
+    where
+        B: Builder,
+    {
+        let Some(object) = resolve(object, builder) else {
+            return Self::default();
+        };
+
+        let array = match &object {
+            Object::Name(name) => return Self::from_name(name).unwrap_or(Self::DeviceRGB),
+            Object::Array(array) => array,
+            _ => return Self::DeviceRGB,
+        };
+
+        let Some(Object::Name(family)) = array.first() else {
+            return Self::DeviceRGB;
+        };
+
+        match family.0.as_slice() {
+            b"ICCBased" => {
+                let stream = array.get(1).and_then(|stream| resolve(stream, builder));
+                let Some(Object::Stream(stream)) = stream else {
+                    return Self::DeviceRGB;
+                };
+
+                match stream.structured.get(&"N".into()) {
+                    Some(&Object::Integer(1)) => Self::DeviceGray,
+                    Some(&Object::Integer(4)) => Self::DeviceCMYK,
+                    _ => match stream.structured.get(&"Alternate".into()) {
+                        Some(alternate) => Self::from_object(alternate, builder),
+                        None => Self::DeviceRGB,
+                    },
+                }
+            }
+            b"Indexed" | b"I" => {
+                let base = array
+                    .get(1)
+                    .map(|base| Self::from_object(base, builder))
+                    .unwrap_or_default();
+                let lookup = match array.get(3).and_then(|lookup| resolve(lookup, builder)) {
+                    Some(Object::String(bytes)) => bytes,
+                    Some(Object::Stream(stream)) => stream.content,
+                    _ => Vec::new(),
+                };
+
+                Self::Indexed {
+                    base: Box::new(base),
+                    lookup,
+                }
+            }
+            b"Separation" => Self::Separation { components: 1 },
+            b"DeviceN" => {
+                let components = match array.get(1).and_then(|names| resolve(names, builder)) {
+                    Some(Object::Array(names)) => names.len(),
+                    _ => 1,
+                };
+                Self::Separation { components }
+            }
+            b"Pattern" => Self::Pattern,
+            _ => Self::from_name(family).unwrap_or(Self::DeviceRGB),
+        }
+    }
+
+    /// The number of components of a colour in this space.
+    pub fn components(&self) -> usize {
+        match self {
+            Self::DeviceGray | Self::Indexed { .. } => 1,
+            Self::DeviceRGB | Self::Lab => 3,
+            Self::DeviceCMYK => 4,
+            Self::Separation { components } => *components,
+            Self::Pattern => 0,
+        }
+    }
+
+    /// The colour that the `CS` and `cs` operators select along with the space.
+    pub fn initial_color(&self) -> Vec<f32> {
+        match self {
+            Self::DeviceCMYK => vec![0.0, 0.0, 0.0, 1.0],
+            Self::Separation { components } => vec![1.0; *components],
+            space => vec![0.0; space.components()],
+        }
+    }
+
+    /// Convert a colour of this space to RGB, each component ranging from 0 to 1. Missing
+    /// components are taken as 0.
+    pub fn to_rgb(&self, components: &[f32]) -> [f32; 3] {
+        let get = |i: usize| components.get(i).copied().unwrap_or(0.0).clamp(0.0, 1.0);
+
+        match self {
+            Self::DeviceGray => [get(0); 3],
+            Self::DeviceRGB => [get(0), get(1), get(2)],
+            Self::DeviceCMYK => {
+                let k = 1.0 - get(3);
+                [(1.0 - get(0)) * k, (1.0 - get(1)) * k, (1.0 - get(2)) * k]
+            }
+            Self::Lab => lab_to_rgb(
+                components.first().copied().unwrap_or(0.0),
+                components.get(1).copied().unwrap_or(0.0),
+                components.get(2).copied().unwrap_or(0.0),
+            ),
+            Self::Indexed { base, lookup } => {
+                let n = base.components();
+                let index = components.first().copied().unwrap_or(0.0).max(0.0) as usize;
+                let entry: Vec<f32> = lookup
+                    .get(index * n..(index + 1) * n)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|&byte| byte as f32 / 255.0)
+                    .collect();
+                base.to_rgb(&entry)
+            }
+            Self::Separation { components: n } => {
+                let tint = (0..*n).map(get).fold(0.0, f32::max);
+                [1.0 - tint; 3]
+            }
+            Self::Pattern => [0.5; 3],
+        }
+    }
+}
+
+/// Convert a CIE L\*a\*b\* colour, relative to the D50 white point, to sRGB.
+fn lab_to_rgb(l: f32, a: f32, b: f32) -> [f32; 3] {
+    const DELTA: f32 = 6.0 / 29.0;
+
+    let inverse = |t: f32| {
+        if t > DELTA {
+            t.powi(3)
+        } else {
+            3.0 * DELTA * DELTA * (t - 4.0 / 29.0)
+        }
+    };
+
+    let fy = (l + 16.0) / 116.0;
+    let x = 0.9642 * inverse(fy + a / 500.0);
+    let y = inverse(fy);
+    let z = 0.8249 * inverse(fy - b / 200.0);
+
+    // XYZ (D50) to linear sRGB, with Bradford adaptation to D65.
+    let linear = [
+        3.1339 * x - 1.6169 * y - 0.4906 * z,
+        -0.9788 * x + 1.9161 * y + 0.0335 * z,
+        0.0719 * x - 0.2290 * y + 1.4052 * z,
+    ];
+
+    linear.map(|c| {
+        let c = c.clamp(0.0, 1.0);
+        if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    })
+}
+
+/// A colour, along with the space its components belong to.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Color {
+    pub space: ColorSpace,
+    pub components: Vec<f32>,
+}
+
+impl Color {
+    /// The initial colour of a space (see [`ColorSpace::initial_color`]).
+    pub fn new(space: ColorSpace) -> Self {
+        Self {
+            components: space.initial_color(),
+            space,
+        }
+    }
+
+    /// A colour from the numeric operands of a colour operator. Operands that are not
+    /// numbers, such as the pattern name of `scn`, are skipped.
+    pub(super) fn with_operands(space: ColorSpace, operands: &[Object]) -> Self {
+        Self {
+            space,
+            components: operands.iter().filter_map(number).collect(),
+        }
+    }
+
+    /// The colour in RGB, each component ranging from 0 to 1.
+    pub fn to_rgb(&self) -> [f32; 3] {
+        self.space.to_rgb(&self.components)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::extraction::extract;
+
+    use super::*;
+
+    fn assert_rgb(actual: [f32; 3], expected: [f32; 3]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-2, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[rstest]
+    #[case(b"/DeviceGray", &[0.5], [0.5, 0.5, 0.5])]
+    #[case(b"/DeviceRGB", &[1.0, 0.0, 0.5], [1.0, 0.0, 0.5])]
+    #[case(b"/DeviceCMYK", &[0.0, 1.0, 1.0, 0.0], [1.0, 0.0, 0.0])]
+    #[case(b"/DeviceCMYK", &[0.0, 0.0, 0.0, 1.0], [0.0, 0.0, 0.0])]
+    #[case(b"[/CalRGB <</WhitePoint [0.9505 1 1.089]>>]", &[0.0, 1.0, 0.0], [0.0, 1.0, 0.0])]
+    #[case(b"[/Lab <</WhitePoint [0.9642 1 0.8249]>>]", &[100.0, 0.0, 0.0], [1.0, 1.0, 1.0])]
+    #[case(b"[/Indexed /DeviceRGB 1 <000000FF8000>]", &[1.0], [1.0, 0.5, 0.0])]
+    #[case(b"[/Separation /Spot /DeviceCMYK 5 0 R]", &[0.25], [0.75, 0.75, 0.75])]
+    #[case(b"[/DeviceN [/Cyan /Spot] /DeviceCMYK 5 0 R]", &[0.0, 1.0], [0.0, 0.0, 0.0])]
+    fn to_rgb(#[case] input: &[u8], #[case] components: &[f32], #[case] expected: [f32; 3]) {
+        let object: Object = extract(&mut input.as_ref()).unwrap();
+        let space = ColorSpace::from_object(&object, &());
+
+        assert_eq!(space.initial_color().len(), space.components());
+        assert_rgb(space.to_rgb(components), expected);
+    }
+
+    #[test]
+    fn operands() {
+        let operands = [Object::Real(0.5), Object::Name("P0".into())];
+        let color = Color::with_operands(ColorSpace::DeviceGray, &operands);
+
+        assert_eq!(color.components, [0.5]);
+    }
+}
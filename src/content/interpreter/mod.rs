@@ -0,0 +1,917 @@
+//! A content-stream interpreter, which runs the operators of a page with the complete graphics
+//! state and reports what they paint to a [`Device`].
+//!
+//! The [`ContentInterpreter`] keeps track of the graphics state and its `q`/`Q` stack, builds
+//! paths, positions glyphs, and looks up the page resources: fonts, colour spaces, graphics
+//! state parameter dictionaries, property lists and external objects. It descends into form
+//! XObjects, with their own matrix, bounding box and resources.
+//!
+//! A device only implements the callbacks it cares about, every one of them doing nothing by
+//! default. Text extraction is itself a device: see [`Painted`](super::layout::Painted).
+//!
+//! ```no_run
+//! use livre::{
+//!     content::interpreter::{Device, GraphicsState, Path},
+//!     InMemoryDocument,
+//! };
+//!
+//! /// Count the painted paths.
+//! #[derive(Default)]
+//! struct Counter(usize);
+//!
+//! impl Device for Counter {
+//!     fn stroke_path(&mut self, _: &Path, _: &GraphicsState) {
+//!         self.0 += 1;
+//!     }
+//! }
+//!
+//! let input = std::fs::read("document.pdf").unwrap();
+//! let doc = InMemoryDocument::from_bytes(&input).unwrap();
+//! let page = &doc.pages().unwrap()[0];
+//!
+//! let mut counter = Counter::default();
+//! page.interpret(&doc, Default::default(), &mut counter).unwrap();
+//! ```
+//!
+//! Livre does not support shadings, inline images and Type 3 glyph procedures yet: `sh` is
+//! ignored, and Type 3 glyphs are reported like any other glyph.
+
+mod color;
+mod path;
+mod state;
+mod xobject;
+
+use std::{collections::HashMap, rc::Rc};
+
+use winnow::ModalResult;
+
+pub use color::{Color, ColorSpace};
+pub use path::{FillRule, Path, PathElement};
+pub use state::{Dash, GraphicsState, LineCap, LineJoin, TextState};
+pub use xobject::Image;
+
+use xobject::XObject;
+
+use crate::{
+    content::{
+        font::Font,
+        operators::{
+            graphics::{GraphicsStateOperator, ModifyTransformationMatrix},
+            path::{
+                AppendRectangle, CurveTo, CurveToFinalReplicated, CurveToInitialReplicated, Fill,
+                LineTo, MoveTo, PathOperator, Stroke,
+            },
+            text::{
+                MoveToNextLine, MoveToNextLineAndShowText, MoveToNextLineAndShowTextWithSpacing,
+                PreTextOperation, SetFontAndFontSize, ShowText, ShowTextArray, TextArrayElement,
+                TextOperator, TextShowingOperator, TextStateOperator,
+            },
+            Operator, UnknownOperator,
+        },
+        state::Matrix,
+    },
+    error::{Error, ErrorKind},
+    extraction::{Map, Name, Object, Reference, ReferenceId},
+    follow_refs::Builder,
+    structure::{OperatorsIter, Resources},
+};
+
+/// A glyph painted by a text-showing operator.
+#[derive(Debug, Clone, Copy)]
+pub struct TextGlyph<'a> {
+    pub font: &'a Font,
+    /// The character code, which the font maps to a glyph and to Unicode text (see
+    /// [`Font::text`]).
+    pub code: u32,
+    /// The text rendering matrix, $T_{rm}$, which maps glyph space, scaled to a unit font size,
+    /// to device space: the glyph's origin lies at `(0, 0)` and the top of its em square at
+    /// `(0, 1)`.
+    pub matrix: Matrix,
+    /// The horizontal displacement of the glyph, $w_0$, in the same unit.
+    pub width: f32,
+}
+
+/// The receiver of what a [`ContentInterpreter`] paints.
+///
+/// Paths are expressed in user space, and the current transformation matrix of the supplied
+/// [`GraphicsState`] maps them to device space.
+#[allow(unused_variables, reason = "Every callback does nothing by default.")]
+pub trait Device {
+    /// The graphics state was saved, by `q` or before running a form XObject.
+    fn save_state(&mut self) {}
+
+    /// The graphics state was restored, by `Q` or after running a form XObject. Clipping paths
+    /// set since the matching [`save_state`](Self::save_state) no longer apply.
+    fn restore_state(&mut self) {}
+
+    fn fill_path(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {}
+
+    fn stroke_path(&mut self, path: &Path, state: &GraphicsState) {}
+
+    /// Fill, then stroke the same path (`B`, `B*`, `b` and `b*` operators).
+    fn fill_and_stroke_path(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {
+        self.fill_path(path, rule, state);
+        self.stroke_path(path, state);
+    }
+
+    /// Intersect the clipping path with `path`, until the state is restored.
+    fn clip(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {}
+
+    /// Paint a glyph. The text rendering mode of the state tells whether it is filled,
+    /// stroked or invisible.
+    fn draw_glyph(&mut self, glyph: &TextGlyph<'_>, state: &GraphicsState) {}
+
+    fn draw_image(&mut self, image: &Image, state: &GraphicsState) {}
+
+    /// Begin a marked-content sequence (`BMC` and `BDC` operators), with its property list if
+    /// any.
+    fn begin_marked_content(&mut self, tag: &Name, properties: Option<&Map<Object>>) {}
+
+    fn end_marked_content(&mut self) {}
+}
+
+/// The resources in scope: those of the page, or of the form XObject being run.
+struct Frame {
+    resources: Resources,
+    /// Fonts by resource name, loaded on first use.
+    fonts: HashMap<Name, Rc<Font>>,
+    color_spaces: HashMap<Name, ColorSpace>,
+    /// Size of the `q`/`Q` stack when the frame starts: the states saved before it cannot be
+    /// restored from within.
+    floor: usize,
+}
+
+impl Frame {
+    fn new(resources: Resources, floor: usize) -> Self {
+        Self {
+            resources,
+            fonts: HashMap::new(),
+            color_spaces: HashMap::new(),
+            floor,
+        }
+    }
+}
+
+/// Runs content-stream operators, and reports what they paint to a [`Device`] (see the
+/// [module documentation](self)).
+///
+/// Resources that cannot be read, such as a broken font or an image with an unsupported
+/// filter, are logged and skipped. Form XObjects that contain themselves, or that are nested
+/// deeper than [`Options::max_depth`](crate::follow_refs::Options), are errors. So is running
+/// more than [`Limits::max_operators`](crate::follow_refs::Limits) operators, counting those
+/// of the forms.
+pub struct ContentInterpreter<'b, B> {
+    builder: &'b B,
+    frames: Vec<Frame>,
+    state: GraphicsState,
+    stack: Vec<GraphicsState>,
+    /// The text matrix, $T_m$.
+    text_matrix: Matrix,
+    /// The text line matrix, $T_{lm}$, i.e. the value of $T_m$ at the start of the line.
+    line_matrix: Matrix,
+    path: Path,
+    /// The clipping rule set by `W` or `W*`, applied once the path is painted.
+    clip: Option<FillRule>,
+    /// Fonts by object, shared by the page and its forms. `None` marks an unreadable font.
+    fonts: HashMap<ReferenceId, Option<Rc<Font>>>,
+    /// The font used when a font resource is missing or cannot be read.
+    fallback: Rc<Font>,
+    /// The form XObjects being run, innermost last.
+    forms: Vec<ReferenceId>,
+    /// External objects by object, decoded once for the page and its forms. `None` marks
+    /// an unreadable object.
+    xobjects: HashMap<ReferenceId, Option<Rc<XObject>>>,
+    /// Number of operators run so far, by the page and the forms it draws, which may not
+    /// exceed [`Limits::max_operators`](crate::follow_refs::Limits).
+    operators: usize,
+}
+
+impl<'b, B> ContentInterpreter<'b, B>
+where
+    B: Builder,
+{
+    /// An interpreter for content that uses the supplied `resources`. The initial
+    /// transformation matrix `ctm` maps user space to device space, e.g. the
+    /// [`Viewport::matrix`](super::layout::Viewport::matrix) of a page.
+    pub fn new(builder: &'b B, resources: Resources, ctm: Matrix) -> Self {
+        Self {
+            builder,
+            frames: vec![Frame::new(resources, 0)],
+            state: GraphicsState::new(ctm),
+            stack: Vec::new(),
+            text_matrix: Matrix::default(),
+            line_matrix: Matrix::default(),
+            path: Path::default(),
+            clip: None,
+            fonts: HashMap::new(),
+            fallback: Rc::new(Font::default()),
+            forms: Vec::new(),
+            xobjects: HashMap::new(),
+            operators: 0,
+        }
+    }
+
+    /// Use these fonts, by resource name, instead of looking them up in the resources.
+    pub fn with_fonts(mut self, fonts: &HashMap<Name, Font>) -> Self {
+        let frame = &mut self.frames[0];
+        for (name, font) in fonts {
+            frame.fonts.insert(name.clone(), Rc::new(font.clone()));
+        }
+        self
+    }
+
+    /// The current graphics state.
+    pub fn state(&self) -> &GraphicsState {
+        &self.state
+    }
+
+    /// Run every operator.
+    pub fn run<I, D>(&mut self, operators: I, device: &mut D) -> ModalResult<()>
+    where
+        I: IntoIterator<Item = ModalResult<Operator>>,
+        D: Device,
+    {
+        let limit = self.builder.options().limits.max_operators;
+
+        for operator in operators {
+            let operator = operator?;
+
+            if self.operators == limit {
+                return Err(Error::new(ErrorKind::TooManyOperators(limit)).cut());
+            }
+            self.operators += 1;
+
+            self.apply(operator, device)?;
+        }
+        Ok(())
+    }
+
+    /// Run a single operator.
+    pub fn apply<D>(&mut self, operator: Operator, device: &mut D) -> ModalResult<()>
+    where
+        D: Device,
+    {
+        match operator {
+            Operator::GraphicsState(op) => match op {
+                GraphicsStateOperator::SaveGraphicsState(_) => self.save(device),
+                GraphicsStateOperator::RestoreGraphicsState(_) => {
+                    // Unbalanced operators are ignored, including those of a form that would
+                    // restore the states saved outside of it.
+                    if self.stack.len() > self.frame().floor {
+                        self.restore(device);
+                    }
+                }
+                GraphicsStateOperator::ModifyTransformationMatrix(ModifyTransformationMatrix(
+                    matrix,
+                )) => self.state.ctm = matrix.then(&self.state.ctm),
+            },
+            Operator::BeginText(_) => {
+                self.text_matrix = Matrix::default();
+                self.line_matrix = Matrix::default();
+            }
+            Operator::EndText(_) => {}
+            Operator::Text(TextOperator::TextStateOperator(
+                TextStateOperator::SetFontAndFontSize(SetFontAndFontSize(font, size)),
+            )) => {
+                self.state.text.font = Some(font);
+                self.state.text.font_size = size;
+            }
+            Operator::Text(TextOperator::TextStateOperator(op)) => {
+                op.preapply(&mut self.line_matrix, &mut self.state.text.parameters);
+            }
+            Operator::Text(TextOperator::TextPositioningOperator(op)) => {
+                op.preapply(&mut self.line_matrix, &mut self.state.text.parameters);
+                self.text_matrix = self.line_matrix;
+            }
+            Operator::Text(TextOperator::TextShowingOperator(op)) => self.show(op, device),
+            Operator::Path(op) => self.path(op, device),
+            // Inline images are not painted yet.
+            Operator::InlineImage(_) => {}
+            Operator::NotImplemented(UnknownOperator { keyword, operands }) => {
+                return self.apply_unknown(&keyword, &operands, device);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn frame(&self) -> &Frame {
+        self.frames.last().expect("the page frame is never popped")
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("the page frame is never popped")
+    }
+
+    fn save<D>(&mut self, device: &mut D)
+    where
+        D: Device,
+    {
+        self.stack.push(self.state.clone());
+        device.save_state();
+    }
+
+    fn restore<D>(&mut self, device: &mut D)
+    where
+        D: Device,
+    {
+        if let Some(state) = self.stack.pop() {
+            self.state = state;
+            device.restore_state();
+        }
+    }
+
+    fn path<D>(&mut self, op: PathOperator, device: &mut D)
+    where
+        D: Device,
+    {
+        let current = self.path.current_point();
+
+        match op {
+            PathOperator::MoveTo(MoveTo(x, y)) => self.path.move_to((x, y)),
+            PathOperator::LineTo(LineTo(x, y)) => self.path.line_to((x, y)),
+            PathOperator::CurveTo(CurveTo(x1, y1, x2, y2, x3, y3)) => {
+                self.path.curve_to((x1, y1), (x2, y2), (x3, y3))
+            }
+            PathOperator::CurveToInitialReplicated(CurveToInitialReplicated(x2, y2, x3, y3)) => {
+                if let Some(current) = current {
+                    self.path.curve_to(current, (x2, y2), (x3, y3));
+                }
+            }
+            PathOperator::CurveToFinalReplicated(CurveToFinalReplicated(x1, y1, x3, y3)) => {
+                self.path.curve_to((x1, y1), (x3, y3), (x3, y3))
+            }
+            PathOperator::ClosePath(_) => self.path.close(),
+            PathOperator::AppendRectangle(AppendRectangle(x, y, width, height)) => {
+                self.path.append_rectangle(x, y, width, height)
+            }
+            PathOperator::Stroke(Stroke { close }) => {
+                if close {
+                    self.path.close();
+                }
+                if !self.path.is_empty() {
+                    device.stroke_path(&self.path, &self.state);
+                }
+                self.end_path(device);
+            }
+            PathOperator::Fill(Fill {
+                even_odd,
+                stroke,
+                close,
+            }) => {
+                if close {
+                    self.path.close();
+                }
+                let rule = if even_odd {
+                    FillRule::EvenOdd
+                } else {
+                    FillRule::NonZero
+                };
+                if !self.path.is_empty() {
+                    if stroke {
+                        device.fill_and_stroke_path(&self.path, rule, &self.state);
+                    } else {
+                        device.fill_path(&self.path, rule, &self.state);
+                    }
+                }
+                self.end_path(device);
+            }
+            PathOperator::EndPath(_) => self.end_path(device),
+        }
+    }
+
+    /// End the path once painted, and apply the pending clipping rule.
+    fn end_path<D>(&mut self, device: &mut D)
+    where
+        D: Device,
+    {
+        let path = std::mem::take(&mut self.path);
+
+        if let Some(rule) = self.clip.take() {
+            device.clip(&path, rule, &self.state);
+        }
+    }
+
+    /// Operators that Livre does not type yet, mostly graphics state and colour operators.
+    fn apply_unknown<D>(
+        &mut self,
+        keyword: &[u8],
+        operands: &[Object],
+        device: &mut D,
+    ) -> ModalResult<()>
+    where
+        D: Device,
+    {
+        if self.state.apply_line_style(keyword, operands) {
+            return Ok(());
+        }
+
+        let name = match operands.first() {
+            Some(Object::Name(name)) => Some(name),
+            _ => None,
+        };
+
+        match keyword {
+            b"gs" => {
+                let parameters = name
+                    .and_then(|name| self.frame().resources.ext_gstate.get(name))
+                    .and_then(|parameters| resolve(parameters, self.builder));
+                if let Some(Object::Dictionary(parameters)) = parameters {
+                    self.state.apply_parameters(&parameters);
+                }
+            }
+            b"CS" | b"cs" => {
+                if let Some(name) = name {
+                    let color = Color::new(self.color_space(name));
+                    if keyword == b"CS" {
+                        self.state.stroke = color;
+                    } else {
+                        self.state.fill = color;
+                    }
+                }
+            }
+            b"SC" | b"SCN" => {
+                let space = self.state.stroke.space.clone();
+                self.state.stroke = Color::with_operands(space, operands);
+            }
+            b"sc" | b"scn" => {
+                let space = self.state.fill.space.clone();
+                self.state.fill = Color::with_operands(space, operands);
+            }
+            b"G" => self.state.stroke = Color::with_operands(ColorSpace::DeviceGray, operands),
+            b"g" => self.state.fill = Color::with_operands(ColorSpace::DeviceGray, operands),
+            b"RG" => self.state.stroke = Color::with_operands(ColorSpace::DeviceRGB, operands),
+            b"rg" => self.state.fill = Color::with_operands(ColorSpace::DeviceRGB, operands),
+            b"K" => self.state.stroke = Color::with_operands(ColorSpace::DeviceCMYK, operands),
+            b"k" => self.state.fill = Color::with_operands(ColorSpace::DeviceCMYK, operands),
+            b"W" => self.clip = Some(FillRule::NonZero),
+            b"W*" => self.clip = Some(FillRule::EvenOdd),
+            b"Do" => {
+                if let Some(name) = name {
+                    self.draw_xobject(name, device)?;
+                }
+            }
+            b"BMC" => {
+                if let Some(tag) = name {
+                    device.begin_marked_content(tag, None);
+                }
+            }
+            b"BDC" => {
+                if let Some(tag) = name {
+                    let properties = match operands.get(1) {
+                        Some(Object::Dictionary(properties)) => Some(properties.clone()),
+                        Some(Object::Name(name)) => self
+                            .frame()
+                            .resources
+                            .properties
+                            .get(name)
+                            .and_then(|properties| resolve(properties, self.builder))
+                            .and_then(|properties| match properties {
+                                Object::Dictionary(properties) => Some(properties),
+                                _ => None,
+                            }),
+                        _ => None,
+                    };
+                    device.begin_marked_content(tag, properties.as_ref());
+                }
+            }
+            b"EMC" => device.end_marked_content(),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// The colour space for a name from the `CS` and `cs` operators.
+    fn color_space(&mut self, name: &Name) -> ColorSpace {
+        if let Some(space) = ColorSpace::from_name(name) {
+            return space;
+        }
+
+        if let Some(space) = self.frame().color_spaces.get(name) {
+            return space.clone();
+        }
+
+        let space = self
+            .frame()
+            .resources
+            .color_space
+            .get(name)
+            .map(|space| ColorSpace::from_object(space, self.builder))
+            .unwrap_or_default();
+
+        self.frame_mut()
+            .color_spaces
+            .insert(name.clone(), space.clone());
+
+        space
+    }
+
+    /// The current font, loaded on first use.
+    fn font(&mut self) -> Rc<Font> {
+        let Some(name) = self.state.text.font.clone() else {
+            return self.fallback.clone();
+        };
+
+        if let Some(font) = self.frame().fonts.get(&name) {
+            return font.clone();
+        }
+
+        let font = match self.frame().resources.font.get(&name) {
+            Some(reference) => {
+                let id = reference.id;
+                let builder = self.builder;
+                self.fonts
+                    .entry(id)
+                    .or_insert_with(|| {
+                        match builder.resolve_reference(Reference::<Font>::from(id)) {
+                            Ok(font) => font.map(Rc::new),
+                            Err(err) => {
+                                tracing::warn!("Skipping unreadable font {id:?}: {err:?}");
+                                None
+                            }
+                        }
+                    })
+                    .clone()
+            }
+            None => None,
+        };
+
+        let font = font.unwrap_or_else(|| self.fallback.clone());
+        self.frame_mut().fonts.insert(name, font.clone());
+
+        font
+    }
+
+    fn next_line(&mut self) {
+        MoveToNextLine.preapply(&mut self.line_matrix, &mut self.state.text.parameters);
+        self.text_matrix = self.line_matrix;
+    }
+
+    fn show<D>(&mut self, op: TextShowingOperator, device: &mut D)
+    where
+        D: Device,
+    {
+        match op {
+            TextShowingOperator::ShowText(ShowText(text)) => self.show_string(&text.0, device),
+            TextShowingOperator::MoveToNextLineAndShowText(MoveToNextLineAndShowText(text)) => {
+                self.next_line();
+                self.show_string(&text.0, device);
+            }
+            TextShowingOperator::MoveToNextLineAndShowTextWithSpacing(
+                MoveToNextLineAndShowTextWithSpacing(aw, ac, text),
+            ) => {
+                self.state.text.parameters.word_spacing = aw;
+                self.state.text.parameters.character_spacing = ac;
+                self.next_line();
+                self.show_string(&text.0, device);
+            }
+            TextShowingOperator::ShowTextArray(ShowTextArray(elements)) => {
+                for element in elements {
+                    match element {
+                        TextArrayElement::Text(text) => self.show_string(&text.0, device),
+                        TextArrayElement::Offset(offset) => {
+                            let TextState {
+                                font_size,
+                                parameters,
+                                ..
+                            } = &self.state.text;
+                            let tx = -offset / 1000.0 * font_size * parameters.horizontal_scaling
+                                / 100.0;
+                            self.text_matrix.move_to(tx, 0.0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn show_string<D>(&mut self, bytes: &[u8], device: &mut D)
+    where
+        D: Device,
+    {
+        let font = self.font();
+        let TextState {
+            font_size,
+            parameters,
+            ..
+        } = self.state.text.clone();
+
+        let scaling = parameters.horizontal_scaling / 100.0;
+
+        for code in font.codes(bytes) {
+            let w0 = font.width(code) / 1000.0;
+
+            let matrix = Matrix::from([
+                font_size * scaling,
+                0.0,
+                0.0,
+                font_size,
+                0.0,
+                parameters.rise,
+            ])
+            .then(&self.text_matrix)
+            .then(&self.state.ctm);
+
+            let glyph = TextGlyph {
+                font: &font,
+                code,
+                matrix,
+                width: w0,
+            };
+            device.draw_glyph(&glyph, &self.state);
+
+            // Word spacing only applies to the single-byte code 32.
+            let word_spacing = if code == 32 && !font.is_composite() {
+                parameters.word_spacing
+            } else {
+                0.0
+            };
+            let tx = (w0 * font_size + parameters.character_spacing + word_spacing) * scaling;
+            self.text_matrix.move_to(tx, 0.0);
+        }
+    }
+
+    fn draw_xobject<D>(&mut self, name: &Name, device: &mut D) -> ModalResult<()>
+    where
+        D: Device,
+    {
+        let Some(id) = self
+            .frame()
+            .resources
+            .xobject
+            .get(name)
+            .map(|reference| reference.id)
+        else {
+            return Ok(());
+        };
+
+        let xobject = self
+            .xobjects
+            .entry(id)
+            .or_insert_with(|| XObject::resolve(id, self.builder).map(Rc::new))
+            .clone();
+
+        match xobject.as_deref() {
+            Some(XObject::Image(image)) => device.draw_image(image, &self.state),
+            Some(XObject::Form {
+                matrix,
+                bbox,
+                resources,
+                content,
+            }) => {
+                let limit = self.builder.options().max_depth;
+                if self.forms.contains(&id) {
+                    return Err(Error::new(ErrorKind::Cycle).object(id).cut());
+                } else if self.forms.len() >= limit {
+                    return Err(Error::new(ErrorKind::DepthLimitExceeded(limit))
+                        .object(id)
+                        .cut());
+                }
+
+                self.save(device);
+                let depth = self.stack.len();
+
+                if let Some(matrix) = *matrix {
+                    self.state.ctm = Matrix::from(matrix).then(&self.state.ctm);
+                }
+                if let Some(bbox) = bbox {
+                    device.clip(&Path::rectangle(bbox), FillRule::NonZero, &self.state);
+                }
+
+                // Forms without resources use those of the page, which is deprecated.
+                let resources = resources
+                    .clone()
+                    .unwrap_or_else(|| self.frame().resources.clone());
+                self.frames.push(Frame::new(resources, depth));
+                self.forms.push(id);
+
+                let operators = OperatorsIter::from_content(self.builder, content.clone());
+                let result = self.run(operators, device);
+
+                self.forms.pop();
+                self.frames.pop();
+
+                // Restore the states that the form left saved, then the state before the form.
+                while self.stack.len() >= depth {
+                    self.restore(device);
+                }
+
+                result?;
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// A number operand.
+fn number(object: &Object) -> Option<f32> {
+    match *object {
+        Object::Integer(i) => Some(i as f32),
+        Object::Real(r) => Some(r),
+        _ => None,
+    }
+}
+
+/// Follow a reference, if the object is one. Dangling and unreadable references yield `None`.
+fn resolve<B>(object: &Object, builder: &B) -> Option<Object>
+where
+    B: Builder,
+{
+    match object {
+        Object::Indirect(reference) => builder.resolve_reference(*reference).ok().flatten(),
+        object => Some(object.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use winnow::BStr;
+
+    use crate::{content::operators::spanned_operators, extraction::extract};
+
+    use super::*;
+
+    /// Records the callbacks, with the device-space coordinates of the first point of paths.
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Recorder {
+        fn path(&mut self, action: &str, path: &Path, state: &GraphicsState, color: &Color) {
+            let start = match path.transform(&state.ctm).elements.first() {
+                Some(PathElement::MoveTo(point)) => *point,
+                _ => (f32::NAN, f32::NAN),
+            };
+            self.0
+                .push(format!("{action} {start:?} {:?}", color.to_rgb()));
+        }
+    }
+
+    impl Device for Recorder {
+        fn save_state(&mut self) {
+            self.0.push("q".into());
+        }
+
+        fn restore_state(&mut self) {
+            self.0.push("Q".into());
+        }
+
+        fn fill_path(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {
+            self.path(&format!("fill {rule:?}"), path, state, &state.fill);
+        }
+
+        fn stroke_path(&mut self, path: &Path, state: &GraphicsState) {
+            self.path("stroke", path, state, &state.stroke);
+        }
+
+        fn clip(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {
+            self.path(&format!("clip {rule:?}"), path, state, &Color::default());
+        }
+
+        fn draw_glyph(&mut self, glyph: &TextGlyph<'_>, _: &GraphicsState) {
+            let origin = glyph.matrix.transform(0.0, 0.0);
+            self.0.push(format!("glyph {} {origin:?}", glyph.code));
+        }
+
+        fn draw_image(&mut self, image: &Image, _: &GraphicsState) {
+            self.0.push(format!(
+                "image {}x{} {:?} {:?}",
+                image.width, image.height, image.color_space, image.filters
+            ));
+        }
+
+        fn begin_marked_content(&mut self, tag: &Name, properties: Option<&Map<Object>>) {
+            let mcid = properties.and_then(|properties| properties.get(&"MCID".into()));
+            self.0.push(format!("begin {tag:?} {mcid:?}"));
+        }
+
+        fn end_marked_content(&mut self) {
+            self.0.push("end".into());
+        }
+    }
+
+    const RESOURCES: &[u8] = b"<<
+        /XObject <</Fm0 7 0 R /Im0 8 0 R /Im1 9 0 R /Loop 10 0 R /Fm1 11 0 R>>
+        /ExtGState <</GS0 <</LW 3 /ca 0.5>>>>
+        /ColorSpace <</CS0 [/Indexed /DeviceRGB 1 <00000000FF00>]>>
+        /Properties <</MC0 <</MCID 4>>>>
+    >>";
+
+    /// The external objects of [`RESOURCES`].
+    fn objects() -> HashMap<ReferenceId, &'static BStr> {
+        let objects: [&[u8]; 5] = [
+            b"7 0 obj\n<</Type/XObject/Subtype/Form/Matrix[2 0 0 2 5 5]/BBox[0 0 10 10]/Length 24>>stream\n1 0 0 rg 0 0 1 1 re f q\nendstream\nendobj",
+            b"8 0 obj\n<</Subtype/Image/Width 2/Height 1/ColorSpace/DeviceRGB/BitsPerComponent 8/Length 6>>stream\nABCDEF\nendstream\nendobj",
+            b"9 0 obj\n<</Subtype/Image/Width 1/Height 1/ColorSpace/DeviceGray/Filter/DCTDecode/Length 4>>stream\nJPEG\nendstream\nendobj",
+            b"10 0 obj\n<</Subtype/Form/Resources<</XObject<</Loop 10 0 R>>>>/Length 8>>stream\n/Loop Do\nendstream\nendobj",
+            b"11 0 obj\n<</Subtype/Form/Length 16>>stream\nQ Q 1 1 1 1 re f\nendstream\nendobj",
+        ];
+
+        objects
+            .into_iter()
+            .enumerate()
+            .map(|(i, object)| (ReferenceId::first(i + 7), BStr::new(object)))
+            .collect()
+    }
+
+    fn interpret(content: &[u8]) -> ModalResult<Vec<String>> {
+        let resources: Resources = extract(&mut RESOURCES.as_ref())?;
+        let builder = objects();
+
+        let mut interpreter = ContentInterpreter::new(&builder, resources, Matrix::default());
+        let mut recorder = Recorder::default();
+
+        let operators = spanned_operators(content, usize::MAX)?
+            .into_iter()
+            .map(|spanned| Ok(spanned.operator));
+        interpreter.run(operators, &mut recorder)?;
+
+        Ok(recorder.0)
+    }
+
+    #[rstest]
+    #[case(
+        b"q 1 0 0 RG 0 0 m 10 0 l S Q 0 0 m 10 0 l S",
+        &["q", "stroke (0.0, 0.0) [1.0, 0.0, 0.0]", "Q", "stroke (0.0, 0.0) [0.0, 0.0, 0.0]"]
+    )]
+    #[case(b"2 0 0 2 1 1 cm 0.5 g 1 1 2 2 re f*", &["fill EvenOdd (3.0, 3.0) [0.5, 0.5, 0.5]"])]
+    #[case(b"0 0 1 0 K 5 5 m 6 6 l s", &["stroke (5.0, 5.0) [1.0, 1.0, 0.0]"])]
+    #[case(b"/CS0 cs 1 scn 0 0 1 1 re B", &[
+        "fill NonZero (0.0, 0.0) [0.0, 1.0, 0.0]",
+        "stroke (0.0, 0.0) [0.0, 0.0, 0.0]",
+    ])]
+    // The clipping path applies once the path is painted.
+    #[case(b"0 0 1 1 re W n", &["clip NonZero (0.0, 0.0) [0.0, 0.0, 0.0]"])]
+    #[case(b"0 0 1 1 re W* f", &[
+        "fill NonZero (0.0, 0.0) [0.0, 0.0, 0.0]",
+        "clip EvenOdd (0.0, 0.0) [0.0, 0.0, 0.0]",
+    ])]
+    // Without a current point, there is nothing to paint.
+    #[case(b"1 1 l S", &[])]
+    #[case(b"Q Q", &[])]
+    #[case(b"/P <</MCID 3>> BDC /Span BMC EMC EMC", &[
+        "begin Name(P) Some(Integer(3))",
+        "begin Name(Span) None",
+        "end",
+        "end",
+    ])]
+    #[case(b"/P /MC0 BDC EMC", &["begin Name(P) Some(Integer(4))", "end"])]
+    #[case(b"BT /F1 10 Tf 1 2 Td (AB) Tj ET", &["glyph 65 (1.0, 2.0)", "glyph 66 (7.67, 2.0)"])]
+    // The form runs with its own matrix and bounding box, and its unbalanced `q` is undone.
+    #[case(b"/Fm0 Do 0 0 m 1 1 l S", &[
+        "q",
+        "clip NonZero (5.0, 5.0) [0.0, 0.0, 0.0]",
+        "fill NonZero (5.0, 5.0) [1.0, 0.0, 0.0]",
+        "q",
+        "Q",
+        "Q",
+        "stroke (0.0, 0.0) [0.0, 0.0, 0.0]",
+    ])]
+    // The unbalanced `Q`s of the form do not restore the states saved outside of it.
+    #[case(b"q 2 0 0 2 0 0 cm /Fm1 Do 1 1 m 2 2 l S Q", &[
+        "q",
+        "q",
+        "fill NonZero (2.0, 2.0) [0.0, 0.0, 0.0]",
+        "Q",
+        "stroke (2.0, 2.0) [0.0, 0.0, 0.0]",
+        "Q",
+    ])]
+    #[case(b"/Im0 Do /Im1 Do /Missing Do", &[
+        "image 2x1 DeviceRGB []",
+        "image 1x1 DeviceGray [Name(DCTDecode)]",
+    ])]
+    fn callbacks(#[case] content: &[u8], #[case] expected: &[&str]) {
+        assert_eq!(interpret(content).unwrap(), expected);
+    }
+
+    #[test]
+    fn parameters() {
+        let resources: Resources = extract(&mut RESOURCES.as_ref()).unwrap();
+        let builder = objects();
+        let mut interpreter = ContentInterpreter::new(&builder, resources, Matrix::default());
+
+        let operators = spanned_operators(b"/GS0 gs 2 J [1 2] 0 d", usize::MAX).unwrap();
+        for spanned in operators {
+            interpreter
+                .apply(spanned.operator, &mut Recorder::default())
+                .unwrap();
+        }
+
+        let state = interpreter.state();
+        assert_eq!(state.line_width, 3.0);
+        assert_eq!(state.fill_alpha, 0.5);
+        assert_eq!(state.line_cap, LineCap::ProjectingSquare);
+        assert_eq!(state.dash.array, [1.0, 2.0]);
+    }
+
+    #[test]
+    fn cycle() {
+        let err = interpret(b"/Loop Do").unwrap_err();
+        assert_eq!(Error::from(err).kind(), &ErrorKind::Cycle);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{content::state::Matrix, extraction::Rectangle};
+
+/// How to tell the inside of a path from its outside, when filling or clipping.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FillRule {
+    /// A point is inside the path if a ray from that point crosses path segments a different
+    /// number of times from left to right and from right to left.
+    #[default]
+    NonZero,
+    /// A point is inside the path if a ray from that point crosses an odd number of path
+    /// segments.
+    EvenOdd,
+}
+
+/// A single step of a path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathElement {
+    /// Begin a new subpath at the given point.
+    MoveTo((f32, f32)),
+    /// Append a straight line to the given point.
+    LineTo((f32, f32)),
+    /// Append a cubic Bézier curve, with two control points and an end point.
+    CurveTo((f32, f32), (f32, f32), (f32, f32)),
+    /// Close the current subpath with a straight line to its starting point.
+    ClosePath,
+}
+
+/// A path, made of subpaths that each start with [`PathElement::MoveTo`].
+///
+/// The interpreter builds paths in user space: use [`transform`](Self::transform) with the
+/// current transformation matrix to get them in device space. The `v` and `y` curves are
+/// expanded to full curves, and `re` rectangles to closed subpaths.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Path {
+    pub elements: Vec<PathElement>,
+}
+
+impl Path {
+    /// A closed rectangular path.
+    pub fn rectangle(rectangle: &Rectangle) -> Self {
+        let mut path = Self::default();
+        path.append_rectangle(
+            rectangle.xmin,
+            rectangle.ymin,
+            rectangle.xmax - rectangle.xmin,
+            rectangle.ymax - rectangle.ymin,
+        );
+        path
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// The current point, i.e. the end of the last element. Closing a subpath brings the
+    /// current point back to its start.
+    pub fn current_point(&self) -> Option<(f32, f32)> {
+        match self.elements.last()? {
+            PathElement::MoveTo(point)
+            | PathElement::LineTo(point)
+            | PathElement::CurveTo(_, _, point) => Some(*point),
+            PathElement::ClosePath => {
+                self.elements
+                    .iter()
+                    .rev()
+                    .find_map(|element| match element {
+                        PathElement::MoveTo(point) => Some(*point),
+                        _ => None,
+                    })
+            }
+        }
+    }
+
+    /// Map every point of the path with the supplied matrix.
+    pub fn transform(&self, matrix: &Matrix) -> Self {
+        let map = |(x, y): (f32, f32)| matrix.transform(x, y);
+
+        let elements = self
+            .elements
+            .iter()
+            .map(|element| match *element {
+                PathElement::MoveTo(point) => PathElement::MoveTo(map(point)),
+                PathElement::LineTo(point) => PathElement::LineTo(map(point)),
+                PathElement::CurveTo(c1, c2, point) => {
+                    PathElement::CurveTo(map(c1), map(c2), map(point))
+                }
+                PathElement::ClosePath => PathElement::ClosePath,
+            })
+            .collect();
+
+        Self { elements }
+    }
+
+    pub(super) fn move_to(&mut self, point: (f32, f32)) {
+        self.elements.push(PathElement::MoveTo(point));
+    }
+
+    /// Segments and curves need a current point, and are ignored otherwise.
+    pub(super) fn line_to(&mut self, point: (f32, f32)) {
+        if self.current_point().is_some() {
+            self.elements.push(PathElement::LineTo(point));
+        }
+    }
+
+    pub(super) fn curve_to(&mut self, c1: (f32, f32), c2: (f32, f32), point: (f32, f32)) {
+        if self.current_point().is_some() {
+            self.elements.push(PathElement::CurveTo(c1, c2, point));
+        }
+    }
+
+    pub(super) fn close(&mut self) {
+        if self.current_point().is_some() {
+            self.elements.push(PathElement::ClosePath);
+        }
+    }
+
+    pub(super) fn append_rectangle(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.move_to((x, y));
+        self.line_to((x + width, y));
+        self.line_to((x + width, y + height));
+        self.line_to((x, y + height));
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_point() {
+        let mut path = Path::default();
+        assert_eq!(path.current_point(), None);
+
+        // Without a current point, segments are ignored.
+        path.line_to((1.0, 1.0));
+        assert!(path.is_empty());
+
+        path.move_to((1.0, 2.0));
+        path.line_to((3.0, 4.0));
+        assert_eq!(path.current_point(), Some((3.0, 4.0)));
+
+        path.close();
+        assert_eq!(path.current_point(), Some((1.0, 2.0)));
+    }
+
+    #[test]
+    fn transform() {
+        let path = Path::rectangle(&Rectangle::from((0.0, 0.0, 1.0, 2.0)));
+        let matrix = Matrix::from([2.0, 0.0, 0.0, 2.0, 10.0, 0.0]);
+
+        assert_eq!(
+            path.transform(&matrix).elements,
+            [
+                PathElement::MoveTo((10.0, 0.0)),
+                PathElement::LineTo((12.0, 0.0)),
+                PathElement::LineTo((12.0, 4.0)),
+                PathElement::LineTo((10.0, 4.0)),
+                PathElement::ClosePath,
+            ]
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    content::state::{Matrix, TextStateParameters},
+    extraction::{Map, Name, Object},
+};
+
+use super::{number, Color};
+
+/// The shape at the ends of open subpaths and dashes, when stroking.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    ProjectingSquare,
+}
+
+impl LineCap {
+    fn from_number(value: f32) -> Self {
+        match value as i32 {
+            1 => Self::Round,
+            2 => Self::ProjectingSquare,
+            _ => Self::Butt,
+        }
+    }
+}
+
+/// The shape at the corners of paths, when stroking.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl LineJoin {
+    fn from_number(value: f32) -> Self {
+        match value as i32 {
+            1 => Self::Round,
+            2 => Self::Bevel,
+            _ => Self::Miter,
+        }
+    }
+}
+
+/// The dash pattern of stroked lines: the lengths of alternating dashes and gaps, in user
+/// space units, starting `phase` units into the pattern. An empty array draws solid lines.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Dash {
+    pub array: Vec<f32>,
+    pub phase: f32,
+}
+
+impl Dash {
+    /// Read a dash pattern from the operands of `d`, or from the `D` entry of a graphics
+    /// state parameter dictionary.
+    pub(super) fn from_operands(operands: &[Object]) -> Option<Self> {
+        match operands {
+            [Object::Array(array), phase] => Some(Self {
+                array: array.iter().filter_map(number).collect(),
+                phase: number(phase).unwrap_or(0.0),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The text state: the current font and the [`TextStateParameters`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TextState {
+    /// The name of the current font, in the font resource dictionary.
+    pub font: Option<Name>,
+    pub font_size: f32,
+    pub parameters: TextStateParameters,
+}
+
+/// The graphics state, saved and restored by the `q` and `Q` operators.
+///
+/// The clipping path is left to the [`Device`](super::Device), which is notified whenever it
+/// changes as well as when the state is saved and restored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphicsState {
+    /// The current transformation matrix, which maps user space to device space.
+    pub ctm: Matrix,
+    /// Thickness of stroked lines, in user space units. Zero stands for the thinnest line
+    /// that the device can render.
+    pub line_width: f32,
+    pub line_cap: LineCap,
+    pub line_join: LineJoin,
+    /// The maximum ratio of the miter length to the line width, above which miter joins
+    /// are beveled.
+    pub miter_limit: f32,
+    pub dash: Dash,
+    pub fill: Color,
+    pub stroke: Color,
+    /// Constant opacity of filling operations, from the `ca` entry of a graphics state
+    /// parameter dictionary.
+    pub fill_alpha: f32,
+    /// Constant opacity of stroking operations, from the `CA` entry.
+    pub stroke_alpha: f32,
+    pub text: TextState,
+}
+
+impl GraphicsState {
+    /// The initial graphics state, whose transformation matrix maps user space to device
+    /// space.
+    pub fn new(ctm: Matrix) -> Self {
+        Self {
+            ctm,
+            line_width: 1.0,
+            line_cap: LineCap::default(),
+            line_join: LineJoin::default(),
+            miter_limit: 10.0,
+            dash: Dash::default(),
+            fill: Color::default(),
+            stroke: Color::default(),
+            fill_alpha: 1.0,
+            stroke_alpha: 1.0,
+            text: TextState::default(),
+        }
+    }
+
+    /// Apply the entries of a graphics state parameter dictionary (`gs` operator). Entries
+    /// that Livre does not support, such as blend modes or soft masks, are ignored.
+    pub(super) fn apply_parameters(&mut self, parameters: &Map<Object>) {
+        for (key, value) in parameters {
+            match (key.0.as_slice(), value) {
+                (b"LW", value) => self.line_width = number(value).unwrap_or(self.line_width),
+                (b"LC", value) => {
+                    self.line_cap = number(value).map_or(self.line_cap, LineCap::from_number)
+                }
+                (b"LJ", value) => {
+                    self.line_join = number(value).map_or(self.line_join, LineJoin::from_number)
+                }
+                (b"ML", value) => self.miter_limit = number(value).unwrap_or(self.miter_limit),
+                (b"D", Object::Array(dash)) => {
+                    if let Some(dash) = Dash::from_operands(dash) {
+                        self.dash = dash;
+                    }
+                }
+                (b"CA", value) => self.stroke_alpha = number(value).unwrap_or(self.stroke_alpha),
+                (b"ca", value) => self.fill_alpha = number(value).unwrap_or(self.fill_alpha),
+                _ => {}
+            }
+        }
+    }
+
+    /// Apply a line style operator: `w`, `J`, `j`, `M` or `d`. Returns `false` for any other
+    /// keyword.
+    pub(super) fn apply_line_style(&mut self, keyword: &[u8], operands: &[Object]) -> bool {
+        let value = operands.first().and_then(number);
+
+        match keyword {
+            b"w" => self.line_width = value.unwrap_or(self.line_width),
+            b"J" => self.line_cap = value.map_or(self.line_cap, LineCap::from_number),
+            b"j" => self.line_join = value.map_or(self.line_join, LineJoin::from_number),
+            b"M" => self.miter_limit = value.unwrap_or(self.miter_limit),
+            b"d" => {
+                if let Some(dash) = Dash::from_operands(operands) {
+                    self.dash = dash;
+                }
+            }
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::extraction::extract;
+
+    use super::*;
+
+    #[test]
+    fn parameters() {
+        let input: &[u8] = b"<</Type/ExtGState/LW 2/LC 1/D[[3 1] 2]/CA 0.5/ca 0.25/BM/Multiply>>";
+        let parameters: Map<Object> = extract(&mut input.as_ref()).unwrap();
+
+        let mut state = GraphicsState::new(Matrix::default());
+        state.apply_parameters(&parameters);
+
+        assert_eq!(state.line_width, 2.0);
+        assert_eq!(state.line_cap, LineCap::Round);
+        assert_eq!(
+            state.dash,
+            Dash {
+                array: vec![3.0, 1.0],
+                phase: 2.0
+            }
+        );
+        assert_eq!((state.stroke_alpha, state.fill_alpha), (0.5, 0.25));
+    }
+}
@@ -0,0 +1,325 @@
+use crate::{
+    extraction::{
+        EncodedStream, MaybeArray, Name, Object, RawDict, Rectangle, Reference, ReferenceId, Stream,
+    },
+    follow_refs::{BuildFromRawDict, Builder},
+    structure::Resources,
+};
+
+use super::ColorSpace;
+
+/// The entries of an external object's dictionary, for images and forms alike.
+#[derive(Debug, BuildFromRawDict)]
+struct Entries {
+    subtype: Option<Name>,
+    matrix: Option<[f32; 6]>,
+    #[livre(rename = "BBox")]
+    bbox: Option<Rectangle>,
+    resources: Option<Resources>,
+    width: Option<u32>,
+    height: Option<u32>,
+    bits_per_component: Option<u8>,
+    color_space: Option<Object>,
+    image_mask: Option<bool>,
+    decode: Option<Vec<f32>>,
+    /// Only left in the dictionary if Livre could not decode the stream, see
+    /// [`EncodedStream`].
+    filter: Option<MaybeArray<Name>>,
+}
+
+/// An external object's dictionary.
+///
+/// The soft mask of an image is kept as a reference, since it may be encoded with a filter
+/// that Livre does not support, which should not prevent from reading the image itself.
+#[derive(Debug)]
+pub(super) struct XObjectDict {
+    entries: Entries,
+    smask: Option<ReferenceId>,
+}
+
+impl BuildFromRawDict for XObjectDict {
+    fn build_from_raw_dict<B>(dict: &mut RawDict<'_>, builder: &B) -> winnow::ModalResult<Self>
+    where
+        B: Builder,
+    {
+        let smask = dict
+            .pop(&"SMask".into())
+            .and_then(|value| value.extract::<Reference<()>>().ok())
+            .map(|reference| reference.id);
+
+        let entries = Entries::build_from_raw_dict(dict, builder)?;
+
+        Ok(Self { entries, smask })
+    }
+}
+
+/// An external object, read from its stream.
+pub(super) enum XObject {
+    Image(Image),
+    Form {
+        matrix: Option<[f32; 6]>,
+        bbox: Option<Rectangle>,
+        resources: Option<Resources>,
+        content: Vec<u8>,
+    },
+}
+
+impl XObject {
+    /// Read the external object `id`. Streams whose filters Livre does not support are kept
+    /// encoded, which only makes sense for images.
+    ///
+    /// Objects that cannot be read are logged and skipped.
+    pub(super) fn resolve<B>(id: ReferenceId, builder: &B) -> Option<Self>
+    where
+        B: Builder,
+    {
+        let stream = resolve_stream(id, builder)?;
+
+        match stream
+            .structured
+            .entries
+            .subtype
+            .as_ref()
+            .map(|name| name.0.as_slice())
+        {
+            Some(b"Image") => Image::new(stream, builder).map(Self::Image),
+            Some(b"Form") if stream.structured.entries.filter.is_none() => {
+                let Entries {
+                    matrix,
+                    bbox,
+                    resources,
+                    ..
+                } = stream.structured.entries;
+
+                Some(Self::Form {
+                    matrix,
+                    bbox,
+                    resources,
+                    content: stream.content,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn resolve_stream<B>(id: ReferenceId, builder: &B) -> Option<Stream<XObjectDict>>
+where
+    B: Builder,
+{
+    match builder.resolve_reference(Reference::<Stream<XObjectDict>>::from(id)) {
+        Ok(stream) => stream,
+        Err(err) => {
+            match builder.resolve_reference(Reference::<EncodedStream<XObjectDict>>::from(id)) {
+                Ok(stream) => stream.map(|EncodedStream(stream)| stream),
+                Err(_) => {
+                    tracing::warn!("Skipping unreadable external object {id:?}: {err:?}");
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// A sampled image, painted by the `Do` operator.
+///
+/// The image is mapped onto the unit square of user space, the first sample being at the
+/// top-left corner `(0, 1)`: the current transformation matrix maps it to device space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub bits_per_component: u8,
+    pub color_space: ColorSpace,
+    /// Stencil masks have a single bit per sample, and are painted with the current fill
+    /// colour wherever the sample is 0 (or 1, if the `decode` array is `[1 0]`).
+    pub image_mask: bool,
+    /// How to map sample values to colour components, two numbers per component.
+    pub decode: Option<Vec<f32>>,
+    /// The samples, row by row, each row starting on a byte boundary. If `filters` is not
+    /// empty, these are the encoded data instead.
+    pub data: Vec<u8>,
+    /// The filters that Livre could not apply to the data, e.g. `DCTDecode` for a JPEG image.
+    pub filters: Vec<Name>,
+    /// The soft mask, i.e. the opacity of each sample, as a `DeviceGray` image.
+    pub soft_mask: Option<Box<Image>>,
+}
+
+impl Image {
+    fn new<B>(stream: Stream<XObjectDict>, builder: &B) -> Option<Self>
+    where
+        B: Builder,
+    {
+        let Stream {
+            structured: XObjectDict { entries, smask },
+            content,
+            ..
+        } = stream;
+
+        let image_mask = entries.image_mask.unwrap_or(false);
+        let color_space = match (&entries.color_space, image_mask) {
+            (Some(space), false) => ColorSpace::from_object(space, builder),
+            _ => ColorSpace::DeviceGray,
+        };
+
+        let soft_mask = smask
+            .and_then(|id| resolve_stream(id, builder))
+            .and_then(|mut stream| {
+                // The soft mask of a soft mask is meaningless.
+                stream.structured.smask = None;
+                Self::new(stream, builder)
+            })
+            .map(Box::new);
+
+        Some(Self {
+            width: entries.width?,
+            height: entries.height?,
+            bits_per_component: if image_mask {
+                1
+            } else {
+                entries.bits_per_component.unwrap_or(8)
+            },
+            color_space,
+            image_mask,
+            decode: entries.decode,
+            data: content,
+            filters: entries.filter.map(Vec::from).unwrap_or_default(),
+            soft_mask,
+        })
+    }
+
+    /// Whether the samples were decoded, i.e. `data` holds the raw samples.
+    pub fn is_decoded(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// The image as 8-bit RGBA pixels, row by row from the top-left corner, or `None` if the
+    /// samples are still encoded or incomplete.
+    ///
+    /// Stencil masks are painted with the `fill` colour. The soft mask, if any, gives the alpha
+    /// channel, and is resampled to the size of the image.
+    pub fn to_rgba(&self, fill: [f32; 3]) -> Option<Vec<u8>> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let n = self.color_space.components();
+        let bits = self.bits_per_component as usize;
+
+        if !self.is_decoded() || n == 0 || !matches!(bits, 1 | 2 | 4 | 8 | 16) {
+            return None;
+        }
+
+        let stride = (width * n * bits).div_ceil(8);
+        if self.data.len() < stride * height {
+            return None;
+        }
+
+        let max = ((1u32 << bits) - 1) as f32;
+        let decode: Vec<(f32, f32)> = match &self.decode {
+            Some(decode) if decode.len() >= 2 * n => decode
+                .chunks_exact(2)
+                .map(|pair| (pair[0], pair[1]))
+                .collect(),
+            // Samples are indices into the colour table.
+            _ if matches!(self.color_space, ColorSpace::Indexed { .. }) => vec![(0.0, max)],
+            _ => vec![(0.0, 1.0); n],
+        };
+
+        let soft_mask = self.soft_mask.as_deref().and_then(|mask| {
+            let pixels = mask.to_rgba([0.0; 3])?;
+            Some((mask.width as usize, mask.height as usize, pixels))
+        });
+
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        let mut components = vec![0.0; n];
+
+        for (y, row) in self.data.chunks(stride.max(1)).take(height).enumerate() {
+            for x in 0..width {
+                for (i, (component, (dmin, dmax))) in components.iter_mut().zip(&decode).enumerate()
+                {
+                    let sample = sample(row, (x * n + i) * bits, bits) as f32;
+                    *component = dmin + sample * (dmax - dmin) / max;
+                }
+
+                let (rgb, mut alpha) = if self.image_mask {
+                    (fill, if components[0] < 0.5 { 255 } else { 0 })
+                } else {
+                    (self.color_space.to_rgb(&components), 255)
+                };
+
+                if let Some((mask_width, mask_height, mask)) = &soft_mask {
+                    let index = (y * mask_height / height) * mask_width + x * mask_width / width;
+                    alpha = alpha.min(mask[index * 4]);
+                }
+
+                pixels.extend(rgb.map(|c| (c * 255.0).round() as u8));
+                pixels.push(alpha);
+            }
+        }
+
+        Some(pixels)
+    }
+}
+
+/// Read the sample of `bits` bits that starts at bit `offset` of the row. Samples of 16 bits
+/// are big-endian.
+fn sample(row: &[u8], offset: usize, bits: usize) -> u32 {
+    let byte = |i: usize| row.get(i).copied().unwrap_or(0) as u32;
+    let index = offset / 8;
+
+    match bits {
+        16 => (byte(index) << 8) | byte(index + 1),
+        8 => byte(index),
+        _ => (byte(index) >> (8 - bits - offset % 8)) & ((1 << bits) - 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn image(color_space: ColorSpace, bits_per_component: u8, data: &[u8]) -> Image {
+        Image {
+            width: 2,
+            height: 1,
+            bits_per_component,
+            color_space,
+            image_mask: false,
+            decode: None,
+            data: data.to_vec(),
+            filters: Vec::new(),
+            soft_mask: None,
+        }
+    }
+
+    #[rstest]
+    #[case(image(ColorSpace::DeviceGray, 8, &[0, 255]), Some(vec![0, 0, 0, 255, 255, 255, 255, 255]))]
+    #[case(image(ColorSpace::DeviceGray, 1, &[0b1000_0000]), Some(vec![255, 255, 255, 255, 0, 0, 0, 255]))]
+    #[case(image(ColorSpace::DeviceRGB, 8, &[255, 0, 0]), None)]
+    #[case(
+        image(
+            ColorSpace::Indexed { base: Box::new(ColorSpace::DeviceRGB), lookup: vec![0, 0, 0, 0, 0, 255] },
+            4,
+            &[0x10],
+        ),
+        Some(vec![0, 0, 255, 255, 0, 0, 0, 255]),
+    )]
+    #[case(
+        Image { image_mask: true, ..image(ColorSpace::DeviceGray, 1, &[0b0100_0000]) },
+        Some(vec![255, 0, 0, 255, 255, 0, 0, 0]),
+    )]
+    #[case(
+        Image {
+            soft_mask: Some(Box::new(Image { width: 1, ..image(ColorSpace::DeviceGray, 8, &[128]) })),
+            ..image(ColorSpace::DeviceGray, 8, &[0, 0])
+        },
+        Some(vec![0, 0, 0, 128, 0, 0, 0, 128]),
+    )]
+    #[case(
+        Image { filters: vec!["DCTDecode".into()], ..image(ColorSpace::DeviceGray, 8, &[0, 0]) },
+        None,
+    )]
+    fn to_rgba(#[case] image: Image, #[case] expected: Option<Vec<u8>>) {
+        assert_eq!(image.to_rgba([1.0, 0.0, 0.0]), expected);
+    }
+}
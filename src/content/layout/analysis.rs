@@ -0,0 +1,478 @@
+//! Grouping of positioned glyphs into words, lines and blocks.
+//!
+//! Glyphs are first split by the direction of their baseline, and each group is analysed in
+//! its own *line space*, in which the baseline runs from left to right and the y axis points
+//! downwards. In that space:
+//!
+//! - glyphs whose baselines are closer than half the font size share a row;
+//! - within a row, a gap wider than [`WORD_GAP`] times the font size, or a space glyph, starts
+//!   a new word, and a gap wider than [`COLUMN_GAP`] times the font size starts a new line,
+//!   since it most likely separates two columns. So does a gap wider than [`GUTTER`] times the
+//!   font size, if the lines right above or below are split by the same gap;
+//! - consecutive lines that overlap horizontally, have similar font sizes and are at most
+//!   [`LINE_SPACING`] times the font size apart make up a block.
+//!
+//! Blocks are then sorted in [reading order](super::order).
+
+use crate::structure::RotationAngle;
+
+use super::{
+    order::{reading_order, Extent},
+    union, Glyph, TextBlock, TextLine, TextPage, Viewport, Word,
+};
+
+/// Minimal gap between two words, relative to the font size. A space is usually a quarter
+/// of the font size wide, while kerning only moves glyphs by a few hundredths of it.
+const WORD_GAP: f32 = 0.15;
+
+/// Minimal gap between two lines that share a baseline, relative to the font size.
+const COLUMN_GAP: f32 = 2.0;
+
+/// Minimal width of a column gutter, relative to the font size. Narrower than
+/// [`COLUMN_GAP`], but only applies to gaps that the neighbouring lines leave empty as well.
+const GUTTER: f32 = 0.8;
+
+/// Maximal distance between the baselines of two consecutive lines of a block, relative to
+/// the font size.
+const LINE_SPACING: f32 = 1.7;
+
+/// Maximal ratio between the font sizes of two consecutive lines of a block.
+const FONT_SIZE_RATIO: f32 = 1.5;
+
+/// Map a point from display space to the line space of a baseline direction.
+fn to_line_space(rotation: RotationAngle, (x, y): (f32, f32)) -> (f32, f32) {
+    match rotation {
+        RotationAngle::Zero => (x, y),
+        RotationAngle::Quarter => (y, -x),
+        RotationAngle::Full => (-x, -y),
+        RotationAngle::ThreeQuarters => (-y, x),
+    }
+}
+
+/// A glyph, positioned in line space.
+struct Positioned {
+    glyph: Glyph,
+    start: f32,
+    end: f32,
+    baseline: f32,
+}
+
+impl Positioned {
+    fn new(glyph: Glyph) -> Self {
+        let (start, baseline) = to_line_space(glyph.rotation, glyph.origin);
+        Self {
+            start,
+            end: start + glyph.width,
+            baseline,
+            glyph,
+        }
+    }
+
+    fn is_space(&self) -> bool {
+        self.glyph.text.trim().is_empty()
+    }
+}
+
+/// A line, along with its extent in line space.
+struct Row {
+    line: TextLine,
+    start: f32,
+    end: f32,
+    baseline: f32,
+    font_size: f32,
+}
+
+impl Row {
+    fn new(words: Vec<Word>, rotation: RotationAngle, glyphs: &[&Positioned]) -> Self {
+        let start = glyphs.iter().map(|g| g.start).fold(f32::MAX, f32::min);
+        let end = glyphs.iter().map(|g| g.end).fold(f32::MIN, f32::max);
+        let baseline = glyphs.iter().map(|g| g.baseline).sum::<f32>() / glyphs.len() as f32;
+        let font_size = words.iter().map(|w| w.font_size).fold(0.0, f32::max);
+
+        Self {
+            line: TextLine {
+                bbox: union(words.iter().map(|w| &w.bbox)),
+                words,
+                rotation,
+            },
+            start,
+            end,
+            baseline,
+            font_size,
+        }
+    }
+
+    fn overlaps(&self, start: f32, end: f32) -> bool {
+        self.start < end && start < self.end
+    }
+}
+
+fn word(glyphs: &[&Positioned]) -> Word {
+    Word {
+        text: glyphs.iter().map(|g| g.glyph.text.as_str()).collect(),
+        bbox: union(glyphs.iter().map(|g| &g.glyph.bbox)),
+        font_size: glyphs.iter().map(|g| g.glyph.font_size).fold(0.0, f32::max),
+    }
+}
+
+/// Whether a glyph of the row covers the central half of the gap between `start` and `end`.
+fn covers(row: &[Positioned], start: f32, end: f32) -> bool {
+    let quarter = (end - start) / 4.0;
+    row.iter()
+        .any(|g| !g.is_space() && g.start < end - quarter && start + quarter < g.end)
+}
+
+/// Whether the row has glyphs right on both sides of the gap between `start` and `end`,
+/// within `margin` of it.
+fn straddles(row: &[Positioned], start: f32, end: f32, margin: f32) -> bool {
+    let glyphs = || row.iter().filter(|g| !g.is_space());
+    glyphs().any(|g| (start - margin..=start).contains(&g.end))
+        && glyphs().any(|g| (end..=end + margin).contains(&g.start))
+}
+
+/// Split a row of glyphs, sorted along the baseline, into lines of words.
+///
+/// The `neighbours` are the rows right above and below, which tell column gutters apart from
+/// wide gaps between words or after a bullet: a gutter runs between the lines of two columns.
+fn split_row(
+    glyphs: &[Positioned],
+    rotation: RotationAngle,
+    neighbours: &[&[Positioned]],
+) -> Vec<Row> {
+    let mut rows = Vec::new();
+    let mut line: Vec<&Positioned> = Vec::new();
+    let mut words = Vec::new();
+    let mut current: Vec<&Positioned> = Vec::new();
+    let mut end = f32::MIN;
+    // Middle of the last space glyph, which only separates words if it lies between them.
+    let mut space = None;
+
+    for glyph in glyphs {
+        if glyph.is_space() {
+            // Spaces painted on another baseline, e.g. on an empty line nearby, do not separate
+            // the words of this row.
+            let aligned = current.last().is_some_and(|last| {
+                (glyph.baseline - last.baseline).abs() < WORD_GAP * glyph.glyph.font_size
+            });
+            if aligned {
+                space = Some(
+                    space
+                        .unwrap_or(f32::MIN)
+                        .max(glyph.start + glyph.glyph.width / 2.0),
+                );
+            }
+            continue;
+        }
+
+        if let Some(last) = current.last() {
+            // Some writers fake bold text by painting every glyph twice, slightly offset.
+            let font_size = last.glyph.font_size.max(glyph.glyph.font_size);
+            if last.glyph.text == glyph.glyph.text
+                && (glyph.start - last.start).abs() < WORD_GAP * font_size
+            {
+                continue;
+            }
+
+            let gap = glyph.start - end;
+            let gutter = gap > GUTTER * font_size
+                && neighbours
+                    .iter()
+                    .any(|row| straddles(row, end, glyph.start, COLUMN_GAP * font_size))
+                && !neighbours.iter().any(|row| covers(row, end, glyph.start));
+
+            if gutter || gap > COLUMN_GAP * font_size {
+                words.push(word(&current));
+                rows.push(Row::new(std::mem::take(&mut words), rotation, &line));
+                current.clear();
+                line.clear();
+            } else if space.is_some_and(|middle| glyph.start >= middle)
+                || gap > WORD_GAP * font_size
+            {
+                words.push(word(&current));
+                current.clear();
+            }
+        }
+
+        current.push(glyph);
+        line.push(glyph);
+        end = end.max(glyph.end);
+        space = None;
+    }
+
+    if !current.is_empty() {
+        words.push(word(&current));
+        rows.push(Row::new(words, rotation, &line));
+    }
+
+    rows
+}
+
+/// Lines of glyphs that share a baseline direction, from top to bottom.
+fn rows(glyphs: Vec<Glyph>, rotation: RotationAngle) -> Vec<Row> {
+    let mut glyphs: Vec<Positioned> = glyphs.into_iter().map(Positioned::new).collect();
+    glyphs.sort_by(|a, b| a.baseline.total_cmp(&b.baseline));
+
+    // Bounds of each row of glyphs within the sorted vector.
+    let mut bounds = Vec::new();
+    let mut first = 0;
+
+    while let Some(head) = glyphs.get(first) {
+        let (baseline, font_size) = (head.baseline, head.glyph.font_size);
+        let len = glyphs[first..]
+            .iter()
+            .position(|g| g.baseline - baseline > 0.5 * font_size.max(g.glyph.font_size))
+            .unwrap_or(glyphs.len() - first);
+
+        // The sort is stable, which keeps glyphs painted at the same position in stream order.
+        glyphs[first..first + len].sort_by(|a, b| a.start.total_cmp(&b.start));
+        bounds.push(first..first + len);
+        first += len;
+    }
+
+    let rows: Vec<&[Positioned]> = bounds.into_iter().map(|range| &glyphs[range]).collect();
+
+    let mut lines = Vec::new();
+
+    for (i, row) in rows.iter().enumerate() {
+        let baseline = row[0].baseline;
+        let font_size = row[0].glyph.font_size;
+
+        let neighbours: Vec<&[Positioned]> = [i.checked_sub(1), Some(i + 1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|j| rows.get(j).copied())
+            .filter(|other| (other[0].baseline - baseline).abs() <= LINE_SPACING * font_size)
+            .collect();
+
+        lines.extend(split_row(row, rotation, &neighbours));
+    }
+
+    lines
+}
+
+/// Upright lines of glyphs, from top to bottom. Lines that share a baseline are split at
+/// column gaps.
+pub(super) fn lines(glyphs: Vec<Glyph>) -> Vec<TextLine> {
+    rows(glyphs, RotationAngle::Zero)
+        .into_iter()
+        .map(|row| row.line)
+        .collect()
+}
+
+/// A block, along with its extent in line space.
+struct Paragraph {
+    rows: Vec<Row>,
+    start: f32,
+    end: f32,
+}
+
+impl Paragraph {
+    /// Whether the row continues the paragraph, either on the same baseline or on the next
+    /// line.
+    fn accepts(&self, row: &Row) -> bool {
+        let last = self.rows.last().expect("paragraphs are never empty");
+        let font_size = last.font_size.max(row.font_size);
+        let distance = row.baseline - last.baseline;
+
+        let overlaps = row.overlaps(self.start, self.end);
+        let similar = font_size <= FONT_SIZE_RATIO * last.font_size.min(row.font_size);
+
+        overlaps && similar && distance <= LINE_SPACING * font_size
+    }
+
+    fn push(&mut self, row: Row) {
+        self.start = self.start.min(row.start);
+        self.end = self.end.max(row.end);
+
+        match self.rows.last_mut() {
+            // A line that was split at a large gap, e.g. in justified text.
+            Some(last) if (row.baseline - last.baseline).abs() < 0.5 * row.font_size => {
+                last.line.bbox = union([&last.line.bbox, &row.line.bbox]);
+                last.line.words.extend(row.line.words);
+                last.end = last.end.max(row.end);
+            }
+            _ => self.rows.push(row),
+        }
+    }
+
+    fn extent(&self) -> Extent {
+        let (first, last) = (&self.rows[0], &self.rows[self.rows.len() - 1]);
+        Extent {
+            left: self.start,
+            right: self.end,
+            top: first.baseline - first.font_size,
+            bottom: last.baseline,
+        }
+    }
+
+    fn into_block(self) -> TextBlock {
+        let lines: Vec<TextLine> = self.rows.into_iter().map(|row| row.line).collect();
+        TextBlock {
+            bbox: union(lines.iter().map(|line| &line.bbox)),
+            lines,
+        }
+    }
+}
+
+/// Group rows into paragraphs, and sort those in reading order.
+fn paragraphs(rows: Vec<Row>) -> Vec<Paragraph> {
+    let mut paragraphs: Vec<Paragraph> = Vec::new();
+
+    for row in rows {
+        match paragraphs.iter_mut().rev().find(|p| p.accepts(&row)) {
+            Some(paragraph) => paragraph.push(row),
+            None => paragraphs.push(Paragraph {
+                start: row.start,
+                end: row.end,
+                rows: vec![row],
+            }),
+        }
+    }
+
+    let extents: Vec<Extent> = paragraphs.iter().map(Paragraph::extent).collect();
+    let mut paragraphs: Vec<Option<Paragraph>> = paragraphs.into_iter().map(Some).collect();
+
+    reading_order(&extents)
+        .into_iter()
+        .filter_map(|i| paragraphs[i].take())
+        .collect()
+}
+
+impl TextPage {
+    /// Group glyphs, positioned in the display space of the `viewport`, into blocks of text
+    /// in reading order.
+    ///
+    /// Upright text comes first, followed by text that runs in other directions.
+    pub fn new(glyphs: Vec<Glyph>, viewport: Viewport) -> Self {
+        let mut blocks = Vec::new();
+
+        for rotation in [
+            RotationAngle::Zero,
+            RotationAngle::Quarter,
+            RotationAngle::Full,
+            RotationAngle::ThreeQuarters,
+        ] {
+            let glyphs: Vec<Glyph> = glyphs
+                .iter()
+                .filter(|g| g.rotation == rotation)
+                .cloned()
+                .collect();
+
+            let paragraphs = paragraphs(rows(glyphs, rotation));
+            blocks.extend(paragraphs.into_iter().map(Paragraph::into_block));
+        }
+
+        Self { viewport, blocks }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::extraction::Rectangle;
+
+    use super::*;
+
+    /// A line of text in a monospaced font, one unit wide and two units high, starting
+    /// at `(x, y)` in display space.
+    fn text(x: f32, y: f32, s: &str) -> Vec<Glyph> {
+        s.chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let x = x + i as f32;
+                Glyph {
+                    text: c.to_string(),
+                    origin: (x, y),
+                    width: 1.0,
+                    font_size: 2.0,
+                    rotation: RotationAngle::Zero,
+                    bbox: Rectangle::from((x, y - 1.6, x + 1.0, y + 0.4)),
+                }
+            })
+            .collect()
+    }
+
+    fn page(glyphs: Vec<Glyph>) -> String {
+        let viewport = Viewport {
+            bbox: Rectangle::from((0.0, 0.0, 100.0, 100.0)),
+            rotation: RotationAngle::Zero,
+        };
+
+        TextPage::new(glyphs, viewport).to_string()
+    }
+
+    #[rstest]
+    #[case(text(0.0, 10.0, "Hello world"), "Hello world")]
+    #[case([text(0.0, 10.0, "Hello"), text(5.5, 10.0, "world")].concat(), "Hello world")]
+    #[case([text(0.0, 10.0, "Hello"), text(5.1, 10.0, "world")].concat(), "Helloworld")]
+    #[case([text(6.0, 10.0, "world"), text(0.0, 10.0, "Hello")].concat(), "Hello world")]
+    #[case([text(0.0, 10.0, "Hello"), text(0.0, 10.1, "Hello")].concat(), "Hello")]
+    #[case([text(0.0, 12.5, "world"), text(0.0, 10.0, "Hello")].concat(), "Hello\nworld")]
+    #[case([text(0.0, 10.0, "Hello"), text(0.0, 20.0, "world")].concat(), "Hello\n\nworld")]
+    fn grouping(#[case] glyphs: Vec<Glyph>, #[case] expected: &str) {
+        assert_eq!(page(glyphs), expected);
+    }
+
+    #[test]
+    fn columns() {
+        let glyphs = [
+            text(0.0, 10.0, "left one"),
+            text(20.0, 10.0, "right one"),
+            text(0.0, 12.5, "left two"),
+            text(20.0, 12.5, "right two"),
+        ]
+        .concat();
+
+        assert_eq!(page(glyphs), "left one\nleft two\n\nright one\nright two");
+    }
+
+    #[test]
+    fn narrow_gutter() {
+        let glyphs = [
+            text(0.0, 10.0, "left one"),
+            text(10.4, 10.0, "right one"),
+            text(0.0, 12.5, "left two"),
+            text(10.4, 12.5, "right two"),
+            text(0.0, 15.0, "left 3"),
+            text(10.4, 15.0, "right 3"),
+        ]
+        .concat();
+
+        assert_eq!(
+            page(glyphs),
+            "left one\nleft two\nleft 3\n\nright one\nright two\nright 3"
+        );
+    }
+
+    #[test]
+    fn justified() {
+        let glyphs = [
+            text(0.0, 10.0, "a line of text"),
+            text(0.0, 12.5, "a"),
+            text(10.0, 12.5, "gap"),
+        ]
+        .concat();
+
+        assert_eq!(page(glyphs), "a line of text\na gap");
+    }
+
+    #[test]
+    fn rotated() {
+        let mut glyphs = text(0.0, 10.0, "upright");
+        for (i, c) in "down".chars().enumerate() {
+            let y = 20.0 + i as f32;
+            glyphs.push(Glyph {
+                text: c.to_string(),
+                origin: (50.0, y),
+                width: 1.0,
+                font_size: 2.0,
+                rotation: RotationAngle::Quarter,
+                bbox: Rectangle::from((50.0, y, 52.0, y + 1.0)),
+            });
+        }
+        glyphs.reverse();
+
+        assert_eq!(page(glyphs), "upright\n\ndown");
+    }
+}
@@ -0,0 +1,352 @@
+//! Export of the text layout to hOCR and ALTO, the XML formats of OCR engines.
+//!
+//! Both describe pages as blocks of lines of words, each with its bounding box. Coordinates
+//! are expressed in display space (see [`Viewport`](super::Viewport)), in points: the page's
+//! crop box and rotation are applied, such that the boxes match the page as it is shown.
+//!
+//! - [hOCR](https://kba.github.io/hocr-spec/1.2/) embeds the layout in XHTML, and only allows
+//!   integer coordinates. Font sizes are given by the `x_fsize` property;
+//! - [ALTO](https://www.loc.gov/standards/alto/) version 4 uses the `pixel` unit, which amounts
+//!   to a resolution of 72 dots per inch. Font sizes are given by shared text styles.
+
+use std::io::{self, Write};
+
+use crate::{content::xml::escape, extraction::Rectangle, structure::RotationAngle};
+
+use super::{TextLine, TextPage};
+
+/// Both formats measure angles counter-clockwise.
+fn counter_clockwise(rotation: RotationAngle) -> u16 {
+    (360 - rotation.degrees()) % 360
+}
+
+/// Largest font size among the words of the line.
+fn font_size(line: &TextLine) -> f32 {
+    line.words.iter().map(|w| w.font_size).fold(0.0, f32::max)
+}
+
+/// The hOCR `bbox` property.
+fn bbox(rectangle: &Rectangle) -> String {
+    format!(
+        "bbox {} {} {} {}",
+        rectangle.xmin.floor(),
+        rectangle.ymin.floor(),
+        rectangle.xmax.ceil(),
+        rectangle.ymax.ceil()
+    )
+}
+
+/// Write the pages as an hOCR document.
+///
+/// Blocks are exported as `ocr_carea` areas holding a single `ocr_par` paragraph.
+pub fn write_hocr<W>(pages: &[TextPage], writer: &mut W) -> io::Result<()>
+where
+    W: Write,
+{
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Transitional//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd">"#
+    )?;
+    writeln!(writer, r#"<html xmlns="http://www.w3.org/1999/xhtml">"#)?;
+    writeln!(writer, " <head>")?;
+    writeln!(writer, "  <title></title>")?;
+    writeln!(
+        writer,
+        r#"  <meta http-equiv="Content-Type" content="text/html; charset=utf-8" />"#
+    )?;
+    writeln!(writer, r#"  <meta name="ocr-system" content="livre" />"#)?;
+    writeln!(
+        writer,
+        r#"  <meta name="ocr-capabilities" content="ocr_page ocr_carea ocr_par ocr_line ocrx_word ocrp_font" />"#
+    )?;
+    writeln!(writer, " </head>")?;
+    writeln!(writer, " <body>")?;
+
+    for (p, page) in pages.iter().enumerate() {
+        let n = p + 1;
+        let area = Rectangle::from((0.0, 0.0, page.viewport.width(), page.viewport.height()));
+        writeln!(
+            writer,
+            r#"  <div class="ocr_page" id="page_{n}" title="{}; ppageno {p}">"#,
+            bbox(&area)
+        )?;
+
+        let (mut lines, mut words) = (0, 0);
+
+        for (b, block) in page.blocks.iter().enumerate() {
+            let b = b + 1;
+            let title = bbox(&block.bbox);
+            writeln!(
+                writer,
+                r#"   <div class="ocr_carea" id="block_{n}_{b}" title="{title}">"#
+            )?;
+            writeln!(
+                writer,
+                r#"    <p class="ocr_par" id="par_{n}_{b}" title="{title}">"#
+            )?;
+
+            for line in &block.lines {
+                lines += 1;
+                let mut title = format!("{}; x_fsize {:.0}", bbox(&line.bbox), font_size(line));
+                if line.rotation != RotationAngle::Zero {
+                    title += &format!("; textangle {}", counter_clockwise(line.rotation));
+                }
+                write!(
+                    writer,
+                    r#"     <span class="ocr_line" id="line_{n}_{lines}" title="{title}">"#
+                )?;
+
+                for (i, word) in line.words.iter().enumerate() {
+                    words += 1;
+                    if i > 0 {
+                        write!(writer, " ")?;
+                    }
+                    write!(
+                        writer,
+                        r#"<span class="ocrx_word" id="word_{n}_{words}" title="{}; x_fsize {:.0}">{}</span>"#,
+                        bbox(&word.bbox),
+                        word.font_size,
+                        escape(&word.text)
+                    )?;
+                }
+
+                writeln!(writer, "</span>")?;
+            }
+
+            writeln!(writer, "    </p>")?;
+            writeln!(writer, "   </div>")?;
+        }
+
+        writeln!(writer, "  </div>")?;
+    }
+
+    writeln!(writer, " </body>")?;
+    writeln!(writer, "</html>")
+}
+
+/// The ALTO position and size attributes.
+fn geometry(rectangle: &Rectangle) -> String {
+    format!(
+        r#"HPOS="{:.2}" VPOS="{:.2}" WIDTH="{:.2}" HEIGHT="{:.2}""#,
+        rectangle.xmin,
+        rectangle.ymin,
+        rectangle.xmax - rectangle.xmin,
+        rectangle.ymax - rectangle.ymin
+    )
+}
+
+/// Font sizes are shared by words, rounded to a tenth of a point.
+fn style(font_size: f32) -> i32 {
+    (font_size * 10.0).round() as i32
+}
+
+/// Write the pages as an ALTO document.
+///
+/// Blocks are exported as `TextBlock` elements, rotated as their first line.
+pub fn write_alto<W>(pages: &[TextPage], writer: &mut W) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut styles: Vec<i32> = pages
+        .iter()
+        .flat_map(|page| &page.blocks)
+        .flat_map(|block| &block.lines)
+        .flat_map(|line| &line.words)
+        .map(|word| style(word.font_size))
+        .collect();
+    styles.sort_unstable();
+    styles.dedup();
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<alto xmlns="http://www.loc.gov/standards/alto/ns-v4#" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://www.loc.gov/standards/alto/ns-v4# http://www.loc.gov/alto/v4/alto-4-2.xsd">"#
+    )?;
+    writeln!(writer, "  <Description>")?;
+    writeln!(writer, "    <MeasurementUnit>pixel</MeasurementUnit>")?;
+    writeln!(writer, "  </Description>")?;
+
+    if !styles.is_empty() {
+        writeln!(writer, "  <Styles>")?;
+        for &size in &styles {
+            writeln!(
+                writer,
+                r#"    <TextStyle ID="FS{size}" FONTSIZE="{:.1}"/>"#,
+                size as f32 / 10.0
+            )?;
+        }
+        writeln!(writer, "  </Styles>")?;
+    }
+
+    writeln!(writer, "  <Layout>")?;
+
+    for (p, page) in pages.iter().enumerate() {
+        let n = p + 1;
+        let (width, height) = (page.viewport.width(), page.viewport.height());
+        writeln!(
+            writer,
+            r#"    <Page ID="P{n}" PHYSICAL_IMG_NR="{n}" WIDTH="{width:.2}" HEIGHT="{height:.2}">"#,
+        )?;
+        writeln!(
+            writer,
+            r#"      <PrintSpace HPOS="0.00" VPOS="0.00" WIDTH="{width:.2}" HEIGHT="{height:.2}">"#,
+        )?;
+
+        let (mut lines, mut words) = (0, 0);
+
+        for (b, block) in page.blocks.iter().enumerate() {
+            let rotation = block
+                .lines
+                .first()
+                .map_or(0, |line| counter_clockwise(line.rotation));
+            let rotation = if rotation == 0 {
+                String::new()
+            } else {
+                format!(r#" ROTATION="{rotation}""#)
+            };
+            writeln!(
+                writer,
+                r#"        <TextBlock ID="P{n}_TB{}" {}{rotation}>"#,
+                b + 1,
+                geometry(&block.bbox)
+            )?;
+
+            for line in &block.lines {
+                lines += 1;
+                writeln!(
+                    writer,
+                    r#"          <TextLine ID="P{n}_TL{lines}" {}>"#,
+                    geometry(&line.bbox)
+                )?;
+
+                for (i, word) in line.words.iter().enumerate() {
+                    if i > 0 {
+                        let previous = &line.words[i - 1].bbox;
+                        let gap = word.bbox.xmin - previous.xmax;
+                        // Spaces only have a position along upright lines.
+                        if line.rotation == RotationAngle::Zero && gap > 0.0 {
+                            writeln!(
+                                writer,
+                                r#"            <SP HPOS="{:.2}" VPOS="{:.2}" WIDTH="{gap:.2}"/>"#,
+                                previous.xmax, line.bbox.ymin
+                            )?;
+                        } else {
+                            writeln!(writer, "            <SP/>")?;
+                        }
+                    }
+
+                    words += 1;
+                    writeln!(
+                        writer,
+                        r#"            <String ID="P{n}_ST{words}" CONTENT="{}" {} STYLEREFS="FS{}"/>"#,
+                        escape(&word.text),
+                        geometry(&word.bbox),
+                        style(word.font_size)
+                    )?;
+                }
+
+                writeln!(writer, "          </TextLine>")?;
+            }
+
+            writeln!(writer, "        </TextBlock>")?;
+        }
+
+        writeln!(writer, "      </PrintSpace>")?;
+        writeln!(writer, "    </Page>")?;
+    }
+
+    writeln!(writer, "  </Layout>")?;
+    writeln!(writer, "</alto>")
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::content::layout::{TextBlock, Viewport, Word};
+
+    use super::*;
+
+    fn word(text: &str, xmin: f32, xmax: f32) -> Word {
+        Word {
+            text: text.to_string(),
+            bbox: Rectangle::from((xmin, 10.0, xmax, 22.0)),
+            font_size: 12.0,
+        }
+    }
+
+    fn page() -> TextPage {
+        let words = vec![
+            word("Fish", 10.0, 32.5),
+            word("&", 36.0, 44.0),
+            word("chips", 48.0, 75.0),
+        ];
+        let bbox = Rectangle::from((10.0, 10.0, 75.0, 22.0));
+
+        TextPage {
+            viewport: Viewport {
+                bbox: Rectangle::from((0.0, 0.0, 200.0, 100.0)),
+                rotation: RotationAngle::Quarter,
+            },
+            blocks: vec![TextBlock {
+                lines: vec![TextLine {
+                    words,
+                    bbox,
+                    rotation: RotationAngle::Zero,
+                }],
+                bbox,
+            }],
+        }
+    }
+
+    #[test]
+    fn hocr() {
+        let mut output = Vec::new();
+        write_hocr(&[page()], &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let expected = indoc! {r#"
+              <div class="ocr_page" id="page_1" title="bbox 0 0 100 200; ppageno 0">
+               <div class="ocr_carea" id="block_1_1" title="bbox 10 10 75 22">
+                <p class="ocr_par" id="par_1_1" title="bbox 10 10 75 22">
+                 <span class="ocr_line" id="line_1_1" title="bbox 10 10 75 22; x_fsize 12"><span class="ocrx_word" id="word_1_1" title="bbox 10 10 33 22; x_fsize 12">Fish</span> <span class="ocrx_word" id="word_1_2" title="bbox 36 10 44 22; x_fsize 12">&amp;</span> <span class="ocrx_word" id="word_1_3" title="bbox 48 10 75 22; x_fsize 12">chips</span></span>
+                </p>
+               </div>
+              </div>
+             </body>
+            </html>
+        "#};
+        assert!(output.ends_with(expected), "{output}");
+    }
+
+    #[test]
+    fn alto() {
+        let mut output = Vec::new();
+        write_alto(&[page()], &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let expected = indoc! {r#"
+              <Styles>
+                <TextStyle ID="FS120" FONTSIZE="12.0"/>
+              </Styles>
+              <Layout>
+                <Page ID="P1" PHYSICAL_IMG_NR="1" WIDTH="100.00" HEIGHT="200.00">
+                  <PrintSpace HPOS="0.00" VPOS="0.00" WIDTH="100.00" HEIGHT="200.00">
+                    <TextBlock ID="P1_TB1" HPOS="10.00" VPOS="10.00" WIDTH="65.00" HEIGHT="12.00">
+                      <TextLine ID="P1_TL1" HPOS="10.00" VPOS="10.00" WIDTH="65.00" HEIGHT="12.00">
+                        <String ID="P1_ST1" CONTENT="Fish" HPOS="10.00" VPOS="10.00" WIDTH="22.50" HEIGHT="12.00" STYLEREFS="FS120"/>
+                        <SP HPOS="32.50" VPOS="10.00" WIDTH="3.50"/>
+                        <String ID="P1_ST2" CONTENT="&amp;" HPOS="36.00" VPOS="10.00" WIDTH="8.00" HEIGHT="12.00" STYLEREFS="FS120"/>
+                        <SP HPOS="44.00" VPOS="10.00" WIDTH="4.00"/>
+                        <String ID="P1_ST3" CONTENT="chips" HPOS="48.00" VPOS="10.00" WIDTH="27.00" HEIGHT="12.00" STYLEREFS="FS120"/>
+                      </TextLine>
+                    </TextBlock>
+                  </PrintSpace>
+                </Page>
+              </Layout>
+            </alto>
+        "#};
+        assert!(output.ends_with(expected), "{output}");
+    }
+}
@@ -0,0 +1,171 @@
+//! Detection of running headers and footers.
+//!
+//! Headers and footers repeat on most pages of a document, and get in the way of the actual
+//! text, e.g. in a search index. A block is deemed a header or a footer if it lies within the
+//! top or bottom [`MARGIN`] of the page, and a similar block appears at the same height on at
+//! least half of the pages - and on two pages at the very least.
+//!
+//! Blocks are compared by their text, with digits masked such that page numbers match, and by
+//! their position relative to the page size given by the [`Viewport`](super::Viewport).
+
+use std::collections::HashMap;
+
+use super::{TextBlock, TextPage};
+
+/// Height of the header and footer areas, relative to the page height.
+const MARGIN: f32 = 0.15;
+
+/// Maximal difference in height between two occurrences of a header or footer, relative to
+/// the page height.
+const TOLERANCE: f32 = 0.02;
+
+/// The text of the block, with its digits masked.
+fn key(block: &TextBlock) -> String {
+    block
+        .to_string()
+        .chars()
+        .map(|c| if c.is_ascii_digit() { '#' } else { c })
+        .collect()
+}
+
+/// The indices of the blocks that are running headers or footers, for each page.
+pub fn headers_and_footers(pages: &[TextPage]) -> Vec<Vec<usize>> {
+    let threshold = pages.len().div_ceil(2).max(2);
+
+    // Candidates by key: the page, the block and its normalized vertical position.
+    let mut candidates: HashMap<String, Vec<(usize, usize, f32)>> = HashMap::new();
+
+    for (p, page) in pages.iter().enumerate() {
+        for (b, block) in page.blocks.iter().enumerate() {
+            let bbox = page.viewport.normalize(&block.bbox);
+            if bbox.ymax <= MARGIN || bbox.ymin >= 1.0 - MARGIN {
+                let middle = (bbox.ymin + bbox.ymax) / 2.0;
+                candidates
+                    .entry(key(block))
+                    .or_default()
+                    .push((p, b, middle));
+            }
+        }
+    }
+
+    let mut running = vec![Vec::new(); pages.len()];
+
+    for occurrences in candidates.values() {
+        for &(p, b, middle) in occurrences {
+            let mut matching: Vec<usize> = occurrences
+                .iter()
+                .filter(|(_, _, other)| (middle - other).abs() <= TOLERANCE)
+                .map(|&(page, _, _)| page)
+                .collect();
+            matching.dedup();
+
+            if matching.len() >= threshold {
+                running[p].push(b);
+            }
+        }
+    }
+
+    for blocks in &mut running {
+        blocks.sort_unstable();
+    }
+
+    running
+}
+
+/// Remove the running headers and footers from the pages (see [`headers_and_footers`]).
+pub fn remove_headers_and_footers(pages: &mut [TextPage]) {
+    let running = headers_and_footers(pages);
+
+    for (page, running) in pages.iter_mut().zip(running) {
+        let mut index = 0;
+        page.blocks.retain(|_| {
+            let keep = running.binary_search(&index).is_err();
+            index += 1;
+            keep
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::{
+        content::layout::{TextLine, Viewport, Word},
+        extraction::Rectangle,
+        structure::RotationAngle,
+    };
+
+    use super::*;
+
+    fn block(text: &str, y: f32) -> TextBlock {
+        let bbox = Rectangle::from((10.0, y, 90.0, y + 2.0));
+        TextBlock {
+            lines: vec![TextLine {
+                words: vec![Word {
+                    text: text.to_string(),
+                    bbox,
+                    font_size: 2.0,
+                }],
+                bbox,
+                rotation: RotationAngle::Zero,
+            }],
+            bbox,
+        }
+    }
+
+    fn page(blocks: Vec<TextBlock>) -> TextPage {
+        TextPage {
+            viewport: Viewport {
+                bbox: Rectangle::from((0.0, 0.0, 100.0, 100.0)),
+                rotation: RotationAngle::Zero,
+            },
+            blocks,
+        }
+    }
+
+    #[rstest]
+    #[case(
+        vec![
+            page(vec![block("Header", 2.0), block("Body", 50.0), block("1", 95.0)]),
+            page(vec![block("Header", 2.0), block("Other", 50.0), block("2", 95.0)]),
+            page(vec![block("Header", 2.0), block("Body", 50.0), block("3", 95.0)]),
+        ],
+        vec![vec![0, 2], vec![0, 2], vec![0, 2]],
+    )]
+    // The title of a chapter only appears once.
+    #[case(
+        vec![
+            page(vec![block("Title", 2.0), block("Body", 50.0)]),
+            page(vec![block("Header", 2.0), block("Body", 50.0)]),
+            page(vec![block("Header", 2.0), block("Body", 50.0)]),
+        ],
+        vec![vec![], vec![0], vec![0]],
+    )]
+    // The same text, at a different height.
+    #[case(
+        vec![
+            page(vec![block("Header", 2.0)]),
+            page(vec![block("Header", 10.0)]),
+        ],
+        vec![vec![], vec![]],
+    )]
+    // A single page has no running headers.
+    #[case(vec![page(vec![block("Header", 2.0)])], vec![vec![]])]
+    fn detection(#[case] pages: Vec<TextPage>, #[case] expected: Vec<Vec<usize>>) {
+        assert_eq!(headers_and_footers(&pages), expected);
+    }
+
+    #[test]
+    fn removal() {
+        let mut pages = vec![
+            page(vec![block("Header", 2.0), block("Body", 50.0)]),
+            page(vec![block("Header", 2.0), block("Other", 50.0)]),
+        ];
+
+        remove_headers_and_footers(&mut pages);
+
+        let text: Vec<String> = pages.iter().map(|page| page.to_string()).collect();
+        assert_eq!(text, ["Body", "Other"]);
+    }
+}
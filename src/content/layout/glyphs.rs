@@ -0,0 +1,341 @@
+//! Positioning of glyphs on the page.
+//!
+//! From the specification, the glyph displacement after painting a glyph is:
+//!
+//! > $t_x = ((w_0 - \frac{T_j}{1000}) \times T_{fs} + T_c + T_w) \times T_h$
+//!
+//! where $w_0$ is the glyph width, $T_j$ the adjustment from a `TJ` array, $T_{fs}$ the font
+//! size, $T_c$ the character spacing, $T_w$ the word spacing (for single-byte code 32 only)
+//! and $T_h$ the horizontal scaling. Moreover:
+//!
+//! > the text rendering matrix, $T_{rm}$, is a temporary matrix [...] that maps from text space
+//! > to device space: $T_{rm} = [T_{fs} \times T_h, 0, 0, T_{fs}, 0, T_{rise}] \times T_m \times CTM$
+
+use std::collections::HashMap;
+
+use crate::{
+    content::{
+        font::Font,
+        interpreter::{
+            ContentInterpreter, Device, FillRule, GraphicsState, Path, PathElement, TextGlyph,
+        },
+        operators::Operator,
+    },
+    extraction::{Name, Rectangle},
+    structure::{Resources, RotationAngle},
+};
+
+use super::{Glyph, Painted, Segment, Viewport};
+
+/// Text extraction and table detection are a device, which expects the interpreter to map user
+/// space to the display space of a [`Viewport`].
+impl Device for Painted {
+    fn fill_path(&mut self, path: &Path, _: FillRule, state: &GraphicsState) {
+        // Filling closes every subpath.
+        self.segments.extend(segments(path, state, true));
+    }
+
+    fn stroke_path(&mut self, path: &Path, state: &GraphicsState) {
+        self.segments.extend(segments(path, state, false));
+    }
+
+    fn fill_and_stroke_path(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {
+        self.fill_path(path, rule, state);
+    }
+
+    fn draw_glyph(&mut self, glyph: &TextGlyph<'_>, _: &GraphicsState) {
+        let TextGlyph {
+            font,
+            code,
+            matrix: rendering,
+            width: w0,
+        } = *glyph;
+
+        let (ascent, descent) = (font.ascent() / 1000.0, font.descent() / 1000.0);
+
+        let origin = rendering.transform(0.0, 0.0);
+        let end = rendering.transform(w0, 0.0);
+        let top = rendering.transform(0.0, 1.0);
+
+        let corners = [
+            rendering.transform(0.0, descent),
+            rendering.transform(0.0, ascent),
+            rendering.transform(w0, descent),
+            rendering.transform(w0, ascent),
+        ];
+        let bbox = corners.iter().fold(
+            Rectangle::from((f32::MAX, f32::MAX, f32::MIN, f32::MIN)),
+            |bbox, &(x, y)| Rectangle {
+                xmin: bbox.xmin.min(x),
+                ymin: bbox.ymin.min(y),
+                xmax: bbox.xmax.max(x),
+                ymax: bbox.ymax.max(y),
+            },
+        );
+
+        // Without a proper baseline direction, e.g. for a glyph of zero width, the vertical
+        // axis of the glyph tells the direction.
+        let (dx, dy) = if end != origin {
+            (end.0 - origin.0, end.1 - origin.1)
+        } else {
+            (origin.1 - top.1, top.0 - origin.0)
+        };
+
+        self.glyphs.push(Glyph {
+            text: font
+                .text(code)
+                .unwrap_or_else(|| char::REPLACEMENT_CHARACTER.to_string()),
+            origin,
+            width: dx.hypot(dy),
+            font_size: (top.0 - origin.0).hypot(top.1 - origin.1),
+            rotation: rotation(dx, dy),
+            bbox,
+        });
+    }
+}
+
+/// The straight segments of a path, in display space. Curves do not make up rules, but move
+/// the current point. If `close` is set, open subpaths are closed, as when filling.
+fn segments(path: &Path, state: &GraphicsState, close: bool) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    // The current point and the start of the current subpath.
+    let mut current: Option<((f32, f32), (f32, f32))> = None;
+
+    let closing = |current: Option<((f32, f32), (f32, f32))>| {
+        current
+            .filter(|(point, start)| point != start)
+            .map(|(point, start)| Segment {
+                start: point,
+                end: start,
+            })
+    };
+
+    for element in path.transform(&state.ctm).elements {
+        match element {
+            PathElement::MoveTo(point) => {
+                if close {
+                    segments.extend(closing(current));
+                }
+                current = Some((point, point));
+            }
+            PathElement::LineTo(point) => {
+                if let Some((previous, start)) = current {
+                    segments.push(Segment {
+                        start: previous,
+                        end: point,
+                    });
+                    current = Some((point, start));
+                }
+            }
+            PathElement::CurveTo(_, _, point) => {
+                current = current.map(|(_, start)| (point, start));
+            }
+            PathElement::ClosePath => {
+                segments.extend(closing(current));
+                current = current.map(|(_, start)| (start, start));
+            }
+        }
+    }
+
+    if close {
+        segments.extend(closing(current));
+    }
+
+    segments
+}
+
+/// The baseline direction `(dx, dy)`, rounded to the closest quarter turn. The y axis points
+/// downwards in display space, hence positive angles are clockwise.
+fn rotation(dx: f32, dy: f32) -> RotationAngle {
+    let quarters = (dy.atan2(dx) / std::f32::consts::FRAC_PI_2).round() as i32;
+
+    match quarters.rem_euclid(4) {
+        0 => RotationAngle::Zero,
+        1 => RotationAngle::Quarter,
+        2 => RotationAngle::Full,
+        _ => RotationAngle::ThreeQuarters,
+    }
+}
+
+/// Run the operators of a content stream, and position the glyphs and straight path segments
+/// they paint in the display space of the `viewport`.
+///
+/// Fonts are looked up by their resource name. A missing font is replaced with a default
+/// [`Font`], such that the text is still extracted, if approximately. Without resources,
+/// external objects are not painted: use [`Page::interpret`](crate::structure::Page::interpret)
+/// with a [`Painted`] device instead.
+pub fn position_content<I>(operators: I, fonts: &HashMap<Name, Font>, viewport: Viewport) -> Painted
+where
+    I: IntoIterator<Item = Operator>,
+{
+    let mut interpreter =
+        ContentInterpreter::new(&(), Resources::default(), viewport.matrix()).with_fonts(fonts);
+    let mut painted = Painted::default();
+
+    // Without external objects, the interpreter cannot fail.
+    let _ = interpreter.run(operators.into_iter().map(Ok), &mut painted);
+
+    painted
+}
+
+/// Run the operators of a content stream, and position the glyphs they paint in the
+/// display space of the `viewport` (see [`position_content`]).
+pub fn position_glyphs<I>(
+    operators: I,
+    fonts: &HashMap<Name, Font>,
+    viewport: Viewport,
+) -> Vec<Glyph>
+where
+    I: IntoIterator<Item = Operator>,
+{
+    position_content(operators, fonts, viewport).glyphs
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use winnow::{
+        ascii::multispace0,
+        combinator::{iterator, preceded},
+        BStr,
+    };
+
+    use crate::extraction::Extract;
+
+    use super::*;
+
+    fn glyphs(content: &[u8], rotation: RotationAngle) -> Vec<Glyph> {
+        let mut input = BStr::new(content);
+        let mut it = iterator(&mut input, preceded(multispace0, Operator::extract));
+        let operators: Vec<Operator> = it.collect();
+
+        let viewport = Viewport {
+            bbox: Rectangle::from((0.0, 0.0, 200.0, 100.0)),
+            rotation,
+        };
+
+        position_glyphs(operators, &HashMap::new(), viewport)
+    }
+
+    type Point = (f32, f32);
+
+    fn segments(content: &[u8]) -> Vec<Segment> {
+        let mut input = BStr::new(content);
+        let mut it = iterator(&mut input, preceded(multispace0, Operator::extract));
+        let operators: Vec<Operator> = it.collect();
+
+        let viewport = Viewport {
+            bbox: Rectangle::from((0.0, 0.0, 200.0, 100.0)),
+            rotation: RotationAngle::Zero,
+        };
+
+        position_content(operators, &HashMap::new(), viewport).segments
+    }
+
+    fn assert_close(actual: (f32, f32), expected: (f32, f32)) {
+        let distance = (actual.0 - expected.0).hypot(actual.1 - expected.1);
+        assert!(distance < 1e-3, "{actual:?} != {expected:?}");
+    }
+
+    #[rstest]
+    // Helvetica: `A` is 667 units wide, `V` 667 and the space 278.
+    #[case(b"BT /F1 10 Tf 10 20 Td (AV) Tj ET", &[(10.0, 80.0), (16.67, 80.0)])]
+    #[case(b"BT /F1 10 Tf 10 20 Td [(A) -1000 (V)] TJ ET", &[(10.0, 80.0), (26.67, 80.0)])]
+    #[case(b"BT /F1 10 Tf 2 Tc 10 20 Td (AV) Tj ET", &[(10.0, 80.0), (18.67, 80.0)])]
+    #[case(b"BT /F1 10 Tf 5 Tw 10 20 Td ( V) Tj ET", &[(10.0, 80.0), (17.78, 80.0)])]
+    #[case(b"BT /F1 10 Tf 50 Tz 10 20 Td (AV) Tj ET", &[(10.0, 80.0), (13.335, 80.0)])]
+    #[case(b"BT /F1 10 Tf 12 TL 10 20 Td (A) Tj T* (V) Tj ET", &[(10.0, 80.0), (10.0, 92.0)])]
+    #[case(b"BT /F1 10 Tf 10 20 Td (A) Tj 0 -12 Td (V) Tj ET", &[(10.0, 80.0), (10.0, 92.0)])]
+    #[case(b"2 0 0 2 0 0 cm BT /F1 10 Tf 10 20 Td (AV) Tj ET", &[(20.0, 60.0), (33.34, 60.0)])]
+    #[case(b"q 2 0 0 2 0 0 cm Q BT /F1 10 Tf 10 20 Td (A) Tj ET", &[(10.0, 80.0)])]
+    fn positions(#[case] content: &[u8], #[case] expected: &[(f32, f32)]) {
+        let glyphs = glyphs(content, RotationAngle::Zero);
+
+        assert_eq!(glyphs.len(), expected.len());
+        for (glyph, &origin) in glyphs.iter().zip(expected) {
+            assert_close(glyph.origin, origin);
+        }
+    }
+
+    #[rstest]
+    #[case(b"BT /F1 10 Tf (A) Tj ET", RotationAngle::Zero, RotationAngle::Zero)]
+    #[case(
+        b"BT /F1 10 Tf 0 1 -1 0 50 50 Tm (A) Tj ET",
+        RotationAngle::Zero,
+        RotationAngle::ThreeQuarters
+    )]
+    #[case(
+        b"BT /F1 10 Tf 0 1 -1 0 50 50 Tm (A) Tj ET",
+        RotationAngle::Quarter,
+        RotationAngle::Zero
+    )]
+    #[case(
+        b"BT /F1 10 Tf (A) Tj ET",
+        RotationAngle::Quarter,
+        RotationAngle::Quarter
+    )]
+    #[case(
+        b"BT /F1 10 Tf -1 0 0 -1 50 50 Tm (A) Tj ET",
+        RotationAngle::Zero,
+        RotationAngle::Full
+    )]
+    fn rotations(
+        #[case] content: &[u8],
+        #[case] page: RotationAngle,
+        #[case] expected: RotationAngle,
+    ) {
+        let glyphs = glyphs(content, page);
+        assert_eq!(glyphs[0].rotation, expected);
+    }
+
+    #[test]
+    fn glyph() {
+        let glyphs = glyphs(b"BT /F1 10 Tf 10 20 Td (A) Tj ET", RotationAngle::Zero);
+        let Glyph {
+            text,
+            width,
+            font_size,
+            bbox,
+            ..
+        } = &glyphs[0];
+
+        assert_eq!(text, "A");
+        assert!((width - 6.67).abs() < 1e-3);
+        assert!((font_size - 10.0).abs() < 1e-3);
+        assert_close((bbox.xmin, bbox.ymin), (10.0, 72.0));
+        assert_close((bbox.xmax, bbox.ymax), (16.67, 82.0));
+    }
+
+    #[rstest]
+    #[case(b"10 20 m 50 20 l S", &[((10.0, 80.0), (50.0, 80.0))])]
+    #[case(b"10 20 m 50 20 l 50 30 l s", &[
+        ((10.0, 80.0), (50.0, 80.0)),
+        ((50.0, 80.0), (50.0, 70.0)),
+        ((50.0, 70.0), (10.0, 80.0)),
+    ])]
+    #[case(b"2 0 0 2 0 0 cm 10 20 m 50 20 l S", &[((20.0, 60.0), (100.0, 60.0))])]
+    #[case(b"10 20 40 1 re f", &[
+        ((10.0, 80.0), (50.0, 80.0)),
+        ((50.0, 80.0), (50.0, 79.0)),
+        ((50.0, 79.0), (10.0, 79.0)),
+        ((10.0, 79.0), (10.0, 80.0)),
+    ])]
+    // Filling closes the subpath.
+    #[case(b"10 20 m 50 20 l 50 30 l f", &[
+        ((10.0, 80.0), (50.0, 80.0)),
+        ((50.0, 80.0), (50.0, 70.0)),
+        ((50.0, 70.0), (10.0, 80.0)),
+    ])]
+    #[case(b"10 20 m 20 30 30 30 50 20 c S", &[])]
+    // Clipping paths are not painted.
+    #[case(b"10 20 40 1 re W n", &[])]
+    fn paths(#[case] content: &[u8], #[case] expected: &[(Point, Point)]) {
+        let segments = segments(content);
+
+        assert_eq!(segments.len(), expected.len());
+        for (segment, &(start, end)) in segments.iter().zip(expected) {
+            assert_close(segment.start, start);
+            assert_close(segment.end, end);
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//! Layout-aware text extraction.
+//!
+//! Content streams paint glyphs one string at a time, in whatever order the PDF writer chose.
+//! Nothing marks the spaces between words - a writer may just as well move the text matrix to
+//! the start of the next word - nor the end of a line or paragraph. Extracting readable text
+//! therefore requires positioning every glyph on the page, and grouping them back together:
+//!
+//! 1. [`position_glyphs`] runs the text-related operators of a content stream, using the
+//!    [fonts](crate::content::font) metrics to place each [`Glyph`] on the page;
+//! 2. [`TextPage::new`] groups the glyphs into [words](Word), [lines](TextLine) and
+//!    [blocks](TextBlock), based on the gaps between them, and sorts them in reading order,
+//!    following the columns of the page;
+//! 3. optionally, [`remove_headers_and_footers`] drops the blocks that repeat across pages.
+//!
+//! The resulting layout can be exported to hOCR and ALTO, with [`write_hocr`] and
+//! [`write_alto`].
+//!
+//! [`position_content`] also collects the straight segments of painted paths, from which
+//! [`find_tables`] detects ruled tables - and unruled ones from the alignment of the glyphs.
+//! The collection itself, [`Painted`], is a [`Device`](super::interpreter::Device) of the
+//! content interpreter, which pages run with [`Page::interpret`].
+//!
+//! Coordinates are expressed in *display space*, defined by the page's [`Viewport`]: the origin
+//! lies at the top-left corner of the visible page, and the y axis points downwards.
+
+mod analysis;
+mod export;
+mod furniture;
+mod glyphs;
+mod order;
+mod tables;
+
+use std::fmt::{self, Display};
+
+pub use export::{write_alto, write_hocr};
+pub use furniture::{headers_and_footers, remove_headers_and_footers};
+pub use glyphs::{position_content, position_glyphs};
+pub use tables::{find_tables, Cell, Table};
+
+use crate::{
+    extraction::Rectangle,
+    structure::{Page, RotationAngle},
+};
+
+use super::state::Matrix;
+
+/// The visible region of a page, as it is displayed.
+///
+/// It maps user space to display space, taking the page rotation into account. For instance,
+/// the display space of a page rotated by a quarter turn is as wide as the page is high.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// The visible region of the page, in user space.
+    pub bbox: Rectangle,
+    /// The clockwise rotation of the page.
+    pub rotation: RotationAngle,
+}
+
+impl Viewport {
+    /// The viewport of a page: its crop box, which defaults to its media box. From the
+    /// specification, the crop box is clipped to the media box.
+    ///
+    /// Pages that define neither fall back to the US Letter format.
+    pub fn of(page: &Page) -> Self {
+        let props = &page.inheritable_props;
+        let media_box = props
+            .media_box
+            .map(ordered_corners)
+            .unwrap_or(Rectangle::from((0.0, 0.0, 612.0, 792.0)));
+
+        let bbox = match props.crop_box.map(ordered_corners) {
+            Some(crop_box) => {
+                let clipped = Rectangle {
+                    xmin: crop_box.xmin.max(media_box.xmin),
+                    ymin: crop_box.ymin.max(media_box.ymin),
+                    xmax: crop_box.xmax.min(media_box.xmax),
+                    ymax: crop_box.ymax.min(media_box.ymax),
+                };
+                // A crop box that lies outside of the media box is ignored.
+                if clipped.xmin < clipped.xmax && clipped.ymin < clipped.ymax {
+                    clipped
+                } else {
+                    media_box
+                }
+            }
+            None => media_box,
+        };
+
+        Self {
+            bbox,
+            rotation: props.rotate.unwrap_or_default(),
+        }
+    }
+
+    /// Width of the page as displayed.
+    pub fn width(&self) -> f32 {
+        match self.rotation {
+            RotationAngle::Zero | RotationAngle::Full => self.bbox.xmax - self.bbox.xmin,
+            RotationAngle::Quarter | RotationAngle::ThreeQuarters => {
+                self.bbox.ymax - self.bbox.ymin
+            }
+        }
+    }
+
+    /// Height of the page as displayed.
+    pub fn height(&self) -> f32 {
+        match self.rotation {
+            RotationAngle::Zero | RotationAngle::Full => self.bbox.ymax - self.bbox.ymin,
+            RotationAngle::Quarter | RotationAngle::ThreeQuarters => {
+                self.bbox.xmax - self.bbox.xmin
+            }
+        }
+    }
+
+    /// Express a rectangle in display space as fractions of the page's width and height,
+    /// such that pages of different sizes can be compared.
+    pub fn normalize(&self, rectangle: &Rectangle) -> Rectangle {
+        let (width, height) = (self.width(), self.height());
+        Rectangle {
+            xmin: rectangle.xmin / width,
+            ymin: rectangle.ymin / height,
+            xmax: rectangle.xmax / width,
+            ymax: rectangle.ymax / height,
+        }
+    }
+
+    /// The transformation from user space to display space.
+    pub fn matrix(&self) -> Matrix {
+        let Rectangle {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+        } = self.bbox;
+
+        let components = match self.rotation {
+            RotationAngle::Zero => [1.0, 0.0, 0.0, -1.0, -xmin, ymax],
+            RotationAngle::Quarter => [0.0, 1.0, 1.0, 0.0, -ymin, -xmin],
+            RotationAngle::Full => [-1.0, 0.0, 0.0, 1.0, xmax, -ymin],
+            RotationAngle::ThreeQuarters => [0.0, -1.0, -1.0, 0.0, ymax, xmax],
+        };
+
+        components.into()
+    }
+}
+
+/// Order the corners of a rectangle, which the specification allows to be any two opposite
+/// corners.
+fn ordered_corners(rectangle: Rectangle) -> Rectangle {
+    Rectangle {
+        xmin: rectangle.xmin.min(rectangle.xmax),
+        ymin: rectangle.ymin.min(rectangle.ymax),
+        xmax: rectangle.xmin.max(rectangle.xmax),
+        ymax: rectangle.ymin.max(rectangle.ymax),
+    }
+}
+
+/// Options for the text extraction of a whole document.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextOptions {
+    /// Drop the running headers and footers, see [`headers_and_footers`].
+    pub drop_headers_and_footers: bool,
+}
+
+impl TextOptions {
+    /// Drop the running headers and footers.
+    pub fn without_headers_and_footers(mut self) -> Self {
+        self.drop_headers_and_footers = true;
+        self
+    }
+}
+
+/// A glyph painted on the page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glyph {
+    /// The Unicode text the glyph stands for. It may hold several characters, e.g. for
+    /// ligatures, and is the replacement character if the font does not tell.
+    pub text: String,
+    /// Position of the glyph's origin, on the baseline.
+    pub origin: (f32, f32),
+    /// Length of the glyph along the baseline.
+    pub width: f32,
+    /// Font size, scaled to display space.
+    pub font_size: f32,
+    /// Direction of the baseline, as a clockwise rotation from the horizontal.
+    pub rotation: RotationAngle,
+    /// Bounding box of the glyph, from the font's descent to its ascent.
+    pub bbox: Rectangle,
+}
+
+/// A straight segment of a painted path, e.g. a rule or the border of a table cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+}
+
+/// What a page paints, as far as layout analysis is concerned.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Painted {
+    pub glyphs: Vec<Glyph>,
+    /// Straight segments of stroked and filled paths. Curves are left out.
+    pub segments: Vec<Segment>,
+}
+
+/// Smallest rectangle that contains every rectangle.
+fn union<'a>(rectangles: impl IntoIterator<Item = &'a Rectangle>) -> Rectangle {
+    rectangles
+        .into_iter()
+        .copied()
+        .reduce(|a, b| Rectangle {
+            xmin: a.xmin.min(b.xmin),
+            ymin: a.ymin.min(b.ymin),
+            xmax: a.xmax.max(b.xmax),
+            ymax: a.ymax.max(b.ymax),
+        })
+        .unwrap_or(Rectangle::from((0.0, 0.0, 0.0, 0.0)))
+}
+
+/// A sequence of glyphs that are not separated by a space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+    pub text: String,
+    pub bbox: Rectangle,
+    /// Largest font size among the word's glyphs.
+    pub font_size: f32,
+}
+
+/// Words that share a baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    pub words: Vec<Word>,
+    pub bbox: Rectangle,
+    /// Direction of the line, as a clockwise rotation from the horizontal.
+    pub rotation: RotationAngle,
+}
+
+/// Lines that are stacked closely together, e.g. a paragraph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextBlock {
+    pub lines: Vec<TextLine>,
+    pub bbox: Rectangle,
+}
+
+/// The text of a page, in reading order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextPage {
+    pub viewport: Viewport,
+    pub blocks: Vec<TextBlock>,
+}
+
+/// Words are separated by a space.
+impl Display for TextLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, word) in self.words.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(&word.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lines are separated by a line break.
+impl Display for TextBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Blocks are separated by an empty line.
+impl Display for TextPage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, block) in self.blocks.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n\n")?;
+            }
+            write!(f, "{block}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::structure::InheritablePageProperties;
+
+    use super::*;
+
+    #[rstest]
+    #[case(RotationAngle::Zero, (10.0, 20.0), (10.0, 772.0))]
+    #[case(RotationAngle::Quarter, (10.0, 20.0), (20.0, 10.0))]
+    #[case(RotationAngle::Full, (10.0, 20.0), (602.0, 20.0))]
+    #[case(RotationAngle::ThreeQuarters, (10.0, 20.0), (772.0, 602.0))]
+    fn viewport(
+        #[case] rotation: RotationAngle,
+        #[case] point: (f32, f32),
+        #[case] expected: (f32, f32),
+    ) {
+        let viewport = Viewport {
+            bbox: Rectangle::from((0.0, 0.0, 612.0, 792.0)),
+            rotation,
+        };
+
+        assert_eq!(viewport.matrix().transform(point.0, point.1), expected);
+
+        let (x, y) = expected;
+        assert!((0.0..=viewport.width()).contains(&x));
+        assert!((0.0..=viewport.height()).contains(&y));
+    }
+
+    #[rstest]
+    #[case(None, Some((0.0, 0.0, 612.0, 792.0)), (0.0, 0.0, 612.0, 792.0))]
+    #[case(Some((10.0, 10.0, 100.0, 100.0)), None, (10.0, 10.0, 100.0, 100.0))]
+    #[case(Some((-10.0, 10.0, 700.0, 100.0)), None, (0.0, 10.0, 612.0, 100.0))]
+    #[case(Some((200.0, 0.0, 100.0, 100.0)), None, (100.0, 0.0, 200.0, 100.0))]
+    #[case(Some((700.0, 0.0, 800.0, 100.0)), None, (0.0, 0.0, 612.0, 792.0))]
+    fn page_viewport(
+        #[case] crop_box: Option<(f32, f32, f32, f32)>,
+        #[case] media_box: Option<(f32, f32, f32, f32)>,
+        #[case] expected: (f32, f32, f32, f32),
+    ) {
+        let page = Page {
+            inheritable_props: InheritablePageProperties {
+                resources: None,
+                media_box: media_box.map(Rectangle::from),
+                crop_box: crop_box.map(Rectangle::from),
+                rotate: None,
+            },
+            contents: Vec::new(),
+            user_unit: 1.0,
+        };
+
+        assert_eq!(Viewport::of(&page).bbox, Rectangle::from(expected));
+    }
+
+    #[test]
+    fn normalize() {
+        let viewport = Viewport {
+            bbox: Rectangle::from((0.0, 0.0, 200.0, 100.0)),
+            rotation: RotationAngle::Quarter,
+        };
+
+        assert_eq!(
+            viewport.normalize(&Rectangle::from((10.0, 20.0, 50.0, 100.0))),
+            Rectangle::from((0.1, 0.1, 0.5, 0.5))
+        );
+    }
+
+    #[test]
+    fn offset_viewport() {
+        let viewport = Viewport {
+            bbox: Rectangle::from((100.0, 100.0, 200.0, 300.0)),
+            rotation: RotationAngle::Zero,
+        };
+
+        assert_eq!(viewport.matrix().transform(100.0, 300.0), (0.0, 0.0));
+        assert_eq!(viewport.matrix().transform(200.0, 100.0), (100.0, 200.0));
+    }
+}
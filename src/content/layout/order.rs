@@ -0,0 +1,195 @@
+//! Reading order of the blocks of a page.
+//!
+//! Sorting blocks from top to bottom interleaves the columns of a multi-column layout. Livre
+//! follows T. M. Breuel's approach instead (*High Performance Document Layout Analysis*, 2003),
+//! which derives a partial order between blocks from two rules:
+//!
+//! 1. a block comes before another if they overlap horizontally, and it is above;
+//! 2. a block comes before another if it is entirely to its left, and no third block lies
+//!    vertically between them while overlapping both horizontally - such a block, e.g. a
+//!    title or a figure that spans both columns, separates two sections of the page.
+//!
+//! Livre restricts the second rule to blocks whose column runs alongside the other block,
+//! i.e. that are below a block of the same column that starts above the other block ends.
+//! Otherwise, a salutation at the bottom left of a letter would come before the address at
+//! the top right.
+//!
+//! Blocks are then sorted topologically, picking the topmost block first whenever the rules
+//! leave a choice. Since the second rule runs in cubic time, pages with more than
+//! [`MAX_BLOCKS`] blocks are simply sorted from top to bottom.
+
+/// Maximum number of blocks to order with the rules above.
+const MAX_BLOCKS: usize = 512;
+
+/// The extent of a block in line space, where the y axis points downwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Extent {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl Extent {
+    fn overlaps_horizontally(&self, other: &Self) -> bool {
+        self.left < other.right && other.left < self.right
+    }
+
+    fn middle(&self) -> f32 {
+        (self.top + self.bottom) / 2.0
+    }
+
+    /// Whether the block should be read before the `other` one.
+    fn precedes(&self, other: &Self, extents: &[Extent]) -> bool {
+        if self.overlaps_horizontally(other) {
+            return self.middle() < other.middle();
+        }
+
+        if self.right > other.left {
+            return false;
+        }
+
+        // The block must belong to a column that runs alongside the other block, rather than
+        // merely sit somewhere lower on the page.
+        let alongside = extents.iter().any(|c| {
+            c.overlaps_horizontally(self)
+                && c.right <= other.left
+                && c.middle() <= self.middle()
+                && c.top <= other.bottom
+        });
+        if !alongside {
+            return false;
+        }
+
+        let (low, high) = if self.middle() < other.middle() {
+            (self.middle(), other.middle())
+        } else {
+            (other.middle(), self.middle())
+        };
+
+        !extents.iter().any(|c| {
+            low < c.middle()
+                && c.middle() < high
+                && c.overlaps_horizontally(self)
+                && c.overlaps_horizontally(other)
+        })
+    }
+
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.top
+            .total_cmp(&other.top)
+            .then(self.left.total_cmp(&other.left))
+    }
+}
+
+/// The indices of the `extents`, in reading order.
+pub(super) fn reading_order(extents: &[Extent]) -> Vec<usize> {
+    let n = extents.len();
+    let mut order: Vec<usize> = (0..n).collect();
+
+    if n > MAX_BLOCKS {
+        order.sort_by(|&a, &b| extents[a].cmp(&extents[b]));
+        return order;
+    }
+
+    // Number of blocks that must be read before each block, and the blocks that come after.
+    let mut before = vec![0_usize; n];
+    let mut after = vec![Vec::new(); n];
+
+    for (a, extent) in extents.iter().enumerate() {
+        for (b, other) in extents.iter().enumerate() {
+            if a != b && extent.precedes(other, extents) {
+                before[b] += 1;
+                after[a].push(b);
+            }
+        }
+    }
+
+    let mut remaining = order;
+    order = Vec::with_capacity(n);
+
+    while !remaining.is_empty() {
+        // Overlapping blocks may create cycles, which are broken by picking the topmost
+        // block regardless of its predecessors.
+        let candidates = remaining.iter().filter(|&&i| before[i] == 0);
+        let next = candidates
+            .min_by(|&&a, &&b| extents[a].cmp(&extents[b]))
+            .or_else(|| {
+                remaining
+                    .iter()
+                    .min_by(|&&a, &&b| extents[a].cmp(&extents[b]))
+            })
+            .copied()
+            .expect("there are remaining blocks");
+
+        remaining.retain(|&i| i != next);
+        for &b in &after[next] {
+            before[b] = before[b].saturating_sub(1);
+        }
+        order.push(next);
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn extent(left: f32, right: f32, top: f32, bottom: f32) -> Extent {
+        Extent {
+            left,
+            right,
+            top,
+            bottom,
+        }
+    }
+
+    #[rstest]
+    // Two columns, whose paragraphs are aligned.
+    #[case(
+        vec![
+            extent(0.0, 40.0, 0.0, 10.0),
+            extent(60.0, 100.0, 0.0, 10.0),
+            extent(0.0, 40.0, 15.0, 30.0),
+            extent(60.0, 100.0, 15.0, 30.0),
+        ],
+        vec![0, 2, 1, 3],
+    )]
+    // A title above two columns, and a footer below.
+    #[case(
+        vec![
+            extent(0.0, 40.0, 10.0, 20.0),
+            extent(60.0, 100.0, 10.0, 20.0),
+            extent(0.0, 100.0, 0.0, 5.0),
+            extent(0.0, 40.0, 25.0, 30.0),
+            extent(0.0, 100.0, 40.0, 45.0),
+        ],
+        vec![2, 0, 3, 1, 4],
+    )]
+    // A figure that spans both columns separates two sections of the page.
+    #[case(
+        vec![
+            extent(0.0, 40.0, 0.0, 10.0),
+            extent(60.0, 100.0, 0.0, 10.0),
+            extent(10.0, 90.0, 15.0, 20.0),
+            extent(0.0, 40.0, 25.0, 35.0),
+            extent(60.0, 100.0, 25.0, 35.0),
+        ],
+        vec![0, 1, 2, 3, 4],
+    )]
+    // A letter, with an address on the right and a salutation lower on the left.
+    #[case(
+        vec![
+            extent(0.0, 30.0, 20.0, 25.0),
+            extent(60.0, 100.0, 0.0, 10.0),
+            extent(0.0, 100.0, 30.0, 40.0),
+        ],
+        vec![1, 0, 2],
+    )]
+    fn order(#[case] extents: Vec<Extent>, #[case] expected: Vec<usize>) {
+        assert_eq!(reading_order(&extents), expected);
+    }
+}
@@ -0,0 +1,801 @@
+//! Detection of tables.
+//!
+//! Livre finds tables in two ways:
+//!
+//! - *ruled* tables are drawn with rules, i.e. stroked lines or thin filled rectangles. Rules
+//!   that cross each other make up a grid, whose lattice gives the rows and columns. A cell
+//!   whose border is missing spans the neighbouring cell;
+//! - *unruled* tables only rely on whitespace: at least [`MIN_ROWS`] consecutive lines that are
+//!   split into several chunks, whose chunks line up into columns. Chunks of prose are longer
+//!   than [`MAX_WORDS`] words on average, which tells two columns of text from a table.
+//!
+//! Glyphs are assigned to a cell of a ruled table by their centre. Unruled tables are only
+//! looked for among upright glyphs that are not part of a ruled table.
+
+use std::fmt::{self, Display};
+
+use crate::{extraction::Rectangle, structure::RotationAngle};
+
+use super::{analysis::lines, union, Glyph, Segment, TextLine, TextPage, Viewport};
+
+/// Tolerance when comparing the positions of rules, in display space units. Also the maximal
+/// thickness of a filled rectangle that counts as a rule.
+const TOLERANCE: f32 = 2.0;
+
+/// Minimal number of rows of an unruled table.
+const MIN_ROWS: usize = 3;
+
+/// Maximal distance between two rows of an unruled table, relative to the font size.
+const ROW_SPACING: f32 = 2.5;
+
+/// Maximal average number of words per cell of an unruled table.
+const MAX_WORDS: f32 = 5.0;
+
+/// A cell of a [`Table`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cell {
+    pub row: usize,
+    pub column: usize,
+    /// Number of rows the cell spans, at least one.
+    pub row_span: usize,
+    /// Number of columns the cell spans, at least one.
+    pub column_span: usize,
+    pub bbox: Rectangle,
+    /// The text of the cell. Lines are separated by a line break.
+    pub text: String,
+}
+
+/// A table found on a page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub bbox: Rectangle,
+    pub rows: usize,
+    pub columns: usize,
+    /// Cells, by row and then by column. Spanning cells are only listed once.
+    pub cells: Vec<Cell>,
+    /// Whether the table is drawn with rules, rather than laid out with whitespace.
+    pub ruled: bool,
+}
+
+impl Table {
+    /// The cell that covers the given row and column, if any.
+    pub fn cell(&self, row: usize, column: usize) -> Option<&Cell> {
+        self.cells.iter().find(|cell| {
+            (cell.row..cell.row + cell.row_span).contains(&row)
+                && (cell.column..cell.column + cell.column_span).contains(&column)
+        })
+    }
+}
+
+/// Rows are separated by a line break, and cells by a tab. Spanning cells appear in their
+/// first row only.
+impl Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..self.rows {
+            if row > 0 {
+                writeln!(f)?;
+            }
+            let cells = self.cells.iter().filter(|cell| cell.row == row);
+            for (i, cell) in cells.enumerate() {
+                if i > 0 {
+                    write!(f, "\t")?;
+                }
+                write!(f, "{}", cell.text.replace('\n', " "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A horizontal or vertical rule. Horizontal rules are at a given `position` on the y axis,
+/// and run from `start` to `end` on the x axis - and conversely.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rule {
+    position: f32,
+    start: f32,
+    end: f32,
+}
+
+impl Rule {
+    fn new(position: f32, a: f32, b: f32) -> Self {
+        Self {
+            position,
+            start: a.min(b),
+            end: a.max(b),
+        }
+    }
+
+    /// Whether the rule runs along the central half of the span from `start` to `end`.
+    fn spans(&self, start: f32, end: f32) -> bool {
+        let quarter = (end - start) / 4.0;
+        self.start <= start + quarter && end - quarter <= self.end
+    }
+
+    fn crosses(&self, other: &Self) -> bool {
+        self.start - TOLERANCE <= other.position
+            && other.position <= self.end + TOLERANCE
+            && other.start - TOLERANCE <= self.position
+            && self.position <= other.end + TOLERANCE
+    }
+}
+
+/// Split segments into horizontal and vertical rules. Rules along the edges of the page,
+/// such as the outline of a background, are left out.
+fn rules(segments: &[Segment], viewport: &Viewport) -> (Vec<Rule>, Vec<Rule>) {
+    let (width, height) = (viewport.width(), viewport.height());
+    let mut horizontal = Vec::new();
+    let mut vertical = Vec::new();
+
+    for &Segment {
+        start: (x0, y0),
+        end: (x1, y1),
+    } in segments
+    {
+        let (dx, dy) = ((x1 - x0).abs(), (y1 - y0).abs());
+        let y = (y0 + y1) / 2.0;
+        let x = (x0 + x1) / 2.0;
+
+        if dy <= TOLERANCE && dx > TOLERANCE && TOLERANCE < y && y < height - TOLERANCE {
+            horizontal.push(Rule::new(y, x0, x1));
+        } else if dx <= TOLERANCE && dy > TOLERANCE && TOLERANCE < x && x < width - TOLERANCE {
+            vertical.push(Rule::new(x, y0, y1));
+        }
+    }
+
+    (merge(horizontal), merge(vertical))
+}
+
+/// Merge collinear rules that touch or overlap, e.g. the edges of adjacent cells or both
+/// sides of a thin filled rectangle.
+fn merge(mut rules: Vec<Rule>) -> Vec<Rule> {
+    rules.sort_by(|a, b| a.position.total_cmp(&b.position));
+
+    let mut merged = Vec::new();
+    let mut first = 0;
+
+    while first < rules.len() {
+        // Rules whose positions are within tolerance of their predecessor are collinear.
+        let len = rules[first..]
+            .windows(2)
+            .position(|pair| pair[1].position - pair[0].position > TOLERANCE)
+            .map_or(rules.len() - first, |i| i + 1);
+        let group = &mut rules[first..first + len];
+        let position = group.iter().map(|r| r.position).sum::<f32>() / len as f32;
+
+        group.sort_by(|a, b| a.start.total_cmp(&b.start));
+        let mut current: Option<Rule> = None;
+        for rule in group.iter() {
+            match &mut current {
+                Some(last) if rule.start <= last.end + TOLERANCE => {
+                    last.end = last.end.max(rule.end);
+                }
+                _ => {
+                    merged.extend(current);
+                    current = Some(Rule::new(position, rule.start, rule.end));
+                }
+            }
+        }
+        merged.extend(current);
+
+        first += len;
+    }
+
+    merged
+}
+
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    // Path compression.
+    let mut i = i;
+    while parents[i] != root {
+        (parents[i], i) = (root, parents[i]);
+    }
+    root
+}
+
+fn join(parents: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parents, a), find(parents, b));
+    parents[a] = b;
+}
+
+/// Sorted positions, where positions within tolerance of each other are averaged.
+fn cluster(mut positions: Vec<f32>) -> Vec<f32> {
+    positions.sort_by(f32::total_cmp);
+
+    let mut clusters: Vec<Vec<f32>> = Vec::new();
+    for position in positions {
+        match clusters.last_mut() {
+            Some(cluster) if position - cluster[cluster.len() - 1] <= TOLERANCE => {
+                cluster.push(position);
+            }
+            _ => clusters.push(vec![position]),
+        }
+    }
+
+    clusters
+        .iter()
+        .map(|cluster| cluster.iter().sum::<f32>() / cluster.len() as f32)
+        .collect()
+}
+
+/// The text of the glyphs whose centre lies within the rectangle.
+fn text_within(glyphs: &[Glyph], bbox: &Rectangle, viewport: Viewport) -> String {
+    let glyphs: Vec<Glyph> = glyphs
+        .iter()
+        .filter(|g| contains(bbox, centre(&g.bbox)))
+        .cloned()
+        .collect();
+
+    let page = TextPage::new(glyphs, viewport);
+    let lines: Vec<String> = page
+        .blocks
+        .iter()
+        .flat_map(|block| &block.lines)
+        .map(TextLine::to_string)
+        .collect();
+
+    lines.join("\n")
+}
+
+fn centre(bbox: &Rectangle) -> (f32, f32) {
+    ((bbox.xmin + bbox.xmax) / 2.0, (bbox.ymin + bbox.ymax) / 2.0)
+}
+
+fn contains(bbox: &Rectangle, (x, y): (f32, f32)) -> bool {
+    bbox.xmin <= x && x < bbox.xmax && bbox.ymin <= y && y < bbox.ymax
+}
+
+/// A ruled table, from a set of rules that cross each other.
+fn grid(
+    horizontal: &[Rule],
+    vertical: &[Rule],
+    glyphs: &[Glyph],
+    viewport: Viewport,
+) -> Option<Table> {
+    if horizontal.len() < 2 || vertical.len() < 2 {
+        return None;
+    }
+
+    // Rules may stop short of the outer border, or run past it.
+    let xs = cluster(
+        vertical
+            .iter()
+            .map(|r| r.position)
+            .chain(horizontal.iter().map(|r| r.start).reduce(f32::min))
+            .chain(horizontal.iter().map(|r| r.end).reduce(f32::max))
+            .collect(),
+    );
+    let ys = cluster(
+        horizontal
+            .iter()
+            .map(|r| r.position)
+            .chain(vertical.iter().map(|r| r.start).reduce(f32::min))
+            .chain(vertical.iter().map(|r| r.end).reduce(f32::max))
+            .collect(),
+    );
+
+    let (rows, columns) = (ys.len() - 1, xs.len() - 1);
+
+    let bordered = |rules: &[Rule], position: f32, start: f32, end: f32| {
+        rules
+            .iter()
+            .any(|r| (r.position - position).abs() <= TOLERANCE && r.spans(start, end))
+    };
+
+    // Lattice cells that are not separated by a rule belong to the same table cell.
+    let mut parents: Vec<usize> = (0..rows * columns).collect();
+    for row in 0..rows {
+        for column in 0..columns {
+            let i = row * columns + column;
+            if column + 1 < columns && !bordered(vertical, xs[column + 1], ys[row], ys[row + 1]) {
+                join(&mut parents, i, i + 1);
+            }
+            if row + 1 < rows && !bordered(horizontal, ys[row + 1], xs[column], xs[column + 1]) {
+                join(&mut parents, i, i + columns);
+            }
+        }
+    }
+
+    // The first and last row and column of each cell, by root.
+    let mut extents: Vec<Option<(usize, usize, usize, usize)>> = vec![None; rows * columns];
+    for row in 0..rows {
+        for column in 0..columns {
+            let root = find(&mut parents, row * columns + column);
+            let extent = extents[root].get_or_insert((row, row, column, column));
+            extent.0 = extent.0.min(row);
+            extent.1 = extent.1.max(row);
+            extent.2 = extent.2.min(column);
+            extent.3 = extent.3.max(column);
+        }
+    }
+
+    let mut cells: Vec<Cell> = extents
+        .into_iter()
+        .flatten()
+        .map(|(first_row, last_row, first_column, last_column)| {
+            let bbox = Rectangle::from((
+                xs[first_column],
+                ys[first_row],
+                xs[last_column + 1],
+                ys[last_row + 1],
+            ));
+            Cell {
+                row: first_row,
+                column: first_column,
+                row_span: last_row - first_row + 1,
+                column_span: last_column - first_column + 1,
+                text: text_within(glyphs, &bbox, viewport),
+                bbox,
+            }
+        })
+        .collect();
+
+    // A mere frame, e.g. around a paragraph, is not a table.
+    if cells.len() < 2 {
+        return None;
+    }
+
+    cells.sort_by_key(|cell| (cell.row, cell.column));
+
+    Some(Table {
+        bbox: Rectangle::from((xs[0], ys[0], xs[columns], ys[rows])),
+        rows,
+        columns,
+        cells,
+        ruled: true,
+    })
+}
+
+/// Ruled tables, from the connected sets of rules.
+fn ruled(glyphs: &[Glyph], segments: &[Segment], viewport: Viewport) -> Vec<Table> {
+    let (horizontal, vertical) = rules(segments, &viewport);
+
+    // Horizontal rules come first, followed by vertical ones.
+    let n = horizontal.len();
+    let mut parents: Vec<usize> = (0..n + vertical.len()).collect();
+    for (h, rule) in horizontal.iter().enumerate() {
+        for (v, other) in vertical.iter().enumerate() {
+            if rule.crosses(other) {
+                join(&mut parents, h, n + v);
+            }
+        }
+    }
+
+    let mut components: Vec<(Vec<Rule>, Vec<Rule>)> = vec![Default::default(); parents.len()];
+    for (h, rule) in horizontal.iter().enumerate() {
+        components[find(&mut parents, h)].0.push(*rule);
+    }
+    for (v, rule) in vertical.iter().enumerate() {
+        components[find(&mut parents, n + v)].1.push(*rule);
+    }
+
+    components
+        .iter()
+        .filter_map(|(horizontal, vertical)| grid(horizontal, vertical, glyphs, viewport))
+        .collect()
+}
+
+/// A piece of a line, set apart from the rest by a wide gap.
+struct Chunk {
+    text: String,
+    bbox: Rectangle,
+    words: usize,
+}
+
+/// Chunks that share a baseline, from left to right.
+struct Band {
+    chunks: Vec<Chunk>,
+    middle: f32,
+    font_size: f32,
+}
+
+/// Group the lines of upright glyphs into bands.
+fn bands(glyphs: Vec<Glyph>) -> Vec<Band> {
+    let mut lines = lines(glyphs);
+    lines.sort_by(|a, b| {
+        centre(&a.bbox)
+            .1
+            .total_cmp(&centre(&b.bbox).1)
+            .then(a.bbox.xmin.total_cmp(&b.bbox.xmin))
+    });
+
+    let mut bands: Vec<Band> = Vec::new();
+
+    for line in lines {
+        let middle = centre(&line.bbox).1;
+        let font_size = line.words.iter().map(|w| w.font_size).fold(0.0, f32::max);
+        let chunk = Chunk {
+            text: line.to_string(),
+            bbox: line.bbox,
+            words: line.words.len(),
+        };
+
+        match bands.last_mut() {
+            Some(band) if middle - band.middle < 0.5 * band.font_size.max(font_size) => {
+                band.chunks.push(chunk);
+                band.font_size = band.font_size.max(font_size);
+            }
+            _ => bands.push(Band {
+                chunks: vec![chunk],
+                middle,
+                font_size,
+            }),
+        }
+    }
+
+    for band in &mut bands {
+        band.chunks
+            .sort_by(|a, b| a.bbox.xmin.total_cmp(&b.bbox.xmin));
+    }
+
+    bands
+}
+
+/// An unruled table, from consecutive bands of several chunks each.
+fn aligned(bands: &[Band]) -> Option<Table> {
+    // Columns are given by the bands that have the most chunks.
+    let most = bands.iter().map(|b| b.chunks.len()).max()?;
+    let full: Vec<&Band> = bands.iter().filter(|b| b.chunks.len() == most).collect();
+    if full.len() < 2 {
+        return None;
+    }
+
+    let mut extents: Vec<(f32, f32)> = full
+        .iter()
+        .flat_map(|band| &band.chunks)
+        .map(|chunk| (chunk.bbox.xmin, chunk.bbox.xmax))
+        .collect();
+    extents.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut columns: Vec<(f32, f32)> = Vec::new();
+    for (start, end) in extents {
+        match columns.last_mut() {
+            Some(column) if start < column.1 => column.1 = column.1.max(end),
+            _ => columns.push((start, end)),
+        }
+    }
+
+    // The chunks of full bands must line up.
+    if columns.len() != most {
+        return None;
+    }
+
+    let mut cells = Vec::new();
+    let mut words = 0;
+
+    for (row, band) in bands.iter().enumerate() {
+        // Cells of the band, with their last column.
+        let mut row_cells: Vec<(Cell, usize)> = Vec::new();
+
+        for chunk in &band.chunks {
+            words += chunk.words;
+
+            let overlapping: Vec<usize> = (0..columns.len())
+                .filter(|&c| chunk.bbox.xmin < columns[c].1 && columns[c].0 < chunk.bbox.xmax)
+                .collect();
+            let (first, last) = match (overlapping.first(), overlapping.last()) {
+                (Some(&first), Some(&last)) => (first, last),
+                _ => {
+                    let middle = centre(&chunk.bbox).0;
+                    let distance = |c: usize| {
+                        let (start, end) = columns[c];
+                        (start - middle).max(middle - end)
+                    };
+                    let nearest = (0..columns.len())
+                        .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+                        .expect("there are columns");
+                    (nearest, nearest)
+                }
+            };
+
+            // Chunks that fall into the same column are pieces of the same cell.
+            match row_cells.last_mut() {
+                Some((cell, end)) if first <= *end => {
+                    cell.text.push(' ');
+                    cell.text.push_str(&chunk.text);
+                    cell.bbox = union([&cell.bbox, &chunk.bbox]);
+                    *end = (*end).max(last);
+                    cell.column_span = *end - cell.column + 1;
+                }
+                _ => row_cells.push((
+                    Cell {
+                        row,
+                        column: first,
+                        row_span: 1,
+                        column_span: last - first + 1,
+                        bbox: chunk.bbox,
+                        text: chunk.text.clone(),
+                    },
+                    last,
+                )),
+            }
+        }
+
+        cells.extend(row_cells.into_iter().map(|(cell, _)| cell));
+    }
+
+    // Columns of prose.
+    if words as f32 / cells.len() as f32 > MAX_WORDS {
+        return None;
+    }
+
+    Some(Table {
+        bbox: union(cells.iter().map(|cell| &cell.bbox)),
+        rows: bands.len(),
+        columns: columns.len(),
+        cells,
+        ruled: false,
+    })
+}
+
+/// Unruled tables, from runs of consecutive bands of several chunks each.
+fn unruled(glyphs: Vec<Glyph>) -> Vec<Table> {
+    let bands = bands(glyphs);
+    let mut tables = Vec::new();
+    let mut first = 0;
+
+    while first < bands.len() {
+        let len = bands[first..]
+            .iter()
+            .enumerate()
+            .take_while(|&(i, band)| {
+                band.chunks.len() >= 2
+                    && (i == 0 || {
+                        let previous = &bands[first + i - 1];
+                        band.middle - previous.middle
+                            <= ROW_SPACING * band.font_size.max(previous.font_size)
+                    })
+            })
+            .count();
+
+        if len >= MIN_ROWS {
+            tables.extend(aligned(&bands[first..first + len]));
+        }
+
+        first += len.max(1);
+    }
+
+    tables
+}
+
+/// Find the tables among the glyphs and path segments of a page, positioned in the display
+/// space of the `viewport`. Tables are sorted from top to bottom.
+pub fn find_tables(glyphs: &[Glyph], segments: &[Segment], viewport: Viewport) -> Vec<Table> {
+    let mut tables = ruled(glyphs, segments, viewport);
+
+    let free: Vec<Glyph> = glyphs
+        .iter()
+        .filter(|g| g.rotation == RotationAngle::Zero)
+        .filter(|g| {
+            !tables
+                .iter()
+                .any(|table| contains(&table.bbox, centre(&g.bbox)))
+        })
+        .cloned()
+        .collect();
+    tables.extend(unruled(free));
+
+    tables.sort_by(|a, b| {
+        a.bbox
+            .ymin
+            .total_cmp(&b.bbox.ymin)
+            .then(a.bbox.xmin.total_cmp(&b.bbox.xmin))
+    });
+
+    tables
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn viewport() -> Viewport {
+        Viewport {
+            bbox: Rectangle::from((0.0, 0.0, 200.0, 200.0)),
+            rotation: RotationAngle::Zero,
+        }
+    }
+
+    /// A line of text in a monospaced font, one unit wide and two units high, starting
+    /// at `(x, y)` in display space.
+    fn text(x: f32, y: f32, s: &str) -> Vec<Glyph> {
+        s.chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let x = x + i as f32;
+                Glyph {
+                    text: c.to_string(),
+                    origin: (x, y),
+                    width: 1.0,
+                    font_size: 2.0,
+                    rotation: RotationAngle::Zero,
+                    bbox: Rectangle::from((x, y - 1.6, x + 1.0, y + 0.4)),
+                }
+            })
+            .collect()
+    }
+
+    fn line(x0: f32, y0: f32, x1: f32, y1: f32) -> Segment {
+        Segment {
+            start: (x0, y0),
+            end: (x1, y1),
+        }
+    }
+
+    /// Horizontal rules at each of the `ys`, and vertical ones at each of the `xs`, spanning
+    /// the whole grid.
+    fn grid(xs: &[f32], ys: &[f32]) -> Vec<Segment> {
+        let (left, right) = (xs[0], xs[xs.len() - 1]);
+        let (top, bottom) = (ys[0], ys[ys.len() - 1]);
+
+        ys.iter()
+            .map(|&y| line(left, y, right, y))
+            .chain(xs.iter().map(|&x| line(x, top, x, bottom)))
+            .collect()
+    }
+
+    fn cells(table: &Table) -> Vec<(usize, usize, usize, usize, &str)> {
+        table
+            .cells
+            .iter()
+            .map(|c| (c.row, c.column, c.row_span, c.column_span, c.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn ruled_grid() {
+        let glyphs = [
+            text(12.0, 16.0, "Item"),
+            text(42.0, 16.0, "Price"),
+            text(12.0, 26.0, "Tea"),
+            text(42.0, 26.0, "3.50"),
+        ]
+        .concat();
+        let segments = grid(&[10.0, 40.0, 70.0], &[10.0, 20.0, 30.0]);
+
+        let tables = find_tables(&glyphs, &segments, viewport());
+
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert!(table.ruled);
+        assert_eq!((table.rows, table.columns), (2, 2));
+        assert_eq!(
+            cells(table),
+            [
+                (0, 0, 1, 1, "Item"),
+                (0, 1, 1, 1, "Price"),
+                (1, 0, 1, 1, "Tea"),
+                (1, 1, 1, 1, "3.50"),
+            ]
+        );
+        assert_eq!(table.to_string(), "Item\tPrice\nTea\t3.50");
+    }
+
+    #[test]
+    fn spans() {
+        // The title spans both columns, and the first column spans the last two rows.
+        let segments = vec![
+            line(10.0, 10.0, 70.0, 10.0),
+            line(10.0, 20.0, 70.0, 20.0),
+            line(40.0, 30.0, 70.0, 30.0),
+            line(10.0, 40.0, 70.0, 40.0),
+            line(10.0, 10.0, 10.0, 40.0),
+            line(40.0, 20.0, 40.0, 40.0),
+            line(70.0, 10.0, 70.0, 40.0),
+        ];
+        let glyphs = [
+            text(12.0, 16.0, "Title"),
+            text(12.0, 26.0, "Tea"),
+            text(42.0, 26.0, "1"),
+            text(42.0, 36.0, "2"),
+        ]
+        .concat();
+
+        let tables = find_tables(&glyphs, &segments, viewport());
+
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert_eq!((table.rows, table.columns), (3, 2));
+        assert_eq!(
+            cells(table),
+            [
+                (0, 0, 1, 2, "Title"),
+                (1, 0, 2, 1, "Tea"),
+                (1, 1, 1, 1, "1"),
+                (2, 1, 1, 1, "2"),
+            ]
+        );
+        assert_eq!(table.cell(2, 0).map(|c| c.text.as_str()), Some("Tea"));
+    }
+
+    #[test]
+    fn thin_rectangles() {
+        // Rules drawn as filled rectangles, half a unit thick.
+        let rectangle = |x: f32, y: f32, w: f32, h: f32| {
+            vec![
+                line(x, y, x + w, y),
+                line(x + w, y, x + w, y + h),
+                line(x + w, y + h, x, y + h),
+                line(x, y + h, x, y),
+            ]
+        };
+        let segments = [
+            rectangle(10.0, 10.0, 60.0, 0.5),
+            rectangle(10.0, 20.0, 60.0, 0.5),
+            rectangle(10.0, 30.0, 60.0, 0.5),
+            rectangle(10.0, 10.0, 0.5, 20.0),
+            rectangle(40.0, 10.0, 0.5, 20.0),
+            rectangle(70.0, 10.0, 0.5, 20.0),
+        ]
+        .concat();
+
+        let tables = find_tables(&[], &segments, viewport());
+
+        assert_eq!(tables.len(), 1);
+        assert_eq!((tables[0].rows, tables[0].columns), (2, 2));
+    }
+
+    #[rstest]
+    // A frame around a paragraph.
+    #[case(grid(&[10.0, 70.0], &[10.0, 40.0]))]
+    // Underlines.
+    #[case(vec![line(10.0, 10.0, 70.0, 10.0), line(10.0, 20.0, 70.0, 20.0)])]
+    // The outline of the page.
+    #[case(grid(&[0.0, 100.0, 200.0], &[0.0, 100.0, 200.0]))]
+    fn not_ruled(#[case] segments: Vec<Segment>) {
+        assert_eq!(find_tables(&[], &segments, viewport()), []);
+    }
+
+    #[test]
+    fn unruled_table() {
+        let glyphs = [
+            text(10.0, 10.0, "Description"),
+            text(50.0, 10.0, "Qty"),
+            text(70.0, 10.0, "Amount"),
+            text(10.0, 14.0, "Green tea"),
+            text(50.0, 14.0, "2"),
+            text(72.0, 14.0, "7.00"),
+            text(10.0, 18.0, "Scones"),
+            text(50.0, 18.0, "12"),
+            text(71.0, 18.0, "18.00"),
+            text(10.0, 22.0, "Total due"),
+            text(71.0, 22.0, "25.00"),
+            // A paragraph below the table.
+            text(10.0, 40.0, "Thank you for your custom."),
+        ]
+        .concat();
+
+        let tables = find_tables(&glyphs, &[], viewport());
+
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert!(!table.ruled);
+        assert_eq!((table.rows, table.columns), (4, 3));
+        assert_eq!(
+            table.to_string(),
+            "Description\tQty\tAmount\nGreen tea\t2\t7.00\nScones\t12\t18.00\nTotal due\t25.00"
+        );
+        assert_eq!(table.cell(3, 2).map(|c| c.text.as_str()), Some("25.00"));
+        assert_eq!(table.cell(3, 1), None);
+    }
+
+    #[test]
+    fn prose_columns() {
+        let glyphs = [
+            text(10.0, 10.0, "The quick brown fox jumps"),
+            text(60.0, 10.0, "over the lazy dog and runs"),
+            text(10.0, 14.0, "far into the dark forest at"),
+            text(60.0, 14.0, "the very end of the long day"),
+            text(10.0, 18.0, "where no one will ever find"),
+            text(60.0, 18.0, "it again until the next year"),
+        ]
+        .concat();
+
+        assert_eq!(find_tables(&glyphs, &[], viewport()), []);
+    }
+}
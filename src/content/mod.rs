@@ -13,10 +13,26 @@
 //! With that in mind, the main entry point for content parsing is the [`parse_text_object`],
 //! which simplifies the process of extracting text from a content stream - and just skips
 //! any other type of operator.
+//!
+//! To get the readable text of a page, use the [`layout`] module instead, which positions every
+//! glyph and groups them into words, lines and blocks.
+//!
+//! Finally, the [`interpreter`] module runs every operator with the complete graphics state,
+//! and reports the glyphs, paths and images it paints to a user-supplied device. The [`svg`]
+//! module builds on it to export pages as vector previews, and the `render` module, behind
+//! the feature of the same name, rasterizes them to pixel buffers.
 
 mod error;
+pub mod font;
+pub mod interpreter;
+pub mod layout;
 pub mod operators;
+mod png;
+#[cfg(feature = "render")]
+pub mod render;
 mod state;
+pub mod svg;
+mod xml;
 
 pub use error::ContentError;
-pub use state::parse_text_object;
+pub use state::{parse_text_object, Matrix, RenderingMode, TextStateParameters};
@@ -0,0 +1,490 @@
+//! Content streams, written back as text.
+//!
+//! [`spanned_operators`] parses a decoded content stream while recording where each operator
+//! lies within it. [`write_content`] then formats the operators as normalised content-stream
+//! text, one operator per line, indented to show the nesting of `q`/`Q` and `BT`/`ET` pairs:
+//!
+//! ```raw
+//! q
+//!   1 0 0 -1 0 792 cm
+//!   BT
+//!     /F1 12 Tf
+//!     (Hello) Tj
+//!   ET
+//! Q
+//! ```
+//!
+//! Operators that Livre does not implement yet are written verbatim, operands included, such
+//! that nothing is lost when comparing the output with the original stream.
+
+use std::{
+    io::{self, Write},
+    ops::Range,
+};
+
+use winnow::{error::ErrMode, BStr, ModalResult, Parser};
+
+use crate::{
+    error::{Error, ErrorKind},
+    extraction::{multicomment0, Extract},
+    serialization::Serialize,
+};
+
+use super::{
+    graphics::{GraphicsStateOperator, ModifyTransformationMatrix},
+    path::{
+        AppendRectangle, CurveTo, CurveToFinalReplicated, CurveToInitialReplicated, Fill, LineTo,
+        MoveTo, PathOperator, Stroke,
+    },
+    skip_malformed,
+    text::{
+        MoveByOffset, MoveByOffsetAndSetLeading, MoveToNextLineAndShowText,
+        MoveToNextLineAndShowTextWithSpacing, SetCharacterSpacing, SetFontAndFontSize,
+        SetHorizontalScaling, SetTextLeading, SetTextMatrix, SetTextRenderingMode, SetTextRise,
+        SetWordSpacing, ShowText, ShowTextArray, TextOperator, TextPositioningOperator,
+        TextShowingOperator, TextStateOperator,
+    },
+    InlineImage, Operator, UnknownOperator,
+};
+
+const INDENT: &[u8] = b"  ";
+
+/// An operator, along with its position in the decoded content stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedOperator {
+    pub operator: Operator,
+    /// Byte range of the operator in the decoded stream, from its first operand to the end of
+    /// its keyword.
+    pub span: Range<usize>,
+}
+
+/// Parse the operators of a decoded content stream, of which there may not be more than
+/// `limit`. Malformed tokens are skipped with a warning, and parsing stops at an operator that
+/// the content ends in the middle of.
+pub fn spanned_operators(content: &[u8], limit: usize) -> ModalResult<Vec<SpannedOperator>> {
+    let mut input = BStr::new(content);
+    let mut operators = Vec::new();
+
+    loop {
+        multicomment0.parse_next(&mut input)?;
+        let start = content.len() - input.len();
+
+        let operator = match Operator::extract(&mut input) {
+            Ok(operator) => operator,
+            Err(ErrMode::Backtrack(_)) => {
+                let mut rest = BStr::new(&content[start..]);
+                if !skip_malformed(&mut rest) {
+                    break;
+                }
+                tracing::warn!("Skipping a malformed token at offset {start}");
+                input = rest;
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        if operators.len() == limit {
+            return Err(Error::new(ErrorKind::TooManyOperators(limit)).cut());
+        }
+
+        operators.push(SpannedOperator {
+            operator,
+            span: start..content.len() - input.len(),
+        });
+    }
+
+    Ok(operators)
+}
+
+/// Options for [`write_content`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Prefix each line with the span of the operator, e.g. `120..135`.
+    pub offsets: bool,
+}
+
+impl FormatOptions {
+    /// Prefix each line with the span of the operator.
+    pub fn with_offsets(mut self) -> Self {
+        self.offsets = true;
+        self
+    }
+}
+
+/// Write the `operators` parsed from `content` as indented content-stream text (see the
+/// [module documentation](self)).
+///
+/// Operators that Livre does not implement, and inline images, are copied from `content`,
+/// using their span.
+pub fn write_content<W>(
+    content: &[u8],
+    operators: &[SpannedOperator],
+    options: FormatOptions,
+    writer: &mut W,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut depth = 0usize;
+
+    for SpannedOperator { operator, span } in operators {
+        if closes(operator) {
+            depth = depth.saturating_sub(1);
+        }
+
+        if options.offsets {
+            write!(writer, "{:<16}", format!("{}..{}", span.start, span.end))?;
+        }
+        writer.write_all(&INDENT.repeat(depth))?;
+
+        match (operator, content.get(span.clone())) {
+            (Operator::NotImplemented(_) | Operator::InlineImage(_), Some(verbatim)) => {
+                writer.write_all(verbatim)?
+            }
+            (operator, _) => operator.serialize(writer)?,
+        }
+        writer.write_all(b"\n")?;
+
+        if opens(operator) {
+            depth += 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the operator opens a nested section, i.e. `q` or `BT`.
+fn opens(operator: &Operator) -> bool {
+    matches!(
+        operator,
+        Operator::BeginText(_)
+            | Operator::GraphicsState(GraphicsStateOperator::SaveGraphicsState(_))
+    )
+}
+
+/// Whether the operator closes a nested section, i.e. `Q` or `ET`.
+fn closes(operator: &Operator) -> bool {
+    matches!(
+        operator,
+        Operator::EndText(_)
+            | Operator::GraphicsState(GraphicsStateOperator::RestoreGraphicsState(_))
+    )
+}
+
+/// Write the operands, each followed by a space, and then the operator keyword.
+macro_rules! operator {
+    ($writer:expr, $keyword:literal $(, $operand:expr)*) => {{
+        $(
+            $operand.serialize($writer)?;
+            $writer.write_all(b" ")?;
+        )*
+        $writer.write_all($keyword)
+    }};
+}
+
+/// Operators are written in content-stream syntax, e.g. `0 0 612 792 re`.
+///
+/// The operands of operators that Livre does not implement are written back from their
+/// [`Object`](crate::extraction::Object) representation: use [`write_content`] to write them
+/// verbatim instead.
+impl Serialize for Operator {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Self::BeginText(_) => operator!(writer, b"BT"),
+            Self::EndText(_) => operator!(writer, b"ET"),
+            Self::GraphicsState(operator) => operator.serialize(writer),
+            Self::Path(operator) => operator.serialize(writer),
+            Self::Text(operator) => operator.serialize(writer),
+            Self::InlineImage(InlineImage { dict, data }) => {
+                let mut entries: Vec<_> = dict.iter().collect();
+                entries.sort_unstable_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+
+                writer.write_all(b"BI")?;
+                for (key, value) in entries {
+                    writer.write_all(b" ")?;
+                    key.serialize(writer)?;
+                    writer.write_all(b" ")?;
+                    value.serialize(writer)?;
+                }
+                writer.write_all(b" ID ")?;
+                writer.write_all(data)?;
+                writer.write_all(b"\nEI")
+            }
+            Self::NotImplemented(UnknownOperator { keyword, operands }) => {
+                for operand in operands {
+                    operand.serialize(writer)?;
+                    writer.write_all(b" ")?;
+                }
+                writer.write_all(keyword)
+            }
+        }
+    }
+}
+
+impl Serialize for GraphicsStateOperator {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Self::SaveGraphicsState(_) => operator!(writer, b"q"),
+            Self::RestoreGraphicsState(_) => operator!(writer, b"Q"),
+            Self::ModifyTransformationMatrix(ModifyTransformationMatrix(matrix)) => {
+                operator!(writer, b"cm", matrix)
+            }
+        }
+    }
+}
+
+impl Serialize for PathOperator {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match *self {
+            Self::MoveTo(MoveTo(x, y)) => operator!(writer, b"m", x, y),
+            Self::LineTo(LineTo(x, y)) => operator!(writer, b"l", x, y),
+            Self::CurveTo(CurveTo(x1, y1, x2, y2, x3, y3)) => {
+                operator!(writer, b"c", x1, y1, x2, y2, x3, y3)
+            }
+            Self::CurveToInitialReplicated(CurveToInitialReplicated(x2, y2, x3, y3)) => {
+                operator!(writer, b"v", x2, y2, x3, y3)
+            }
+            Self::CurveToFinalReplicated(CurveToFinalReplicated(x1, y1, x3, y3)) => {
+                operator!(writer, b"y", x1, y1, x3, y3)
+            }
+            Self::ClosePath(_) => operator!(writer, b"h"),
+            Self::AppendRectangle(AppendRectangle(x, y, width, height)) => {
+                operator!(writer, b"re", x, y, width, height)
+            }
+            Self::Stroke(Stroke { close: false }) => operator!(writer, b"S"),
+            Self::Stroke(Stroke { close: true }) => operator!(writer, b"s"),
+            Self::Fill(Fill {
+                even_odd,
+                stroke,
+                close,
+            }) => {
+                // Only `b` and `b*` close the path: an explicit `h` stands for the others.
+                if close && !stroke {
+                    writer.write_all(b"h ")?;
+                }
+                let keyword: &[u8] = match (stroke, close, even_odd) {
+                    (false, _, false) => b"f",
+                    (false, _, true) => b"f*",
+                    (true, false, false) => b"B",
+                    (true, false, true) => b"B*",
+                    (true, true, false) => b"b",
+                    (true, true, true) => b"b*",
+                };
+                writer.write_all(keyword)
+            }
+            Self::EndPath(_) => operator!(writer, b"n"),
+        }
+    }
+}
+
+impl Serialize for TextOperator {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Self::TextStateOperator(operator) => operator.serialize(writer),
+            Self::TextPositioningOperator(operator) => operator.serialize(writer),
+            Self::TextShowingOperator(operator) => operator.serialize(writer),
+        }
+    }
+}
+
+impl Serialize for TextStateOperator {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Self::SetCharacterSpacing(SetCharacterSpacing(spacing)) => {
+                operator!(writer, b"Tc", spacing)
+            }
+            Self::SetWordSpacing(SetWordSpacing(spacing)) => operator!(writer, b"Tw", spacing),
+            Self::SetHorizontalScaling(SetHorizontalScaling(scale)) => {
+                operator!(writer, b"Tz", scale)
+            }
+            Self::SetTextLeading(SetTextLeading(leading)) => operator!(writer, b"TL", leading),
+            Self::SetFontAndFontSize(SetFontAndFontSize(font, size)) => {
+                operator!(writer, b"Tf", font, size)
+            }
+            Self::SetTextRenderingMode(SetTextRenderingMode(mode)) => {
+                operator!(writer, b"Tr", mode)
+            }
+            Self::SetTextRise(SetTextRise(rise)) => operator!(writer, b"Ts", rise),
+        }
+    }
+}
+
+impl Serialize for TextPositioningOperator {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Self::MoveByOffset(MoveByOffset(tx, ty)) => operator!(writer, b"Td", tx, ty),
+            Self::MoveByOffsetAndSetLeading(MoveByOffsetAndSetLeading(tx, ty)) => {
+                operator!(writer, b"TD", tx, ty)
+            }
+            Self::SetTextMatrix(SetTextMatrix(matrix)) => operator!(writer, b"Tm", matrix),
+            Self::MoveToNextLine(_) => operator!(writer, b"T*"),
+        }
+    }
+}
+
+impl Serialize for TextShowingOperator {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Self::ShowText(ShowText(text)) => operator!(writer, b"Tj", text),
+            Self::MoveToNextLineAndShowText(MoveToNextLineAndShowText(text)) => {
+                operator!(writer, b"'", text)
+            }
+            Self::MoveToNextLineAndShowTextWithSpacing(MoveToNextLineAndShowTextWithSpacing(
+                aw,
+                ac,
+                text,
+            )) => operator!(writer, b"\"", aw, ac, text),
+            Self::ShowTextArray(ShowTextArray(elements)) => operator!(writer, b"TJ", elements),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rstest::rstest;
+
+    use crate::extraction::extract;
+
+    use super::*;
+
+    #[rstest]
+    #[case(b"BT", "BT")]
+    #[case(b"q", "q")]
+    #[case(b"1 0 0 -1 0 792 cm", "1 0 0 -1 0 792 cm")]
+    #[case(b".75  0 0 .75 0 0 cm", "0.75 0 0 0.75 0 0 cm")]
+    #[case(b"10 20.5 m", "10 20.5 m")]
+    #[case(b"1 2 3 4 5 6 c", "1 2 3 4 5 6 c")]
+    #[case(b"0 0 816 1056 re", "0 0 816 1056 re")]
+    #[case(b"f*", "f*")]
+    #[case(b"F", "f")]
+    #[case(b"b", "b")]
+    #[case(b"/F1 12 Tf", "/F1 12 Tf")]
+    #[case(b"2 Tr", "2 Tr")]
+    #[case(b"1 0 0 1 72 720 Tm", "1 0 0 1 72 720 Tm")]
+    #[case(b"T*", "T*")]
+    #[case(b"<48656C6C6F> Tj", "(Hello) Tj")]
+    #[case(b"[(A) -120 (B)] TJ", "[(A) -120 (B)] TJ")]
+    #[case(b"1 2 (text) \"", "1 2 (text) \"")]
+    #[case(b"/GS1 gs", "/GS1 gs")]
+    #[case(b"/P <</MCID 0>>BDC", "/P <</MCID 0>> BDC")]
+    fn serialize(#[case] input: &[u8], #[case] expected: &str) {
+        let operator: Operator = extract(&mut input.as_ref()).unwrap();
+        assert_eq!(operator.to_bytes(), expected.as_bytes());
+    }
+
+    #[test]
+    fn spans() {
+        let content = b"  q 1 0 0 1 0 0 cm\n/GS1 gs\nQ %";
+        let operators = spanned_operators(content, usize::MAX).unwrap();
+
+        let spans: Vec<_> = operators.iter().map(|op| op.span.clone()).collect();
+        assert_eq!(spans, vec![2..3, 4..18, 19..26, 27..28]);
+        assert_eq!(&content[19..26], b"/GS1 gs");
+    }
+
+    #[test]
+    fn malformed_and_comments() {
+        // A keyword with operands of the wrong type is kept as an unknown operator, and
+        // comments are skipped.
+        let content = b"q 1 m Q % note\n/F1 Tf BT ET";
+        let operators = spanned_operators(content, usize::MAX).unwrap();
+
+        let serialized: Vec<_> = operators.iter().map(|op| op.operator.to_bytes()).collect();
+        assert_eq!(
+            serialized,
+            [&b"q"[..], b"1 m", b"Q", b"/F1 Tf", b"BT", b"ET"]
+        );
+        assert_eq!(operators[3].span, 15..21);
+    }
+
+    #[test]
+    fn inline_images() {
+        // The data contains an unbalanced parenthesis and a misleading `EI`.
+        let content = b"q BI /W 1 /H 1 /BPC 8 /CS /G ID (\xffEI EI Q BT /F1 12 Tf (x) Tj ET";
+        let operators = spanned_operators(content, usize::MAX).unwrap();
+
+        let serialized: Vec<_> = operators.iter().map(|op| op.operator.to_bytes()).collect();
+        assert_eq!(
+            serialized,
+            [
+                &b"q"[..],
+                b"BI /BPC 8 /CS /G /H 1 /W 1 ID (\xffEI\nEI",
+                b"Q",
+                b"BT",
+                b"/F1 12 Tf",
+                b"(x) Tj",
+                b"ET"
+            ]
+        );
+        assert_eq!(
+            &content[operators[1].span.clone()],
+            b"BI /W 1 /H 1 /BPC 8 /CS /G ID (\xffEI EI"
+        );
+    }
+
+    #[test]
+    fn limit() {
+        assert!(spanned_operators(b"q Q", 2).is_ok());
+        assert!(spanned_operators(b"q Q q", 2).is_err());
+    }
+
+    #[rstest]
+    #[case(
+        FormatOptions::default(),
+        indoc! {"
+            q
+              /GS1  gs
+              BT
+                /F1 12 Tf
+                (Hello) Tj
+              ET
+            Q
+            Q
+        "}
+    )]
+    #[case(
+        FormatOptions::default().with_offsets(),
+        indoc! {"
+            0..1            q
+            2..10             /GS1  gs
+            11..13            BT
+            14..23              /F1 12 Tf
+            24..33              (Hello) Tj
+            34..36            ET
+            37..38          Q
+            39..40          Q
+        "}
+    )]
+    fn format(#[case] options: FormatOptions, #[case] expected: &str) {
+        // The unbalanced `Q` is kept at the top level.
+        let content = b"q\n/GS1  gs\nBT\n/F1 12 Tf\n(Hello)Tj\nET\nQ Q";
+        let operators = spanned_operators(content, usize::MAX).unwrap();
+
+        let mut output = Vec::new();
+        write_content(content, &operators, options, &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+}
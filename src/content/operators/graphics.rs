@@ -0,0 +1,61 @@
+//! Special graphics state operators. See section 8.4.4 of the PDF specification.
+//!
+//! These operators save and restore the graphics state, and modify the current transformation
+//! matrix (CTM), which maps user space to device space. They are required to place text on the
+//! page, since text space is defined relative to user space.
+
+use crate::{content::state::Matrix, extraction::Extract};
+
+/// Abstraction over the special graphics state operators.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphicsStateOperator {
+    /// The `q` operator. Save the current graphics state on the graphics state stack.
+    SaveGraphicsState(SaveGraphicsState),
+    /// The `Q` operator. Restore the graphics state by removing the most recently saved state
+    /// from the stack.
+    RestoreGraphicsState(RestoreGraphicsState),
+    /// The `cm` operator. Modify the current transformation matrix.
+    ModifyTransformationMatrix(ModifyTransformationMatrix),
+}
+
+/// `q` operator.
+///
+/// > Save the current graphics state on the graphics state stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Extract)]
+pub struct SaveGraphicsState;
+
+/// `Q` operator.
+///
+/// > Restore the graphics state by removing the most recently saved state from the stack and
+/// > making it the current state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Extract)]
+pub struct RestoreGraphicsState;
+
+/// `cm` operator.
+///
+/// > Modify the current transformation matrix (CTM) by concatenating the specified matrix.
+///
+/// ```raw
+/// 1 0 0 -1 0 792 cm
+/// .75 0 0 .75 72 72 cm
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Extract)]
+pub struct ModifyTransformationMatrix(pub(crate) Matrix);
+
+macro_rules! impl_from {
+    ($($t:ident,)+) => {
+        $(
+            impl From<$t> for GraphicsStateOperator {
+                fn from(value: $t) -> Self {
+                    Self::$t(value)
+                }
+            }
+        )+
+    };
+}
+
+impl_from!(
+    SaveGraphicsState,
+    RestoreGraphicsState,
+    ModifyTransformationMatrix,
+);
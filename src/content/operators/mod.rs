@@ -15,9 +15,24 @@
 //! Livre uses the second approach, since it is more efficient. Indeed, parsing an `Object`
 //! requires going through every alternative until one matches, while skipping over the operands
 //! allows us to use the right parser directly, which is possible once we know the operator.
+//! The operands of operators that Livre does not type yet are parsed as generic objects (see
+//! [`UnknownOperator`]). Inline images are the exception to the operands-then-keyword rule:
+//! their data follows the `ID` keyword, and is kept verbatim (see [`InlineImage`]).
 
+mod format;
+pub mod graphics;
+pub mod path;
 pub mod text;
 
+pub use format::{spanned_operators, write_content, FormatOptions, SpannedOperator};
+
+use graphics::{
+    GraphicsStateOperator, ModifyTransformationMatrix, RestoreGraphicsState, SaveGraphicsState,
+};
+use path::{
+    AppendRectangle, ClosePath, CurveTo, CurveToFinalReplicated, CurveToInitialReplicated, EndPath,
+    Fill, LineTo, MoveTo, PathOperator, Stroke,
+};
 use text::{
     BeginText, EndText, MoveByOffset, MoveByOffsetAndSetLeading, MoveToNextLine,
     MoveToNextLineAndShowText, MoveToNextLineAndShowTextWithSpacing, SetCharacterSpacing,
@@ -26,15 +41,17 @@ use text::{
 };
 
 use winnow::{
-    ascii::multispace0,
-    combinator::{fail, peek, preceded, repeat, trace},
+    combinator::{fail, opt, peek, preceded, repeat, trace},
     dispatch,
-    error::ErrMode,
-    token::any,
+    error::{ContextError, ErrMode},
+    token::{any, one_of},
     BStr, ModalResult, Parser,
 };
 
-use crate::extraction::{take_till_delimiter, Angles, Brackets, Extract, Name, Parentheses};
+use crate::extraction::{
+    extract, multicomment0, take_till_delimiter, Angles, Brackets, Extract, Map, Name, Object,
+    Parentheses,
+};
 
 /// Content stream operator.
 ///
@@ -59,8 +76,56 @@ pub enum Operator {
     // Delimiters
     BeginText(BeginText),
     EndText(EndText),
+    GraphicsState(GraphicsStateOperator),
+    Path(PathOperator),
     Text(TextOperator),
-    NotImplemented(String),
+    InlineImage(InlineImage),
+    NotImplemented(UnknownOperator),
+}
+
+/// An operator that Livre does not type yet, e.g. a colour or marked-content operator, or a
+/// vendor extension within a `BX`/`EX` compatibility section.
+///
+/// It is kept as its keyword and its operands, parsed as generic objects, such that downstream
+/// code can handle it and content streams can be written back without loss.
+///
+/// ```raw
+/// /P <</MCID 0>> BDC
+/// 0.5 0 0 RG
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownOperator {
+    pub keyword: Vec<u8>,
+    pub operands: Vec<Object>,
+}
+
+/// An inline image, i.e. a `BI` operator followed by the image dictionary, the `ID`
+/// operator, the image data and the `EI` operator.
+///
+/// The data is kept verbatim, still encoded with the filters of the dictionary, whose keys
+/// may be abbreviated (e.g. `/W` for `/Width`).
+///
+/// ```raw
+/// BI /W 2 /H 1 /BPC 8 /CS /G ID
+/// <binary data>
+/// EI
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineImage {
+    pub dict: Map<Object>,
+    pub data: Vec<u8>,
+}
+
+impl From<InlineImage> for Operator {
+    fn from(value: InlineImage) -> Self {
+        Self::InlineImage(value)
+    }
+}
+
+impl From<UnknownOperator> for Operator {
+    fn from(value: UnknownOperator) -> Self {
+        Self::NotImplemented(value)
+    }
 }
 
 macro_rules! impl_from {
@@ -77,6 +142,59 @@ macro_rules! impl_from {
 
 impl_from!(BeginText, EndText,);
 
+macro_rules! impl_from_graphics_state {
+    ($($t:ident,)+) => {
+        $(
+            impl From<$t> for Operator {
+                fn from(value: $t) -> Self {
+                    Self::GraphicsState(value.into())
+                }
+            }
+        )+
+    };
+}
+
+impl_from_graphics_state!(
+    SaveGraphicsState,
+    RestoreGraphicsState,
+    ModifyTransformationMatrix,
+);
+
+macro_rules! impl_from_path {
+    ($($t:ident,)+) => {
+        $(
+            impl From<$t> for Operator {
+                fn from(value: $t) -> Self {
+                    Self::Path(value.into())
+                }
+            }
+        )+
+    };
+}
+
+impl_from_path!(
+    MoveTo,
+    LineTo,
+    CurveTo,
+    CurveToInitialReplicated,
+    CurveToFinalReplicated,
+    ClosePath,
+    AppendRectangle,
+    Stroke,
+    Fill,
+    EndPath,
+);
+
+/// Helper function that builds a [`Fill`] operator.
+fn fill(even_odd: bool, stroke: bool, close: bool) -> Operator {
+    Fill {
+        even_odd,
+        stroke,
+        close,
+    }
+    .into()
+}
+
 impl Extract<'_> for Operator {
     fn extract(input: &mut &BStr) -> ModalResult<Self> {
         trace("livre-operator", parse_operator).parse_next(input)
@@ -92,18 +210,112 @@ fn recognize_operand<'de>(input: &mut &'de BStr) -> ModalResult<&'de [u8]> {
         b'(' => Parentheses::recognize,
         b'<' => Angles::recognize,
         b'+' | b'-' | b'.' | b'0'..=b'9' => take_till_delimiter(1..).map_err(ErrMode::Backtrack),
+        b't' | b'f' | b'n' => take_till_delimiter(1..)
+            .map_err(ErrMode::Backtrack)
+            .verify(|keyword: &[u8]| matches!(keyword, b"true" | b"false" | b"null")),
         _ => fail
     }
     .parse_next(input)
 }
 
-/// Helper function that extracts an operator and converts it to [`Operator`].
-fn extract_operator<'a, T>(input: &mut &'a BStr) -> ModalResult<Operator>
+/// Parse an inline image, which follows the `BI` keyword: pairs of keys and values up to the
+/// `ID` keyword, and then the image data up to the `EI` keyword.
+///
+/// The data ends at the first `EI` keyword that is preceded by a white-space character and
+/// followed by a delimiter, like most PDF readers do.
+fn inline_image(input: &mut &BStr) -> ModalResult<Operator> {
+    let mut dict = Map::new();
+
+    loop {
+        multicomment0.parse_next(input)?;
+        if is_keyword(input, b"ID") {
+            break;
+        }
+
+        let key = Name::extract(input)?;
+        let value = preceded(multicomment0, recognize_operand).parse_next(input)?;
+        dict.insert(key, extract(&mut value.as_ref())?);
+    }
+
+    // A single white-space character separates the `ID` keyword from the data.
+    (b"ID", one_of(WHITESPACE)).parse_next(input)?;
+
+    let end = (0..input.len())
+        .find(|&i| WHITESPACE.contains(&input[i]) && is_keyword(&input[i + 1..], b"EI"))
+        .ok_or(ErrMode::Backtrack(ContextError::new()))?;
+
+    let data = input[..end].to_vec();
+    *input = BStr::new(&input[end + 3..]);
+
+    Ok(InlineImage { dict, data }.into())
+}
+
+const WHITESPACE: &[u8] = b"\0\t\n\x0c\r ";
+
+/// Whether the input starts with the supplied keyword, followed by a delimiter or by the end of
+/// the input.
+fn is_keyword(input: &[u8], keyword: &[u8]) -> bool {
+    input.strip_prefix(keyword).is_some_and(|rest| {
+        rest.first()
+            .is_none_or(|&b| WHITESPACE.contains(&b) || b"()<>[]{}/%".contains(&b))
+    })
+}
+
+/// Helper function that extracts an operator and converts it to [`Operator`]. Operands that
+/// do not match the expected types make an [`UnknownOperator`] instead, such that no operator
+/// is lost.
+fn extract_operator<'a, T>(keyword: &[u8], input: &mut &'a BStr) -> ModalResult<Operator>
 where
     T: Extract<'a>,
     Operator: From<T>,
 {
-    T::extract.map(Operator::from).parse_next(input)
+    let mut typed = *input;
+
+    match T::extract(&mut typed) {
+        Ok(operator) => Ok(operator.into()),
+        Err(ErrMode::Backtrack(_)) => unknown_operator(keyword, input),
+        Err(err) => Err(err),
+    }
+}
+
+/// Build an [`UnknownOperator`], parsing each of its operands as an [`Object`].
+fn unknown_operator(keyword: &[u8], input: &mut &BStr) -> ModalResult<Operator> {
+    let mut operands = Vec::new();
+
+    while let Some(operand) = opt(preceded(multicomment0, recognize_operand)).parse_next(input)? {
+        operands.push(extract(&mut operand.as_ref())?);
+    }
+
+    Ok(UnknownOperator {
+        keyword: keyword.to_vec(),
+        operands,
+    }
+    .into())
+}
+
+/// Skip the malformed token that stopped [`Operator::extract`], along with the operands that
+/// precede it. Returns `false` if there is no such token, i.e. the input ends in the middle of
+/// an operator, e.g. with operands whose keyword is missing or with an unterminated string.
+pub(crate) fn skip_malformed(input: &mut &BStr) -> bool {
+    let mut rest = *input;
+    let operands: ModalResult<()> =
+        repeat(0.., preceded(multicomment0, recognize_operand)).parse_next(&mut rest);
+    let _ = operands.and_then(|()| multicomment0.parse_next(&mut rest));
+
+    match rest.first() {
+        // An unbalanced opening delimiter runs to the end of the input.
+        None | Some(b'(' | b'[' | b'<') => false,
+        // So does an inline image without its `EI` keyword.
+        Some(_) if is_keyword(rest, b"BI") => false,
+        // Either a stray delimiter, or a keyword whose operands cannot be parsed.
+        Some(_) => {
+            if take_till_delimiter(1..).parse_next(&mut rest).is_err() {
+                rest = BStr::new(&rest[1..]);
+            }
+            *input = rest;
+            true
+        }
+    }
 }
 
 /// Parse an operator from the input.
@@ -113,38 +325,61 @@ where
 fn parse_operator(input: &mut &BStr) -> ModalResult<Operator> {
     let mut cursor = *input;
 
-    repeat(0.., preceded(multispace0, recognize_operand))
+    repeat(0.., preceded(multicomment0, recognize_operand))
         .map(|()| ())
         .parse_next(input)?;
 
     let op = preceded(
-        multispace0,
-        take_till_delimiter(1..=3).map_err(ErrMode::Backtrack),
+        multicomment0,
+        take_till_delimiter(1..).map_err(ErrMode::Backtrack),
     )
     .parse_next(input)?;
 
     let operator = match op {
+        b"BI" => inline_image(input)?,
         // Text object operators
         b"BT" => BeginText.into(),
         b"ET" => EndText.into(),
+        // Special graphics state operators
+        b"q" => SaveGraphicsState.into(),
+        b"Q" => RestoreGraphicsState.into(),
+        b"cm" => extract_operator::<ModifyTransformationMatrix>(op, &mut cursor)?,
+        // Path construction operators
+        b"m" => extract_operator::<MoveTo>(op, &mut cursor)?,
+        b"l" => extract_operator::<LineTo>(op, &mut cursor)?,
+        b"c" => extract_operator::<CurveTo>(op, &mut cursor)?,
+        b"v" => extract_operator::<CurveToInitialReplicated>(op, &mut cursor)?,
+        b"y" => extract_operator::<CurveToFinalReplicated>(op, &mut cursor)?,
+        b"h" => ClosePath.into(),
+        b"re" => extract_operator::<AppendRectangle>(op, &mut cursor)?,
+        // Path painting operators
+        b"S" => Stroke { close: false }.into(),
+        b"s" => Stroke { close: true }.into(),
+        b"f" | b"F" => fill(false, false, false),
+        b"f*" => fill(true, false, false),
+        b"B" => fill(false, true, false),
+        b"B*" => fill(true, true, false),
+        b"b" => fill(false, true, true),
+        b"b*" => fill(true, true, true),
+        b"n" => EndPath.into(),
         // Text state operators
-        b"Tc" => extract_operator::<SetCharacterSpacing>(&mut cursor)?,
-        b"Tw" => extract_operator::<SetWordSpacing>(&mut cursor)?,
-        b"Tz" => extract_operator::<SetHorizontalScaling>(&mut cursor)?,
-        b"TL" => extract_operator::<SetTextLeading>(&mut cursor)?,
-        b"Tf" => extract_operator::<SetFontAndFontSize>(&mut cursor)?,
-        b"Tr" => extract_operator::<SetTextRenderingMode>(&mut cursor)?,
-        b"Ts" => extract_operator::<SetTextRise>(&mut cursor)?,
+        b"Tc" => extract_operator::<SetCharacterSpacing>(op, &mut cursor)?,
+        b"Tw" => extract_operator::<SetWordSpacing>(op, &mut cursor)?,
+        b"Tz" => extract_operator::<SetHorizontalScaling>(op, &mut cursor)?,
+        b"TL" => extract_operator::<SetTextLeading>(op, &mut cursor)?,
+        b"Tf" => extract_operator::<SetFontAndFontSize>(op, &mut cursor)?,
+        b"Tr" => extract_operator::<SetTextRenderingMode>(op, &mut cursor)?,
+        b"Ts" => extract_operator::<SetTextRise>(op, &mut cursor)?,
         // Text positioning operators
-        b"Td" => extract_operator::<MoveByOffset>(&mut cursor)?,
-        b"TD" => extract_operator::<MoveByOffsetAndSetLeading>(&mut cursor)?,
-        b"Tm" => extract_operator::<SetTextMatrix>(&mut cursor)?,
-        b"T*" => extract_operator::<MoveToNextLine>(&mut cursor)?,
-        b"Tj" => extract_operator::<ShowText>(&mut cursor)?,
-        b"'" => extract_operator::<MoveToNextLineAndShowText>(&mut cursor)?,
-        b"\"" => extract_operator::<MoveToNextLineAndShowTextWithSpacing>(&mut cursor)?,
-        b"TJ" => extract_operator::<ShowTextArray>(&mut cursor)?,
-        _ => Operator::NotImplemented(String::from_utf8_lossy(op).to_string()),
+        b"Td" => extract_operator::<MoveByOffset>(op, &mut cursor)?,
+        b"TD" => extract_operator::<MoveByOffsetAndSetLeading>(op, &mut cursor)?,
+        b"Tm" => extract_operator::<SetTextMatrix>(op, &mut cursor)?,
+        b"T*" => extract_operator::<MoveToNextLine>(op, &mut cursor)?,
+        b"Tj" => extract_operator::<ShowText>(op, &mut cursor)?,
+        b"'" => extract_operator::<MoveToNextLineAndShowText>(op, &mut cursor)?,
+        b"\"" => extract_operator::<MoveToNextLineAndShowTextWithSpacing>(op, &mut cursor)?,
+        b"TJ" => extract_operator::<ShowTextArray>(op, &mut cursor)?,
+        _ => unknown_operator(op, &mut cursor)?,
     };
 
     Ok(operator)
@@ -231,6 +466,21 @@ mod tests {
     #[case(b"0.12 Tc", op!(0.12 Tc))]
     #[case(b"1.0 Tw", op!(1.0 Tw))]
     #[case(b"T*", MoveToNextLine)]
+    #[case(b"10 20 m", MoveTo(10.0, 20.0))]
+    #[case(b"10 20.5 l", LineTo(10.0, 20.5))]
+    #[case(b"1 2 3 4 5 6 c", CurveTo(1.0, 2.0, 3.0, 4.0, 5.0, 6.0))]
+    #[case(b"0 0 816 1056 re", AppendRectangle(0.0, 0.0, 816.0, 1056.0))]
+    #[case(b"h", ClosePath)]
+    #[case(b"S", Stroke { close: false })]
+    #[case(b"f*", Fill { even_odd: true, stroke: false, close: false })]
+    #[case(b"b", Fill { even_odd: false, stroke: true, close: true })]
+    #[case(b"n", EndPath)]
+    #[case(b"q", SaveGraphicsState)]
+    #[case(b"Q", RestoreGraphicsState)]
+    #[case(
+        b"1 0 0 -1 0 792 cm",
+        ModifyTransformationMatrix([1.0, 0.0, 0.0, -1.0, 0.0, 792.0].into())
+    )]
     fn units<O>(#[case] input: &[u8], #[case] expected: O)
     where
         O: Into<Operator>,
@@ -239,4 +489,66 @@ mod tests {
         let expected: Operator = expected.into();
         assert_eq!(expected, result);
     }
+
+    fn unknown(keyword: &str, operands: Vec<Object>) -> UnknownOperator {
+        UnknownOperator {
+            keyword: keyword.into(),
+            operands,
+        }
+    }
+
+    #[rstest]
+    #[case(b"BX", unknown("BX", vec![]))]
+    #[case(
+        b"0.5 0 0 RG",
+        unknown("RG", vec![Object::Real(0.5), Object::Integer(0), Object::Integer(0)])
+    )]
+    #[case(
+        b"/P <</MCID 0>> BDC",
+        unknown(
+            "BDC",
+            vec![
+                Object::Name("P".into()),
+                Object::Dictionary([("MCID".into(), Object::Integer(0))].into_iter().collect()),
+            ]
+        )
+    )]
+    #[case(b"[3 2] 0 d", unknown("d", vec![vec![3, 2].into(), Object::Integer(0)]))]
+    #[case(b"(text) 12 vendorOp", unknown("vendorOp", vec![Object::String(b"text".to_vec()), Object::Integer(12)]))]
+    #[case(b"1 m", unknown("m", vec![Object::Integer(1)]))]
+    #[case(b"/F1 Tf", unknown("Tf", vec![Object::Name("F1".into())]))]
+    #[case(b"true null vendorOp", unknown("vendorOp", vec![Object::Boolean(true), Object::Null]))]
+    #[case(b"% comment\n(a) 1 % another\nTJ", unknown("TJ", vec![Object::String(b"a".to_vec()), Object::Integer(1)]))]
+    fn unknown_operators(#[case] input: &[u8], #[case] expected: UnknownOperator) {
+        let result: Operator = extract(&mut input.as_ref()).unwrap();
+        assert_eq!(result, Operator::NotImplemented(expected));
+    }
+
+    #[rstest]
+    #[case(b"BI /W 1 /H 1 ID \xff\nEI", &[("W", 1.into()), ("H", 1.into())], b"\xff")]
+    #[case(
+        b"BI /IM true /D [1 0] ID\r(EI)\rEI\n",
+        &[("IM", true.into()), ("D", vec![1, 0].into())],
+        b"(EI)"
+    )]
+    #[case(b"BI ID  EI", &[], b"")]
+    fn inline_images(#[case] input: &[u8], #[case] dict: &[(&str, Object)], #[case] data: &[u8]) {
+        let result: Operator = extract(&mut input.as_ref()).unwrap();
+        let expected = InlineImage {
+            dict: dict
+                .iter()
+                .map(|(key, value)| (Name::from(*key), value.clone()))
+                .collect(),
+            data: data.to_vec(),
+        };
+        assert_eq!(result, Operator::InlineImage(expected));
+    }
+
+    #[rstest]
+    #[case(b"BI /W 1 ID \xff")]
+    #[case(b"BI /W 1 EI")]
+    fn incomplete_inline_images(#[case] input: &[u8]) {
+        assert!(Operator::extract(&mut input.as_ref()).is_err());
+        assert!(!skip_malformed(&mut input.as_ref()));
+    }
 }
@@ -0,0 +1,167 @@
+//! Path construction and painting operators. See sections 8.5.2 and 8.5.3 of the PDF
+//! specification.
+//!
+//! A path is built from straight lines, curves and rectangles, and then painted - stroked,
+//! filled or both - or merely used for clipping. Livre reads paths to detect the rules and
+//! borders that make up tables.
+
+use crate::extraction::Extract;
+
+/// Abstraction over the path construction and painting operators.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathOperator {
+    /// The `m` operator. Begin a new subpath.
+    MoveTo(MoveTo),
+    /// The `l` operator. Append a straight line segment to the current subpath.
+    LineTo(LineTo),
+    /// The `c` operator. Append a cubic Bézier curve to the current subpath.
+    CurveTo(CurveTo),
+    /// The `v` operator. Append a cubic Bézier curve, using the current point as the first
+    /// control point.
+    CurveToInitialReplicated(CurveToInitialReplicated),
+    /// The `y` operator. Append a cubic Bézier curve, using the final point as the second
+    /// control point.
+    CurveToFinalReplicated(CurveToFinalReplicated),
+    /// The `h` operator. Close the current subpath.
+    ClosePath(ClosePath),
+    /// The `re` operator. Append a rectangle as a complete subpath.
+    AppendRectangle(AppendRectangle),
+    /// The `S` and `s` operators. Stroke the path, after closing it for `s`.
+    Stroke(Stroke),
+    /// The `f`, `F`, `f*`, `B`, `B*`, `b` and `b*` operators. Fill the path, and stroke it for
+    /// the latter four.
+    Fill(Fill),
+    /// The `n` operator. End the path without painting it, which is used for clipping.
+    EndPath(EndPath),
+}
+
+/// `m` operator.
+///
+/// > Begin a new subpath by moving the current point to coordinates `(x, y)`, omitting any
+/// > connecting line segment.
+#[derive(Debug, Clone, Copy, PartialEq, Extract)]
+pub struct MoveTo(pub(crate) f32, pub(crate) f32);
+
+/// `l` operator.
+///
+/// > Append a straight line segment from the current point to the point `(x, y)`.
+#[derive(Debug, Clone, Copy, PartialEq, Extract)]
+pub struct LineTo(pub(crate) f32, pub(crate) f32);
+
+/// `c` operator.
+///
+/// > Append a cubic Bézier curve to the current path. The curve shall extend from the current
+/// > point to the point `(x3, y3)`, using `(x1, y1)` and `(x2, y2)` as the Bézier control
+/// > points.
+#[derive(Debug, Clone, Copy, PartialEq, Extract)]
+pub struct CurveTo(
+    pub(crate) f32,
+    pub(crate) f32,
+    pub(crate) f32,
+    pub(crate) f32,
+    pub(crate) f32,
+    pub(crate) f32,
+);
+
+/// `v` operator.
+///
+/// > Append a cubic Bézier curve to the current path. The curve shall extend from the current
+/// > point to the point `(x3, y3)`, using the current point and `(x2, y2)` as the Bézier
+/// > control points.
+#[derive(Debug, Clone, Copy, PartialEq, Extract)]
+pub struct CurveToInitialReplicated(
+    pub(crate) f32,
+    pub(crate) f32,
+    pub(crate) f32,
+    pub(crate) f32,
+);
+
+/// `y` operator.
+///
+/// > Append a cubic Bézier curve to the current path. The curve shall extend from the current
+/// > point to the point `(x3, y3)`, using `(x1, y1)` and `(x3, y3)` as the Bézier control
+/// > points.
+#[derive(Debug, Clone, Copy, PartialEq, Extract)]
+pub struct CurveToFinalReplicated(
+    pub(crate) f32,
+    pub(crate) f32,
+    pub(crate) f32,
+    pub(crate) f32,
+);
+
+/// `h` operator.
+///
+/// > Close the current subpath by appending a straight line segment from the current point to
+/// > the starting point of the subpath.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Extract)]
+pub struct ClosePath;
+
+/// `re` operator.
+///
+/// > Append a rectangle to the current path as a complete subpath, with lower-left corner
+/// > `(x, y)` and dimensions `width` and `height` in user space.
+///
+/// ```raw
+/// 0 0 816 1056 re
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Extract)]
+pub struct AppendRectangle(
+    pub(crate) f32,
+    pub(crate) f32,
+    pub(crate) f32,
+    pub(crate) f32,
+);
+
+/// `S` and `s` operators.
+///
+/// > Stroke the path.
+///
+/// The `s` operator closes the path first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stroke {
+    pub close: bool,
+}
+
+/// `f`, `F`, `f*`, `B`, `B*`, `b` and `b*` operators.
+///
+/// > Fill the path, using the nonzero winding number rule to determine the region to fill.
+///
+/// The starred variants use the even-odd rule instead. `B` and `b` fill and then stroke the
+/// path, `b` closing it first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fill {
+    pub even_odd: bool,
+    pub stroke: bool,
+    pub close: bool,
+}
+
+/// `n` operator.
+///
+/// > End the path object without filling or stroking it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Extract)]
+pub struct EndPath;
+
+macro_rules! impl_from {
+    ($($t:ident,)+) => {
+        $(
+            impl From<$t> for PathOperator {
+                fn from(value: $t) -> Self {
+                    Self::$t(value)
+                }
+            }
+        )+
+    };
+}
+
+impl_from!(
+    MoveTo,
+    LineTo,
+    CurveTo,
+    CurveToInitialReplicated,
+    CurveToFinalReplicated,
+    ClosePath,
+    AppendRectangle,
+    Stroke,
+    Fill,
+    EndPath,
+);
@@ -34,7 +34,7 @@ pub enum TextPositioningOperator {
 /// 8.1511078 0 Td
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Extract)]
-pub struct MoveByOffset(f32, f32);
+pub struct MoveByOffset(pub(crate) f32, pub(crate) f32);
 
 impl PreTextOperation for MoveByOffset {
     fn preapply(self, matrix: &mut TextMatrix, _parameters: &mut TextStateParameters) {
@@ -68,7 +68,7 @@ impl PreTextOperation for MoveByOffsetAndSetLeading {
 /// 1 0 0 -1 370.70721 .47981739 Tm
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Extract)]
-pub struct SetTextMatrix(TextMatrix);
+pub struct SetTextMatrix(pub(crate) TextMatrix);
 
 impl PreTextOperation for SetTextMatrix {
     fn preapply(self, matrix: &mut TextMatrix, _: &mut TextStateParameters) {
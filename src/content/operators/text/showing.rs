@@ -1,6 +1,9 @@
 //! Text-showing operators. See section 9.4.3 of the PDF specification.
 
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
 
 use enum_dispatch::enum_dispatch;
 use winnow::{combinator::peek, dispatch, token::any, BStr, ModalResult, Parser};
@@ -8,6 +11,7 @@ use winnow::{combinator::peek, dispatch, token::any, BStr, ModalResult, Parser};
 use crate::{
     content::state::TextObject,
     extraction::{extract, Extract, HexadecimalString, LiteralString, PDFString},
+    serialization::Serialize,
 };
 
 use super::TextOperation;
@@ -44,7 +48,7 @@ impl Display for TextShowingOperator {
 /// <0052> Tj
 /// ```
 #[derive(Debug, Clone, PartialEq, Extract)]
-pub struct ShowText(PDFString);
+pub struct ShowText(pub(crate) PDFString);
 
 impl Display for ShowText {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -67,7 +71,7 @@ impl TextOperation for ShowText {
 /// string Tj
 /// ```
 #[derive(Debug, Clone, PartialEq, Extract)]
-pub struct MoveToNextLineAndShowText(PDFString);
+pub struct MoveToNextLineAndShowText(pub(crate) PDFString);
 
 impl TextOperation for MoveToNextLineAndShowText {
     fn apply(self, text_object: &mut TextObject) {
@@ -88,7 +92,11 @@ impl Display for MoveToNextLineAndShowText {
 /// `ac` as the character spacing (setting the corresponding parameters in the text state).
 /// `aw` and `ac` shall be numbers expressed in unscaled text space units.
 #[derive(Debug, Clone, PartialEq, Extract)]
-pub struct MoveToNextLineAndShowTextWithSpacing(f32, f32, PDFString);
+pub struct MoveToNextLineAndShowTextWithSpacing(
+    pub(crate) f32,
+    pub(crate) f32,
+    pub(crate) PDFString,
+);
 
 impl TextOperation for MoveToNextLineAndShowTextWithSpacing {
     fn apply(self, text_object: &mut TextObject) {
@@ -122,7 +130,7 @@ impl Display for MoveToNextLineAndShowTextWithSpacing {
 /// [(5)-6(1)-6(,)-2( )-2(A)] TJ
 /// ```
 #[derive(Debug, Clone, PartialEq, Extract)]
-pub struct ShowTextArray(Vec<TextArrayElement>);
+pub struct ShowTextArray(pub(crate) Vec<TextArrayElement>);
 
 impl TextOperation for ShowTextArray {
     fn apply(self, text_object: &mut TextObject) {
@@ -178,6 +186,18 @@ impl From<f32> for TextArrayElement {
     }
 }
 
+impl Serialize for TextArrayElement {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Self::Text(text) => text.serialize(writer),
+            Self::Offset(offset) => offset.serialize(writer),
+        }
+    }
+}
+
 impl Extract<'_> for TextArrayElement {
     fn extract(input: &mut &BStr) -> ModalResult<Self> {
         dispatch! {peek(any);
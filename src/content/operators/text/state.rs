@@ -48,7 +48,7 @@ impl PreTextOperation for SetWordSpacing {
 
 /// `Tz` operator.
 #[derive(Debug, Clone, Copy, PartialEq, Extract)]
-pub struct SetHorizontalScaling(f32);
+pub struct SetHorizontalScaling(pub(crate) f32);
 
 impl PreTextOperation for SetHorizontalScaling {
     fn preapply(self, _: &mut TextMatrix, parameters: &mut TextStateParameters) {
@@ -58,7 +58,7 @@ impl PreTextOperation for SetHorizontalScaling {
 
 /// `TL` operator.
 #[derive(Debug, Clone, Copy, PartialEq, Extract)]
-pub struct SetTextLeading(f32);
+pub struct SetTextLeading(pub(crate) f32);
 
 impl PreTextOperation for SetTextLeading {
     fn preapply(self, _: &mut TextMatrix, parameters: &mut TextStateParameters) {
@@ -87,7 +87,7 @@ impl PreTextOperation for SetFontAndFontSize {
 /// 2 Tr
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Extract)]
-pub struct SetTextRenderingMode(RenderingMode);
+pub struct SetTextRenderingMode(pub(crate) RenderingMode);
 
 impl PreTextOperation for SetTextRenderingMode {
     fn preapply(self, _: &mut TextMatrix, parameters: &mut TextStateParameters) {
@@ -97,7 +97,7 @@ impl PreTextOperation for SetTextRenderingMode {
 
 /// `Ts` operator.
 #[derive(Debug, Clone, Copy, PartialEq, Extract)]
-pub struct SetTextRise(f32);
+pub struct SetTextRise(pub(crate) f32);
 
 impl PreTextOperation for SetTextRise {
     fn preapply(self, _: &mut TextMatrix, parameters: &mut TextStateParameters) {
@@ -0,0 +1,40 @@
+//! A minimal PNG encoder, for the images of the SVG export and for rendered pages.
+
+use std::io::Write;
+
+use flate2::{write::ZlibEncoder, Compression, Crc};
+
+/// Encode RGBA pixels as a PNG image, without filtering.
+pub(crate) fn encode(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        png.extend((data.len() as u32).to_be_bytes());
+        png.extend(kind);
+        png.extend(data);
+
+        let mut crc = Crc::new();
+        crc.update(kind);
+        crc.update(data);
+        png.extend(crc.sum().to_be_bytes());
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // 8 bits per channel, RGBA, default compression and filtering, no interlacing.
+    header.extend([8, 6, 0, 0, 0]);
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in pixels.chunks((width as usize * 4).max(1)) {
+        encoder
+            .write_all(&[0])
+            .and_then(|_| encoder.write_all(row))
+            .expect("writing to a vector cannot fail");
+    }
+    let data = encoder.finish().expect("writing to a vector cannot fail");
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &data);
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
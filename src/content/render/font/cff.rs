@@ -0,0 +1,978 @@
+//! Compact Font Format programs, as embedded by `FontFile3` streams and OpenType fonts, with
+//! their Type 2 charstrings.
+
+use std::{collections::HashMap, ops::Range};
+
+use crate::content::{
+    interpreter::{Path, PathElement},
+    Matrix,
+};
+
+use super::{u16_at, u32_at};
+
+/// Subroutine calls and `seac` accents nested deeper than this are cut short.
+const MAX_DEPTH: usize = 10;
+
+/// Operands of a charstring, at most.
+const MAX_STACK: usize = 48;
+
+/// The first strings of the standard strings, up to the ISOAdobe charset. String identifiers
+/// (SIDs) up to 390 are standard, while the others index the font's String INDEX.
+#[rustfmt::skip]
+const STANDARD_STRINGS: [&str; 229] = [
+    ".notdef", "space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand",
+    "quoteright", "parenleft", "parenright", "asterisk", "plus", "comma", "hyphen", "period",
+    "slash", "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    "colon", "semicolon", "less", "equal", "greater", "question", "at",
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M",
+    "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z",
+    "bracketleft", "backslash", "bracketright", "asciicircum", "underscore", "quoteleft",
+    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m",
+    "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z",
+    "braceleft", "bar", "braceright", "asciitilde", "exclamdown", "cent", "sterling",
+    "fraction", "yen", "florin", "section", "currency", "quotesingle", "quotedblleft",
+    "guillemotleft", "guilsinglleft", "guilsinglright", "fi", "fl", "endash", "dagger",
+    "daggerdbl", "periodcentered", "paragraph", "bullet", "quotesinglbase", "quotedblbase",
+    "quotedblright", "guillemotright", "ellipsis", "perthousand", "questiondown", "grave",
+    "acute", "circumflex", "tilde", "macron", "breve", "dotaccent", "dieresis", "ring",
+    "cedilla", "hungarumlaut", "ogonek", "caron", "emdash", "AE", "ordfeminine", "Lslash",
+    "Oslash", "OE", "ordmasculine", "ae", "dotlessi", "lslash", "oslash", "oe", "germandbls",
+    "onesuperior", "logicalnot", "mu", "trademark", "Eth", "onehalf", "plusminus", "Thorn",
+    "onequarter", "divide", "brokenbar", "degree", "thorn", "threequarters", "twosuperior",
+    "registered", "minus", "eth", "multiply", "threesuperior", "copyright", "Aacute",
+    "Acircumflex", "Adieresis", "Agrave", "Aring", "Atilde", "Ccedilla", "Eacute",
+    "Ecircumflex", "Edieresis", "Egrave", "Iacute", "Icircumflex", "Idieresis", "Igrave",
+    "Ntilde", "Oacute", "Ocircumflex", "Odieresis", "Ograve", "Otilde", "Scaron", "Uacute",
+    "Ucircumflex", "Udieresis", "Ugrave", "Yacute", "Ydieresis", "Zcaron", "aacute",
+    "acircumflex", "adieresis", "agrave", "aring", "atilde", "ccedilla", "eacute",
+    "ecircumflex", "edieresis", "egrave", "iacute", "icircumflex", "idieresis", "igrave",
+    "ntilde", "oacute", "ocircumflex", "odieresis", "ograve", "otilde", "scaron", "uacute",
+    "ucircumflex", "udieresis", "ugrave", "yacute", "ydieresis", "zcaron",
+];
+
+/// The number of standard strings.
+const STANDARD_STRING_COUNT: usize = 391;
+
+/// The SID of a code in the standard encoding, if the code is defined.
+fn standard_encoding(code: u8) -> Option<u16> {
+    let sid = match code {
+        0x20..=0x7E => code - 0x1F,
+        0xA1..=0xAF => code - 0xA1 + 96,
+        0xB1..=0xB4 => code - 0xB1 + 111,
+        0xB6..=0xBB => code - 0xB6 + 115,
+        0xBC..=0xBD => code - 0xBC + 121,
+        0xBF => 123,
+        0xC1..=0xC8 => code - 0xC1 + 124,
+        0xCA..=0xCB => code - 0xCA + 132,
+        0xCD..=0xCF => code - 0xCD + 134,
+        0xD0 => 137,
+        0xE1 => 138,
+        0xE3 => 139,
+        0xE8..=0xEB => code - 0xE8 + 140,
+        0xF1 => 144,
+        0xF5 => 145,
+        0xF8..=0xFB => code - 0xF8 + 146,
+        _ => return None,
+    };
+    Some(sid as u16)
+}
+
+/// A CFF font program, with its first font only.
+#[derive(Debug)]
+pub(crate) struct Cff {
+    data: Vec<u8>,
+    strings: Vec<Range<usize>>,
+    global_subrs: Vec<Range<usize>>,
+    char_strings: Vec<Range<usize>>,
+    /// The local subroutines of each font DICT: a single one, unless the font is CID-keyed.
+    local_subrs: Vec<Vec<Range<usize>>>,
+    /// The font DICT of each glyph, for CID-keyed fonts.
+    fd_select: Option<Vec<u8>>,
+    /// The SID of each glyph, or its CID for CID-keyed fonts.
+    charset: Vec<u16>,
+    /// The glyph of each code of the built-in encoding.
+    encoding: HashMap<u8, u16>,
+    matrix: Matrix,
+    cid_keyed: bool,
+}
+
+impl Cff {
+    pub(super) fn parse(data: Vec<u8>) -> Option<Self> {
+        let header_size = *data.get(2)? as usize;
+        let (_names, end) = index(&data, header_size)?;
+        let (top_dicts, end) = index(&data, end)?;
+        let (strings, end) = index(&data, end)?;
+        let (global_subrs, _) = index(&data, end)?;
+
+        let top = dict(data.get(top_dicts.first()?.clone())?);
+        let offset = |key| top.get(&key).and_then(|v| v.first()).map(|&v| v as usize);
+
+        let (char_strings, _) = index(&data, offset(17)?)?;
+        let cid_keyed = top.contains_key(&1230);
+
+        let local_subrs = if cid_keyed {
+            let (fonts, _) = index(&data, offset(1236)?)?;
+            fonts
+                .into_iter()
+                .map(|font| {
+                    let font = dict(data.get(font).unwrap_or_default());
+                    private_subrs(&data, font.get(&18))
+                })
+                .collect()
+        } else {
+            vec![private_subrs(&data, top.get(&18))]
+        };
+
+        let fd_select = if cid_keyed {
+            offset(1237).and_then(|offset| fd_select(&data, offset, char_strings.len()))
+        } else {
+            None
+        };
+
+        let charset = match offset(15).unwrap_or(0) {
+            // The predefined charsets. Only ISOAdobe is supported, whose SIDs are the glyph
+            // indices.
+            0..=2 => (0..char_strings.len() as u16).collect(),
+            offset => charset(&data, offset, char_strings.len())?,
+        };
+
+        let mut cff = Self {
+            strings,
+            global_subrs,
+            char_strings,
+            local_subrs,
+            fd_select,
+            charset,
+            encoding: HashMap::new(),
+            matrix: Matrix::from([0.001, 0.0, 0.0, 0.001, 0.0, 0.0]),
+            cid_keyed,
+            data,
+        };
+
+        if let Some(&[a, b, c, d, e, f]) = top.get(&1207).map(Vec::as_slice) {
+            cff.matrix = Matrix::from([a, b, c, d, e, f].map(|v| v as f32));
+        }
+
+        if !cid_keyed {
+            cff.encoding = match offset(16).unwrap_or(0) {
+                0 => (0..=255)
+                    .filter_map(|code| Some((code, cff.sid_to_gid(standard_encoding(code)?)?)))
+                    .collect(),
+                // The expert encoding is only used by fonts of small caps and old-style figures.
+                1 => HashMap::new(),
+                offset => cff.custom_encoding(offset).unwrap_or_default(),
+            };
+        }
+
+        Some(cff)
+    }
+
+    pub(super) fn is_cid_keyed(&self) -> bool {
+        self.cid_keyed
+    }
+
+    /// The glyph of a code of the built-in encoding.
+    pub(super) fn encoded(&self, code: u8) -> Option<u16> {
+        self.encoding.get(&code).copied()
+    }
+
+    /// The glyph of a CID, for CID-keyed fonts. Other fonts use glyph indices as CIDs.
+    pub(super) fn cid_to_gid(&self, cid: u16) -> Option<u16> {
+        if !self.cid_keyed {
+            return Some(cid);
+        }
+        self.charset
+            .iter()
+            .position(|&found| found == cid)
+            .map(|gid| gid as u16)
+    }
+
+    /// The glyph with the given name.
+    pub(super) fn named(&self, name: &[u8]) -> Option<u16> {
+        let sid = match STANDARD_STRINGS.iter().position(|s| s.as_bytes() == name) {
+            Some(sid) => sid,
+            None => {
+                let index = self
+                    .strings
+                    .iter()
+                    .position(|range| &self.data[range.clone()] == name)?;
+                STANDARD_STRING_COUNT + index
+            }
+        };
+        self.sid_to_gid(u16::try_from(sid).ok()?)
+    }
+
+    fn sid_to_gid(&self, sid: u16) -> Option<u16> {
+        if self.cid_keyed {
+            return None;
+        }
+        self.charset
+            .iter()
+            .position(|&found| found == sid)
+            .map(|gid| gid as u16)
+    }
+
+    fn custom_encoding(&self, offset: usize) -> Option<HashMap<u8, u16>> {
+        let data = &self.data;
+        let format = *data.get(offset)?;
+        let mut encoding = HashMap::new();
+
+        let mut end = match format & 0x7F {
+            0 => {
+                let count = *data.get(offset + 1)? as usize;
+                for (gid, &code) in data.get(offset + 2..offset + 2 + count)?.iter().enumerate() {
+                    encoding.insert(code, gid as u16 + 1);
+                }
+                offset + 2 + count
+            }
+            1 => {
+                let ranges = *data.get(offset + 1)? as usize;
+                let mut gid = 1;
+                for range in data
+                    .get(offset + 2..offset + 2 + 2 * ranges)?
+                    .chunks_exact(2)
+                {
+                    for code in range[0]..=range[0].saturating_add(range[1]) {
+                        encoding.insert(code, gid);
+                        gid += 1;
+                    }
+                }
+                offset + 2 + 2 * ranges
+            }
+            _ => return None,
+        };
+
+        // Supplements map additional codes to glyphs by name.
+        if format & 0x80 != 0 {
+            let count = *data.get(end)? as usize;
+            end += 1;
+            for supplement in data.get(end..end + 3 * count)?.chunks_exact(3) {
+                let sid = u16::from_be_bytes([supplement[1], supplement[2]]);
+                if let Some(gid) = self.sid_to_gid(sid) {
+                    encoding.insert(supplement[0], gid);
+                }
+            }
+        }
+
+        Some(encoding)
+    }
+
+    /// The outline of a glyph, in em units.
+    pub(super) fn outline(&self, gid: u16) -> Option<Path> {
+        let mut charstring = Charstring {
+            cff: self,
+            local_subrs: self.local_subrs_of(gid),
+            path: Path::default(),
+            stack: Vec::new(),
+            stems: 0,
+            width_parsed: false,
+            point: (0.0, 0.0),
+            open: false,
+        };
+        charstring.run(self.char_strings.get(gid as usize)?.clone(), 0)?;
+        charstring.close();
+
+        Some(charstring.path.transform(&self.matrix))
+    }
+
+    fn local_subrs_of(&self, gid: u16) -> &[Range<usize>] {
+        let font = match &self.fd_select {
+            Some(select) => select.get(gid as usize).copied().unwrap_or(0) as usize,
+            None => 0,
+        };
+        self.local_subrs.get(font).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Parse an INDEX, returning the ranges of its objects and the offset of its end.
+fn index(data: &[u8], offset: usize) -> Option<(Vec<Range<usize>>, usize)> {
+    let count = u16_at(data, offset)? as usize;
+    if count == 0 {
+        return Some((Vec::new(), offset + 2));
+    }
+
+    let size = *data.get(offset + 2)? as usize;
+    if !(1..=4).contains(&size) {
+        return None;
+    }
+
+    let offsets = offset + 3;
+    // Offsets are relative to the byte that precedes the object data.
+    let base = offsets + (count + 1) * size - 1;
+    let read = |i: usize| -> Option<usize> {
+        let bytes = data.get(offsets + i * size..offsets + (i + 1) * size)?;
+        Some(
+            bytes
+                .iter()
+                .fold(0, |res, &byte| (res << 8) | byte as usize)
+                + base,
+        )
+    };
+
+    let ranges = (0..count)
+        .map(|i| {
+            let (start, end) = (read(i)?, read(i + 1)?);
+            (start <= end && end <= data.len()).then_some(start..end)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let end = read(count)?;
+
+    Some((ranges, end))
+}
+
+/// Parse a DICT into its operands, by operator. Two-byte operators are numbered from 1200.
+fn dict(data: &[u8]) -> HashMap<u16, Vec<f64>> {
+    let mut dict = HashMap::new();
+    let mut operands = Vec::new();
+    let mut i = 0;
+
+    while let Some(&b0) = data.get(i) {
+        i += 1;
+        let byte = |i: usize| data.get(i).copied().unwrap_or(0);
+        match b0 {
+            0..=21 => {
+                let operator = if b0 == 12 {
+                    i += 1;
+                    1200 + byte(i - 1) as u16
+                } else {
+                    b0 as u16
+                };
+                dict.insert(operator, std::mem::take(&mut operands));
+            }
+            28 => {
+                operands.push(i16::from_be_bytes([byte(i), byte(i + 1)]) as f64);
+                i += 2;
+            }
+            29 => {
+                operands.push(
+                    i32::from_be_bytes([byte(i), byte(i + 1), byte(i + 2), byte(i + 3)]) as f64,
+                );
+                i += 4;
+            }
+            30 => {
+                let mut real = String::new();
+                'real: while let Some(&b) = data.get(i) {
+                    i += 1;
+                    for nibble in [b >> 4, b & 0xF] {
+                        match nibble {
+                            0..=9 => real.push((b'0' + nibble) as char),
+                            0xA => real.push('.'),
+                            0xB => real.push('E'),
+                            0xC => real.push_str("E-"),
+                            0xE => real.push('-'),
+                            0xF => break 'real,
+                            _ => {}
+                        }
+                    }
+                }
+                operands.push(real.parse().unwrap_or(0.0));
+            }
+            32..=246 => operands.push(b0 as f64 - 139.0),
+            247..=250 => {
+                operands.push((b0 as f64 - 247.0) * 256.0 + byte(i) as f64 + 108.0);
+                i += 1;
+            }
+            251..=254 => {
+                operands.push(-(b0 as f64 - 251.0) * 256.0 - byte(i) as f64 - 108.0);
+                i += 1;
+            }
+            _ => {}
+        }
+    }
+
+    dict
+}
+
+/// The local subroutines of the Private DICT referenced by a `Private` entry.
+fn private_subrs(data: &[u8], private: Option<&Vec<f64>>) -> Vec<Range<usize>> {
+    let Some(&[size, offset]) = private.map(Vec::as_slice) else {
+        return Vec::new();
+    };
+    let (size, offset) = (size as usize, offset as usize);
+    let Some(private) = data.get(offset..offset.saturating_add(size)) else {
+        return Vec::new();
+    };
+
+    // The subroutines are relative to the Private DICT.
+    dict(private)
+        .get(&19)
+        .and_then(|subrs| subrs.first())
+        .and_then(|&subrs| index(data, offset + subrs as usize))
+        .map(|(subrs, _)| subrs)
+        .unwrap_or_default()
+}
+
+fn charset(data: &[u8], offset: usize, glyphs: usize) -> Option<Vec<u16>> {
+    let format = *data.get(offset)?;
+    let mut charset = vec![0];
+    let mut position = offset + 1;
+
+    while charset.len() < glyphs {
+        match format {
+            0 => {
+                charset.push(u16_at(data, position)?);
+                position += 2;
+            }
+            1 | 2 => {
+                let first = u16_at(data, position)?;
+                let left = if format == 1 {
+                    position += 3;
+                    *data.get(position - 1)? as u16
+                } else {
+                    position += 4;
+                    u16_at(data, position - 2)?
+                };
+                charset.extend((0..=left).map(|i| first.wrapping_add(i)));
+            }
+            _ => return None,
+        }
+    }
+
+    charset.truncate(glyphs);
+    Some(charset)
+}
+
+fn fd_select(data: &[u8], offset: usize, glyphs: usize) -> Option<Vec<u8>> {
+    match *data.get(offset)? {
+        0 => data
+            .get(offset + 1..offset + 1 + glyphs)
+            .map(<[u8]>::to_vec),
+        3 => {
+            let ranges = u16_at(data, offset + 1)? as usize;
+            let mut select = vec![0; glyphs];
+            for i in 0..ranges {
+                let range = offset + 3 + 3 * i;
+                let first = u16_at(data, range)? as usize;
+                let font = *data.get(range + 2)?;
+                let end = u16_at(data, range + 3)? as usize;
+                if let Some(glyphs) = select.get_mut(first..end.min(glyphs)) {
+                    glyphs.fill(font);
+                }
+            }
+            Some(select)
+        }
+        _ => None,
+    }
+}
+
+/// The bias added to subroutine numbers, which depends on the number of subroutines.
+fn bias(subrs: &[Range<usize>]) -> i32 {
+    match subrs.len() {
+        0..1240 => 107,
+        1240..33900 => 1131,
+        _ => 32768,
+    }
+}
+
+/// The state of a Type 2 charstring being run.
+struct Charstring<'a> {
+    cff: &'a Cff,
+    local_subrs: &'a [Range<usize>],
+    path: Path,
+    stack: Vec<f32>,
+    /// The number of stem hints, which sets the size of hint masks.
+    stems: usize,
+    /// Whether the optional advance width, before the first operator, was dealt with.
+    width_parsed: bool,
+    point: (f32, f32),
+    /// Whether a subpath is open.
+    open: bool,
+}
+
+impl Charstring<'_> {
+    fn close(&mut self) {
+        if self.open {
+            self.path.elements.push(PathElement::ClosePath);
+            self.open = false;
+        }
+    }
+
+    fn move_to(&mut self, dx: f32, dy: f32) {
+        self.close();
+        self.point = (self.point.0 + dx, self.point.1 + dy);
+        self.path.elements.push(PathElement::MoveTo(self.point));
+        self.open = true;
+    }
+
+    fn line_to(&mut self, dx: f32, dy: f32) {
+        self.point = (self.point.0 + dx, self.point.1 + dy);
+        self.path.elements.push(PathElement::LineTo(self.point));
+    }
+
+    fn curve_to(&mut self, [dxa, dya, dxb, dyb, dxc, dyc]: [f32; 6]) {
+        let (x, y) = self.point;
+        let a = (x + dxa, y + dya);
+        let b = (a.0 + dxb, a.1 + dyb);
+        self.point = (b.0 + dxc, b.1 + dyc);
+        self.path
+            .elements
+            .push(PathElement::CurveTo(a, b, self.point));
+    }
+
+    /// Drop the advance width, which precedes the arguments of the first stack-clearing
+    /// operator if there is one more argument than `expected` allows.
+    fn width(&mut self, odd: bool) {
+        if !self.width_parsed {
+            self.width_parsed = true;
+            if odd && !self.stack.is_empty() {
+                self.stack.remove(0);
+            }
+        }
+    }
+
+    /// Run a charstring, returning `Some(true)` once `endchar` is reached.
+    fn run(&mut self, range: Range<usize>, depth: usize) -> Option<bool> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        let cff = self.cff;
+        let data = cff.data.get(range)?;
+        let mut i = 0;
+
+        while let Some(&b0) = data.get(i) {
+            i += 1;
+            let byte = |i: usize| data.get(i).copied().unwrap_or(0) as f32;
+
+            let operator = match b0 {
+                28 => {
+                    self.push(i16::from_be_bytes([byte(i) as u8, byte(i + 1) as u8]) as f32);
+                    i += 2;
+                    continue;
+                }
+                32..=246 => {
+                    self.push(b0 as f32 - 139.0);
+                    continue;
+                }
+                247..=250 => {
+                    self.push((b0 as f32 - 247.0) * 256.0 + byte(i) + 108.0);
+                    i += 1;
+                    continue;
+                }
+                251..=254 => {
+                    self.push(-(b0 as f32 - 251.0) * 256.0 - byte(i) - 108.0);
+                    i += 1;
+                    continue;
+                }
+                255 => {
+                    let value = u32_at(data, i).unwrap_or(0) as i32;
+                    self.push(value as f32 / 65536.0);
+                    i += 4;
+                    continue;
+                }
+                12 => {
+                    i += 1;
+                    1200 + byte(i - 1) as u16
+                }
+                _ => b0 as u16,
+            };
+
+            let args = std::mem::take(&mut self.stack);
+            match operator {
+                // hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => {
+                    self.stack = args;
+                    self.width(self.stack.len() % 2 == 1);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                }
+                // hintmask, cntrmask: the arguments are implicit vertical stems.
+                19 | 20 => {
+                    self.stack = args;
+                    self.width(self.stack.len() % 2 == 1);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                    i += self.stems.div_ceil(8);
+                }
+                // rmoveto, hmoveto, vmoveto
+                21 | 22 | 4 => {
+                    let expected = if operator == 21 { 2 } else { 1 };
+                    self.stack = args;
+                    self.width(self.stack.len() > expected);
+                    let args = std::mem::take(&mut self.stack);
+                    match (operator, args.as_slice()) {
+                        (21, &[dx, dy, ..]) => self.move_to(dx, dy),
+                        (22, &[dx, ..]) => self.move_to(dx, 0.0),
+                        (4, &[dy, ..]) => self.move_to(0.0, dy),
+                        _ => {}
+                    }
+                }
+                // rlineto
+                5 => {
+                    for pair in args.chunks_exact(2) {
+                        self.line_to(pair[0], pair[1]);
+                    }
+                }
+                // hlineto, vlineto
+                6 | 7 => {
+                    for (index, &d) in args.iter().enumerate() {
+                        if (index % 2 == 0) == (operator == 6) {
+                            self.line_to(d, 0.0);
+                        } else {
+                            self.line_to(0.0, d);
+                        }
+                    }
+                }
+                // rrcurveto
+                8 => {
+                    for curve in args.chunks_exact(6) {
+                        self.curve_to(curve.try_into().ok()?);
+                    }
+                }
+                // rcurveline
+                24 => {
+                    let curves = args.len().saturating_sub(2) / 6;
+                    for curve in args.chunks_exact(6).take(curves) {
+                        self.curve_to(curve.try_into().ok()?);
+                    }
+                    if let [dx, dy] = args[6 * curves..] {
+                        self.line_to(dx, dy);
+                    }
+                }
+                // rlinecurve
+                25 => {
+                    let lines = args.len().saturating_sub(6) / 2;
+                    for pair in args.chunks_exact(2).take(lines) {
+                        self.line_to(pair[0], pair[1]);
+                    }
+                    if let Ok(curve) = args[2 * lines..].try_into() {
+                        self.curve_to(curve);
+                    }
+                }
+                // vvcurveto, hhcurveto
+                26 | 27 => {
+                    let (first, rest) = match args.len() % 4 {
+                        1 => (args[0], &args[1..]),
+                        _ => (0.0, &args[..]),
+                    };
+                    for (index, curve) in rest.chunks_exact(4).enumerate() {
+                        let shift = if index == 0 { first } else { 0.0 };
+                        let [a, b, c, d] = curve.try_into().ok()?;
+                        self.curve_to(if operator == 26 {
+                            [shift, a, b, c, 0.0, d]
+                        } else {
+                            [a, shift, b, c, d, 0.0]
+                        });
+                    }
+                }
+                // vhcurveto, hvcurveto
+                30 | 31 => {
+                    let mut horizontal = operator == 31;
+                    let mut rest = &args[..];
+                    while rest.len() >= 4 {
+                        let last = if rest.len() == 5 { rest[4] } else { 0.0 };
+                        let [a, b, c, d] = rest[..4].try_into().ok()?;
+                        self.curve_to(if horizontal {
+                            [a, 0.0, b, c, last, d]
+                        } else {
+                            [0.0, a, b, c, d, last]
+                        });
+                        rest = &rest[4..];
+                        horizontal = !horizontal;
+                    }
+                }
+                // callsubr, callgsubr
+                10 | 29 => {
+                    self.stack = args;
+                    let subrs = if operator == 10 {
+                        self.local_subrs
+                    } else {
+                        self.cff.global_subrs.as_slice()
+                    };
+                    let index = self.stack.pop()? as i32 + bias(subrs);
+                    let subr = subrs.get(usize::try_from(index).ok()?)?.clone();
+                    if self.run(subr, depth + 1)? {
+                        return Some(true);
+                    }
+                }
+                // return
+                11 => {
+                    self.stack = args;
+                    return Some(false);
+                }
+                // endchar
+                14 => {
+                    self.stack = args;
+                    self.width(self.stack.len() == 1 || self.stack.len() == 5);
+                    if let &[adx, ady, base, accent] = self.stack.as_slice() {
+                        self.seac(adx, ady, base as u8, accent as u8, depth)?;
+                    }
+                    self.close();
+                    return Some(true);
+                }
+                // flex
+                1235 => {
+                    if let Some(args) = args.get(..12) {
+                        self.curve_to(args[..6].try_into().ok()?);
+                        self.curve_to(args[6..].try_into().ok()?);
+                    }
+                }
+                // hflex
+                1234 => {
+                    if let &[dx1, dx2, dy2, dx3, dx4, dx5, dx6, ..] = args.as_slice() {
+                        self.curve_to([dx1, 0.0, dx2, dy2, dx3, 0.0]);
+                        self.curve_to([dx4, 0.0, dx5, -dy2, dx6, 0.0]);
+                    }
+                }
+                // hflex1
+                1236 => {
+                    if let &[dx1, dy1, dx2, dy2, dx3, dx4, dx5, dy5, dx6, ..] = args.as_slice() {
+                        self.curve_to([dx1, dy1, dx2, dy2, dx3, 0.0]);
+                        self.curve_to([dx4, 0.0, dx5, dy5, dx6, -(dy1 + dy2 + dy5)]);
+                    }
+                }
+                // flex1
+                1237 => {
+                    if let Some(args) = args.get(..11) {
+                        let dx: f32 = args[..10].iter().step_by(2).sum();
+                        let dy: f32 = args[1..10].iter().step_by(2).sum();
+                        let last = if dx.abs() > dy.abs() {
+                            [args[10], -dy]
+                        } else {
+                            [-dx, args[10]]
+                        };
+                        self.curve_to(args[..6].try_into().ok()?);
+                        self.curve_to([args[6], args[7], args[8], args[9], last[0], last[1]]);
+                    }
+                }
+                // Arithmetic operators, which hardly ever occur.
+                1200..=1230 => {
+                    self.stack = args;
+                    self.arithmetic(operator - 1200);
+                }
+                _ => {}
+            }
+        }
+
+        Some(false)
+    }
+
+    fn push(&mut self, value: f32) {
+        if self.stack.len() < MAX_STACK {
+            self.stack.push(value);
+        }
+    }
+
+    fn arithmetic(&mut self, operator: u16) {
+        let stack = &mut self.stack;
+        let unary = |stack: &mut Vec<f32>, f: fn(f32) -> f32| {
+            if let Some(a) = stack.pop() {
+                stack.push(f(a));
+            }
+        };
+        let binary = |stack: &mut Vec<f32>, f: fn(f32, f32) -> f32| {
+            if let (Some(b), Some(a)) = (stack.pop(), stack.pop()) {
+                stack.push(f(a, b));
+            }
+        };
+
+        match operator {
+            3 => binary(stack, |a, b| (a != 0.0 && b != 0.0) as u8 as f32),
+            4 => binary(stack, |a, b| (a != 0.0 || b != 0.0) as u8 as f32),
+            5 => unary(stack, |a| (a == 0.0) as u8 as f32),
+            9 => unary(stack, f32::abs),
+            10 => binary(stack, |a, b| a + b),
+            11 => binary(stack, |a, b| a - b),
+            12 => binary(stack, |a, b| if b == 0.0 { 0.0 } else { a / b }),
+            14 => unary(stack, |a| -a),
+            15 => binary(stack, |a, b| (a == b) as u8 as f32),
+            18 => {
+                stack.pop();
+            }
+            24 => binary(stack, |a, b| a * b),
+            26 => unary(stack, |a| a.max(0.0).sqrt()),
+            27 => {
+                if let Some(&a) = stack.last() {
+                    stack.push(a);
+                }
+            }
+            28 => {
+                let n = stack.len();
+                if n >= 2 {
+                    stack.swap(n - 1, n - 2);
+                }
+            }
+            // Storage, conditions and random numbers are not supported.
+            _ => stack.clear(),
+        }
+    }
+
+    /// Draw an accented character from two glyphs of the standard encoding.
+    fn seac(&mut self, adx: f32, ady: f32, base: u8, accent: u8, depth: usize) -> Option<()> {
+        let glyph = |code| {
+            let gid = self.cff.sid_to_gid(standard_encoding(code)?)?;
+            self.cff.char_strings.get(gid as usize).cloned()
+        };
+        let (base, accent) = (glyph(base)?, glyph(accent)?);
+
+        let mut component = |range, offset: (f32, f32)| -> Option<()> {
+            self.close();
+            self.point = offset;
+            self.stems = 0;
+            self.run(range, depth + 1)?;
+            Some(())
+        };
+        component(base, (0.0, 0.0))?;
+        component(accent, (adx, ady))?;
+
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    /// Serialize an INDEX with one-byte offsets.
+    fn index_of(objects: &[&[u8]]) -> Vec<u8> {
+        if objects.is_empty() {
+            return vec![0, 0];
+        }
+        let mut data = (objects.len() as u16).to_be_bytes().to_vec();
+        data.push(1);
+        let mut offset = 1;
+        data.push(offset);
+        for object in objects {
+            offset += object.len() as u8;
+            data.push(offset);
+        }
+        for object in objects {
+            data.extend(*object);
+        }
+        data
+    }
+
+    /// A font with `.notdef`, a square named `A`, and a glyph named `B` drawn by a local
+    /// subroutine.
+    fn font() -> Vec<u8> {
+        // Charstrings: numbers are encoded as `n + 139`.
+        let notdef: &[u8] = &[14];
+        // Width 500, then a 100 × 100 square at (100, 0).
+        let square: &[u8] = &[
+            139 + 100,
+            139 + 100,
+            139,
+            21,
+            139 + 100,
+            6,
+            139 + 100,
+            7,
+            39,
+            6,
+            14,
+        ];
+        // Call local subroutine 0, biased by -107.
+        let subr_call: &[u8] = &[139 - 107, 10, 14];
+        let subr: &[u8] = &[139 + 10, 139 + 20, 21, 139 + 50, 139 + 50, 5, 11];
+
+        let char_strings = index_of(&[notdef, square, subr_call]);
+        let subrs = index_of(&[subr]);
+
+        // The Private DICT only holds its subroutines, right after it.
+        let private: &[u8] = &[139 + 2, 19];
+
+        let header = [1, 0, 4, 1];
+        let names = index_of(&[b"Test"]);
+        let strings = index_of(&[]);
+        let global_subrs = index_of(&[]);
+
+        // The Top DICT, whose offsets are written as 2-byte integers (operator 28).
+        let top_size = 4 + 1 + 4 + 4;
+        let top_index_size = 2 + 1 + 2 + top_size;
+        let char_strings_offset =
+            header.len() + names.len() + top_index_size + strings.len() + global_subrs.len();
+        let charset_offset = char_strings_offset + char_strings.len();
+        let charset = [0, 0, 34, 0, 35];
+        let private_offset = charset_offset + charset.len();
+
+        let be = |value: usize| [28, (value >> 8) as u8, value as u8];
+        let mut top = vec![];
+        top.extend(be(char_strings_offset));
+        top.push(17);
+        top.push(139 + private.len() as u8);
+        top.extend(be(private_offset));
+        top.push(18);
+        top.extend(be(charset_offset));
+        top.push(15);
+        assert_eq!(top.len(), top_size);
+
+        let mut data = header.to_vec();
+        data.extend(names);
+        data.extend(index_of(&[&top]));
+        data.extend(strings);
+        data.extend(global_subrs);
+        data.extend(char_strings);
+        data.extend(charset);
+        data.extend(private);
+        data.extend(subrs);
+        data
+    }
+
+    #[rstest]
+    #[case(&[139, 140, 28, 1, 0, 21], &[(21, vec![0.0, 1.0, 256.0])])]
+    #[case(&[247, 0, 251, 0, 1], &[(1, vec![108.0, -108.0])])]
+    #[case(&[30, 0x1A, 0x5F, 12, 7], &[(1207, vec![1.5])])]
+    #[case(&[30, 0xE2, 0xA5, 0xC3, 0xFF, 12, 7], &[(1207, vec![-2.5e-3])])]
+    fn dicts(#[case] data: &[u8], #[case] expected: &[(u16, Vec<f64>)]) {
+        let expected: HashMap<u16, Vec<f64>> = expected.iter().cloned().collect();
+        assert_eq!(dict(data), expected);
+    }
+
+    #[rstest]
+    #[case(b"A", Some(1))]
+    #[case(b"B", Some(2))]
+    #[case(b"C", None)]
+    #[case(b"unknown", None)]
+    fn names(#[case] name: &[u8], #[case] expected: Option<u16>) {
+        let cff = Cff::parse(font()).unwrap();
+        assert_eq!(cff.named(name), expected);
+    }
+
+    #[test]
+    fn built_in_encoding() {
+        let cff = Cff::parse(font()).unwrap();
+        assert_eq!(cff.encoded(b'A'), Some(1));
+        assert_eq!(cff.encoded(b'C'), None);
+    }
+
+    /// The elements of a path, in font units.
+    fn font_units(path: Path) -> Vec<PathElement> {
+        path.transform(&Matrix::from([1000.0, 0.0, 0.0, 1000.0, 0.0, 0.0]))
+            .elements
+            .into_iter()
+            .map(|element| match element {
+                PathElement::MoveTo((x, y)) => PathElement::MoveTo((x.round(), y.round())),
+                PathElement::LineTo((x, y)) => PathElement::LineTo((x.round(), y.round())),
+                element => element,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn outlines() {
+        use PathElement::*;
+
+        let cff = Cff::parse(font()).unwrap();
+
+        assert_eq!(
+            font_units(cff.outline(1).unwrap()),
+            vec![
+                MoveTo((100.0, 0.0)),
+                LineTo((200.0, 0.0)),
+                LineTo((200.0, 100.0)),
+                LineTo((100.0, 100.0)),
+                ClosePath,
+            ]
+        );
+
+        assert_eq!(
+            font_units(cff.outline(2).unwrap()),
+            vec![MoveTo((10.0, 20.0)), LineTo((60.0, 70.0)), ClosePath]
+        );
+
+        assert_eq!(cff.outline(0), Some(Path::default()));
+        assert_eq!(cff.outline(3), None);
+    }
+}
@@ -0,0 +1,182 @@
+//! Embedded font programs, which provide the outlines of the glyphs.
+//!
+//! The character codes of a simple font select glyphs through the font dictionary's encoding
+//! and the program's own character maps, following section 9.6.5 of the PDF specification.
+//! Composite fonts select glyphs by CID.
+
+mod cff;
+mod sfnt;
+
+use crate::content::{
+    font::{glyph_name, Encoding, Font},
+    interpreter::Path,
+};
+
+use cff::Cff;
+use sfnt::Sfnt;
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn i16_at(data: &[u8], offset: usize) -> Option<i16> {
+    u16_at(data, offset).map(|value| value as i16)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// A parsed font program.
+#[derive(Debug)]
+pub(super) enum FontProgram {
+    TrueType(Sfnt),
+    Cff(Cff),
+    /// An OpenType font with CFF outlines, whose character maps live in the `sfnt` tables.
+    OpenType(Sfnt, Cff),
+}
+
+impl FontProgram {
+    /// Parse the decoded content of a `FontFile2` or `FontFile3` stream, whatever its
+    /// subtype says.
+    pub(super) fn parse(data: Vec<u8>) -> Option<Self> {
+        match data.get(..4)? {
+            [0, 1, 0, 0] | b"true" => Sfnt::parse(data).map(Self::TrueType),
+            b"OTTO" => {
+                let sfnt = Sfnt::parse(data)?;
+                let cff = Cff::parse(sfnt.table(b"CFF ")?.to_vec())?;
+                Some(Self::OpenType(sfnt, cff))
+            }
+            [1, ..] => Cff::parse(data).map(Self::Cff),
+            _ => None,
+        }
+    }
+
+    /// The outline of the glyph that a character code selects, in em units.
+    /// `cid_to_gid` is the `CIDToGIDMap` of a composite TrueType font.
+    pub(super) fn outline(
+        &self,
+        font: &Font,
+        code: u32,
+        cid_to_gid: Option<&[u8]>,
+    ) -> Option<Path> {
+        match self {
+            Self::TrueType(sfnt) => sfnt.outline(self.glyph(font, code, cid_to_gid)?),
+            Self::Cff(cff) | Self::OpenType(_, cff) => {
+                cff.outline(self.glyph(font, code, cid_to_gid)?)
+            }
+        }
+    }
+
+    fn glyph(&self, font: &Font, code: u32, cid_to_gid: Option<&[u8]>) -> Option<u16> {
+        if font.is_composite() {
+            let cid = u16::try_from(code).ok()?;
+            return match (self, cid_to_gid) {
+                (Self::TrueType(_), Some(map)) => u16_at(map, 2 * cid as usize),
+                (Self::TrueType(_), None) => Some(cid),
+                (Self::Cff(cff) | Self::OpenType(_, cff), _) => cff.cid_to_gid(cid),
+            };
+        }
+
+        let code = u8::try_from(code).ok()?;
+        match self {
+            Self::TrueType(sfnt) => truetype_glyph(sfnt, font, code),
+            Self::Cff(cff) => cff_glyph(cff, font, code),
+            Self::OpenType(sfnt, cff) => {
+                truetype_glyph(sfnt, font, code).or_else(|| cff_glyph(cff, font, code))
+            }
+        }
+    }
+}
+
+/// The glyph of a simple TrueType font. Symbolic fonts look codes up in the (3, 0) character
+/// map, where they are often offset by `0xF000`, while other fonts look glyph names up in the
+/// Unicode (3, 1) map. The Macintosh (1, 0) map is the last resort.
+fn truetype_glyph(sfnt: &Sfnt, font: &Font, code: u8) -> Option<u16> {
+    if !sfnt.has_cmap() {
+        return Some(code as u16);
+    }
+
+    let code = code as u32;
+    let found = if font.is_symbolic() {
+        sfnt.cmap(3, Some(0))
+            .and_then(|cmap| cmap.get(code).or_else(|| cmap.get(0xF000 | code)))
+    } else {
+        let encoding = font.encoding().cloned().unwrap_or_else(Encoding::default);
+        let unicode = encoding
+            .glyph(code as u8)
+            .and_then(|name| glyph_name(&name.0))
+            .and_then(|text| text.chars().next());
+
+        unicode.and_then(|char| {
+            [(3, Some(1)), (0, None)]
+                .into_iter()
+                .find_map(|(platform, encoding)| sfnt.cmap(platform, encoding)?.get(char as u32))
+        })
+    };
+
+    found
+        .or_else(|| sfnt.cmap(1, Some(0))?.get(code))
+        .or_else(|| sfnt.cmap(3, Some(0))?.get(0xF000 | code))
+}
+
+/// The glyph of a simple CFF font: glyph names from the `Differences` array come first, then
+/// those of the base encoding unless the font is symbolic, then the built-in encoding.
+fn cff_glyph(cff: &Cff, font: &Font, code: u8) -> Option<u16> {
+    if cff.is_cid_keyed() {
+        return cff.cid_to_gid(code as u16);
+    }
+
+    let encoding = font.encoding();
+    let named = encoding.and_then(|encoding| {
+        let name = match encoding.names.get(&code) {
+            Some(name) => name.clone(),
+            None if !font.is_symbolic() => encoding.glyph(code)?,
+            None => return None,
+        };
+        cff.named(&name.0)
+    });
+
+    named.or_else(|| cff.encoded(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::follow_refs::Builder;
+
+    use super::*;
+
+    #[rstest]
+    #[case(b'A', Some(1))]
+    #[case(b'C', Some(3))]
+    #[case(b'Z', None)]
+    fn truetype_glyphs(#[case] code: u8, #[case] expected: Option<u16>) {
+        let program = FontProgram::parse(sfnt::tests::font()).unwrap();
+        let font = Font::default();
+        assert_eq!(program.glyph(&font, code as u32, None), expected);
+        assert_eq!(
+            program.outline(&font, code as u32, None).is_some(),
+            expected.is_some()
+        );
+    }
+
+    #[rstest]
+    #[case(&[0, 1, 0, 2], 0, Some(1))]
+    #[case(&[0, 1, 0, 2], 1, Some(2))]
+    #[case(&[0, 1, 0, 2], 2, None)]
+    fn cid_to_gid_maps(#[case] map: &[u8], #[case] cid: u32, #[case] expected: Option<u16>) {
+        let program = FontProgram::parse(sfnt::tests::font()).unwrap();
+        let font: Font = ().build(&mut b"<</Subtype/Type0>>".as_slice().into()).unwrap();
+        assert_eq!(program.glyph(&font, cid, Some(map)), expected);
+    }
+
+    #[test]
+    fn unknown_programs() {
+        assert!(FontProgram::parse(b"%!PS-AdobeFont-1.0".to_vec()).is_none());
+        assert!(FontProgram::parse(vec![0, 1]).is_none());
+    }
+}
@@ -0,0 +1,523 @@
+//! TrueType and OpenType font files: the table directory, character maps and `glyf` outlines.
+//!
+//! Hinting instructions are ignored, which is what most renderers do at thumbnail sizes.
+
+use std::{collections::HashMap, ops::Range};
+
+use crate::content::interpreter::{Path, PathElement};
+
+use super::{i16_at, u16_at, u32_at};
+
+/// Composite glyphs nested deeper than this are cut short.
+const MAX_DEPTH: usize = 8;
+
+/// An `sfnt`-wrapped font program.
+#[derive(Debug)]
+pub(crate) struct Sfnt {
+    data: Vec<u8>,
+    tables: HashMap<[u8; 4], Range<usize>>,
+    units_per_em: f32,
+    /// Whether the `loca` table holds 32-bit offsets.
+    long_offsets: bool,
+}
+
+impl Sfnt {
+    pub(super) fn parse(data: Vec<u8>) -> Option<Self> {
+        let count = u16_at(&data, 4)? as usize;
+        let mut tables = HashMap::new();
+
+        for i in 0..count {
+            let record = 12 + 16 * i;
+            let tag = data.get(record..record + 4)?.try_into().ok()?;
+            let offset = u32_at(&data, record + 8)? as usize;
+            let length = u32_at(&data, record + 12)? as usize;
+            // Some producers overstate the length of the last table.
+            let end = offset.saturating_add(length).min(data.len());
+            if offset <= end {
+                tables.insert(tag, offset..end);
+            }
+        }
+
+        let (units_per_em, long_offsets) = match tables.get(b"head") {
+            Some(head) => {
+                let head = &data[head.clone()];
+                let units = u16_at(head, 18).filter(|&units| units > 0).unwrap_or(1000);
+                (units as f32, i16_at(head, 50) == Some(1))
+            }
+            None => (1000.0, false),
+        };
+
+        Some(Self {
+            data,
+            tables,
+            units_per_em,
+            long_offsets,
+        })
+    }
+
+    pub(super) fn table(&self, tag: &[u8; 4]) -> Option<&[u8]> {
+        self.tables.get(tag).map(|range| &self.data[range.clone()])
+    }
+
+    /// The character map for the given platform and encoding, or for any encoding of the
+    /// platform if `encoding` is `None`.
+    pub(super) fn cmap(&self, platform: u16, encoding: Option<u16>) -> Option<CharacterMap<'_>> {
+        let cmap = self.table(b"cmap")?;
+        let count = u16_at(cmap, 2)? as usize;
+
+        (0..count).find_map(|i| {
+            let record = 4 + 8 * i;
+            let found = (u16_at(cmap, record)?, u16_at(cmap, record + 2)?);
+            if found.0 != platform || encoding.is_some_and(|encoding| encoding != found.1) {
+                return None;
+            }
+            let offset = u32_at(cmap, record + 4)? as usize;
+            Some(CharacterMap(cmap.get(offset..)?))
+        })
+    }
+
+    /// Whether the font has a character map at all.
+    pub(super) fn has_cmap(&self) -> bool {
+        self.table(b"cmap")
+            .and_then(|cmap| u16_at(cmap, 2))
+            .is_some_and(|count| count > 0)
+    }
+
+    /// The outline of a glyph from the `glyf` table, in em units.
+    pub(super) fn outline(&self, gid: u16) -> Option<Path> {
+        let mut path = Path::default();
+        self.glyph(gid, [1.0, 0.0, 0.0, 1.0, 0.0, 0.0], 0, &mut path)?;
+
+        let scale = 1.0 / self.units_per_em;
+        for element in &mut path.elements {
+            let scale = |(x, y): (f32, f32)| (x * scale, y * scale);
+            *element = match *element {
+                PathElement::MoveTo(p) => PathElement::MoveTo(scale(p)),
+                PathElement::LineTo(p) => PathElement::LineTo(scale(p)),
+                PathElement::CurveTo(a, b, c) => PathElement::CurveTo(scale(a), scale(b), scale(c)),
+                PathElement::ClosePath => PathElement::ClosePath,
+            };
+        }
+
+        Some(path)
+    }
+
+    /// The data of a glyph in the `glyf` table, empty for glyphs without outline.
+    fn glyph_data(&self, gid: u16) -> Option<&[u8]> {
+        let loca = self.table(b"loca")?;
+        let glyf = self.table(b"glyf")?;
+        let gid = gid as usize;
+
+        let (start, end) = if self.long_offsets {
+            (u32_at(loca, 4 * gid)?, u32_at(loca, 4 * gid + 4)?)
+        } else {
+            let start = u16_at(loca, 2 * gid)? as u32 * 2;
+            (start, u16_at(loca, 2 * gid + 2)? as u32 * 2)
+        };
+
+        glyf.get(start as usize..end as usize)
+    }
+
+    /// Append the outline of a glyph, mapped by the affine transformation `m`, in font units.
+    fn glyph(&self, gid: u16, m: [f32; 6], depth: usize, path: &mut Path) -> Option<()> {
+        let data = self.glyph_data(gid)?;
+        if data.is_empty() {
+            return Some(());
+        }
+
+        let contours = i16_at(data, 0)?;
+        if contours >= 0 {
+            simple_glyph(data, contours as usize, m, path)
+        } else if depth < MAX_DEPTH {
+            self.composite_glyph(data, m, depth, path)
+        } else {
+            Some(())
+        }
+    }
+
+    fn composite_glyph(
+        &self,
+        data: &[u8],
+        m: [f32; 6],
+        depth: usize,
+        path: &mut Path,
+    ) -> Option<()> {
+        const WORDS: u16 = 0x1;
+        const XY_VALUES: u16 = 0x2;
+        const SCALE: u16 = 0x8;
+        const MORE_COMPONENTS: u16 = 0x20;
+        const XY_SCALE: u16 = 0x40;
+        const TWO_BY_TWO: u16 = 0x80;
+
+        let f2dot14 = |offset| i16_at(data, offset).map(|value| value as f32 / 16384.0);
+
+        let mut offset = 10;
+        loop {
+            let flags = u16_at(data, offset)?;
+            let gid = u16_at(data, offset + 2)?;
+            offset += 4;
+
+            let (e, f) = if flags & WORDS != 0 {
+                offset += 4;
+                (
+                    i16_at(data, offset - 4)? as f32,
+                    i16_at(data, offset - 2)? as f32,
+                )
+            } else {
+                offset += 2;
+                let x = *data.get(offset - 2)? as i8;
+                (x as f32, *data.get(offset - 1)? as i8 as f32)
+            };
+            // Components positioned by matching points are left in place.
+            let (e, f) = if flags & XY_VALUES != 0 {
+                (e, f)
+            } else {
+                (0.0, 0.0)
+            };
+
+            let [a, b, c, d] = if flags & SCALE != 0 {
+                offset += 2;
+                let scale = f2dot14(offset - 2)?;
+                [scale, 0.0, 0.0, scale]
+            } else if flags & XY_SCALE != 0 {
+                offset += 4;
+                [f2dot14(offset - 4)?, 0.0, 0.0, f2dot14(offset - 2)?]
+            } else if flags & TWO_BY_TWO != 0 {
+                offset += 8;
+                [
+                    f2dot14(offset - 8)?,
+                    f2dot14(offset - 6)?,
+                    f2dot14(offset - 4)?,
+                    f2dot14(offset - 2)?,
+                ]
+            } else {
+                [1.0, 0.0, 0.0, 1.0]
+            };
+
+            // The component's transformation, then the glyph's.
+            let component = [
+                a * m[0] + b * m[2],
+                a * m[1] + b * m[3],
+                c * m[0] + d * m[2],
+                c * m[1] + d * m[3],
+                e * m[0] + f * m[2] + m[4],
+                e * m[1] + f * m[3] + m[5],
+            ];
+            self.glyph(gid, component, depth + 1, path)?;
+
+            if flags & MORE_COMPONENTS == 0 {
+                return Some(());
+            }
+        }
+    }
+}
+
+fn simple_glyph(data: &[u8], contours: usize, m: [f32; 6], path: &mut Path) -> Option<()> {
+    const ON_CURVE: u8 = 0x1;
+    const X_SHORT: u8 = 0x2;
+    const Y_SHORT: u8 = 0x4;
+    const REPEAT: u8 = 0x8;
+    const X_SAME: u8 = 0x10;
+    const Y_SAME: u8 = 0x20;
+
+    let ends = (0..contours)
+        .map(|i| u16_at(data, 10 + 2 * i).map(usize::from))
+        .collect::<Option<Vec<_>>>()?;
+    let count = ends.last().map_or(0, |&end| end + 1);
+
+    let instructions = u16_at(data, 10 + 2 * contours)? as usize;
+    let mut offset = 12 + 2 * contours + instructions;
+
+    let mut flags = Vec::with_capacity(count);
+    while flags.len() < count {
+        let flag = *data.get(offset)?;
+        offset += 1;
+        flags.push(flag);
+        if flag & REPEAT != 0 {
+            let repeat = *data.get(offset)?;
+            offset += 1;
+            flags.extend(std::iter::repeat_n(flag, repeat as usize));
+        }
+    }
+    flags.truncate(count);
+
+    let mut coordinates = |short: u8, same: u8| -> Option<Vec<f32>> {
+        let mut value = 0i32;
+        flags
+            .iter()
+            .map(|&flag| {
+                if flag & short != 0 {
+                    let delta = *data.get(offset)? as i32;
+                    offset += 1;
+                    value += if flag & same != 0 { delta } else { -delta };
+                } else if flag & same == 0 {
+                    value += i16_at(data, offset)? as i32;
+                    offset += 2;
+                }
+                Some(value as f32)
+            })
+            .collect()
+    };
+    let xs = coordinates(X_SHORT, X_SAME)?;
+    let ys = coordinates(Y_SHORT, Y_SAME)?;
+
+    let points: Vec<((f32, f32), bool)> = (0..count)
+        .map(|i| {
+            let (x, y) = (xs[i], ys[i]);
+            let point = (m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5]);
+            (point, flags[i] & ON_CURVE != 0)
+        })
+        .collect();
+
+    let mut start = 0;
+    for end in ends {
+        if let Some(contour) = points.get(start..=end) {
+            contour_path(contour, path);
+        }
+        start = end + 1;
+    }
+
+    Some(())
+}
+
+/// Append a contour of quadratic curves, where two consecutive off-curve points imply an
+/// on-curve point halfway between them.
+fn contour_path(contour: &[((f32, f32), bool)], path: &mut Path) {
+    let mid = |a: (f32, f32), b: (f32, f32)| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+
+    let n = contour.len();
+    if n < 2 {
+        return;
+    }
+
+    let (first, last) = (contour[0], contour[n - 1]);
+    let (start, points) = if first.1 {
+        (first.0, &contour[1..])
+    } else if last.1 {
+        (last.0, &contour[..n - 1])
+    } else {
+        (mid(first.0, last.0), contour)
+    };
+
+    path.elements.push(PathElement::MoveTo(start));
+    let mut current = start;
+    let mut control = None;
+
+    let quadratic = |from: (f32, f32), control: (f32, f32), to: (f32, f32)| {
+        PathElement::CurveTo(
+            (
+                from.0 + 2.0 / 3.0 * (control.0 - from.0),
+                from.1 + 2.0 / 3.0 * (control.1 - from.1),
+            ),
+            (
+                to.0 + 2.0 / 3.0 * (control.0 - to.0),
+                to.1 + 2.0 / 3.0 * (control.1 - to.1),
+            ),
+            to,
+        )
+    };
+
+    for &(point, on_curve) in points.iter().chain([&(start, true)]) {
+        match (on_curve, control) {
+            (true, Some(c)) => {
+                path.elements.push(quadratic(current, c, point));
+                current = point;
+                control = None;
+            }
+            (true, None) => {
+                path.elements.push(PathElement::LineTo(point));
+                current = point;
+            }
+            (false, Some(c)) => {
+                let implied = mid(c, point);
+                path.elements.push(quadratic(current, c, implied));
+                current = implied;
+                control = Some(point);
+            }
+            (false, None) => control = Some(point),
+        }
+    }
+
+    path.elements.push(PathElement::ClosePath);
+}
+
+/// A subtable of the `cmap` table, which maps character codes to glyph indices.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct CharacterMap<'a>(&'a [u8]);
+
+impl CharacterMap<'_> {
+    /// The glyph index of a character code, if it is mapped to a glyph other than `.notdef`.
+    pub(super) fn get(&self, code: u32) -> Option<u16> {
+        let data = self.0;
+        let gid = match u16_at(data, 0)? {
+            0 => *data.get(6 + usize::try_from(code).ok().filter(|&code| code < 256)?)? as u16,
+            4 => {
+                let code = u16::try_from(code).ok()?;
+                let segments = u16_at(data, 6)? as usize / 2;
+                let ends = 14;
+                let starts = ends + 2 * segments + 2;
+                let deltas = starts + 2 * segments;
+                let range_offsets = deltas + 2 * segments;
+
+                let segment = (0..segments)
+                    .find(|&i| u16_at(data, ends + 2 * i).is_some_and(|end| end >= code))?;
+                let start = u16_at(data, starts + 2 * segment)?;
+                if code < start {
+                    return None;
+                }
+                let delta = u16_at(data, deltas + 2 * segment)?;
+                let range_offset = u16_at(data, range_offsets + 2 * segment)? as usize;
+
+                if range_offset == 0 {
+                    code.wrapping_add(delta)
+                } else {
+                    let address =
+                        range_offsets + 2 * segment + range_offset + 2 * (code - start) as usize;
+                    match u16_at(data, address)? {
+                        0 => 0,
+                        gid => gid.wrapping_add(delta),
+                    }
+                }
+            }
+            6 => {
+                let first = u16_at(data, 6)? as u32;
+                let count = u16_at(data, 8)? as u32;
+                let index = code.checked_sub(first).filter(|&index| index < count)?;
+                u16_at(data, 10 + 2 * index as usize)?
+            }
+            12 => {
+                let groups = u32_at(data, 12)? as usize;
+                (0..groups).find_map(|i| {
+                    let group = 16 + 12 * i;
+                    let start = u32_at(data, group)?;
+                    let end = u32_at(data, group + 4)?;
+                    let gid = u32_at(data, group + 8)?;
+                    (start..=end)
+                        .contains(&code)
+                        .then(|| u16::try_from(gid + code - start).ok())
+                        .flatten()
+                })?
+            }
+            _ => return None,
+        };
+
+        (gid != 0).then_some(gid)
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    /// A font with a square glyph `1` made of on-curve points, a glyph `2` made of off-curve
+    /// points only, and a composite glyph `3` that scales the square by half and moves it by
+    /// `(100, 0)`. Codes `A` to `C` are mapped to them by a format 4 `cmap`.
+    pub(crate) fn font() -> Vec<u8> {
+        let be16 = |values: &[i32]| -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|&value| (value as u16).to_be_bytes())
+                .collect()
+        };
+
+        let mut head = vec![0; 54];
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+
+        // A square from (0, 0) to (500, 500), with long coordinates.
+        let mut square = be16(&[1, 0, 0, 500, 500, 3, 0]);
+        square.extend([0x1; 4]);
+        square.extend(be16(&[0, 0, 500, 0]));
+        square.extend(be16(&[0, 500, 0, -500]));
+
+        // A diamond of off-curve points, with short coordinates.
+        let mut diamond = be16(&[1, 0, 0, 100, 100, 3, 0]);
+        diamond.extend([0x32, 0x36, 0x26, 0x06, 50, 50, 50, 50, 50, 50, 50, 0]);
+
+        let mut composite = be16(&[-1, 0, 0, 0, 0]);
+        // Words, XY values, scale: glyph 1 at (100, 0) scaled by 0.5.
+        composite.extend(be16(&[0x1 | 0x2 | 0x8, 1, 100, 0, 0x2000]));
+
+        let mut glyf = Vec::new();
+        let mut loca = vec![0];
+        for glyph in [&[][..], &square, &diamond, &composite] {
+            glyf.extend(glyph);
+            loca.push(glyf.len() as i32 / 2);
+        }
+        let loca = be16(&loca);
+
+        // Format 4: one segment for A to C, and the final 0xFFFF segment.
+        let mut subtable = be16(&[4, 32, 0, 4, 0, 0, 0]);
+        subtable.extend(be16(&[0x43, 0xFFFF, 0, 0x41, 0xFFFF, 1 - 0x41, 1, 0, 0]));
+        let mut cmap = be16(&[0, 1, 3, 1, 0, 12]);
+        cmap.extend(subtable);
+
+        let tables: [(&[u8; 4], Vec<u8>); 4] = [
+            (b"cmap", cmap),
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"loca", loca),
+        ];
+        let mut data = vec![0, 1, 0, 0];
+        data.extend(be16(&[tables.len() as i32, 0, 0, 0]));
+        let mut offset = 12 + 16 * tables.len();
+        let mut contents: Vec<u8> = Vec::new();
+        for (tag, table) in &tables {
+            data.extend(*tag);
+            data.extend([0; 4]);
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((table.len() as u32).to_be_bytes());
+            offset += table.len();
+            contents.extend(table);
+        }
+        data.extend(contents);
+        data
+    }
+
+    #[rstest]
+    #[case(0x41, Some(1))]
+    #[case(0x43, Some(3))]
+    #[case(0x44, None)]
+    #[case(0x20, None)]
+    fn cmap(#[case] code: u32, #[case] expected: Option<u16>) {
+        let sfnt = Sfnt::parse(font()).unwrap();
+        assert_eq!(sfnt.cmap(3, Some(1)).unwrap().get(code), expected);
+        assert!(sfnt.cmap(1, None).is_none());
+    }
+
+    #[test]
+    fn outlines() {
+        use PathElement::*;
+
+        let sfnt = Sfnt::parse(font()).unwrap();
+
+        let square = sfnt.outline(1).unwrap();
+        assert_eq!(
+            square.elements,
+            vec![
+                MoveTo((0.0, 0.0)),
+                LineTo((0.0, 0.5)),
+                LineTo((0.5, 0.5)),
+                LineTo((0.5, 0.0)),
+                LineTo((0.0, 0.0)),
+                ClosePath,
+            ]
+        );
+
+        // Four curves through the implied midpoints.
+        let diamond = sfnt.outline(2).unwrap();
+        assert_eq!(diamond.elements[0], MoveTo((0.025, 0.025)));
+        assert_eq!(diamond.elements.len(), 6);
+
+        // The square, scaled by half and moved by (100, 0).
+        let composite = sfnt.outline(3).unwrap();
+        let LineTo((x, y)) = composite.elements[2] else {
+            panic!("expected a line");
+        };
+        assert_eq!(((x * 1000.0).round(), (y * 1000.0).round()), (350.0, 250.0));
+
+        assert_eq!(sfnt.outline(0), Some(Path::default()));
+        assert_eq!(sfnt.outline(4), None);
+    }
+}
@@ -0,0 +1,582 @@
+//! A baseline JPEG decoder, for images compressed with the `DCTDecode` filter.
+//!
+//! Only sequential Huffman-coded images are supported, which covers the vast majority of
+//! images in PDF files. Progressive and arithmetic-coded images are not decoded.
+
+use std::f32::consts::PI;
+
+/// The position of each coefficient of a block in the encoded, zig-zag order.
+#[rustfmt::skip]
+const ZIGZAG: [usize; 64] = [
+     0,  1,  8, 16,  9,  2,  3, 10,
+    17, 24, 32, 25, 18, 11,  4,  5,
+    12, 19, 26, 33, 40, 48, 41, 34,
+    27, 20, 13,  6,  7, 14, 21, 28,
+    35, 42, 49, 56, 57, 50, 43, 36,
+    29, 22, 15, 23, 30, 37, 44, 51,
+    58, 59, 52, 45, 38, 31, 39, 46,
+    53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// A decoded image, with interleaved 8-bit samples.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Jpeg {
+    pub(super) width: u32,
+    pub(super) height: u32,
+    pub(super) components: usize,
+    pub(super) data: Vec<u8>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Huffman {
+    /// The largest code of each length, or -1.
+    max_code: [i32; 17],
+    /// The smallest code of each length.
+    min_code: [i32; 17],
+    /// The index in `values` of the smallest code of each length.
+    offset: [i32; 17],
+    values: Vec<u8>,
+}
+
+impl Huffman {
+    fn new(counts: &[u8], values: &[u8]) -> Self {
+        let mut table = Self {
+            values: values.to_vec(),
+            ..Default::default()
+        };
+
+        let (mut code, mut index) = (0, 0);
+        for length in 1..=16 {
+            let count = counts[length - 1] as i32;
+            table.offset[length] = index;
+            table.min_code[length] = code;
+            code += count;
+            index += count;
+            table.max_code[length] = if count > 0 { code - 1 } else { -1 };
+            code <<= 1;
+        }
+
+        table
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quantization: usize,
+    /// The width of the component's plane, a multiple of 8.
+    stride: usize,
+    plane: Vec<u8>,
+    dc_table: usize,
+    ac_table: usize,
+    prediction: i32,
+}
+
+/// Reads the entropy-coded segment of a scan, bit by bit.
+struct Bits<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl Bits<'_> {
+    fn bit(&mut self) -> u32 {
+        if self.count == 0 {
+            let byte = match self.data.get(self.position) {
+                // A marker ends the segment: the missing bits are zeros.
+                Some(0xFF) if self.data.get(self.position + 1) != Some(&0) => 0,
+                Some(0xFF) => {
+                    self.position += 2;
+                    0xFF
+                }
+                Some(&byte) => {
+                    self.position += 1;
+                    byte
+                }
+                None => 0,
+            };
+            self.buffer = byte as u32;
+            self.count = 8;
+        }
+        self.count -= 1;
+        (self.buffer >> self.count) & 1
+    }
+
+    fn bits(&mut self, count: u8) -> u32 {
+        (0..count).fold(0, |value, _| (value << 1) | self.bit())
+    }
+
+    fn decode(&mut self, table: &Huffman) -> Option<u8> {
+        let mut code = 0;
+        for length in 1..=16 {
+            code = (code << 1) | self.bit() as i32;
+            if code <= table.max_code[length] {
+                let index = table.offset[length] + code - table.min_code[length];
+                return table.values.get(index as usize).copied();
+            }
+        }
+        None
+    }
+
+    /// A coefficient of `size` bits, whose first bit tells the sign.
+    fn receive(&mut self, size: u8) -> i32 {
+        if size == 0 || size > 16 {
+            return 0;
+        }
+        let value = self.bits(size) as i32;
+        if value < 1 << (size - 1) {
+            value - (1 << size) + 1
+        } else {
+            value
+        }
+    }
+
+    /// Skip to the next restart marker.
+    fn restart(&mut self) {
+        self.count = 0;
+        while let Some(&byte) = self.data.get(self.position) {
+            let next = self.data.get(self.position + 1);
+            self.position += 1;
+            if byte == 0xFF && next.is_some_and(|next| (0xD0..=0xD7).contains(next)) {
+                self.position += 1;
+                return;
+            }
+        }
+    }
+}
+
+/// Decode a baseline JPEG image of at most `max_pixels` pixels. Samples are those of the
+/// encoded color space, except for YCbCr and YCCK, which are converted to RGB and CMYK.
+pub(super) fn decode(data: &[u8], max_pixels: usize) -> Option<Jpeg> {
+    if data.get(..2)? != [0xFF, 0xD8] {
+        return None;
+    }
+
+    let mut quantization = [[0u16; 64]; 4];
+    let mut dc_tables = vec![Huffman::default(); 4];
+    let mut ac_tables = vec![Huffman::default(); 4];
+    let mut components: Vec<Component> = Vec::new();
+    let (mut width, mut height) = (0, 0);
+    let mut restart_interval = 0;
+    let mut transform = None;
+    let mut position = 2;
+
+    loop {
+        // Skip fill bytes up to the next marker.
+        while *data.get(position)? != 0xFF || data.get(position + 1) == Some(&0xFF) {
+            position += 1;
+        }
+        let marker = *data.get(position + 1)?;
+        position += 2;
+
+        if marker == 0xD9 {
+            break;
+        }
+        if (0xD0..=0xD7).contains(&marker) || marker == 0x01 {
+            continue;
+        }
+
+        let length = u16::from_be_bytes([*data.get(position)?, *data.get(position + 1)?]) as usize;
+        let segment = data.get(position + 2..position + length)?;
+        position += length;
+
+        match marker {
+            // Baseline and extended sequential, Huffman-coded.
+            0xC0 | 0xC1 => {
+                if *segment.first()? != 8 {
+                    return None;
+                }
+                height = u16::from_be_bytes([*segment.get(1)?, *segment.get(2)?]) as usize;
+                width = u16::from_be_bytes([*segment.get(3)?, *segment.get(4)?]) as usize;
+                let count = *segment.get(5)? as usize;
+                for i in 0..count {
+                    let spec = segment.get(6 + 3 * i..9 + 3 * i)?;
+                    components.push(Component {
+                        id: spec[0],
+                        h: (spec[1] >> 4).clamp(1, 4) as usize,
+                        v: (spec[1] & 0xF).clamp(1, 4) as usize,
+                        quantization: (spec[2] & 3) as usize,
+                        stride: 0,
+                        plane: Vec::new(),
+                        dc_table: 0,
+                        ac_table: 0,
+                        prediction: 0,
+                    });
+                }
+                if width == 0 || height == 0 || components.is_empty() {
+                    return None;
+                }
+                // The header alone must not make the decoder allocate unbounded planes.
+                if width
+                    .checked_mul(height)
+                    .is_none_or(|pixels| pixels > max_pixels)
+                {
+                    return None;
+                }
+
+                let h_max = components.iter().map(|c| c.h).max()?;
+                let v_max = components.iter().map(|c| c.v).max()?;
+                let mcus_x = width.div_ceil(8 * h_max);
+                let mcus_y = height.div_ceil(8 * v_max);
+                for component in &mut components {
+                    component.stride = mcus_x * component.h * 8;
+                    component.plane = vec![0; component.stride * mcus_y * component.v * 8];
+                }
+            }
+            // Other frame types: progressive, lossless, arithmetic coding.
+            0xC2..=0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return None,
+            0xC4 => {
+                let mut rest = segment;
+                while let [class, counts @ ..] = rest {
+                    let counts = counts.get(..16)?;
+                    let total: usize = counts.iter().map(|&count| count as usize).sum();
+                    let values = rest.get(17..17 + total)?;
+                    let table = Huffman::new(counts, values);
+                    let index = (class & 3) as usize;
+                    if class >> 4 == 0 {
+                        dc_tables[index] = table;
+                    } else {
+                        ac_tables[index] = table;
+                    }
+                    rest = &rest[17 + total..];
+                }
+            }
+            0xDB => {
+                let mut rest = segment;
+                while let [spec, values @ ..] = rest {
+                    let table = &mut quantization[(spec & 3) as usize];
+                    let wide = spec >> 4 != 0;
+                    for (i, &zigzag) in ZIGZAG.iter().enumerate() {
+                        table[zigzag] = if wide {
+                            u16::from_be_bytes([*values.get(2 * i)?, *values.get(2 * i + 1)?])
+                        } else {
+                            *values.get(i)? as u16
+                        };
+                    }
+                    rest = &values[if wide { 128 } else { 64 }..];
+                }
+            }
+            0xDD => {
+                restart_interval =
+                    u16::from_be_bytes([*segment.first()?, *segment.get(1)?]) as usize;
+            }
+            0xEE if segment.starts_with(b"Adobe") => transform = segment.get(11).copied(),
+            0xDA => {
+                let count = *segment.first()? as usize;
+                let mut scan = Vec::with_capacity(count);
+                for i in 0..count {
+                    let (id, tables) = (*segment.get(1 + 2 * i)?, *segment.get(2 + 2 * i)?);
+                    let index = components.iter().position(|c| c.id == id)?;
+                    components[index].dc_table = (tables >> 4 & 3) as usize;
+                    components[index].ac_table = (tables & 3) as usize;
+                    scan.push(index);
+                }
+
+                let tables = Tables {
+                    quantization: &quantization,
+                    dc: &dc_tables,
+                    ac: &ac_tables,
+                };
+                let mut bits = Bits {
+                    data,
+                    position,
+                    buffer: 0,
+                    count: 0,
+                };
+                decode_scan(
+                    &mut components,
+                    &scan,
+                    (width, height),
+                    restart_interval,
+                    &tables,
+                    &mut bits,
+                );
+                position = bits.position;
+            }
+            _ => {}
+        }
+    }
+
+    if components.is_empty() {
+        return None;
+    }
+
+    Some(Jpeg {
+        width: width as u32,
+        height: height as u32,
+        components: components.len(),
+        data: output(&components, width, height, transform),
+    })
+}
+
+struct Tables<'a> {
+    quantization: &'a [[u16; 64]; 4],
+    dc: &'a [Huffman],
+    ac: &'a [Huffman],
+}
+
+fn decode_scan(
+    components: &mut [Component],
+    scan: &[usize],
+    (width, height): (usize, usize),
+    restart_interval: usize,
+    tables: &Tables,
+    bits: &mut Bits,
+) {
+    let h_max = components.iter().map(|c| c.h).max().unwrap_or(1);
+    let v_max = components.iter().map(|c| c.v).max().unwrap_or(1);
+
+    // A scan of a single component is made of single blocks, not of whole MCUs.
+    let single = scan.len() == 1;
+    let (mcus_x, mcus_y) = if single {
+        let c = &components[scan[0]];
+        (
+            (width * c.h).div_ceil(8 * h_max),
+            (height * c.v).div_ceil(8 * v_max),
+        )
+    } else {
+        (width.div_ceil(8 * h_max), height.div_ceil(8 * v_max))
+    };
+
+    for &index in scan {
+        components[index].prediction = 0;
+    }
+
+    let mut block = [0f32; 64];
+    for mcu in 0..mcus_x * mcus_y {
+        if restart_interval > 0 && mcu > 0 && mcu % restart_interval == 0 {
+            bits.restart();
+            for &index in scan {
+                components[index].prediction = 0;
+            }
+        }
+
+        let (mx, my) = (mcu % mcus_x, mcu / mcus_x);
+        for &index in scan {
+            let component = &mut components[index];
+            let (h, v) = if single {
+                (1, 1)
+            } else {
+                (component.h, component.v)
+            };
+            for by in 0..v {
+                for bx in 0..h {
+                    decode_block(component, tables, bits, &mut block);
+                    let (x, y) = ((mx * h + bx) * 8, (my * v + by) * 8);
+                    let stride = component.stride;
+                    for (row, samples) in block.chunks_exact(8).enumerate() {
+                        let start = (y + row) * stride + x;
+                        if let Some(target) = component.plane.get_mut(start..start + 8) {
+                            for (target, &sample) in target.iter_mut().zip(samples) {
+                                *target = (sample + 128.0).round().clamp(0.0, 255.0) as u8;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn decode_block(
+    component: &mut Component,
+    tables: &Tables,
+    bits: &mut Bits,
+    block: &mut [f32; 64],
+) {
+    let quantization = &tables.quantization[component.quantization];
+    let mut coefficients = [0i32; 64];
+
+    let size = bits.decode(&tables.dc[component.dc_table]).unwrap_or(0);
+    // Corrupt data can make coefficients overflow, which only garbles the image.
+    component.prediction = component.prediction.wrapping_add(bits.receive(size));
+    coefficients[0] = component.prediction;
+
+    let mut k = 1;
+    while k < 64 {
+        let Some(symbol) = bits.decode(&tables.ac[component.ac_table]) else {
+            break;
+        };
+        let (run, size) = ((symbol >> 4) as usize, symbol & 0xF);
+        if size == 0 {
+            if run == 15 {
+                k += 16;
+                continue;
+            }
+            break;
+        }
+        k += run;
+        if k < 64 {
+            coefficients[ZIGZAG[k]] = bits.receive(size);
+        }
+        k += 1;
+    }
+
+    for (coefficient, &factor) in coefficients.iter_mut().zip(quantization) {
+        *coefficient = coefficient.saturating_mul(factor as i32);
+    }
+
+    idct(&coefficients, block);
+}
+
+/// The inverse discrete cosine transform of a block, computed separably.
+fn idct(coefficients: &[i32; 64], block: &mut [f32; 64]) {
+    let mut cosines = [[0f32; 8]; 8];
+    for (x, row) in cosines.iter_mut().enumerate() {
+        for (u, cosine) in row.iter_mut().enumerate() {
+            let scale = if u == 0 { 1.0 / 2f32.sqrt() } else { 1.0 };
+            *cosine = scale / 2.0 * ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos();
+        }
+    }
+
+    let mut rows = [0f32; 64];
+    for v in 0..8 {
+        for x in 0..8 {
+            rows[v * 8 + x] = (0..8)
+                .map(|u| cosines[x][u] * coefficients[v * 8 + u] as f32)
+                .sum();
+        }
+    }
+    for x in 0..8 {
+        for y in 0..8 {
+            block[y * 8 + x] = (0..8).map(|v| cosines[y][v] * rows[v * 8 + x]).sum();
+        }
+    }
+}
+
+/// Interleave the components, upsampled to the size of the image, and convert the colors.
+fn output(components: &[Component], width: usize, height: usize, transform: Option<u8>) -> Vec<u8> {
+    let h_max = components.iter().map(|c| c.h).max().unwrap_or(1);
+    let v_max = components.iter().map(|c| c.v).max().unwrap_or(1);
+    let count = components.len();
+
+    let mut data = Vec::with_capacity(width * height * count);
+    for y in 0..height {
+        for x in 0..width {
+            for c in components {
+                let (cx, cy) = (x * c.h / h_max, y * c.v / v_max);
+                data.push(c.plane.get(cy * c.stride + cx).copied().unwrap_or(0));
+            }
+        }
+    }
+
+    // Three components are YCbCr unless told otherwise, four are YCCK if told so.
+    let convert = match count {
+        3 => transform != Some(0),
+        4 => transform == Some(2),
+        _ => false,
+    };
+    if convert {
+        for pixel in data.chunks_exact_mut(count) {
+            let (y, cb, cr) = (
+                pixel[0] as f32,
+                pixel[1] as f32 - 128.0,
+                pixel[2] as f32 - 128.0,
+            );
+            let rgb = [
+                y + 1.402 * cr,
+                y - 0.344_136 * cb - 0.714_136 * cr,
+                y + 1.772 * cb,
+            ];
+            for (sample, value) in pixel.iter_mut().zip(rgb) {
+                let value = value.round().clamp(0.0, 255.0) as u8;
+                // YCCK encodes the complement of CMY.
+                *sample = if count == 4 { 255 - value } else { value };
+            }
+        }
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16 × 8 grayscale image, made of two flat blocks whose DC coefficients are `-512` and
+    /// `512`, quantized by 8 (i.e. samples of 64 and 192), with a restart marker between them.
+    fn image() -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        // Quantization table 0, filled with 8.
+        data.extend([0xFF, 0xDB, 0, 67, 0]);
+        data.extend([8; 64]);
+        // Frame: 8 bits, 8 × 16, one component with id 1.
+        data.extend([0xFF, 0xC0, 0, 11, 8, 0, 8, 0, 16, 1, 1, 0x11, 0]);
+        // DC table 0: a single code `0` of length 1, for size 7.
+        data.extend([0xFF, 0xC4, 0, 20, 0x00, 1]);
+        data.extend([0; 15]);
+        data.push(7);
+        // AC table 0: a single code `0` of length 1, for the end of block.
+        data.extend([0xFF, 0xC4, 0, 20, 0x10, 1]);
+        data.extend([0; 15]);
+        data.push(0);
+        // A restart interval of one MCU.
+        data.extend([0xFF, 0xDD, 0, 4, 0, 1]);
+        data.extend([0xFF, 0xDA, 0, 8, 1, 1, 0x00, 0, 63, 0]);
+        // Code `0`, then -64 on 7 bits (`0111111`), then end of block, padded with ones.
+        data.extend([0b0011_1111, 0b0111_1111]);
+        data.extend([0xFF, 0xD0]);
+        // Code `0`, then 64 on 7 bits (`1000000`), then end of block.
+        data.extend([0b0100_0000, 0b0111_1111]);
+        data.extend([0xFF, 0xD9]);
+        data
+    }
+
+    #[test]
+    fn baseline() {
+        let jpeg = decode(&image(), usize::MAX).unwrap();
+        assert_eq!((jpeg.width, jpeg.height, jpeg.components), (16, 8, 1));
+        assert_eq!(jpeg.data[0], 64);
+        assert_eq!(jpeg.data[7], 64);
+        assert_eq!(jpeg.data[8], 192);
+        assert_eq!(jpeg.data[127], 192);
+    }
+
+    #[test]
+    fn too_many_pixels() {
+        assert!(decode(&image(), 128).is_some());
+        assert_eq!(decode(&image(), 127), None);
+    }
+
+    /// Coefficients of 16 bits, multiplied by 16-bit quantization factors, do not fit in
+    /// an `i32`.
+    #[test]
+    fn overflow() {
+        let mut data = vec![0xFF, 0xD8];
+        // A 16-bit quantization table 0, filled with `0xFFFF`.
+        data.extend([0xFF, 0xDB, 0, 131, 0x10]);
+        data.extend([0xFF; 128]);
+        data.extend([0xFF, 0xC0, 0, 11, 8, 0, 8, 0, 8, 1, 1, 0x11, 0]);
+        // DC table 0: a single code `0` of length 1, for size 16.
+        data.extend([0xFF, 0xC4, 0, 20, 0x00, 1]);
+        data.extend([0; 15]);
+        data.push(16);
+        // AC table 0: a single code `0` of length 1, for the end of block.
+        data.extend([0xFF, 0xC4, 0, 20, 0x10, 1]);
+        data.extend([0; 15]);
+        data.push(0);
+        data.extend([0xFF, 0xDA, 0, 8, 1, 1, 0x00, 0, 63, 0]);
+        // Code `0`, then `0xFFFF` on 16 bits, with a stuffed byte, then end of block.
+        data.extend([0b0111_1111, 0xFF, 0x00, 0b1011_1111]);
+        data.extend([0xFF, 0xD9]);
+
+        let jpeg = decode(&data, usize::MAX).unwrap();
+        assert_eq!(jpeg.data, [255; 64]);
+    }
+
+    #[test]
+    fn unsupported() {
+        let mut progressive = image();
+        let frame = progressive
+            .windows(2)
+            .position(|w| w == [0xFF, 0xC0])
+            .unwrap();
+        progressive[frame + 1] = 0xC2;
+        assert_eq!(decode(&progressive, usize::MAX), None);
+
+        assert_eq!(decode(b"not a jpeg", usize::MAX), None);
+    }
+}
@@ -0,0 +1,505 @@
+//! Rasterization of pages to RGBA pixel buffers, behind the `render` feature.
+//!
+//! The [`Renderer`] paints what the [content interpreter](super::interpreter) reports onto a
+//! white, anti-aliased canvas, in display space (see [`Viewport`](super::layout::Viewport)):
+//!
+//! - paths are filled with the nonzero or even-odd rule, and stroked with their line width,
+//!   joins, caps and dash pattern;
+//! - clipping paths are applied to everything painted until the state is restored;
+//! - colours of every colour space are converted to RGB, and painted with their opacity;
+//! - images are resampled, including stencil masks and soft masks. Baseline `DCTDecode`
+//!   images are decoded, while other image filters are not supported;
+//! - glyphs are drawn from the outlines of embedded TrueType, CFF and OpenType programs.
+//!   Type 1 programs are not read: the glyphs of such fonts, and of fonts that are not
+//!   embedded, are drawn as grey boxes that suggest the text.
+//!
+//! Shadings and blend modes are not supported. Text rendering modes that add glyphs to the
+//! clipping path only paint them.
+//!
+//! ```no_run
+//! use livre::InMemoryDocument;
+//!
+//! let input = std::fs::read("document.pdf").unwrap();
+//! let doc = InMemoryDocument::from_bytes(&input).unwrap();
+//! let page = &doc.pages().unwrap()[0];
+//!
+//! let pixmap = page.render(&doc, 72.0).unwrap();
+//! std::fs::write("thumbnail.png", pixmap.to_png()).unwrap();
+//! ```
+
+mod font;
+mod jpeg;
+mod raster;
+mod stroke;
+
+use std::{borrow::Cow, collections::HashMap, rc::Rc};
+
+use font::FontProgram;
+use raster::{flatten, Canvas, Mask, Point, TOLERANCE};
+
+use crate::{
+    extraction::{Reference, ReferenceId, Stream},
+    follow_refs::Builder,
+};
+
+use super::{
+    font::Font,
+    interpreter::{ColorSpace, Device, FillRule, GraphicsState, Image, Path, TextGlyph},
+    png,
+    state::{Matrix, RenderingMode},
+};
+
+/// A rendered page, as 8-bit RGBA pixels, row by row from the top-left corner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pixmap {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Pixmap {
+    /// The pixels, encoded as a PNG image.
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode(self.width, self.height, &self.data)
+    }
+}
+
+/// A [`Device`] that rasterizes what it is given (see the [module documentation](self)).
+///
+/// Font programs are read on first use and shared by every font that embeds them.
+pub struct Renderer<'b, B> {
+    builder: &'b B,
+    canvas: Canvas,
+    /// The clipping mask, `None` if nothing is clipped.
+    clip: Option<Rc<Mask>>,
+    stack: Vec<Option<Rc<Mask>>>,
+    /// Font programs by stream. `None` marks a program that cannot be read.
+    programs: HashMap<ReferenceId, Option<Rc<FontProgram>>>,
+    cid_to_gid_maps: HashMap<ReferenceId, Option<Rc<Vec<u8>>>>,
+}
+
+impl<'b, B> Renderer<'b, B>
+where
+    B: Builder,
+{
+    /// A renderer onto a white canvas of `width` by `height` pixels. The `builder` resolves
+    /// the embedded font programs.
+    pub fn new(builder: &'b B, width: u32, height: u32) -> Self {
+        Self {
+            builder,
+            canvas: Canvas::new(width as usize, height as usize),
+            clip: None,
+            stack: Vec::new(),
+            programs: HashMap::new(),
+            cid_to_gid_maps: HashMap::new(),
+        }
+    }
+
+    pub fn finish(self) -> Pixmap {
+        let (width, height) = self.canvas.size();
+        Pixmap {
+            width: width as u32,
+            height: height as u32,
+            data: self.canvas.data,
+        }
+    }
+
+    fn paint(&mut self, polygons: &[Vec<Point>], rule: FillRule, color: [f32; 3], alpha: f32) {
+        let clipped_out = self.clip.as_ref().is_some_and(|clip| clip.is_empty());
+        if alpha <= 0.0 || clipped_out {
+            return;
+        }
+        let mask = Mask::fill(polygons, rule, self.canvas.size());
+        self.canvas.fill(&mask, color, alpha, self.clip.as_deref());
+    }
+
+    fn content(&self, id: ReferenceId) -> Option<Vec<u8>> {
+        match self
+            .builder
+            .resolve_reference(Reference::<Stream<()>>::from(id))
+        {
+            Ok(stream) => stream.map(|stream| stream.content),
+            Err(err) => {
+                tracing::warn!("Skipping unreadable font stream {id:?}: {err:?}");
+                None
+            }
+        }
+    }
+
+    fn program(&mut self, id: ReferenceId) -> Option<Rc<FontProgram>> {
+        if let Some(program) = self.programs.get(&id) {
+            return program.clone();
+        }
+
+        let program = self.content(id).and_then(FontProgram::parse).map(Rc::new);
+        if program.is_none() {
+            tracing::debug!("Drawing the glyphs of font program {id:?} as boxes");
+        }
+        self.programs.insert(id, program.clone());
+        program
+    }
+
+    fn cid_to_gid_map(&mut self, id: ReferenceId) -> Option<Rc<Vec<u8>>> {
+        if let Some(map) = self.cid_to_gid_maps.get(&id) {
+            return map.clone();
+        }
+
+        let map = self.content(id).map(Rc::new);
+        self.cid_to_gid_maps.insert(id, map.clone());
+        map
+    }
+
+    /// The outline of a glyph in glyph space, scaled to a unit font size.
+    fn outline(&mut self, font: &Font, code: u32) -> Option<Path> {
+        let program = self.program(font.font_file()?)?;
+        let map = font.cid_to_gid_map().and_then(|id| self.cid_to_gid_map(id));
+        program.outline(font, code, map.as_ref().map(|map| map.as_slice()))
+    }
+}
+
+/// The polygons of a path mapped by `matrix`, to be filled.
+fn polygons(path: &Path, matrix: &Matrix) -> Vec<Vec<Point>> {
+    flatten(path, matrix, TOLERANCE)
+        .into_iter()
+        .map(|polyline| polyline.points)
+        .collect()
+}
+
+/// The bounds of polygons that form a single axis-aligned rectangle.
+fn rectangle(polygons: &[Vec<Point>]) -> Option<[f32; 4]> {
+    let [polygon] = polygons else {
+        return None;
+    };
+    if !(4..=5).contains(&polygon.len()) {
+        return None;
+    }
+
+    let (mut xs, mut ys): (Vec<f32>, Vec<f32>) = polygon.iter().copied().unzip();
+    xs.sort_by(f32::total_cmp);
+    ys.sort_by(f32::total_cmp);
+    xs.dedup();
+    ys.dedup();
+
+    match (xs.as_slice(), ys.as_slice()) {
+        (&[x0, x1], &[y0, y1]) => Some([x0, y0, x1, y1]),
+        _ => None,
+    }
+}
+
+/// The glyph of a font whose program cannot be read: a box over most of the glyph's width,
+/// and half of the em square's height.
+fn greeked(glyph: &TextGlyph<'_>) -> Option<Path> {
+    let blank = glyph
+        .font
+        .text(glyph.code)
+        .is_some_and(|text| text.chars().all(char::is_whitespace));
+    if blank || glyph.width <= 0.0 {
+        return None;
+    }
+
+    let (x0, x1) = (0.1 * glyph.width, 0.9 * glyph.width);
+    Some(Path::rectangle(&(x0, 0.0, x1, 0.5).into()))
+}
+
+impl<B> Device for Renderer<'_, B>
+where
+    B: Builder,
+{
+    fn save_state(&mut self) {
+        self.stack.push(self.clip.clone());
+    }
+
+    fn restore_state(&mut self) {
+        if let Some(clip) = self.stack.pop() {
+            self.clip = clip;
+        }
+    }
+
+    fn fill_path(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {
+        let polygons = polygons(path, &state.ctm);
+        self.paint(&polygons, rule, state.fill.to_rgb(), state.fill_alpha);
+    }
+
+    fn stroke_path(&mut self, path: &Path, state: &GraphicsState) {
+        let polygons = stroke::stroke(path, state);
+        self.paint(
+            &polygons,
+            FillRule::NonZero,
+            state.stroke.to_rgb(),
+            state.stroke_alpha,
+        );
+    }
+
+    fn clip(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {
+        let polygons = polygons(path, &state.ctm);
+
+        // Producers often clip every text object to the page: a rectangle that contains the
+        // current clipping region, give or take a partially covered pixel, changes nothing.
+        let (width, height) = self.canvas.size();
+        let region = match &self.clip {
+            Some(clip) => [clip.x, clip.y, clip.x + clip.width, clip.y + clip.height],
+            None => [0, 0, width, height],
+        };
+        let bounds = rectangle(&polygons);
+        if let Some([x0, y0, x1, y1]) = bounds {
+            let [rx0, ry0, rx1, ry1] = region.map(|value| value as f32);
+            if x0 < rx0 + 1.0 && y0 < ry0 + 1.0 && x1 > rx1 - 1.0 && y1 > ry1 - 1.0 {
+                return;
+            }
+        }
+
+        let mask = match bounds {
+            Some(bounds) => Mask::rectangle(bounds, self.canvas.size()),
+            None => Mask::fill(&polygons, rule, self.canvas.size()),
+        };
+
+        let mask = match &self.clip {
+            Some(clip) => clip.intersect(&mask),
+            None => mask,
+        };
+        self.clip = Some(Rc::new(mask));
+    }
+
+    fn draw_glyph(&mut self, glyph: &TextGlyph<'_>, state: &GraphicsState) {
+        let (fill, stroke) = match state.text.parameters.rendering_mode {
+            RenderingMode::Fill | RenderingMode::FillAndClip => (true, false),
+            RenderingMode::Stroke | RenderingMode::StrokeAndClip => (false, true),
+            RenderingMode::FillThenStroke | RenderingMode::FillThenStrokeAndClip => (true, true),
+            RenderingMode::Invisible | RenderingMode::AddTextAndClip => return,
+        };
+
+        let Some(outline) = self.outline(glyph.font, glyph.code) else {
+            if let Some(path) = greeked(glyph) {
+                let polygons = polygons(&path, &glyph.matrix);
+                let color = if fill {
+                    state.fill.to_rgb()
+                } else {
+                    state.stroke.to_rgb()
+                };
+                self.paint(&polygons, FillRule::NonZero, color, 0.5 * state.fill_alpha);
+            }
+            return;
+        };
+
+        if fill {
+            let polygons = polygons(&outline, &glyph.matrix);
+            self.paint(
+                &polygons,
+                FillRule::NonZero,
+                state.fill.to_rgb(),
+                state.fill_alpha,
+            );
+        }
+
+        // The stroke is computed in user space, where the line width is defined.
+        if let (true, Some(inverse)) = (stroke, state.ctm.inverse()) {
+            let outline = outline.transform(&glyph.matrix.then(&inverse));
+            self.stroke_path(&outline, state);
+        }
+    }
+
+    fn draw_image(&mut self, image: &Image, state: &GraphicsState) {
+        let limit = self.builder.options().limits.max_pixels;
+        if image.width as u64 * image.height as u64 > limit as u64 {
+            tracing::debug!("Skipping an image of more than {limit} pixels");
+            return;
+        }
+        let Some(image) = decoded(image, limit) else {
+            tracing::debug!(
+                "Skipping an image that cannot be decoded, with filters {:?}",
+                image.filters
+            );
+            return;
+        };
+        let Some(pixels) = image.to_rgba(state.fill.to_rgb()) else {
+            tracing::debug!("Skipping an image with incomplete samples");
+            return;
+        };
+
+        let (width, height) = (image.width as f32, image.height as f32);
+        // Image space, with the first sample at the top-left corner, to user space.
+        let image_matrix = Matrix::from([1.0 / width, 0.0, 0.0, -1.0 / height, 0.0, 1.0]);
+        let Some(inverse) = image_matrix.then(&state.ctm).inverse() else {
+            return;
+        };
+
+        let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]
+            .map(|(x, y)| state.ctm.transform(x, y));
+        let (canvas_width, canvas_height) = self.canvas.size();
+        let range = |values: [f32; 4], max: usize| {
+            let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+            let end = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            (min.floor().max(0.0) as usize)..(end.ceil().clamp(0.0, max as f32) as usize)
+        };
+        let xs = range(corners.map(|c| c.0), canvas_width);
+        let ys = range(corners.map(|c| c.1), canvas_height);
+
+        // Average several samples per pixel when the image is downscaled.
+        let [a, b, c, d, ..] = state.ctm.components();
+        let area = (a * d - b * c).abs();
+        let samples = ((width * height / area).sqrt().ceil() as usize).clamp(1, 4);
+        let step = 1.0 / samples as f32;
+
+        for y in ys {
+            for x in xs.clone() {
+                let mut color = [0.0; 3];
+                let mut coverage = 0.0;
+
+                for i in 0..samples * samples {
+                    let px = x as f32 + (i % samples) as f32 * step + step / 2.0;
+                    let py = y as f32 + (i / samples) as f32 * step + step / 2.0;
+                    let (u, v) = inverse.transform(px, py);
+                    if u < 0.0 || v < 0.0 || u >= width || v >= height {
+                        continue;
+                    }
+
+                    let index = (v as usize * image.width as usize + u as usize) * 4;
+                    let alpha = pixels[index + 3] as f32 / 255.0;
+                    for (channel, &value) in color.iter_mut().zip(&pixels[index..index + 3]) {
+                        *channel += value as f32 / 255.0 * alpha;
+                    }
+                    coverage += alpha;
+                }
+
+                if coverage <= 0.0 {
+                    continue;
+                }
+                let color = color.map(|channel| channel / coverage);
+                let mut coverage = coverage / (samples * samples) as f32 * state.fill_alpha;
+                if let Some(clip) = &self.clip {
+                    coverage *= clip.get(x, y) as f32 / 255.0;
+                }
+                self.canvas.blend(x, y, color, coverage);
+            }
+        }
+    }
+}
+
+/// The image with its samples decoded, along with those of its soft mask, if Livre can
+/// decode them.
+fn decoded(image: &Image, max_pixels: usize) -> Option<Cow<'_, Image>> {
+    let soft_mask_decoded = image
+        .soft_mask
+        .as_ref()
+        .is_none_or(|mask| mask.is_decoded());
+    if image.is_decoded() && soft_mask_decoded {
+        return Some(Cow::Borrowed(image));
+    }
+
+    let mut owned = image.clone();
+
+    if !image.is_decoded() {
+        if !matches!(image.filters.as_slice(), [filter] if filter.0 == b"DCTDecode") {
+            return None;
+        }
+        let jpeg = jpeg::decode(&image.data, max_pixels)?;
+        if jpeg.components != image.color_space.components() {
+            return None;
+        }
+
+        owned.width = jpeg.width;
+        owned.height = jpeg.height;
+        owned.bits_per_component = 8;
+        owned.data = jpeg.data;
+        owned.filters.clear();
+    }
+
+    // An undecodable soft mask is dropped, rather than the whole image.
+    owned.soft_mask = image
+        .soft_mask
+        .as_deref()
+        .and_then(|mask| decoded(mask, max_pixels))
+        .map(|mask| Box::new(mask.into_owned()))
+        .filter(|mask| mask.color_space == ColorSpace::DeviceGray);
+
+    Some(Cow::Owned(owned))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::{
+        content::{interpreter::ContentInterpreter, operators::spanned_operators},
+        structure::Resources,
+    };
+
+    use super::*;
+
+    /// Render content onto a 20 × 20 canvas, with the origin at the bottom-left corner.
+    fn render(content: &[u8]) -> Pixmap {
+        let ctm = Matrix::from([1.0, 0.0, 0.0, -1.0, 0.0, 20.0]);
+        let mut renderer = Renderer::new(&(), 20, 20);
+        let operators = spanned_operators(content, usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| Ok(spanned.operator));
+        ContentInterpreter::new(&(), Resources::default(), ctm)
+            .run(operators, &mut renderer)
+            .unwrap();
+        renderer.finish()
+    }
+
+    /// The RGB colour of a pixel, from the top-left corner.
+    fn pixel(pixmap: &Pixmap, x: usize, y: usize) -> [u8; 3] {
+        let index = (y * pixmap.width as usize + x) * 4;
+        pixmap.data[index..index + 3].try_into().unwrap()
+    }
+
+    #[rstest]
+    #[case(b"", (5, 5), [255, 255, 255])]
+    #[case(b"1 0 0 rg 0 0 10 10 re f", (5, 15), [255, 0, 0])]
+    #[case(b"1 0 0 rg 0 0 10 10 re f", (15, 5), [255, 255, 255])]
+    #[case(b"0.5 g 0 0 20 20 re f", (10, 10), [128, 128, 128])]
+    #[case(b"0 1 1 0 k 0 0 20 20 re f", (10, 10), [255, 0, 0])]
+    #[case(b"0 0 1 RG 4 w 0 10 m 20 10 l S", (10, 10), [0, 0, 255])]
+    #[case(b"0 0 1 RG 4 w 0 10 m 20 10 l S", (10, 5), [255, 255, 255])]
+    #[case(b"q 0 0 5 5 re W n 0 0 20 20 re f Q", (2, 17), [0, 0, 0])]
+    #[case(b"q 0 0 5 5 re W n 0 0 20 20 re f Q", (10, 10), [255, 255, 255])]
+    #[case(b"q 0 0 5 5 re W n Q 0 0 20 20 re f", (10, 10), [0, 0, 0])]
+    #[case(b"0 0 20 20 re 5 5 10 10 re f*", (10, 10), [255, 255, 255])]
+    #[case(b"0 0 20 20 re 5 5 10 10 re f", (10, 10), [0, 0, 0])]
+    fn paths(#[case] content: &[u8], #[case] at: (usize, usize), #[case] expected: [u8; 3]) {
+        assert_eq!(pixel(&render(content), at.0, at.1), expected);
+    }
+
+    #[test]
+    fn images() {
+        let image = Image {
+            width: 2,
+            height: 1,
+            bits_per_component: 8,
+            color_space: ColorSpace::DeviceRGB,
+            image_mask: false,
+            decode: None,
+            data: vec![255, 0, 0, 0, 0, 255],
+            filters: Vec::new(),
+            soft_mask: None,
+        };
+
+        // The image covers the whole canvas: red on the left, blue on the right.
+        let state = GraphicsState::new(Matrix::from([20.0, 0.0, 0.0, 20.0, 0.0, 0.0]));
+        let mut renderer = Renderer::new(&(), 20, 20);
+        renderer.draw_image(&image, &state);
+        let pixmap = renderer.finish();
+
+        assert_eq!(pixel(&pixmap, 5, 5), [255, 0, 0]);
+        assert_eq!(pixel(&pixmap, 15, 15), [0, 0, 255]);
+    }
+
+    #[test]
+    fn greeked_text() {
+        let pixmap = render(b"BT /F1 10 Tf 0 5 Td (I I) Tj ET");
+
+        // Glyphs are boxes over the lower half of the em square; spaces are left out.
+        assert_ne!(pixel(&pixmap, 1, 13), [255, 255, 255]);
+        assert_eq!(pixel(&pixmap, 1, 8), [255, 255, 255]);
+        assert_eq!(pixel(&pixmap, 4, 13), [255, 255, 255]);
+    }
+
+    #[test]
+    fn png() {
+        let png = render(b"").to_png();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+}
@@ -0,0 +1,524 @@
+//! Scan conversion of polygons into anti-aliased coverage masks, and compositing onto the
+//! canvas.
+//!
+//! Each pixel row is sampled along several horizontal lines. Along each of them, the spans
+//! that lie inside the polygons are accumulated with their exact horizontal coverage, such
+//! that edges are anti-aliased in both directions.
+
+use crate::content::{
+    interpreter::{FillRule, Path, PathElement},
+    Matrix,
+};
+
+/// Maximum distance between a curve and the polyline that approximates it, in pixels.
+pub(super) const TOLERANCE: f32 = 0.2;
+
+/// Sampled lines per pixel row.
+const SAMPLES: usize = 5;
+
+pub(super) type Point = (f32, f32);
+
+/// A flattened subpath.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Polyline {
+    pub(super) points: Vec<Point>,
+    pub(super) closed: bool,
+}
+
+/// Flatten a path mapped by `matrix` into polylines, whose distance to the curves is at
+/// most `tolerance`. Subpaths made of a single `m` are dropped, since they paint nothing.
+pub(super) fn flatten(path: &Path, matrix: &Matrix, tolerance: f32) -> Vec<Polyline> {
+    let map = |(x, y): Point| matrix.transform(x, y);
+
+    let mut polylines = Vec::new();
+    let mut current: Option<Polyline> = None;
+
+    for element in &path.elements {
+        match *element {
+            PathElement::MoveTo(point) => {
+                polylines.extend(current.take());
+                current = Some(Polyline {
+                    points: vec![map(point)],
+                    closed: false,
+                });
+            }
+            PathElement::LineTo(point) => {
+                if let Some(line) = &mut current {
+                    line.points.push(map(point));
+                }
+            }
+            PathElement::CurveTo(c1, c2, point) => {
+                if let Some(line) = &mut current {
+                    let start = line.points[line.points.len() - 1];
+                    flatten_curve(
+                        [start, map(c1), map(c2), map(point)],
+                        tolerance,
+                        &mut line.points,
+                    );
+                }
+            }
+            PathElement::ClosePath => {
+                if let Some(mut line) = current.take() {
+                    let start = line.points[0];
+                    line.closed = true;
+                    polylines.push(line);
+
+                    // A segment that follows starts from the beginning of the closed subpath.
+                    current = Some(Polyline {
+                        points: vec![start],
+                        closed: false,
+                    });
+                }
+            }
+        }
+    }
+
+    polylines.extend(current);
+    polylines.retain(|line| line.points.len() > 1);
+    polylines
+}
+
+/// Append the points of a flattened cubic Bézier curve, its start excluded. The number of
+/// segments follows Wang's formula.
+fn flatten_curve([p0, p1, p2, p3]: [Point; 4], tolerance: f32, points: &mut Vec<Point>) {
+    let second_difference =
+        |a: Point, b: Point, c: Point| (a.0 - 2.0 * b.0 + c.0).hypot(a.1 - 2.0 * b.1 + c.1);
+    let m = second_difference(p0, p1, p2).max(second_difference(p1, p2, p3));
+
+    let n = (0.75 * m / tolerance).sqrt().ceil();
+    let n = if n.is_finite() {
+        n.clamp(1.0, 256.0)
+    } else {
+        1.0
+    } as usize;
+
+    for i in 1..=n {
+        let t = i as f32 / n as f32;
+        let u = 1.0 - t;
+        let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+        points.push((
+            a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+            a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+        ));
+    }
+}
+
+/// A polygon edge, from top to bottom.
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    top: f32,
+    bottom: f32,
+    /// The abscissa at `top`.
+    x: f32,
+    slope: f32,
+    /// `1` for edges going down, `-1` for edges going up.
+    winding: i32,
+}
+
+/// The coverage of the pixels of a rectangle of the canvas, from 0 to 255. Pixels outside of
+/// the rectangle are not covered.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Mask {
+    pub(super) x: usize,
+    pub(super) y: usize,
+    pub(super) width: usize,
+    pub(super) height: usize,
+    pub(super) data: Vec<u8>,
+}
+
+impl Mask {
+    /// A mask that covers nothing.
+    pub(super) fn empty() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+            data: Vec::new(),
+        }
+    }
+
+    /// The coverage of an axis-aligned rectangle, computed from the area of each pixel it
+    /// overlaps rather than by scanning edges.
+    pub(super) fn rectangle(bounds: [f32; 4], size: (usize, usize)) -> Self {
+        let [xmin, ymin, xmax, ymax] = bounds;
+        let x0 = xmin.floor().max(0.0) as usize;
+        let y0 = ymin.floor().max(0.0) as usize;
+        let x1 = (xmax.ceil().min(size.0 as f32).max(0.0) as usize).max(x0);
+        let y1 = (ymax.ceil().min(size.1 as f32).max(0.0) as usize).max(y0);
+
+        let (width, height) = (x1 - x0, y1 - y0);
+        if width == 0 || height == 0 {
+            return Self::empty();
+        }
+
+        let overlap = |pixel: usize, low: f32, high: f32| {
+            let pixel = pixel as f32;
+            (high.min(pixel + 1.0) - low.max(pixel)).clamp(0.0, 1.0)
+        };
+        let columns: Vec<f32> = (x0..x1).map(|x| overlap(x, xmin, xmax)).collect();
+        let covered: Vec<u8> = columns
+            .iter()
+            .map(|horizontal| (horizontal * 255.0).round() as u8)
+            .collect();
+
+        let mut data = vec![0; width * height];
+        for (y, coverage) in (y0..y1).zip(data.chunks_exact_mut(width)) {
+            let vertical = overlap(y, ymin, ymax);
+            if vertical == 1.0 {
+                coverage.copy_from_slice(&covered);
+                continue;
+            }
+            for (value, horizontal) in coverage.iter_mut().zip(&columns) {
+                *value = (vertical * horizontal * 255.0).round() as u8;
+            }
+        }
+
+        Self {
+            x: x0,
+            y: y0,
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Rasterize polygons, which are implicitly closed, onto a canvas of the given size.
+    pub(super) fn fill(polygons: &[Vec<Point>], rule: FillRule, size: (usize, usize)) -> Self {
+        let points = polygons
+            .iter()
+            .flatten()
+            .filter(|(x, y)| x.is_finite() && y.is_finite());
+        let (xmin, ymin, xmax, ymax) = points.fold(
+            (
+                f32::INFINITY,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+                f32::NEG_INFINITY,
+            ),
+            |(x0, y0, x1, y1), &(x, y)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+        );
+
+        let x0 = xmin.floor().max(0.0) as usize;
+        let y0 = ymin.floor().max(0.0) as usize;
+        let x1 = (xmax.ceil().min(size.0 as f32).max(0.0) as usize).max(x0);
+        let y1 = (ymax.ceil().min(size.1 as f32).max(0.0) as usize).max(y0);
+
+        let (width, height) = (x1 - x0, y1 - y0);
+        if width == 0 || height == 0 {
+            return Self::empty();
+        }
+
+        let mut edges: Vec<Edge> = polygons
+            .iter()
+            .flat_map(|polygon| {
+                let next = polygon.iter().cycle().skip(1);
+                polygon.iter().zip(next).filter_map(|(&a, &b)| edge(a, b))
+            })
+            .collect();
+        edges.sort_by(|a, b| a.top.total_cmp(&b.top));
+
+        let mut data = vec![0; width * height];
+        let mut row = vec![0.0f32; width];
+        let mut active: Vec<Edge> = Vec::new();
+        let mut next = 0;
+        let mut crossings: Vec<(f32, i32)> = Vec::new();
+
+        let weight = 1.0 / SAMPLES as f32;
+
+        for (y, coverage) in (y0..y1).zip(data.chunks_exact_mut(width)) {
+            row.fill(0.0);
+
+            for sample in 0..SAMPLES {
+                let sy = y as f32 + (sample as f32 + 0.5) * weight;
+
+                while next < edges.len() && edges[next].top <= sy {
+                    active.push(edges[next]);
+                    next += 1;
+                }
+                active.retain(|edge| edge.bottom > sy);
+
+                crossings.clear();
+                crossings.extend(
+                    active
+                        .iter()
+                        .filter(|edge| edge.top <= sy)
+                        .map(|edge| (edge.x + (sy - edge.top) * edge.slope, edge.winding)),
+                );
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let mut winding = 0;
+                for pair in crossings.windows(2) {
+                    winding += pair[0].1;
+                    let inside = match rule {
+                        FillRule::NonZero => winding != 0,
+                        FillRule::EvenOdd => winding % 2 != 0,
+                    };
+                    if inside {
+                        add_span(
+                            &mut row,
+                            pair[0].0 - x0 as f32,
+                            pair[1].0 - x0 as f32,
+                            weight,
+                        );
+                    }
+                }
+            }
+
+            for (pixel, &value) in coverage.iter_mut().zip(&row) {
+                *pixel = (value.min(1.0) * 255.0).round() as u8;
+            }
+        }
+
+        Self {
+            x: x0,
+            y: y0,
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// The coverage of a pixel of the canvas.
+    pub(super) fn get(&self, x: usize, y: usize) -> u8 {
+        if x < self.x || y < self.y || x >= self.x + self.width || y >= self.y + self.height {
+            return 0;
+        }
+        self.data[(y - self.y) * self.width + x - self.x]
+    }
+
+    /// The coverage of the pixels from `x0` to `x1` on row `y`, which must lie within the mask.
+    fn row(&self, y: usize, x0: usize, x1: usize) -> &[u8] {
+        let start = (y - self.y) * self.width;
+        &self.data[start + x0 - self.x..start + x1 - self.x]
+    }
+
+    /// The pixels covered by both masks.
+    pub(super) fn intersect(&self, other: &Self) -> Self {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = (self.x + self.width).min(other.x + other.width);
+        let y1 = (self.y + self.height).min(other.y + other.height);
+
+        if x1 <= x0 || y1 <= y0 {
+            return Self::empty();
+        }
+
+        let mut data = Vec::with_capacity((x1 - x0) * (y1 - y0));
+        for y in y0..y1 {
+            let (first, second) = (self.row(y, x0, x1), other.row(y, x0, x1));
+            data.extend(
+                first
+                    .iter()
+                    .zip(second)
+                    .map(|(&a, &b)| (a as u32 * b as u32 / 255) as u8),
+            );
+        }
+
+        Self {
+            x: x0,
+            y: y0,
+            width: x1 - x0,
+            height: y1 - y0,
+            data,
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
+
+fn edge(a: Point, b: Point) -> Option<Edge> {
+    if a.1 == b.1 || !(a.0.is_finite() && a.1.is_finite() && b.0.is_finite() && b.1.is_finite()) {
+        return None;
+    }
+
+    let (top, bottom, winding) = if a.1 < b.1 { (a, b, 1) } else { (b, a, -1) };
+
+    Some(Edge {
+        top: top.1,
+        bottom: bottom.1,
+        x: top.0,
+        slope: (bottom.0 - top.0) / (bottom.1 - top.1),
+        winding,
+    })
+}
+
+/// Add the coverage of the span `[a, b)` to a row of pixels.
+fn add_span(row: &mut [f32], a: f32, b: f32, weight: f32) {
+    let width = row.len() as f32;
+    let (a, b) = (a.clamp(0.0, width), b.clamp(0.0, width));
+    if b <= a {
+        return;
+    }
+
+    let (first, last) = (a as usize, b as usize);
+    if first == last {
+        row[first] += (b - a) * weight;
+        return;
+    }
+
+    row[first] += (first as f32 + 1.0 - a) * weight;
+    for pixel in &mut row[first + 1..last] {
+        *pixel += weight;
+    }
+    if last < row.len() {
+        row[last] += (b - last as f32) * weight;
+    }
+}
+
+/// The pixels of a page being rendered, in RGBA. The canvas is opaque.
+#[derive(Debug, Clone)]
+pub(super) struct Canvas {
+    pub(super) width: usize,
+    pub(super) height: usize,
+    pub(super) data: Vec<u8>,
+}
+
+impl Canvas {
+    /// A white canvas.
+    pub(super) fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![255; width * height * 4],
+        }
+    }
+
+    pub(super) fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Paint a pixel with a colour, `coverage` ranging from 0 to 1.
+    pub(super) fn blend(&mut self, x: usize, y: usize, color: [f32; 3], coverage: f32) {
+        if coverage <= 0.0 {
+            return;
+        }
+
+        let index = (y * self.width + x) * 4;
+        for (channel, value) in self.data[index..index + 3].iter_mut().zip(color) {
+            let current = *channel as f32;
+            *channel = (current + (value * 255.0 - current) * coverage.min(1.0)).round() as u8;
+        }
+    }
+
+    /// Paint the pixels of a mask with a colour and an opacity, within the clipping path.
+    pub(super) fn fill(&mut self, mask: &Mask, color: [f32; 3], alpha: f32, clip: Option<&Mask>) {
+        let color = color.map(|c| c.clamp(0.0, 1.0));
+
+        for y in mask.y..mask.y + mask.height {
+            for x in mask.x..mask.x + mask.width {
+                let mut coverage = mask.get(x, y) as f32 / 255.0 * alpha;
+                if let Some(clip) = clip {
+                    coverage *= clip.get(x, y) as f32 / 255.0;
+                }
+                self.blend(x, y, color, coverage);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::extraction::Rectangle;
+
+    use super::*;
+
+    fn square(x: f32, y: f32, size: f32) -> Vec<Point> {
+        vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size)]
+    }
+
+    #[rstest]
+    #[case(FillRule::NonZero, 255)]
+    #[case(FillRule::EvenOdd, 0)]
+    fn fill_rules(#[case] rule: FillRule, #[case] expected: u8) {
+        // Two overlapping squares with the same orientation.
+        let mask = Mask::fill(
+            &[square(0.0, 0.0, 4.0), square(1.0, 1.0, 2.0)],
+            rule,
+            (10, 10),
+        );
+
+        assert_eq!((mask.x, mask.y, mask.width, mask.height), (0, 0, 4, 4));
+        assert_eq!(mask.get(0, 0), 255);
+        assert_eq!(mask.get(2, 2), expected);
+        assert_eq!(mask.get(5, 5), 0);
+    }
+
+    #[test]
+    fn anti_aliasing() {
+        let mask = Mask::fill(&[square(0.5, 0.0, 1.0)], FillRule::NonZero, (10, 10));
+
+        assert_eq!(mask.get(0, 0), 128);
+        assert_eq!(mask.get(1, 0), 128);
+    }
+
+    #[test]
+    fn clipped_to_canvas() {
+        let mask = Mask::fill(&[square(-5.0, -5.0, 20.0)], FillRule::NonZero, (4, 3));
+        assert_eq!((mask.width, mask.height), (4, 3));
+        assert!(mask.data.iter().all(|&coverage| coverage == 255));
+
+        let outside = Mask::fill(&[square(-5.0, -5.0, 2.0)], FillRule::NonZero, (4, 3));
+        assert!(outside.is_empty());
+    }
+
+    #[test]
+    fn intersect() {
+        let a = Mask::fill(&[square(0.0, 0.0, 4.0)], FillRule::NonZero, (10, 10));
+        let b = Mask::fill(&[square(2.0, 2.0, 4.0)], FillRule::NonZero, (10, 10));
+
+        let both = a.intersect(&b);
+        assert_eq!((both.x, both.y, both.width, both.height), (2, 2, 2, 2));
+        assert!(a.intersect(&Mask::empty()).is_empty());
+    }
+
+    #[test]
+    fn rectangles() {
+        let mask = Mask::rectangle([1.5, 2.0, 4.0, 3.25], (10, 10));
+        assert_eq!((mask.x, mask.y, mask.width, mask.height), (1, 2, 3, 2));
+        assert_eq!(
+            (mask.get(1, 2), mask.get(2, 2), mask.get(3, 3)),
+            (128, 255, 64)
+        );
+
+        let filled = Mask::fill(&[square(2.0, 2.0, 4.0)], FillRule::NonZero, (10, 10));
+        let exact = Mask::rectangle([2.0, 2.0, 6.0, 6.0], (10, 10));
+        assert_eq!(filled.data, exact.data);
+        assert!(Mask::rectangle([12.0, 0.0, 14.0, 2.0], (10, 10)).is_empty());
+    }
+
+    #[test]
+    fn flatten_paths() {
+        let mut path = Path::rectangle(&Rectangle::from((0.0, 0.0, 1.0, 1.0)));
+        path.elements.push(PathElement::LineTo((5.0, 5.0)));
+        path.elements.push(PathElement::MoveTo((9.0, 9.0)));
+
+        let polylines = flatten(&path, &Matrix::default(), TOLERANCE);
+
+        assert_eq!(polylines.len(), 2);
+        assert!(polylines[0].closed);
+        assert_eq!(polylines[1].points, [(0.0, 0.0), (5.0, 5.0)]);
+    }
+
+    #[test]
+    fn flatten_curves() {
+        let path = Path {
+            elements: vec![
+                PathElement::MoveTo((0.0, 0.0)),
+                PathElement::CurveTo((0.0, 50.0), (100.0, 50.0), (100.0, 0.0)),
+            ],
+        };
+
+        let points = &flatten(&path, &Matrix::default(), TOLERANCE)[0].points;
+
+        assert!(points.len() > 10);
+        assert_eq!(points[points.len() - 1], (100.0, 0.0));
+        // The top of the curve lies at 37.5.
+        let top = points.iter().map(|&(_, y)| y).fold(0.0, f32::max);
+        assert!((top - 37.5).abs() < TOLERANCE);
+    }
+}
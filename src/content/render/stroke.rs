@@ -0,0 +1,409 @@
+//! Conversion of stroked paths into polygons, filled with the nonzero winding rule.
+//!
+//! Strokes are computed in user space, where the line width is defined, and the resulting
+//! polygons are then mapped to device space: a transformation that does not preserve angles
+//! stretches the pen as it should. Each segment, join and cap is a polygon of its own, all
+//! of them with the same orientation, such that their union is painted exactly once.
+
+use std::f32::consts::PI;
+
+use crate::content::{
+    interpreter::{Dash, GraphicsState, LineCap, LineJoin, Path},
+    Matrix,
+};
+
+use super::raster::{flatten, Point, Polyline, TOLERANCE};
+
+/// Patterns with more dashes than this are stroked as solid lines.
+const MAX_DASHES: f32 = 100_000.0;
+
+/// The polygons that make up the stroke of a path, in device space.
+pub(super) fn stroke(path: &Path, state: &GraphicsState) -> Vec<Vec<Point>> {
+    let ctm = &state.ctm;
+    let [a, b, c, d, ..] = ctm.components();
+
+    // The average scaling factor from user space to device space.
+    let scale = (a * d - b * c).abs().sqrt();
+    if scale == 0.0 || !scale.is_finite() {
+        return Vec::new();
+    }
+
+    // A zero width stands for the thinnest line, one pixel wide.
+    let half_width = if state.line_width > 0.0 {
+        state.line_width / 2.0
+    } else {
+        0.5 / scale
+    };
+
+    let pen = Pen {
+        half_width,
+        cap: state.line_cap,
+        join: state.line_join,
+        miter_limit: state.miter_limit,
+        circle: circle_segments(half_width * scale),
+    };
+
+    let mut polygons = Vec::new();
+
+    for polyline in flatten(path, &Matrix::default(), TOLERANCE / scale) {
+        for (points, closed) in dash(polyline, &state.dash) {
+            pen.outline(&points, closed, &mut polygons);
+        }
+    }
+
+    for polygon in &mut polygons {
+        if signed_area(polygon) < 0.0 {
+            polygon.reverse();
+        }
+        for point in polygon.iter_mut() {
+            *point = ctm.transform(point.0, point.1);
+        }
+    }
+
+    polygons
+}
+
+/// The number of segments that approximate a circle of the given radius, in pixels.
+fn circle_segments(radius: f32) -> usize {
+    if radius <= TOLERANCE {
+        return 8;
+    }
+    let angle = (1.0 - TOLERANCE / radius).acos();
+    ((PI / angle).ceil() as usize).clamp(8, 128)
+}
+
+fn signed_area(polygon: &[Point]) -> f32 {
+    let next = polygon.iter().cycle().skip(1);
+    polygon
+        .iter()
+        .zip(next)
+        .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+        .sum::<f32>()
+        / 2.0
+}
+
+/// Split a polyline into dashes. Every subpath starts the dash pattern anew. Lines are
+/// returned along with whether they are closed, which only solid lines can be.
+fn dash(polyline: Polyline, dash: &Dash) -> Vec<(Vec<Point>, bool)> {
+    let mut array = dash.array.clone();
+    if array.iter().any(|&length| length < 0.0) {
+        array.clear();
+    }
+    // An odd number of lengths is repeated, e.g. `[3]` stands for `[3 3]`.
+    if array.len() % 2 == 1 {
+        array.extend_from_within(..);
+    }
+
+    let period: f32 = array.iter().sum();
+    let mut points = polyline.points;
+    if polyline.closed {
+        points.push(points[0]);
+    }
+
+    let length: f32 = points
+        .windows(2)
+        .map(|pair| distance(pair[0], pair[1]))
+        .sum();
+
+    if period <= 0.0 || length / period > MAX_DASHES {
+        if polyline.closed {
+            points.pop();
+        }
+        return vec![(points, polyline.closed)];
+    }
+
+    // Skip the phase.
+    let mut index = 0;
+    let mut remaining = array[0];
+    let mut phase = dash.phase.rem_euclid(period);
+    while phase > 0.0 {
+        if phase >= remaining {
+            phase -= remaining;
+            index = (index + 1) % array.len();
+            remaining = array[index];
+        } else {
+            remaining -= phase;
+            phase = 0.0;
+        }
+    }
+
+    let mut dashes = Vec::new();
+    let mut current = if index % 2 == 0 {
+        vec![points[0]]
+    } else {
+        Vec::new()
+    };
+
+    for pair in points.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let length = distance(start, end);
+        let mut travelled = 0.0;
+
+        while length - travelled > remaining {
+            travelled += remaining;
+            let t = travelled / length;
+            let point = (
+                start.0 + (end.0 - start.0) * t,
+                start.1 + (end.1 - start.1) * t,
+            );
+
+            if index % 2 == 0 {
+                current.push(point);
+                dashes.push((std::mem::take(&mut current), false));
+            } else {
+                current = vec![point];
+            }
+
+            index = (index + 1) % array.len();
+            remaining = array[index];
+        }
+
+        remaining -= length - travelled;
+        if index % 2 == 0 {
+            current.push(end);
+        }
+    }
+
+    if index % 2 == 0 && current.len() > 1 {
+        dashes.push((current, false));
+    }
+
+    dashes
+}
+
+fn distance(a: Point, b: Point) -> f32 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+fn unit(from: Point, to: Point) -> Point {
+    let length = distance(from, to);
+    ((to.0 - from.0) / length, (to.1 - from.1) / length)
+}
+
+/// The line style, in user space.
+struct Pen {
+    half_width: f32,
+    cap: LineCap,
+    join: LineJoin,
+    miter_limit: f32,
+    /// Segments of round joins and caps.
+    circle: usize,
+}
+
+impl Pen {
+    fn offset(&self, point: Point, direction: Point, normal: f32, along: f32) -> Point {
+        let w = self.half_width;
+        (
+            point.0 - direction.1 * w * normal + direction.0 * w * along,
+            point.1 + direction.0 * w * normal + direction.1 * w * along,
+        )
+    }
+
+    fn circle(&self, center: Point) -> Vec<Point> {
+        (0..self.circle)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f32 / self.circle as f32;
+                (
+                    center.0 + self.half_width * angle.cos(),
+                    center.1 + self.half_width * angle.sin(),
+                )
+            })
+            .collect()
+    }
+
+    /// The polygons of the stroke of a polyline.
+    fn outline(&self, points: &[Point], closed: bool, polygons: &mut Vec<Vec<Point>>) {
+        let mut points = points.to_vec();
+        points.dedup_by(|a, b| distance(*a, *b) < 1e-6);
+        if closed && points.len() > 1 && distance(points[0], points[points.len() - 1]) < 1e-6 {
+            points.pop();
+        }
+
+        // A zero-length line only paints its caps.
+        if points.len() == 1 {
+            let point = points[0];
+            match self.cap {
+                LineCap::Butt => {}
+                LineCap::Round => polygons.push(self.circle(point)),
+                LineCap::ProjectingSquare => polygons.push(
+                    [(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)]
+                        .map(|(normal, along)| self.offset(point, (1.0, 0.0), normal, along))
+                        .to_vec(),
+                ),
+            }
+            return;
+        }
+
+        let closed = closed && points.len() > 2;
+        let n = points.len();
+        let segments = if closed { n } else { n - 1 };
+
+        for i in 0..segments {
+            let (start, end) = (points[i], points[(i + 1) % n]);
+            let direction = unit(start, end);
+            polygons.push(vec![
+                self.offset(start, direction, -1.0, 0.0),
+                self.offset(end, direction, -1.0, 0.0),
+                self.offset(end, direction, 1.0, 0.0),
+                self.offset(start, direction, 1.0, 0.0),
+            ]);
+        }
+
+        let joins = if closed { 0..n } else { 1..n - 1 };
+        for i in joins {
+            let previous = points[(i + n - 1) % n];
+            self.join(previous, points[i], points[(i + 1) % n], polygons);
+        }
+
+        if !closed {
+            self.cap(points[0], unit(points[1], points[0]), polygons);
+            self.cap(points[n - 1], unit(points[n - 2], points[n - 1]), polygons);
+        }
+    }
+
+    /// The join at `point`, between the segments from `previous` and to `next`.
+    fn join(&self, previous: Point, point: Point, next: Point, polygons: &mut Vec<Vec<Point>>) {
+        let (d0, d1) = (unit(previous, point), unit(point, next));
+        let cross = d0.0 * d1.1 - d0.1 * d1.0;
+        let dot = d0.0 * d1.0 + d0.1 * d1.1;
+
+        if cross.abs() < 1e-6 && dot > 0.0 {
+            return;
+        }
+
+        // The outer side of the turn.
+        let side = if cross > 0.0 { -1.0 } else { 1.0 };
+        let a = self.offset(point, d0, side, 0.0);
+        let b = self.offset(point, d1, side, 0.0);
+        let bevel = vec![point, a, b];
+
+        match self.join {
+            // Flattened curves turn by small angles, where a bevel is indistinguishable.
+            LineJoin::Round if dot > 0.99 => polygons.push(bevel),
+            LineJoin::Round => polygons.push(self.circle(point)),
+            LineJoin::Bevel => polygons.push(bevel),
+            LineJoin::Miter => {
+                // The ratio of the miter length to the line width.
+                let ratio = (2.0 / (1.0 + dot)).sqrt();
+                if ratio.is_finite() && ratio <= self.miter_limit {
+                    let bisector = unit(point, ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0));
+                    let tip = (
+                        point.0 + bisector.0 * self.half_width * ratio,
+                        point.1 + bisector.1 * self.half_width * ratio,
+                    );
+                    polygons.push(vec![point, a, tip, b]);
+                } else {
+                    polygons.push(bevel);
+                }
+            }
+        }
+    }
+
+    /// The cap at the `end` of a line, which points towards `direction`.
+    fn cap(&self, end: Point, direction: Point, polygons: &mut Vec<Vec<Point>>) {
+        match self.cap {
+            LineCap::Butt => {}
+            LineCap::Round => polygons.push(self.circle(end)),
+            LineCap::ProjectingSquare => polygons.push(vec![
+                self.offset(end, direction, -1.0, 0.0),
+                self.offset(end, direction, -1.0, 1.0),
+                self.offset(end, direction, 1.0, 1.0),
+                self.offset(end, direction, 1.0, 0.0),
+            ]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::content::{interpreter::PathElement, render::raster::Mask};
+
+    use super::*;
+
+    fn line(points: &[Point]) -> Path {
+        let mut elements = vec![PathElement::MoveTo(points[0])];
+        elements.extend(points[1..].iter().map(|&point| PathElement::LineTo(point)));
+        Path { elements }
+    }
+
+    fn coverage(path: &Path, state: &GraphicsState, pixel: (usize, usize)) -> u8 {
+        let mask = Mask::fill(
+            &stroke(path, state),
+            crate::content::interpreter::FillRule::NonZero,
+            (20, 20),
+        );
+        mask.get(pixel.0, pixel.1)
+    }
+
+    #[rstest]
+    #[case(LineCap::Butt, (2, 10), 0)]
+    #[case(LineCap::ProjectingSquare, (1, 10), 255)]
+    #[case(LineCap::Round, (2, 10), 255)]
+    #[case(LineCap::Butt, (5, 10), 255)]
+    #[case(LineCap::Butt, (5, 13), 0)]
+    fn caps(#[case] cap: LineCap, #[case] pixel: (usize, usize), #[case] expected: u8) {
+        let mut state = GraphicsState::new(Matrix::default());
+        state.line_width = 4.0;
+        state.line_cap = cap;
+
+        let path = line(&[(3.0, 10.5), (17.0, 10.5)]);
+        assert_eq!(coverage(&path, &state, pixel), expected);
+    }
+
+    #[rstest]
+    #[case(LineJoin::Miter, 255)]
+    #[case(LineJoin::Bevel, 0)]
+    #[case(LineJoin::Round, 0)]
+    fn joins(#[case] join: LineJoin, #[case] expected: u8) {
+        let mut state = GraphicsState::new(Matrix::default());
+        state.line_width = 6.0;
+        state.line_join = join;
+
+        // A right angle, whose outer corner lies at (17, 3).
+        let path = line(&[(3.0, 6.0), (14.0, 6.0), (14.0, 17.0)]);
+        assert_eq!(coverage(&path, &state, (16, 3)), expected);
+        assert_eq!(coverage(&path, &state, (14, 6)), 255);
+    }
+
+    #[test]
+    fn miter_limit() {
+        let mut state = GraphicsState::new(Matrix::default());
+        state.line_width = 6.0;
+        state.miter_limit = 1.2;
+
+        let path = line(&[(3.0, 6.0), (14.0, 6.0), (14.0, 17.0)]);
+        assert_eq!(coverage(&path, &state, (16, 3)), 0);
+    }
+
+    #[rstest]
+    #[case(vec![2.0], 0.0, vec![(0.0, 2.0), (4.0, 6.0), (8.0, 10.0)])]
+    #[case(vec![2.0, 1.0], 1.0, vec![(0.0, 1.0), (2.0, 4.0), (5.0, 7.0), (8.0, 10.0)])]
+    #[case(vec![], 0.0, vec![(0.0, 10.0)])]
+    #[case(vec![0.0, 0.0], 0.0, vec![(0.0, 10.0)])]
+    fn dashes(#[case] array: Vec<f32>, #[case] phase: f32, #[case] expected: Vec<(f32, f32)>) {
+        let polyline = Polyline {
+            points: vec![(0.0, 0.0), (10.0, 0.0)],
+            closed: false,
+        };
+
+        let dashes: Vec<(f32, f32)> = dash(polyline, &Dash { array, phase })
+            .into_iter()
+            .map(|(points, _)| (points[0].0, points[points.len() - 1].0))
+            .collect();
+
+        assert_eq!(dashes, expected);
+    }
+
+    #[test]
+    fn transformed() {
+        let mut state = GraphicsState::new(Matrix::from([2.0, 0.0, 0.0, 2.0, 0.0, 0.0]));
+        state.line_width = 2.0;
+
+        // The line is 4 pixels wide in device space.
+        let path = line(&[(1.0, 5.0), (9.0, 5.0)]);
+        assert_eq!(coverage(&path, &state, (10, 8)), 255);
+        assert_eq!(coverage(&path, &state, (10, 12)), 0);
+    }
+}
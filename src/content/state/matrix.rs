@@ -0,0 +1,180 @@
+//! Transformation matrices, used to map one coordinate space onto another.
+//!
+//! From the specification:
+//!
+//! > PDF represents coordinates in a two-dimensional space. The point (x, y) in such a space
+//! > can be expressed in vector form as [x y 1]. The third element of this vector (1) is
+//! > needed to make the vector compatible with 3-by-3 matrices used in computations.
+//!
+//! Since the third column of such a matrix is always `[0 0 1]`, a matrix is represented by its
+//! six remaining components `[a b c d e f]`.
+
+use std::io::{self, Write};
+
+use winnow::{combinator::trace, BStr, ModalResult, Parser};
+
+use crate::{
+    extraction::{extract, Extract},
+    serialization::Serialize,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Matrix {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+    f: f32,
+}
+
+impl Extract<'_> for Matrix {
+    fn extract(input: &mut &BStr) -> ModalResult<Self> {
+        trace(
+            "livre-matrix",
+            extract.map(|(a, b, c, d, e, f)| Self { a, b, c, d, e, f }),
+        )
+        .parse_next(input)
+    }
+}
+
+/// Matrices are written as their six components, as they appear before the `cm` and `Tm`
+/// operators.
+impl Serialize for Matrix {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        for (i, component) in self.components().iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b" ")?;
+            }
+            component.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Self {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 1.0,
+            e: 0.0,
+            f: 0.0,
+        }
+    }
+}
+
+impl From<[f32; 6]> for Matrix {
+    fn from([a, b, c, d, e, f]: [f32; 6]) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+}
+
+impl Matrix {
+    /// The six components `[a b c d e f]` of the matrix.
+    pub fn components(&self) -> [f32; 6] {
+        let Self { a, b, c, d, e, f } = *self;
+        [a, b, c, d, e, f]
+    }
+
+    pub fn position(&self) -> (f32, f32) {
+        (self.e, self.f)
+    }
+
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        let Self { a, b, c, d, e, f } = *self;
+
+        let e = a * x + c * y + e;
+        let f = b * x + d * y + f;
+
+        *self = Self { a, b, c, d, e, f };
+    }
+
+    /// The product `self × other`, i.e. the transformation that applies `self` first,
+    /// then `other`. This is how the `cm` operator updates the current transformation matrix.
+    pub fn then(&self, other: &Self) -> Self {
+        Self {
+            a: self.a * other.a + self.b * other.c,
+            b: self.a * other.b + self.b * other.d,
+            c: self.c * other.a + self.d * other.c,
+            d: self.c * other.b + self.d * other.d,
+            e: self.e * other.a + self.f * other.c + other.e,
+            f: self.e * other.b + self.f * other.d + other.f,
+        }
+    }
+
+    /// The inverse transformation, if the matrix is invertible.
+    pub fn inverse(&self) -> Option<Self> {
+        let Self { a, b, c, d, e, f } = *self;
+
+        let determinant = a * d - b * c;
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        Some(Self {
+            a: d / determinant,
+            b: -b / determinant,
+            c: -c / determinant,
+            d: a / determinant,
+            e: (c * f - d * e) / determinant,
+            f: (b * e - a * f) / determinant,
+        })
+    }
+
+    /// Map the point `(x, y)` to the target space.
+    pub fn transform(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case([1.0, 0.0, 0.0, 1.0, 10.0, 20.0], (1.0, 1.0), (11.0, 21.0))]
+    #[case([2.0, 0.0, 0.0, 3.0, 0.0, 0.0], (1.0, 1.0), (2.0, 3.0))]
+    #[case([0.0, 1.0, -1.0, 0.0, 0.0, 0.0], (1.0, 0.0), (0.0, 1.0))]
+    fn transform(
+        #[case] matrix: [f32; 6],
+        #[case] point: (f32, f32),
+        #[case] expected: (f32, f32),
+    ) {
+        let matrix = Matrix::from(matrix);
+        assert_eq!(matrix.transform(point.0, point.1), expected);
+    }
+
+    #[test]
+    fn then() {
+        let scale = Matrix::from([2.0, 0.0, 0.0, 2.0, 0.0, 0.0]);
+        let translate = Matrix::from([1.0, 0.0, 0.0, 1.0, 5.0, 0.0]);
+
+        // Scaling first, then translating.
+        assert_eq!(scale.then(&translate).transform(1.0, 1.0), (7.0, 2.0));
+        // Translating first, then scaling.
+        assert_eq!(translate.then(&scale).transform(1.0, 1.0), (12.0, 2.0));
+    }
+
+    #[rstest]
+    #[case([2.0, 0.0, 0.0, 4.0, 10.0, 20.0])]
+    #[case([0.0, 1.0, -1.0, 0.0, 3.0, 0.0])]
+    #[case([1.0, 2.0, 3.0, 4.0, 5.0, 6.0])]
+    fn inverse(#[case] matrix: [f32; 6]) {
+        let matrix = Matrix::from(matrix);
+        let (x, y) = matrix.then(&matrix.inverse().unwrap()).transform(7.0, -3.0);
+
+        assert!((x - 7.0).abs() < 1e-4 && (y + 3.0).abs() < 1e-4);
+        assert_eq!(Matrix::from([1.0, 2.0, 2.0, 4.0, 0.0, 0.0]).inverse(), None);
+    }
+}
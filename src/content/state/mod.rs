@@ -2,6 +2,8 @@
 //!
 //! For now, livre only supports text objects.
 
+mod matrix;
 mod text;
 
+pub use matrix::Matrix;
 pub use text::{parse_text_object, RenderingMode, TextMatrix, TextObject, TextStateParameters};
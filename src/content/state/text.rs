@@ -10,14 +10,13 @@
 //! > the horizontal or vertical displacement of each glyph painted as well as
 //! > any character or word-spacing parameters in the text state.
 
-use std::collections::VecDeque;
-
-use winnow::{
-    combinator::trace,
-    error::{ContextError, ErrMode},
-    BStr, ModalResult, Parser,
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
 };
 
+use winnow::error::{ContextError, ErrMode};
+
 use crate::{
     content::{
         error::{ContentError, Result},
@@ -29,9 +28,14 @@ use crate::{
             Operator,
         },
     },
-    extraction::{extract, Extract, Name, PDFString},
+    extraction::{Extract, Name, PDFString},
+    serialization::Serialize,
 };
 
+use super::Matrix;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TextStateParameters {
     /// Spacing between characters, in unscaled text space units. Added to the horizontal or
     /// vertical component of the glyph's displacement, depending on the writing mode.
@@ -79,55 +83,11 @@ impl Default for TextStateParameters {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct TextMatrix {
-    a: f32,
-    b: f32,
-    c: f32,
-    d: f32,
-    e: f32,
-    f: f32,
-}
-
-impl Extract<'_> for TextMatrix {
-    fn extract(input: &mut &BStr) -> ModalResult<Self> {
-        trace(
-            "livre-text-matrix",
-            extract.map(|(a, b, c, d, e, f)| Self { a, b, c, d, e, f }),
-        )
-        .parse_next(input)
-    }
-}
-
-impl Default for TextMatrix {
-    fn default() -> Self {
-        Self {
-            a: 1.0,
-            b: 0.0,
-            c: 0.0,
-            d: 1.0,
-            e: 0.0,
-            f: 0.0,
-        }
-    }
-}
-
-impl TextMatrix {
-    pub fn position(&self) -> (f32, f32) {
-        (self.e, self.f)
-    }
-
-    pub fn move_to(&mut self, x: f32, y: f32) {
-        let Self { a, b, c, d, e, f } = *self;
-
-        let e = a * x + c * y + e;
-        let f = b * x + d * y + f;
-
-        *self = Self { a, b, c, d, e, f };
-    }
-}
+/// The text matrix $T_m$, which maps text space to user space.
+pub type TextMatrix = Matrix;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum RenderingMode {
     /// Fill text.
     Fill,
@@ -163,6 +123,25 @@ impl Extract<'_> for RenderingMode {
     }
 }
 
+impl Serialize for RenderingMode {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mode: u8 = match self {
+            Self::Fill => 0,
+            Self::Stroke => 1,
+            Self::FillThenStroke => 2,
+            Self::Invisible => 3,
+            Self::FillAndClip => 4,
+            Self::StrokeAndClip => 5,
+            Self::FillThenStrokeAndClip => 6,
+            Self::AddTextAndClip => 7,
+        };
+        mode.serialize(writer)
+    }
+}
+
 /// A text object.
 ///
 /// Can be iterated over to extract text elements. Text is decoded
@@ -0,0 +1,556 @@
+//! Export of pages to SVG, as a vector preview.
+//!
+//! The [`SvgDevice`] writes what the [content interpreter](super::interpreter) paints as SVG
+//! elements, in display space (see [`Viewport`]):
+//!
+//! - paths are filled and stroked with the current colours, opacity and line style;
+//! - clipping paths are applied to everything painted until the state is restored;
+//! - glyphs are written as `<text>` elements, one per glyph, positioned with the text
+//!   rendering matrix. Livre does not read font programs: the text is drawn with a system
+//!   font that resembles the `BaseFont`, and glyphs without Unicode text are left out;
+//! - images are embedded as data URIs: PNG for the images that Livre can decode, and JPEG
+//!   for `DCTDecode` images. Other images are left out.
+//!
+//! Text rendering modes that add glyphs to the clipping path only paint them.
+//!
+//! ```no_run
+//! use livre::InMemoryDocument;
+//!
+//! let input = std::fs::read("document.pdf").unwrap();
+//! let doc = InMemoryDocument::from_bytes(&input).unwrap();
+//! let page = &doc.pages().unwrap()[0];
+//!
+//! std::fs::write("page.svg", page.to_svg(&doc).unwrap()).unwrap();
+//! ```
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use super::{
+    font::Font,
+    interpreter::{
+        Device, FillRule, GraphicsState, Image, LineCap, LineJoin, Path, PathElement, TextGlyph,
+    },
+    layout::Viewport,
+    png,
+    state::{Matrix, RenderingMode},
+    xml::escape,
+};
+
+/// A number with at most three decimals, without trailing zeros.
+fn number(value: f32) -> String {
+    let formatted = format!("{value:.3}");
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+
+    match trimmed {
+        "-0" | "" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+fn color(rgb: [f32; 3]) -> String {
+    let [r, g, b] = rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn transform(matrix: &Matrix) -> String {
+    let components: Vec<String> = matrix.components().into_iter().map(number).collect();
+    format!(r#"transform="matrix({})""#, components.join(" "))
+}
+
+/// The `d` attribute of a path element.
+fn path_data(path: &Path) -> String {
+    let point = |(x, y): (f32, f32)| format!("{} {}", number(x), number(y));
+
+    let commands: Vec<String> = path
+        .elements
+        .iter()
+        .map(|element| match *element {
+            PathElement::MoveTo(p) => format!("M{}", point(p)),
+            PathElement::LineTo(p) => format!("L{}", point(p)),
+            PathElement::CurveTo(c1, c2, p) => {
+                format!("C{} {} {}", point(c1), point(c2), point(p))
+            }
+            PathElement::ClosePath => "Z".to_string(),
+        })
+        .collect();
+
+    commands.join(" ")
+}
+
+fn fill_attributes(state: &GraphicsState, rule: FillRule) -> String {
+    let mut attributes = format!(r#"fill="{}""#, color(state.fill.to_rgb()));
+
+    if rule == FillRule::EvenOdd {
+        attributes.push_str(r#" fill-rule="evenodd""#);
+    }
+    if state.fill_alpha < 1.0 {
+        attributes.push_str(&format!(r#" fill-opacity="{}""#, number(state.fill_alpha)));
+    }
+
+    attributes
+}
+
+/// The stroke attributes, in user space: the path element is transformed by the current
+/// transformation matrix, line width included.
+fn stroke_attributes(state: &GraphicsState) -> String {
+    let mut attributes = format!(r#"stroke="{}""#, color(state.stroke.to_rgb()));
+
+    if state.line_width > 0.0 {
+        attributes.push_str(&format!(r#" stroke-width="{}""#, number(state.line_width)));
+    } else {
+        // The thinnest line that can be rendered.
+        attributes.push_str(r#" stroke-width="1" vector-effect="non-scaling-stroke""#);
+    }
+
+    match state.line_cap {
+        LineCap::Butt => {}
+        LineCap::Round => attributes.push_str(r#" stroke-linecap="round""#),
+        LineCap::ProjectingSquare => attributes.push_str(r#" stroke-linecap="square""#),
+    }
+
+    match state.line_join {
+        LineJoin::Miter => attributes.push_str(&format!(
+            r#" stroke-miterlimit="{}""#,
+            number(state.miter_limit.max(1.0))
+        )),
+        LineJoin::Round => attributes.push_str(r#" stroke-linejoin="round""#),
+        LineJoin::Bevel => attributes.push_str(r#" stroke-linejoin="bevel""#),
+    }
+
+    // A pattern of zero-length dashes paints nothing, like a solid line in SVG.
+    if state.dash.array.iter().any(|&length| length > 0.0) {
+        let array: Vec<String> = state.dash.array.iter().copied().map(number).collect();
+        attributes.push_str(&format!(r#" stroke-dasharray="{}""#, array.join(" ")));
+
+        if state.dash.phase != 0.0 {
+            attributes.push_str(&format!(
+                r#" stroke-dashoffset="{}""#,
+                number(state.dash.phase)
+            ));
+        }
+    }
+
+    if state.stroke_alpha < 1.0 {
+        attributes.push_str(&format!(
+            r#" stroke-opacity="{}""#,
+            number(state.stroke_alpha)
+        ));
+    }
+
+    attributes
+}
+
+/// The font attributes of a `<text>` element, guessed from the `BaseFont` name.
+fn font_attributes(font: &Font) -> String {
+    let name = font
+        .base_font()
+        .map(|name| String::from_utf8_lossy(&name.0).into_owned())
+        .unwrap_or_default();
+
+    // Drop the subset tag.
+    let name = match name.split_once('+') {
+        Some((tag, name)) if tag.len() == 6 => name,
+        _ => name.as_str(),
+    };
+    let (family, style) = name.split_once([',', '-']).unwrap_or((name, ""));
+
+    let lowercase = name.to_lowercase();
+    let generic = if lowercase.contains("courier") || lowercase.contains("mono") {
+        "monospace"
+    } else if lowercase.contains("times")
+        || (lowercase.contains("serif") && !lowercase.contains("sans"))
+    {
+        "serif"
+    } else {
+        "sans-serif"
+    };
+
+    let mut attributes = if family.is_empty() {
+        format!(r#"font-family="{generic}""#)
+    } else {
+        format!(r#"font-family="'{}', {generic}""#, escape(family))
+    };
+
+    let style = style.to_lowercase();
+    if style.contains("bold") || style.contains("black") || style.contains("heavy") {
+        attributes.push_str(r#" font-weight="bold""#);
+    }
+    if style.contains("italic") || style.contains("oblique") {
+        attributes.push_str(r#" font-style="italic""#);
+    }
+
+    attributes
+}
+
+/// The data URI of an image, if it can be embedded.
+fn data_uri(image: &Image, fill: [f32; 3]) -> Option<String> {
+    let encode = |bytes: &[u8]| STANDARD.encode(bytes);
+
+    if image.is_decoded() {
+        let pixels = image.to_rgba(fill)?;
+        return Some(format!(
+            "data:image/png;base64,{}",
+            encode(&png::encode(image.width, image.height, &pixels))
+        ));
+    }
+
+    match image.filters.as_slice() {
+        [filter] if filter.0 == b"DCTDecode" && !image.image_mask => {
+            Some(format!("data:image/jpeg;base64,{}", encode(&image.data)))
+        }
+        _ => None,
+    }
+}
+
+/// A [`Device`] that writes SVG, for a content interpreter that maps user space to the
+/// display space of a [`Viewport`] (see the [module documentation](self)).
+#[derive(Debug)]
+pub struct SvgDevice {
+    width: f32,
+    height: f32,
+    /// The clipping paths.
+    defs: String,
+    body: String,
+    /// The number of groups opened by clipping paths, for each saved state. The last entry
+    /// stands for the current state.
+    groups: Vec<usize>,
+    clip_paths: usize,
+}
+
+impl SvgDevice {
+    /// A device for a page of the given viewport.
+    pub fn new(viewport: Viewport) -> Self {
+        Self {
+            width: viewport.width(),
+            height: viewport.height(),
+            defs: String::new(),
+            body: String::new(),
+            groups: vec![0],
+            clip_paths: 0,
+        }
+    }
+
+    /// The SVG document.
+    pub fn finish(mut self) -> String {
+        while self.groups.len() > 1 {
+            self.restore_state();
+        }
+        self.close_groups();
+
+        let (width, height) = (number(self.width), number(self.height));
+
+        let mut svg = format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" "#,
+                r#"width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#,
+                "\n",
+            ),
+            width = width,
+            height = height,
+        );
+
+        if !self.defs.is_empty() {
+            svg.push_str("<defs>\n");
+            svg.push_str(&self.defs);
+            svg.push_str("</defs>\n");
+        }
+
+        svg.push_str(&self.body);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Close the groups opened by the clipping paths of the current state.
+    fn close_groups(&mut self) {
+        if let Some(groups) = self.groups.last_mut() {
+            for _ in 0..*groups {
+                self.body.push_str("</g>\n");
+            }
+            *groups = 0;
+        }
+    }
+
+    fn push_path(&mut self, path: &Path, attributes: &str, state: &GraphicsState) {
+        // Lone points paint nothing.
+        let painted = path
+            .elements
+            .iter()
+            .any(|element| !matches!(element, PathElement::MoveTo(_)));
+        if !painted {
+            return;
+        }
+
+        self.body.push_str(&format!(
+            "<path {} d=\"{}\" {attributes}/>\n",
+            transform(&state.ctm),
+            path_data(path),
+        ));
+    }
+}
+
+impl Device for SvgDevice {
+    fn save_state(&mut self) {
+        self.groups.push(0);
+    }
+
+    fn restore_state(&mut self) {
+        // Unbalanced restores are ignored by the interpreter.
+        if self.groups.len() > 1 {
+            self.close_groups();
+            self.groups.pop();
+        }
+    }
+
+    fn fill_path(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {
+        self.push_path(path, &fill_attributes(state, rule), state);
+    }
+
+    fn stroke_path(&mut self, path: &Path, state: &GraphicsState) {
+        let attributes = format!(r#"fill="none" {}"#, stroke_attributes(state));
+        self.push_path(path, &attributes, state);
+    }
+
+    fn fill_and_stroke_path(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {
+        let attributes = format!(
+            "{} {}",
+            fill_attributes(state, rule),
+            stroke_attributes(state)
+        );
+        self.push_path(path, &attributes, state);
+    }
+
+    fn clip(&mut self, path: &Path, rule: FillRule, state: &GraphicsState) {
+        let id = format!("clip{}", self.clip_paths);
+        self.clip_paths += 1;
+
+        let rule = match rule {
+            FillRule::NonZero => "nonzero",
+            FillRule::EvenOdd => "evenodd",
+        };
+
+        self.defs.push_str(&format!(
+            "<clipPath id=\"{id}\"><path {} d=\"{}\" clip-rule=\"{rule}\"/></clipPath>\n",
+            transform(&state.ctm),
+            path_data(path),
+        ));
+        self.body
+            .push_str(&format!("<g clip-path=\"url(#{id})\">\n"));
+
+        if let Some(groups) = self.groups.last_mut() {
+            *groups += 1;
+        }
+    }
+
+    fn draw_glyph(&mut self, glyph: &TextGlyph<'_>, state: &GraphicsState) {
+        let (fill, stroke) = match state.text.parameters.rendering_mode {
+            RenderingMode::Fill | RenderingMode::FillAndClip => (true, false),
+            RenderingMode::Stroke | RenderingMode::StrokeAndClip => (false, true),
+            RenderingMode::FillThenStroke | RenderingMode::FillThenStrokeAndClip => (true, true),
+            RenderingMode::Invisible | RenderingMode::AddTextAndClip => return,
+        };
+
+        let Some(text) = glyph.font.text(glyph.code) else {
+            return;
+        };
+        if text.trim().is_empty() {
+            return;
+        }
+
+        // SVG glyphs extend upwards from their origin, towards negative y.
+        let flip = Matrix::from([1.0, 0.0, 0.0, -1.0, 0.0, 0.0]);
+        let [a, b, c, d, e, f] = flip.then(&glyph.matrix).components();
+
+        // The vertical scale of the glyph becomes the font size, which keeps it legible for
+        // renderers that enforce a minimum font size.
+        let size = c.hypot(d);
+        if size == 0.0 {
+            return;
+        }
+        let matrix = Matrix::from([a / size, b / size, c / size, d / size, e, f]);
+
+        let mut attributes = format!(
+            r#"{} font-size="{}" {}"#,
+            transform(&matrix),
+            number(size),
+            font_attributes(glyph.font),
+        );
+
+        if fill {
+            attributes.push(' ');
+            attributes.push_str(&fill_attributes(state, FillRule::NonZero));
+        } else {
+            attributes.push_str(r#" fill="none""#);
+        }
+
+        if stroke {
+            // The element is not expressed in user space: the line width is approximated
+            // in device space.
+            let [a, b, c, d, ..] = state.ctm.components();
+            let width = state.line_width * (a * d - b * c).abs().sqrt();
+            attributes.push_str(&format!(
+                r#" stroke="{}" stroke-width="{}" vector-effect="non-scaling-stroke""#,
+                color(state.stroke.to_rgb()),
+                number(width.max(1.0)),
+            ));
+        }
+
+        self.body.push_str(&format!(
+            "<text {attributes} xml:space=\"preserve\">{}</text>\n",
+            escape(&text)
+        ));
+    }
+
+    fn draw_image(&mut self, image: &Image, state: &GraphicsState) {
+        let Some(uri) = data_uri(image, state.fill.to_rgb()) else {
+            tracing::debug!(
+                "Skipping an image that cannot be embedded, with filters {:?}",
+                image.filters
+            );
+            return;
+        };
+
+        // SVG images extend downwards from their origin, while the first row of a PDF image
+        // lies at the top of the unit square.
+        let flip = Matrix::from([1.0, 0.0, 0.0, -1.0, 0.0, 1.0]);
+        let matrix = flip.then(&state.ctm);
+
+        let mut attributes = transform(&matrix);
+        if state.fill_alpha < 1.0 {
+            attributes.push_str(&format!(r#" opacity="{}""#, number(state.fill_alpha)));
+        }
+
+        self.body.push_str(&format!(
+            "<image {attributes} width=\"1\" height=\"1\" preserveAspectRatio=\"none\" xlink:href=\"{uri}\"/>\n"
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::{
+        content::{interpreter::ContentInterpreter, operators::spanned_operators},
+        extraction::Rectangle,
+        structure::{Resources, RotationAngle},
+    };
+
+    use super::{super::interpreter::ColorSpace, *};
+
+    fn svg(content: &[u8]) -> String {
+        let viewport = Viewport {
+            bbox: Rectangle::from((0.0, 0.0, 200.0, 100.0)),
+            rotation: RotationAngle::Zero,
+        };
+
+        let mut interpreter = ContentInterpreter::new(&(), Resources::default(), viewport.matrix());
+        let mut device = SvgDevice::new(viewport);
+
+        let operators = spanned_operators(content, usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| Ok(spanned.operator));
+        interpreter.run(operators, &mut device).unwrap();
+
+        device.finish()
+    }
+
+    #[rstest]
+    #[case(b"", r#"width="200" height="100" viewBox="0 0 200 100""#)]
+    #[case(
+        b"1 0 0 rg 10 20 30 40 re f",
+        r##"<path transform="matrix(1 0 0 -1 0 100)" d="M10 20 L40 20 L40 60 L10 60 Z" fill="#ff0000"/>"##
+    )]
+    #[case(b"0 0 m 1 1 l f*", r#"fill-rule="evenodd""#)]
+    #[case(
+        b"2 w 1 J 2 j [3 1] 0 d 0 0 1 RG 0 0 m 10 10 l S",
+        r##"fill="none" stroke="#0000ff" stroke-width="2" stroke-linecap="round" stroke-linejoin="bevel" stroke-dasharray="3 1"/>"##
+    )]
+    #[case(b"0 w 0 0 m 10 10 l S", r#"vector-effect="non-scaling-stroke""#)]
+    #[case(
+        b"0.5 g 0 0 m 10 10 l 0 10 l B",
+        r##"fill="#808080" stroke="#000000""##
+    )]
+    #[case(
+        b"q 0 0 10 10 re W n 0 0 5 5 re f Q 0 0 1 1 re f",
+        "<g clip-path=\"url(#clip0)\">\n<path transform=\"matrix(1 0 0 -1 0 100)\" d=\"M0 0 L5 0 L5 5 L0 5 Z\" fill=\"#000000\"/>\n</g>\n<path"
+    )]
+    #[case(
+        b"0 0 10 10 re W* n",
+        r#"<clipPath id="clip0"><path transform="matrix(1 0 0 -1 0 100)" d="M0 0 L10 0 L10 10 L0 10 Z" clip-rule="evenodd"/></clipPath>"#
+    )]
+    #[case(
+        b"BT /F1 12 Tf 1 0 0 1 10 20 Tm (A&) Tj ET",
+        r##"<text transform="matrix(1 0 0 1 10 80)" font-size="12" font-family="sans-serif" fill="#000000" xml:space="preserve">A</text>"##
+    )]
+    #[case(b"BT /F1 12 Tf (&) Tj ET", "&amp;</text>")]
+    #[case(b"BT /F1 12 Tf 1 Tr (A) Tj ET", r##"fill="none" stroke="#000000""##)]
+    fn elements(#[case] content: &[u8], #[case] expected: &str) {
+        let svg = svg(content);
+        assert!(svg.contains(expected), "{svg}");
+    }
+
+    #[rstest]
+    #[case(b"BT /F1 12 Tf 3 Tr (A) Tj ET")]
+    #[case(b"BT /F1 12 Tf ( ) Tj ET")]
+    #[case(b"0 0 m f")]
+    fn nothing_painted(#[case] content: &[u8]) {
+        let svg = svg(content);
+        assert!(!svg.contains("<text") && !svg.contains("<path"), "{svg}");
+    }
+
+    #[test]
+    fn unbalanced_clipping() {
+        let svg = svg(b"q q 0 0 1 1 re W n q 0 0 1 1 re W n");
+        assert_eq!(svg.matches("<g ").count(), svg.matches("</g>").count());
+    }
+
+    #[rstest]
+    #[case("Helvetica", r#"font-family="'Helvetica', sans-serif""#)]
+    #[case(
+        "ABCDEF+TimesNewRoman,BoldItalic",
+        r#"font-family="'TimesNewRoman', serif" font-weight="bold" font-style="italic""#
+    )]
+    #[case(
+        "Courier-Oblique",
+        r#"font-family="'Courier', monospace" font-style="italic""#
+    )]
+    fn fonts(#[case] base_font: &str, #[case] expected: &str) {
+        let input = format!("<</Type/Font/Subtype/Type1/BaseFont/{base_font}>>");
+        let font: Font =
+            crate::follow_refs::Build::build(&mut input.as_bytes().as_ref(), &()).unwrap();
+
+        assert_eq!(font_attributes(&font), expected);
+    }
+
+    #[test]
+    fn images() {
+        let mut image = Image {
+            width: 1,
+            height: 1,
+            bits_per_component: 8,
+            color_space: ColorSpace::DeviceGray,
+            image_mask: false,
+            decode: None,
+            data: vec![0],
+            filters: Vec::new(),
+            soft_mask: None,
+        };
+
+        let uri = data_uri(&image, [0.0; 3]).unwrap();
+        let png = STANDARD
+            .decode(uri.strip_prefix("data:image/png;base64,").unwrap())
+            .unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x01\0\0\0\x01"));
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+
+        image.filters = vec!["DCTDecode".into()];
+        assert_eq!(
+            data_uri(&image, [0.0; 3]).as_deref(),
+            Some("data:image/jpeg;base64,AA==")
+        );
+
+        image.filters = vec!["JPXDecode".into()];
+        assert_eq!(data_uri(&image, [0.0; 3]), None);
+    }
+}
@@ -0,0 +1,41 @@
+//! Helpers for the XML exports: hOCR and ALTO for the text layout, and SVG for pages.
+
+/// Escape the characters that are special to XML. Characters that XML 1.0 does not allow,
+/// such as most control characters, which a broken `ToUnicode` map may produce, are replaced
+/// with U+FFFD.
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1F}' | '\u{FFFE}' | '\u{FFFF}' => {
+                escaped.push(char::REPLACEMENT_CHARACTER)
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("plain text", "plain text")]
+    #[case("<a & 'b'>", "&lt;a &amp; &apos;b&apos;&gt;")]
+    #[case("\"quoted\"", "&quot;quoted&quot;")]
+    #[case("tab\tline\r\n", "tab\tline\r\n")]
+    #[case("\u{1}A\u{0}", "\u{FFFD}A\u{FFFD}")]
+    #[case("\u{FFFF}é", "\u{FFFD}é")]
+    fn escaped(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(escape(text), expected);
+    }
+}
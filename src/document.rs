@@ -1,34 +1,30 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, time::Instant};
 
-use winnow::{
-    error::{ContextError, ErrMode},
-    BStr, ModalResult,
-};
+use winnow::{BStr, ModalResult};
 
+#[cfg(feature = "serde")]
+use crate::serialization::{ObjectGraph, StreamData};
 use crate::{
-    extraction::{extract, Extract, Indirect, Reference, ReferenceId},
-    follow_refs::{Build, Builder, BuilderParser},
-    structure::{Catalog, ObjectStream, Page, RefLocation, StartXRef, Trailer, XRefTrailerBlock},
+    content::layout::{remove_headers_and_footers, TextOptions, TextPage},
+    error::{self, Error, ErrorKind},
+    extraction::{EncodedStream, Extract, Indirect, Map, Object, Reference, ReferenceId},
+    follow_refs::{Build, Builder, Options, Strictness},
+    serialization::{Composition, IncrementalUpdate, Rewrite},
+    structure::{Catalog, History, ObjectStream, Page, PagesIter, RefLocation, XRefTable},
 };
 
 impl Builder for HashMap<ReferenceId, &BStr> {
-    fn build_reference<T>(&self, Reference { id, .. }: Reference<T>) -> ModalResult<T>
+    fn resolve_reference<T>(&self, Reference { id, .. }: Reference<T>) -> ModalResult<Option<T>>
     where
         T: Build,
     {
-        let input = &mut self
-            .get(&id)
-            .copied()
-            .ok_or(ErrMode::Cut(ContextError::new()))?;
-
-        let Indirect {
-            id: reference_id,
-            inner,
-        } = Indirect::parse(input, self.as_parser())?;
+        let Some(&(mut input)) = self.get(&id) else {
+            return Ok(None);
+        };
 
-        debug_assert_eq!(reference_id, id);
+        let Indirect { inner, .. } = Indirect::build(&mut input, self)?;
 
-        Ok(inner)
+        Ok(Some(inner))
     }
 }
 
@@ -37,115 +33,377 @@ pub struct InMemoryBuilder {
     /// The entire input slice
     input: Vec<u8>,
     /// The cross-reference table
-    pub xrefs: HashMap<ReferenceId, RefLocation>,
+    pub xrefs: XRefTable,
+    /// Parsing options
+    pub options: Options,
+    /// The moment at which the [time limit](crate::follow_refs::Limits::timeout) is reached.
+    deadline: Option<Instant>,
 }
 
-impl Builder for InMemoryBuilder {
-    fn build_reference<T>(&self, Reference { id, .. }: Reference<T>) -> ModalResult<T>
+impl InMemoryBuilder {
+    /// The raw bytes of the document.
+    pub(crate) fn input(&self) -> &[u8] {
+        &self.input
+    }
+
+    /// Build the indirect object `id`, whose definition starts at `offset`.
+    fn build_at<T>(&self, id: ReferenceId, offset: usize) -> ModalResult<T>
     where
         T: Build,
     {
-        let &offset = self
-            .xrefs
-            .get(&id)
-            .ok_or(ErrMode::Backtrack(ContextError::new()))?;
+        let input = &mut self
+            .input
+            .get(offset..)
+            .ok_or_else(|| Error::new(ErrorKind::BadXRefEntry).at(offset).backtrack())?
+            .as_ref();
 
-        match offset {
-            RefLocation::Plain(offset) => {
-                let input = &mut self
-                    .input
-                    .get(offset..)
-                    .ok_or(ErrMode::Backtrack(ContextError::new()))?
-                    .as_ref();
+        let Indirect {
+            id: reference_id,
+            inner,
+        } = Indirect::build(input, self).map_err(|err| Error::within::<T>(err, id, offset))?;
 
-                let Indirect {
-                    id: reference_id,
-                    inner,
-                } = Indirect::parse(input, self.as_parser())?;
+        if reference_id != id {
+            tracing::error!(
+                "The cross-reference table points to {reference_id:?} instead of {id:?}"
+            );
+            return Err(Error::new(ErrorKind::BadXRefEntry).at(offset).cut());
+        }
+
+        Ok(inner)
+    }
 
-                debug_assert_eq!(reference_id, id);
+    /// Check whether the definition of the indirect object `id` starts at `offset`.
+    fn header_at(&self, id: ReferenceId, offset: usize) -> bool {
+        let header = format!("{} {} obj", id.object, id.generation);
+        self.input
+            .get(offset..)
+            .is_some_and(|input| input.starts_with(header.as_bytes()))
+    }
+
+    /// Search the input for the definition of the indirect object `id`, picking the occurrence
+    /// that is the closest to the expected `offset`.
+    fn locate(&self, id: ReferenceId, offset: usize) -> Option<usize> {
+        let header = format!("{} {} obj", id.object, id.generation);
+
+        self.input
+            .windows(header.len())
+            .enumerate()
+            .filter(|&(i, window)| {
+                window == header.as_bytes() && (i == 0 || !self.input[i - 1].is_ascii_digit())
+            })
+            .map(|(i, _)| i)
+            .min_by_key(|i| i.abs_diff(offset))
+    }
 
-                Ok(inner)
+    /// Build the indirect object `id`, found at `location`.
+    fn build_from<T>(&self, id: ReferenceId, location: RefLocation) -> ModalResult<T>
+    where
+        T: Build,
+    {
+        match location {
+            RefLocation::Plain(offset) => {
+                // A lenient builder tolerates wrong offsets, e.g. caused by stray bytes
+                // between objects, by looking for the object definition.
+                let offset = match self.options.strictness {
+                    Strictness::Lenient if !self.header_at(id, offset) => {
+                        match self.locate(id, offset) {
+                            Some(found) => {
+                                tracing::warn!(
+                                    "Object {id:?} found at offset {found} instead of {offset}"
+                                );
+                                found
+                            }
+                            None => offset,
+                        }
+                    }
+                    _ => offset,
+                };
+
+                self.build_at(id, offset)
             }
             RefLocation::Compressed {
                 stream_id,
                 // `index` is already contained within the stream.
                 index: _,
             } => {
-                let stream: ObjectStream =
-                    self.build_reference(ReferenceId::first(stream_id).into())?;
-                stream.build_object(&id, self)
+                let stream_id = ReferenceId::first(stream_id);
+
+                // Object streams cannot be stored in object streams, like any other stream.
+                if let Some(RefLocation::Compressed { .. }) = self.xrefs.get(&stream_id) {
+                    return Err(Error::new(ErrorKind::BadXRefEntry).object(id).cut());
+                }
+
+                let stream: ObjectStream = self.build_reference(stream_id.into())?;
+
+                stream.build_object(&id, self).map_err(|err| {
+                    // Report the error at the offset of the object stream, if available.
+                    match self.xrefs.get(&stream_id) {
+                        Some(RefLocation::Plain(offset)) => Error::within::<T>(err, id, offset),
+                        _ => err,
+                    }
+                })
             }
+            RefLocation::Free => unreachable!("free entries are filtered out by the lookup"),
+        }
+    }
+}
+
+impl Builder for InMemoryBuilder {
+    fn resolve_reference<T>(&self, Reference { id, .. }: Reference<T>) -> ModalResult<Option<T>>
+    where
+        T: Build,
+    {
+        self.check_deadline()?;
+
+        // Free entries, missing entries and generation mismatches all resolve to null.
+        let Some(location) = self.xrefs.get(&id) else {
+            return Ok(None);
+        };
+
+        let _resolving = Resolving::enter(id, self.options.max_depth)?;
+        self.build_from(id, location).map(Some)
+    }
+
+    fn options(&self) -> Options {
+        self.options
+    }
+
+    fn check_deadline(&self) -> ModalResult<()> {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() > deadline)
+        {
+            return Err(Error::new(ErrorKind::TimeLimitExceeded).cut());
         }
+
+        Ok(())
+    }
+}
+
+thread_local! {
+    /// The objects that are being resolved on this thread, from the outermost one. Builders
+    /// are shared by reference, hence the stack is kept aside.
+    static RESOLVING: RefCell<Vec<ReferenceId>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks an object as being resolved, until dropped.
+struct Resolving;
+
+impl Resolving {
+    /// Start resolving the object `id`, such that an object that requires itself to be
+    /// built, e.g. an object stream said to be stored within itself, is reported instead of
+    /// overflowing the stack.
+    fn enter(id: ReferenceId, limit: usize) -> ModalResult<Self> {
+        RESOLVING.with_borrow_mut(|resolving| {
+            if resolving.contains(&id) {
+                Err(Error::new(ErrorKind::Cycle).object(id).cut())
+            } else if resolving.len() >= limit {
+                Err(Error::new(ErrorKind::DepthLimitExceeded(limit))
+                    .object(id)
+                    .cut())
+            } else {
+                resolving.push(id);
+                Ok(Self)
+            }
+        })
+    }
+}
+
+impl Drop for Resolving {
+    fn drop(&mut self) {
+        RESOLVING.with_borrow_mut(Vec::pop);
     }
 }
 
 pub struct InMemoryDocument {
     pub catalog: Catalog,
     pub builder: InMemoryBuilder,
+    /// The chain of incremental updates that produced the document.
+    pub history: History,
 }
 
 impl Extract<'_> for InMemoryDocument {
     fn extract(i: &mut &BStr) -> winnow::ModalResult<Self> {
-        let input = *i;
+        Self::load(i, Options::default())
+    }
+}
+
+impl Builder for InMemoryDocument {
+    fn resolve_reference<T>(&self, reference: Reference<T>) -> ModalResult<Option<T>>
+    where
+        T: Build,
+    {
+        self.builder.resolve_reference(reference)
+    }
+
+    fn options(&self) -> Options {
+        self.builder.options
+    }
 
-        let StartXRef(start) = StartXRef::find(i)?;
-        let i = &mut &input[start..];
+    fn check_deadline(&self) -> ModalResult<()> {
+        self.builder.check_deadline()
+    }
+}
 
-        let mut cross_references = Vec::new();
+impl InMemoryDocument {
+    fn load(input: &BStr, options: Options) -> ModalResult<Self> {
+        let deadline = options
+            .limits
+            .timeout
+            .and_then(|timeout| Instant::now().checked_add(timeout));
 
-        let XRefTrailerBlock {
-            trailer:
-                Trailer {
-                    root,
-                    size: _,
-                    id: _,
-                    mut prev,
-                },
-            xrefs,
-        } = extract(i)?;
-        cross_references.extend(xrefs);
-
-        while let Some(p) = prev {
-            let i = &mut &input[p..];
-
-            let XRefTrailerBlock {
-                xrefs,
-                trailer:
-                    Trailer {
-                        size: _,
-                        prev: previous,
-                        root: _,
-                        id: _,
-                    },
-            } = extract(i)?;
-            cross_references.extend(xrefs);
-
-            prev = previous;
-        }
+        let history = History::extract_with(input, options)?;
 
         let builder = InMemoryBuilder {
             input: input.to_vec(),
-            xrefs: cross_references.into_iter().collect(),
+            xrefs: history.cross_references(),
+            options,
+            deadline,
         };
 
-        let catalog = builder.build_reference(root)?;
+        if history.latest().trailer.encrypt.is_some() {
+            return Err(Error::new(ErrorKind::EncryptionRequired).cut());
+        }
 
-        Ok(Self { catalog, builder })
+        let catalog = builder.build_reference(history.latest().trailer.root)?;
+
+        Ok(Self {
+            catalog,
+            builder,
+            history,
+        })
     }
-}
 
-impl Builder for InMemoryDocument {
-    fn build_reference<T>(&self, reference: Reference<T>) -> ModalResult<T>
-    where
-        T: Build,
-    {
-        self.builder.build_reference(reference)
+    /// Load a document from its raw bytes.
+    ///
+    /// This is equivalent to [`extract`](Extract::extract), but reports failures with a
+    /// descriptive [`Error`].
+    pub fn from_bytes(input: &[u8]) -> error::Result<Self> {
+        Self::from_bytes_with(input, Options::default())
+    }
+
+    /// Load a document from its raw bytes, with the supplied [`Options`]. Use
+    /// [`Options::lenient`] to recover from common producer bugs.
+    pub fn from_bytes_with(input: &[u8], options: Options) -> error::Result<Self> {
+        Ok(Self::load(input.as_ref(), options)?)
+    }
+
+    /// Start an [incremental update](IncrementalUpdate) of the document, which leaves the
+    /// original bytes untouched.
+    pub fn update(&self) -> IncrementalUpdate<'_> {
+        IncrementalUpdate::new(self)
+    }
+
+    /// Rewrite the entire document, keeping only the objects that are reachable from the
+    /// trailer (see [`Rewrite`]).
+    pub fn rewrite(&self) -> error::Result<Rewrite> {
+        Rewrite::new(self)
+    }
+
+    /// Split the document into `parts` documents of consecutive pages (see [`Composition`]).
+    pub fn split(&self, parts: usize) -> error::Result<Vec<Rewrite>> {
+        Composition::split(self, parts)
+    }
+
+    /// Read the indirect object `id`, if it exists.
+    ///
+    /// Streams whose filters Livre cannot decode, such as JPEG images, are kept encoded:
+    /// their dictionary keeps its `Filter` entry.
+    pub fn object(&self, id: ReferenceId) -> error::Result<Option<Object>> {
+        match self.resolve_reference::<Object>(id.into()) {
+            Ok(object) => Ok(object),
+            Err(err) => match self.resolve_reference::<EncodedStream<Map<Object>>>(id.into()) {
+                Ok(stream) => Ok(stream.map(|EncodedStream(stream)| Object::Stream(stream))),
+                Err(_) => Err(err.into()),
+            },
+        }
     }
-}
 
-impl InMemoryDocument {
     pub fn pages(&self) -> ModalResult<Vec<Page>> {
         self.catalog.pages.list_pages(self)
     }
+
+    /// The number of pages of the document, read from the page tree.
+    pub fn page_count(&self) -> ModalResult<usize> {
+        self.catalog.pages.page_count(self)
+    }
+
+    /// Build the page at `index` (starting from zero), if it exists, without building the
+    /// pages that come before it.
+    pub fn page(&self, index: usize) -> ModalResult<Option<Page>> {
+        self.catalog.pages.page(index, self)
+    }
+
+    /// Iterate over the pages of the document, building them one at a time.
+    pub fn pages_iter(&self) -> PagesIter<'_, Self> {
+        self.catalog.pages.pages_iter(self)
+    }
+
+    /// The indirect objects of the document, to be exported with [`serde`] (see
+    /// [`ObjectGraph`](crate::serialization::ObjectGraph)).
+    #[cfg(feature = "serde")]
+    pub fn object_graph(&self, streams: StreamData) -> ObjectGraph<'_> {
+        ObjectGraph::new(self, streams)
+    }
+
+    /// The object graph of the document, as pretty-printed JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self, streams: StreamData) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.object_graph(streams))
+    }
+
+    /// The text of every page, in reading order (see [`Page::text_page`]).
+    pub fn text_pages(&self) -> ModalResult<Vec<TextPage>> {
+        self.pages_iter()
+            .map(|page| page?.text_page(self))
+            .collect()
+    }
+
+    /// The plain text of the document, page by page (see [`Page::extract_text`]). Pages are
+    /// separated by a form feed (`\x0c`).
+    pub fn extract_text(&self) -> ModalResult<String> {
+        self.extract_text_with(TextOptions::default())
+    }
+
+    /// The plain text of the document, extracted with the supplied [`TextOptions`].
+    pub fn extract_text_with(&self, options: TextOptions) -> ModalResult<String> {
+        let mut pages = self.text_pages()?;
+
+        if options.drop_headers_and_footers {
+            remove_headers_and_footers(&mut pages);
+        }
+
+        let pages: Vec<String> = pages.iter().map(TextPage::to_string).collect();
+        Ok(pages.join("\x0c"))
+    }
+
+    /// Open the document as it was at a given revision, `0` being the original document
+    /// (see [`History`]). This is useful for forensic purposes, e.g. to diff two revisions.
+    ///
+    /// Fails with [`ErrorKind::RevisionNotFound`] if the revision does not exist.
+    pub fn at_revision(&self, revision: usize) -> ModalResult<Self> {
+        let xrefs = self.history.cross_references_at(revision).ok_or_else(|| {
+            Error::new(ErrorKind::RevisionNotFound {
+                requested: revision,
+                available: self.history.len(),
+            })
+            .cut()
+        })?;
+
+        let builder = InMemoryBuilder {
+            input: self.builder.input.clone(),
+            xrefs,
+            options: self.builder.options,
+            deadline: self.builder.deadline,
+        };
+
+        let root = self.history.revisions()[revision].trailer.root;
+        let catalog = builder.build_reference(root)?;
+
+        Ok(Self {
+            catalog,
+            builder,
+            history: self.history.clone(),
+        })
+    }
 }
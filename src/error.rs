@@ -0,0 +1,245 @@
+//! Error type for document loading.
+//!
+//! Livre's parsers are built on [`winnow`], and return a [`ModalResult`](winnow::ModalResult)
+//! all the way down. Whenever a parser knows *why* it fails, it attaches a livre [`Error`] as
+//! the cause of the underlying [`ContextError`]. As the error bubbles up through indirect
+//! objects, the [`Builder`](crate::follow_refs::Builder) records the chain of object IDs it
+//! was following, as well as the byte offset at which the failure occurred.
+//!
+//! Use [`Error::from`] to recover the livre error from a winnow error at the API boundary.
+
+use std::fmt::Display;
+
+use thiserror::Error as ThisError;
+use winnow::error::{ContextError, ErrMode, FromExternalError};
+
+use crate::extraction::ReferenceId;
+
+/// The different kinds of errors that may arise while loading a document.
+#[derive(ThisError, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    #[error("could not find the `startxref` tag at the end of the file")]
+    MissingStartXRef,
+    #[error("invalid cross-reference section")]
+    BadXRefEntry,
+    #[error("could not resolve reference {0:?}")]
+    UnresolvableReference(ReferenceId),
+    #[error("unsupported filter `{0}`")]
+    UnsupportedFilter(String),
+    #[error("failed to decompress stream: {0}")]
+    Decompression(String),
+    #[error("object does not match the expected type `{expected}`")]
+    TypeMismatch { expected: &'static str },
+    #[error("the document is encrypted, which Livre does not support")]
+    EncryptionRequired,
+    #[error("page {0} does not exist")]
+    PageNotFound(usize),
+    #[error("revision {requested} does not exist, the document has {available} revisions")]
+    RevisionNotFound { requested: usize, available: usize },
+    /// A chain of objects loops back on itself, e.g. a page tree node that is its own ancestor.
+    #[error("the document structure contains a cycle")]
+    Cycle,
+    /// A chain of objects is longer than [`Options::max_depth`](crate::follow_refs::Options).
+    #[error("the document structure is nested deeper than the limit of {0}")]
+    DepthLimitExceeded(usize),
+    /// A decoded stream is larger than [`Limits::max_stream_size`](crate::follow_refs::Limits).
+    #[error("a decoded stream exceeds the limit of {0} bytes")]
+    StreamTooLarge(usize),
+    /// An object nests arrays and dictionaries deeper than
+    /// [`Limits::max_nesting`](crate::follow_refs::Limits).
+    #[error("an object is nested deeper than the limit of {0}")]
+    NestingLimitExceeded(usize),
+    /// The cross-reference sections hold more than
+    /// [`Limits::max_xref_entries`](crate::follow_refs::Limits) entries.
+    #[error("the cross-reference sections exceed the limit of {0} entries")]
+    TooManyXRefEntries(usize),
+    /// The content of a page holds more than
+    /// [`Limits::max_operators`](crate::follow_refs::Limits) operators.
+    #[error("the page content exceeds the limit of {0} operators")]
+    TooManyOperators(usize),
+    /// A rasterized page or a decoded image holds more than
+    /// [`Limits::max_pixels`](crate::follow_refs::Limits) pixels.
+    #[error("an image exceeds the limit of {0} pixels")]
+    TooManyPixels(usize),
+    /// Processing the document took longer than [`Limits::timeout`](crate::follow_refs::Limits).
+    #[error("the time limit for processing the document was exceeded")]
+    TimeLimitExceeded,
+    /// The input could not be parsed, and the parser did not say why.
+    #[error("could not parse the input")]
+    Parse,
+}
+
+/// The error type for document loading.
+///
+/// An `Error` is made of its [`ErrorKind`], the byte offset at which it occurred (if known),
+/// and the chain of indirect objects that were being built, innermost first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    offset: Option<usize>,
+    objects: Vec<ReferenceId>,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            offset: None,
+            objects: Vec::new(),
+        }
+    }
+
+    /// Set the byte offset of the error, if it is not already known.
+    pub fn at(mut self, offset: usize) -> Self {
+        self.offset.get_or_insert(offset);
+        self
+    }
+
+    /// Record the indirect object at which the error occurred.
+    pub(crate) fn object(mut self, id: ReferenceId) -> Self {
+        self.objects.push(id);
+        self
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Byte offset (from the beginning of the file) at which the error occurred, if known.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// The chain of indirect objects that were being built when the error occurred, starting
+    /// with the innermost one.
+    pub fn objects(&self) -> &[ReferenceId] {
+        &self.objects
+    }
+
+    /// Wrap the error into a recoverable winnow error.
+    pub fn backtrack(self) -> ErrMode<ContextError> {
+        ErrMode::Backtrack(self.into_context())
+    }
+
+    /// Wrap the error into an unrecoverable winnow error.
+    pub fn cut(self) -> ErrMode<ContextError> {
+        ErrMode::Cut(self.into_context())
+    }
+
+    /// Attach the error to an empty [`ContextError`], as its cause.
+    ///
+    /// We do not implement `From<Error>` for `ContextError`, since it would break type
+    /// inference for winnow parsers throughout the crate.
+    fn into_context(self) -> ContextError {
+        ContextError::from_external_error(&(), self)
+    }
+
+    /// Record that `err` occurred while building the indirect object `id` as a `T`, starting at
+    /// `offset`. The mode of the error (backtrack or cut) is preserved.
+    ///
+    /// An opaque parsing error is reported as a [type mismatch](ErrorKind::TypeMismatch).
+    pub(crate) fn within<T>(
+        err: ErrMode<ContextError>,
+        id: ReferenceId,
+        offset: usize,
+    ) -> ErrMode<ContextError> {
+        err.map(|e| {
+            let mut error = Self::from_context(&e).unwrap_or_else(|| {
+                Self::new(ErrorKind::TypeMismatch {
+                    expected: std::any::type_name::<T>(),
+                })
+            });
+            error.objects.push(id);
+            error.at(offset).into_context()
+        })
+    }
+
+    /// Recover the livre error attached to a winnow error, if any.
+    pub(crate) fn from_context(err: &ContextError) -> Option<Self> {
+        err.cause()?.downcast_ref::<Self>().cloned()
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)?;
+
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {offset}")?;
+        }
+
+        for id in &self.objects {
+            write!(f, ", while building object {} {}", id.object, id.generation)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl From<ErrMode<ContextError>> for Error {
+    fn from(err: ErrMode<ContextError>) -> Self {
+        match err {
+            ErrMode::Backtrack(e) | ErrMode::Cut(e) => {
+                Self::from_context(&e).unwrap_or_else(|| Self::new(ErrorKind::Parse))
+            }
+            ErrMode::Incomplete(_) => Self::new(ErrorKind::Parse),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain() {
+        let err = Error::new(ErrorKind::Decompression("corrupt deflate stream".into())).cut();
+
+        let err = Error::within::<()>(err, ReferenceId::first(4), 120);
+        let err = Error::within::<()>(err, ReferenceId::first(2), 40);
+
+        assert!(matches!(err, ErrMode::Cut(_)));
+
+        let error = Error::from(err);
+
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::Decompression("corrupt deflate stream".into())
+        );
+        assert_eq!(error.offset(), Some(120));
+        assert_eq!(
+            error.objects(),
+            &[ReferenceId::first(4), ReferenceId::first(2)]
+        );
+        assert_eq!(
+            error.to_string(),
+            "failed to decompress stream: corrupt deflate stream at offset 120, \
+             while building object 4 0, while building object 2 0"
+        );
+    }
+
+    #[test]
+    fn opaque() {
+        let error = Error::from(ErrMode::Backtrack(ContextError::new()));
+        assert_eq!(error.kind(), &ErrorKind::Parse);
+        assert_eq!(error.offset(), None);
+
+        let err = Error::within::<u8>(ErrMode::Backtrack(ContextError::new()), (3, 0).into(), 7);
+        assert!(matches!(err, ErrMode::Backtrack(_)));
+
+        let error = Error::from(err);
+        assert_eq!(error.kind(), &ErrorKind::TypeMismatch { expected: "u8" });
+        assert_eq!(error.offset(), Some(7));
+    }
+}
@@ -54,4 +54,9 @@ pub use special::{
     ReferenceId, Stream, Todo,
 };
 
-pub(crate) use utilities::{take_till_delimiter, Angles, Brackets, Parentheses};
+#[cfg(feature = "serde")]
+pub(crate) use special::serialize_sorted;
+pub(crate) use special::EncodedStream;
+
+pub(crate) use primitives::{malformed_number, whole_number};
+pub(crate) use utilities::{take_till_delimiter, Angles, Brackets, DoubleAngles, Parentheses};
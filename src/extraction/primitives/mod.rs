@@ -14,3 +14,5 @@ mod option;
 mod tuple;
 mod unit;
 mod vec;
+
+pub(crate) use numbers::{malformed_number, whole_number};
@@ -1,109 +1,51 @@
 use std::{
-    num::NonZeroU8,
-    ops::{AddAssign, MulAssign, Neg},
+    num::{NonZeroU8, ParseIntError},
+    str::FromStr,
 };
 
 use winnow::{
     ascii::{digit1, float},
     combinator::{alt, opt, preceded, trace},
+    error::{ContextError, ErrMode},
+    token::take_while,
     BStr, ModalResult, Parser,
 };
 
 use crate::extraction::Extract;
 
-/// Convert a slice of bytes representing decimal integers to a Rust number
+/// Convert a slice of bytes representing a decimal integer, with an optional sign, to a Rust
+/// number. Fails if the number does not fit.
 ///
-/// WARNING: this function expects a slice of digits.
-fn convert_unsigned<T>(digits: &[u8]) -> T
+/// WARNING: this function expects a slice of digits, optionally preceded by a sign.
+fn convert<T>(digits: &[u8]) -> Result<T, T::Err>
 where
-    T: From<u8> + MulAssign<T> + AddAssign<T>,
+    T: FromStr,
 {
-    // Convert each byte to T
-    let mut digits = digits.iter().map(|byte| T::from(byte - b'0'));
-
-    let mut result = digits
-        .next()
-        .expect("digit1 parser returns at least 1 digit");
-
-    for digit in digits {
-        result *= T::from(10);
-        result += digit;
-    }
-
-    result
-}
-
-/// Convert a slice of bytes representing signed decimal integers to a Rust number.
-///
-/// We need a separate function from [`convert_unsigned`], because a signed integer
-/// cannot be converted from a `u8`.
-///
-/// WARNING: this function expects a slice of digits.
-///
-/// NOTE: since `i<bits>::MIN = - (i<bits>::MAX + 1)`, the output of this function
-/// is the negative associated number. It should be converted to a positive integer
-/// if need be.
-fn convert_negative_signed<T>(digits: &[u8]) -> T
-where
-    T: From<i8> + MulAssign<T> + AddAssign<T>,
-{
-    // Convert each byte to T
-    let mut digits = digits
-        .iter()
-        .copied()
-        .map(|byte| -((byte - b'0') as i8))
-        .map(T::from);
-
-    let mut result = digits
-        .next()
-        .expect("digit1 parser returns at least 1 digit");
-
-    for digit in digits {
-        result *= T::from(10);
-        result += digit;
-    }
-
-    result
+    // SAFETY: the slice only contains ASCII characters.
+    let digits = unsafe { std::str::from_utf8_unchecked(digits) };
+    digits.parse()
 }
 
 fn parse_unsigned<T>(input: &mut &BStr) -> ModalResult<T>
 where
-    T: From<u8> + MulAssign<T> + AddAssign<T>,
+    T: FromStr<Err = ParseIntError>,
 {
     trace(
         "livre-unsigned",
-        preceded(opt(b'+'), digit1.map(convert_unsigned)),
-    )
-    .parse_next(input)
-}
-
-fn is_neg(input: &mut &BStr) -> ModalResult<bool> {
-    trace(
-        "livre-is-neg",
-        opt(alt((b'-', b'+'))).map(|s| s == Some(b'-')),
+        preceded(opt(b'+'), digit1).try_map(convert),
     )
     .parse_next(input)
 }
 
 fn parse_signed<T>(input: &mut &BStr) -> ModalResult<T>
 where
-    T: From<i8> + MulAssign<T> + AddAssign<T> + Neg<Output = T>,
+    T: FromStr<Err = ParseIntError>,
 {
-    fn inner<T>(input: &mut &BStr) -> ModalResult<T>
-    where
-        T: From<i8> + MulAssign<T> + AddAssign<T> + Neg<Output = T>,
-    {
-        let neg = is_neg(input)?;
-        let mut num: T = digit1.map(convert_negative_signed).parse_next(input)?;
-
-        if !neg {
-            num = -num;
-        }
-
-        Ok(num)
-    }
-
-    trace("livre-signed", inner).parse_next(input)
+    trace(
+        "livre-signed",
+        (opt(alt((b'-', b'+'))), digit1).take().try_map(convert),
+    )
+    .parse_next(input)
 }
 
 macro_rules! unsigned {
@@ -165,6 +107,72 @@ real! {
     f64
 }
 
+fn is_numeric(byte: u8) -> bool {
+    byte.is_ascii_digit() || b"+-.".contains(&byte)
+}
+
+fn number_token<'de>(input: &mut &'de BStr) -> ModalResult<&'de [u8]> {
+    take_while(1.., is_numeric).parse_next(input)
+}
+
+/// Parse a number, failing if the token does not represent exactly one number.
+fn parse_exact<T>(token: &[u8]) -> ModalResult<T>
+where
+    T: for<'a> Extract<'a>,
+{
+    let mut input = BStr::new(token);
+    let value = T::extract(&mut input)?;
+
+    if input.is_empty() {
+        Ok(value)
+    } else {
+        Err(ErrMode::Backtrack(ContextError::new()))
+    }
+}
+
+/// Parse a number from the entire numeric token, such that the parser does not stop midway
+/// through a malformed number (e.g. `0.0.1`).
+pub(crate) fn whole_number<T>(input: &mut &BStr) -> ModalResult<T>
+where
+    T: for<'a> Extract<'a>,
+{
+    let token = number_token(input)?;
+    parse_exact(token)
+}
+
+/// Parse a malformed number, e.g. `--5` or `0.0.1`, the way most PDF readers do: redundant
+/// signs are collapsed, and everything that follows a second decimal point is ignored.
+pub(crate) fn malformed_number<T>(input: &mut &BStr) -> ModalResult<T>
+where
+    T: for<'a> Extract<'a>,
+{
+    let token = number_token(input)?;
+
+    let split = token
+        .iter()
+        .position(|b| !b"+-".contains(b))
+        .unwrap_or(token.len());
+    let (signs, mut digits) = token.split_at(split);
+
+    if let Some(i) = digits
+        .iter()
+        .enumerate()
+        .filter(|(_, &b)| b == b'.')
+        .nth(1)
+        .map(|(i, _)| i)
+    {
+        digits = &digits[..i];
+    }
+
+    let mut normalized = Vec::with_capacity(digits.len() + 1);
+    if signs.contains(&b'-') {
+        normalized.push(b'-');
+    }
+    normalized.extend_from_slice(digits);
+
+    parse_exact(&normalized)
+}
+
 impl Extract<'_> for NonZeroU8 {
     fn extract(input: &mut &BStr) -> ModalResult<Self> {
         let n = u8::extract.verify(|&r| r != 0).parse_next(input)?;
@@ -182,6 +190,7 @@ mod tests {
     use std::fmt::Debug;
 
     use rstest::rstest;
+    use winnow::BStr;
 
     use crate::extraction::{extract, Extract};
 
@@ -206,4 +215,28 @@ mod tests {
         let res = extract(&mut input.as_ref()).unwrap();
         assert_eq!(expected, res);
     }
+
+    #[rstest]
+    #[case(b"--5", -5i32)]
+    #[case(b"+-5", -5i32)]
+    #[case(b"--5", -5.0f32)]
+    #[case(b"0.0.1", 0.0f32)]
+    #[case(b"1.5.2", 1.5f64)]
+    #[case(b"++42", 42usize)]
+    fn malformed<T>(#[case] input: &[u8], #[case] expected: T)
+    where
+        T: for<'a> Extract<'a> + Debug + PartialEq,
+    {
+        let input = &mut input.as_ref();
+        let res: T = super::malformed_number(input).unwrap();
+        assert_eq!(expected, res);
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn overflow() {
+        assert!(extract::<u8>(&mut BStr::new(b"256")).is_err());
+        assert!(extract::<i8>(&mut BStr::new(b"-129")).is_err());
+        assert!(extract::<usize>(&mut BStr::new(b"99999999999999999999999")).is_err());
+    }
 }
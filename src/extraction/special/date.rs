@@ -1,11 +1,14 @@
+use std::io::{self, Write};
+
 use winnow::{
     combinator::{fail, opt, trace},
     dispatch,
-    token::{any, take},
+    stream::AsChar,
+    token::{any, take, take_while},
     BStr, ModalResult, Parser,
 };
 
-use crate::extraction::Extract;
+use crate::{extraction::Extract, serialization::Serialize};
 
 /// Standard date used in PDF, with a bespoke format.
 ///
@@ -14,6 +17,7 @@ use crate::extraction::Extract;
 /// For the time being, Livre does not try to be clever about that aspect, and represents
 /// every fied (except `year`) with an optional.
 #[derive(Debug, Default, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Date {
     pub year: u16,
     pub month: Option<u8>,
@@ -49,6 +53,14 @@ fn utc_diff(input: &mut &BStr) -> ModalResult<i16> {
     .parse_next(input)
 }
 
+/// Parse exactly two decimal digits. Unlike a bare `parse_to`, this does not accept the sign
+/// of the UTC offset as part of a number.
+fn two_digits(input: &mut &BStr) -> ModalResult<u8> {
+    take_while(2, AsChar::is_dec_digit)
+        .parse_to()
+        .parse_next(input)
+}
+
 fn parse_date_without_offset(input: &mut &BStr) -> ModalResult<Date> {
     let year = take(4usize).parse_to().parse_next(input)?;
 
@@ -57,30 +69,36 @@ fn parse_date_without_offset(input: &mut &BStr) -> ModalResult<Date> {
         ..Default::default()
     };
 
-    let res = opt(take(2usize).parse_to()).parse_next(input)?;
+    let res = opt(two_digits).parse_next(input)?;
     if res.is_none() {
         return Ok(date);
     }
     date.month = res;
 
-    let res = opt(take(2usize).parse_to()).parse_next(input)?;
+    let res = opt(two_digits).parse_next(input)?;
     if res.is_none() {
         return Ok(date);
     }
     date.day = res;
 
-    let res = opt(take(2usize).parse_to()).parse_next(input)?;
+    let res = opt(two_digits).parse_next(input)?;
     if res.is_none() {
         return Ok(date);
     }
     date.hour = res;
 
-    let res = opt(take(2usize).parse_to()).parse_next(input)?;
+    let res = opt(two_digits).parse_next(input)?;
     if res.is_none() {
         return Ok(date);
     }
     date.minute = res;
 
+    let res = opt(two_digits).parse_next(input)?;
+    if res.is_none() {
+        return Ok(date);
+    }
+    date.second = res;
+
     Ok(date)
 }
 
@@ -96,6 +114,34 @@ impl Extract<'_> for Date {
     }
 }
 
+/// Dates are written with the same precision they were read with: every field following the
+/// first missing one is ignored.
+impl Serialize for Date {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write!(writer, "D:{:04}", self.year)?;
+
+        for field in [self.month, self.day, self.hour, self.minute, self.second] {
+            match field {
+                Some(value) => write!(writer, "{value:02}")?,
+                None => break,
+            }
+        }
+
+        match self.offset {
+            Some(0) => writer.write_all(b"Z"),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.unsigned_abs();
+                write!(writer, "{sign}{:02}'{:02}", offset / 60, offset % 60)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
@@ -141,6 +187,18 @@ mod tests {
             offset: Some(0),
         },
     )]
+    #[case(
+        b"D:19981223195207Z",
+        Date{
+            year: 1998,
+            month:Some(12),
+            day: Some(23),
+            hour: Some(19),
+            minute: Some(52),
+            second: Some(7),
+            offset: Some(0),
+        },
+    )]
     #[case(
         b"D:2000",
         Date{
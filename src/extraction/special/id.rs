@@ -1,4 +1,9 @@
-use crate::extraction::{extract, Extract, HexadecimalString};
+use std::io::{self, Write};
+
+use crate::{
+    extraction::{extract, Extract, HexadecimalString},
+    serialization::Serialize,
+};
 
 /// PDF ID.
 ///
@@ -46,6 +51,19 @@ impl Extract<'_> for Id {
     }
 }
 
+impl Serialize for Id {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        [
+            HexadecimalString(self.creation.clone()),
+            HexadecimalString(self.modification.clone()),
+        ]
+        .serialize(writer)
+    }
+}
+
 impl<T> From<[T; 2]> for Id
 where
     T: Into<Vec<u8>>,
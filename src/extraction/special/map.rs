@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
 
 use winnow::{
     ascii::multispace0,
@@ -16,6 +19,7 @@ use crate::{
         Extract, FromRawDict,
     },
     follow_refs::{Build, BuildFromRawDict, Builder},
+    serialization::{DictWriter, Serialize, SerializeDict},
 };
 
 use super::name::Name;
@@ -23,6 +27,19 @@ use super::name::Name;
 /// In PDFs, dictionary keys are [`Name`]s.
 pub type Map<T> = HashMap<Name, T>;
 
+/// Serialize a map with its keys in order, such that the output is stable. Meant for
+/// `#[serde(serialize_with = "...")]` attributes.
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_sorted<V, S>(map: &Map<V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    V: serde::Serialize,
+    S: serde::Serializer,
+{
+    let mut entries: Vec<(&Name, &V)> = map.iter().collect();
+    entries.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+    serializer.collect_map(entries)
+}
+
 impl<'de, T> FromRawDict<'de> for Map<T>
 where
     T: Extract<'de>,
@@ -43,6 +60,47 @@ where
     }
 }
 
+impl<T> BuildFromRawDict for Map<T>
+where
+    T: Build,
+{
+    fn build_from_raw_dict<B>(dict: &mut RawDict<'_>, builder: &B) -> ModalResult<Self>
+    where
+        B: Builder,
+    {
+        let mut map = Map::with_capacity(dict.0.len());
+
+        // Values that cannot be built are dropped, like in the `FromRawDict` implementation.
+        for (key, value) in dict.0.drain() {
+            if let Ok(value) = value.build(builder) {
+                map.insert(key, value);
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+/// Entries are sorted by key, such that the output is deterministic.
+impl<T> SerializeDict for Map<T>
+where
+    T: Serialize,
+{
+    fn serialize_entries<W>(&self, dict: &mut DictWriter<'_, W>) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+
+        for (key, value) in entries {
+            dict.entry(key, value)?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Parse a single key-value pair. Consumes trailing whitespace if there is any.
 fn parse_key_value<'de, T>(input: &mut &'de BStr) -> ModalResult<(Name, T)>
 where
@@ -149,6 +207,11 @@ impl<'de> RawDict<'de> {
         self.0.remove(key)
     }
 
+    /// Consume the dictionary, yielding its entries in no particular order.
+    pub(crate) fn into_entries(self) -> impl Iterator<Item = (Name, RawValue<'de>)> {
+        self.0.into_iter()
+    }
+
     pub fn pop_and_extract<T>(&mut self, key: &Name) -> Option<ModalResult<T>>
     where
         T: Extract<'de>,
@@ -229,6 +292,15 @@ impl BuildFromRawDict for Nil {
     }
 }
 
+impl SerializeDict for Nil {
+    fn serialize_entries<W>(&self, _dict: &mut DictWriter<'_, W>) -> io::Result<()>
+    where
+        W: Write,
+    {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
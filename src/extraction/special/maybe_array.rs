@@ -1,11 +1,14 @@
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+};
 
 use winnow::{
     combinator::{alt, trace},
     BStr, ModalResult, Parser,
 };
 
-use crate::extraction::Extract;
+use crate::{extraction::Extract, serialization::Serialize};
 
 /// `MaybeArray` helps represent in a type-safe way some PDF objects that may be represented
 /// by a single value, or an array of values.
@@ -49,6 +52,22 @@ where
     }
 }
 
+/// A single value is written as is, rather than as a one-element array.
+impl<T> Serialize for MaybeArray<T>
+where
+    T: Serialize,
+{
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self.0.as_slice() {
+            [value] => value.serialize(writer),
+            values => values.serialize(writer),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fmt::Debug;
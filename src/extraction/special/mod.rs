@@ -14,12 +14,15 @@ mod todo;
 pub use comments::{multicomment0, multicomment1, Comment};
 pub use date::Date;
 pub use id::Id;
+#[cfg(feature = "serde")]
+pub(crate) use map::serialize_sorted;
 pub use map::{Map, Nil, RawDict};
 pub use maybe_array::MaybeArray;
 pub use name::Name;
 pub use object::Object;
 pub use rectangle::Rectangle;
 pub use refs::{Indirect, OptRef, Reference, ReferenceId};
+pub(crate) use stream::EncodedStream;
 pub use stream::Stream;
 pub use strings::{HexadecimalString, LiteralString, PDFString};
 pub use todo::Todo;
@@ -1,4 +1,9 @@
-use std::{borrow::Cow, fmt::Debug, ops::Deref};
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    io::{self, Write},
+    ops::Deref,
+};
 
 use winnow::{
     ascii::hex_uint,
@@ -8,7 +13,10 @@ use winnow::{
     BStr, ModalResult, Parser,
 };
 
-use crate::extraction::{utilities::escaped_sequence, Extract};
+use crate::{
+    extraction::{utilities::escaped_sequence, Extract},
+    serialization::Serialize,
+};
 
 /// In the PDF specification, a *Name* represents a dictionary key,
 /// or a (sub-)type specification. It uses an escape scheme using
@@ -36,6 +44,17 @@ impl Debug for Name {
     }
 }
 
+/// Names are written as in PDF syntax, with a leading solidus, e.g. `"/Type"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Name {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(&format_args!("/{}", String::from_utf8_lossy(&self.0)))
+    }
+}
+
 impl<'de> Extract<'de> for Name {
     fn recognize(input: &mut &'de BStr) -> ModalResult<&'de [u8]> {
         trace(
@@ -66,6 +85,40 @@ fn escape_name<'de>(input: &mut &'de BStr) -> Result<Cow<'de, [u8]>, ContextErro
     Ok(Cow::Owned(vec![n]))
 }
 
+impl Name {
+    /// Write `name` as a PDF name object.
+    ///
+    /// Delimiters, white-space, the number sign itself and bytes outside of the printable
+    /// ASCII range are written using the `#xx` escape sequence.
+    pub(crate) fn write<W>(name: &[u8], writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(b"/")?;
+
+        for &b in name {
+            match b {
+                b'#' | b'/' | b'%' | b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' => {
+                    write!(writer, "#{b:02X}")?
+                }
+                0x21..=0x7E => writer.write_all(&[b])?,
+                _ => write!(writer, "#{b:02X}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for Name {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        Self::write(&self.0, writer)
+    }
+}
+
 impl Deref for Name {
     type Target = [u8];
 
@@ -115,4 +168,13 @@ mod tests {
         assert_eq!(name, result.into());
         assert_eq!(format!("{name:?}"), format!("Name({result})"));
     }
+
+    #[rstest]
+    #[case("Name1", b"/Name1")]
+    #[case("Lime Green", b"/Lime#20Green")]
+    #[case("paired()parentheses", b"/paired#28#29parentheses")]
+    #[case("The_Key_of_F#_Minor", b"/The_Key_of_F#23_Minor")]
+    fn serialize(#[case] name: &str, #[case] expected: &[u8]) {
+        assert_eq!(Name::from(name).to_bytes(), expected);
+    }
 }
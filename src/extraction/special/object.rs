@@ -1,16 +1,23 @@
+use std::io::{self, Write};
+
 use winnow::{
     ascii::multispace0,
-    combinator::{alt, fail, peek, preceded},
+    combinator::{alt, delimited, fail, peek, preceded, repeat, trace},
     dispatch,
-    error::ContextError,
+    error::{ContextError, ErrMode},
     token::any,
     BStr, ModalResult, Parser,
 };
 
-use crate::extraction::{extract, Extract};
+use crate::{
+    error::{Error, ErrorKind},
+    extraction::{extract, malformed_number, whole_number, Extract},
+    follow_refs::{recover, Build, Builder, Options},
+    serialization::Serialize,
+};
 
 use super::{
-    map::{Map, RawDict},
+    map::{Map, RawDict, RawValue},
     name::Name,
     refs::Reference,
     stream::Stream,
@@ -45,6 +52,41 @@ pub enum Object {
     Indirect(Reference<Object>),
 }
 
+/// Objects are written as plain JSON values where possible. Following the conventions of
+/// [qpdf](https://qpdf.readthedocs.io/en/stable/json.html), the other types are written as
+/// strings that cannot be mistaken for one another:
+///
+/// - names keep their leading solidus, e.g. `"/Type"`;
+/// - references are written as in PDF syntax, e.g. `"12 0 R"`;
+/// - strings are decoded (see [`PDFString::decode`](super::PDFString::decode)) and prefixed
+///   with `u:`, e.g. `"u:Hello"`.
+///
+/// Dictionary keys are sorted, and streams are summarized by their dictionary, filters and
+/// decoded length.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Object {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Boolean(value) => serializer.serialize_bool(*value),
+            Self::Integer(value) => serializer.serialize_i32(*value),
+            Self::Real(value) => serializer.serialize_f32(*value),
+            Self::String(bytes) => {
+                let text = super::PDFString(bytes.clone()).decode();
+                serializer.collect_str(&format_args!("u:{text}"))
+            }
+            Self::Name(name) => serde::Serialize::serialize(name, serializer),
+            Self::Array(array) => serializer.collect_seq(array),
+            Self::Dictionary(map) => super::map::serialize_sorted(map, serializer),
+            Self::Stream(stream) => stream.serialize_with_data(false, serializer),
+            Self::Indirect(reference) => serde::Serialize::serialize(reference, serializer),
+        }
+    }
+}
+
 impl From<()> for Object {
     fn from(_: ()) -> Self {
         Self::Null
@@ -120,9 +162,12 @@ impl From<Stream<Map<Object>>> for Object {
 
 impl From<Stream<()>> for Object {
     fn from(value: Stream<()>) -> Self {
-        let Stream { content, .. } = value;
+        let Stream {
+            filters, content, ..
+        } = value;
         Self::Stream(Stream {
             structured: Map::new(),
+            filters,
             content,
         })
     }
@@ -160,56 +205,182 @@ impl From<Reference<Object>> for Object {
 
 impl Extract<'_> for Object {
     fn extract(input: &mut &BStr) -> ModalResult<Self> {
-        dispatch! {peek(any);
-            b'n' => b"null".map(|_| Object::Null),
-            b't' | b'f' => bool::extract.map(Object::Boolean),
-            b'0'..=b'9' => alt((Reference::extract.map(Object::from), number)),
-            b'+' | b'-' => number,
-            b'(' => LiteralString::extract.map(Object::from),
-            b'/' => Name::extract.map(Object::from),
-            b'<' => alt((map_or_stream, HexadecimalString::extract.map(Object::from))),
-            b'[' => Vec::<Object>::extract.map(Object::Array),
-            _ => fail,
+        nested(input, 0, Options::default())
+    }
+}
+
+/// Parse an object found at the supplied nesting `depth`. Arrays and dictionaries nested deeper
+/// than the [`max_nesting`](crate::follow_refs::Limits::max_nesting) limit are rejected, since
+/// each level of nesting takes up some stack. Malformed numbers are recovered if the `options`
+/// are lenient.
+fn nested(input: &mut &BStr, depth: usize, options: Options) -> ModalResult<Object> {
+    dispatch! {peek(any);
+        b'n' => b"null".map(|_| Object::Null),
+        b't' | b'f' => bool::extract.map(Object::Boolean),
+        b'0'..=b'9' => alt((Reference::extract.map(Object::from), number(options))),
+        b'+' | b'-' => number(options),
+        b'(' => LiteralString::extract.map(Object::from),
+        b'/' => Name::extract.map(Object::from),
+        b'<' => alt((
+            move |i: &mut &BStr| map_or_stream(i, depth, options),
+            HexadecimalString::extract.map(Object::from),
+        )),
+        b'[' => move |i: &mut &BStr| array(i, depth, options),
+        _ => fail,
+    }
+    .parse_next(input)
+}
+
+/// Enter an array or a dictionary, returning the nesting depth of its elements.
+fn enter(depth: usize, limit: usize) -> ModalResult<usize> {
+    if depth >= limit {
+        Err(Error::new(ErrorKind::NestingLimitExceeded(limit)).cut())
+    } else {
+        Ok(depth + 1)
+    }
+}
+
+fn array(input: &mut &BStr, depth: usize, options: Options) -> ModalResult<Object> {
+    let depth = enter(depth, options.limits.max_nesting)?;
+
+    trace(
+        "livre-object-array",
+        delimited(
+            b'[',
+            repeat(
+                0..,
+                preceded(multispace0, move |i: &mut &BStr| nested(i, depth, options)),
+            ),
+            (multispace0, b']'),
+        ),
+    )
+    .map(Object::Array)
+    .parse_next(input)
+}
+
+/// Like [`Map::from_raw_dict`](crate::extraction::FromRawDict), values that cannot be extracted
+/// are dropped. Reaching the nesting limit is an error, however.
+fn dictionary(input: &mut &BStr, depth: usize, options: Options) -> ModalResult<Object> {
+    let dict = RawDict::extract(input)?;
+    let depth = enter(depth, options.limits.max_nesting)?;
+
+    let mut map = Map::new();
+
+    for (key, RawValue(mut value)) in dict.into_entries() {
+        match nested(&mut value, depth, options) {
+            Ok(value) => {
+                map.insert(key, value);
+            }
+            Err(ErrMode::Cut(err)) if is_nesting_error(&err) => return Err(ErrMode::Cut(err)),
+            Err(_) => {}
+        }
+    }
+
+    Ok(Object::Dictionary(map))
+}
+
+fn is_nesting_error(err: &ContextError) -> bool {
+    Error::from_context(err)
+        .is_some_and(|err| matches!(err.kind(), ErrorKind::NestingLimitExceeded(_)))
+}
+
+impl Serialize for Object {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Self::Null => ().serialize(writer),
+            Self::Boolean(b) => b.serialize(writer),
+            Self::Integer(n) => n.serialize(writer),
+            Self::Real(x) => {
+                x.serialize(writer)?;
+                // Real numbers are told apart from integers by their decimal point.
+                if x.fract() == 0.0 {
+                    writer.write_all(b".0")?;
+                }
+                Ok(())
+            }
+            Self::String(s) => LiteralString::write(s, writer),
+            Self::Name(name) => name.serialize(writer),
+            Self::Array(array) => array.serialize(writer),
+            Self::Dictionary(map) => map.serialize(writer),
+            Self::Stream(stream) => stream.serialize(writer),
+            Self::Indirect(reference) => reference.serialize(writer),
         }
+    }
+}
+
+/// A number, kept as an integer unless it has a decimal point or does not fit.
+struct Number(Object);
+
+impl Extract<'_> for Number {
+    fn extract(input: &mut &BStr) -> ModalResult<Self> {
+        alt((
+            f32::extract
+                .with_taken()
+                .verify_map(|(x, bytes)| bytes.contains(&b'.').then_some(x))
+                .map(Object::from),
+            i32::extract.map(Object::from),
+            // Integers that do not fit are kept as real numbers.
+            f32::extract.map(Object::from),
+        ))
+        .map(Number)
         .parse_next(input)
     }
 }
 
-fn number(input: &mut &BStr) -> ModalResult<Object> {
-    alt((
-        f32::extract
-            .with_taken()
-            .verify_map(|(x, bytes)| bytes.contains(&b'.').then_some(x))
-            .map(Object::from),
-        i32::extract.map(Object::from),
-    ))
-    .parse_next(input)
+/// Parse a number, which a lenient parser recovers if malformed, like typed numbers.
+fn number<'de>(options: Options) -> impl Parser<&'de BStr, Object, ErrMode<ContextError>> {
+    recover(
+        &options,
+        "Recovered a malformed number",
+        whole_number::<Number>,
+        malformed_number::<Number>,
+    )
+    .map(|Number(object)| object)
 }
 
-fn map_or_stream(input: &mut &BStr) -> ModalResult<Object> {
-    // Recognise the `<<...>>` block without fully parsing it, save the start position,
-    // then peek past optional whitespace for the `stream` keyword. This lets us dispatch
-    // to the right parser with a single parse of the dictionary bytes.
-    let start = *input;
+/// Check whether the input starts with a stream, rather than a plain dictionary.
+fn is_stream(input: &BStr) -> bool {
+    // Recognise the `<<...>>` block without fully parsing it, then peek past optional
+    // whitespace for the `stream` keyword. This lets us dispatch to the right parser with a
+    // single parse of the dictionary bytes.
+    //
     // Use `RawDict::recognize` rather than `Map<Object>::recognize`: the latter defaults to
     // calling `extract` and discarding the result (expensive), while `RawDict::recognize`
     // delegates to `DoubleAngles::recognize`, which simply scans for the closing `>>`.
-    RawDict::recognize(input)?;
-
-    let is_stream = preceded(multispace0::<&BStr, ContextError>, peek(b"stream"))
-        .parse_next(input)
-        .is_ok();
-
-    *input = start;
+    let mut input = input;
+    (RawDict::recognize, multispace0, peek(b"stream"))
+        .parse_next(&mut input)
+        .is_ok()
+}
 
-    if is_stream {
+fn map_or_stream(input: &mut &BStr, depth: usize, options: Options) -> ModalResult<Object> {
+    if is_stream(input) {
         extract::<Stream<Map<Object>>>
             .map(Object::Stream)
             .parse_next(input)
     } else {
-        Map::<Object>::extract
-            .map(Object::Dictionary)
-            .parse_next(input)
+        dictionary(input, depth, options)
+    }
+}
+
+/// Objects are mostly extracted, since references are kept as such. Streams are the exception:
+/// their `Length` may be an indirect object, which requires the builder.
+impl Build for Object {
+    fn build<B>(input: &mut &BStr, builder: &B) -> ModalResult<Self>
+    where
+        B: Builder,
+    {
+        trace("livre-object", move |i: &mut &BStr| {
+            if i.starts_with(b"<<") && is_stream(i) {
+                builder.build(i).map(Object::Stream)
+            } else {
+                nested(i, 0, builder.options())
+            }
+        })
+        .parse_next(input)
     }
 }
 
@@ -260,6 +431,7 @@ mod tests {
         "},
         Stream {
             content: b"0".into(),
+            filters: vec![],
             structured: (),
         }
     )]
@@ -271,6 +443,7 @@ mod tests {
         "},
         Stream::<Map<Object>> {
             content: b"0".into(),
+            filters: vec![],
             structured: vec![
                 (Name::from("Test"), Object::from("test"))
             ].into_iter().collect(),
@@ -282,4 +455,47 @@ mod tests {
 
         assert_eq!(expected, result);
     }
+
+    #[rstest]
+    #[case(b"--5", None, Some(Object::Integer(-5)))]
+    #[case(b"0.5.1", None, Some(Object::Real(0.5)))]
+    #[case(
+        b"[1 0.0.1]",
+        None,
+        Some(Object::Array(vec![Object::Integer(1), Object::Real(0.0)]))
+    )]
+    #[case(
+        b"<</A --5>>",
+        Some(Object::Dictionary(Map::new())),
+        Some(vec![("A", Object::Integer(-5))].into_iter().collect())
+    )]
+    fn malformed_numbers(
+        #[case] input: &[u8],
+        #[case] strict: Option<Object>,
+        #[case] lenient: Option<Object>,
+    ) {
+        let result: Option<Object> = Options::default().build(&mut input.as_ref()).ok();
+        assert_eq!(result, strict);
+
+        let result: Option<Object> = Options::lenient().build(&mut input.as_ref()).ok();
+        assert_eq!(result, lenient);
+    }
+
+    #[test]
+    fn malformed_numbers_in_map() {
+        let input: &[u8] = b"<</A --5 /B 1>>";
+
+        let map: Map<Object> = Options::default().build(&mut input.as_ref()).unwrap();
+        assert_eq!(map, [(Name::from("B"), Object::Integer(1))].into());
+
+        let map: Map<Object> = Options::lenient().build(&mut input.as_ref()).unwrap();
+        assert_eq!(
+            map,
+            [
+                (Name::from("A"), Object::Integer(-5)),
+                (Name::from("B"), Object::Integer(1))
+            ]
+            .into()
+        );
+    }
 }
@@ -1,8 +1,14 @@
+use std::io::{self, Write};
+
 use winnow::{combinator::trace, BStr, ModalResult, Parser};
 
-use crate::extraction::{extract, Extract};
+use crate::{
+    extraction::{extract, Extract},
+    serialization::Serialize,
+};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Rectangle {
     pub xmin: f32,
     pub ymin: f32,
@@ -36,6 +42,15 @@ impl Extract<'_> for Rectangle {
     }
 }
 
+impl Serialize for Rectangle {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        [self.xmin, self.ymin, self.xmax, self.ymax].serialize(writer)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
@@ -1,9 +1,14 @@
+use std::io::{self, Write};
+
 use winnow::{
     combinator::{separated_pair, trace},
     BStr, ModalResult, Parser,
 };
 
-use crate::extraction::{extract, Extract};
+use crate::{
+    extraction::{extract, Extract},
+    serialization::Serialize,
+};
 
 /// An ID that uniquely identifies an object and its version.
 ///
@@ -14,6 +19,7 @@ use crate::extraction::{extract, Extract};
 /// In the future we *might* want to look at the document's history,
 /// hence the [`ReferenceId`] keeps the generation number.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ReferenceId {
     pub object: usize,
     pub generation: u16,
@@ -42,6 +48,15 @@ impl Extract<'_> for ReferenceId {
     }
 }
 
+impl Serialize for ReferenceId {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write!(writer, "{} {}", self.object, self.generation)
+    }
+}
+
 impl From<(usize, u16)> for ReferenceId {
     fn from((object, generation): (usize, u16)) -> Self {
         Self::new(object, generation)
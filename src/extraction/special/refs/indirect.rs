@@ -1,13 +1,16 @@
+use std::io::{self, Write};
+
 use winnow::{
-    ascii::multispace1,
-    combinator::{delimited, trace},
+    ascii::{multispace0, multispace1},
+    combinator::{delimited, opt, trace},
     error::{ContextError, ErrMode},
     BStr, ModalResult, Parser,
 };
 
 use crate::{
     extraction::{extract, Extract},
-    follow_refs::{Build, Builder, BuilderParser},
+    follow_refs::{recover, Build, Builder, BuilderParser},
+    serialization::Serialize,
 };
 
 use super::ReferenceId;
@@ -63,11 +66,42 @@ impl<T> Build for Indirect<T>
 where
     T: Build,
 {
+    /// Contrary to [`parse`](Self::parse), a lenient builder tolerates a missing `endobj` tag.
     fn build<B>(input: &mut &BStr, builder: &B) -> ModalResult<Self>
     where
         B: Builder,
     {
-        Self::parse(input, builder.as_parser())
+        let endobj = recover(
+            builder,
+            "Tolerated a missing or malformed `endobj` tag",
+            (multispace1, b"endobj").void(),
+            opt((multispace0, b"endobj")).void(),
+        );
+
+        trace(
+            "livre-indirect",
+            (
+                ReferenceId::extract,
+                delimited((b" obj", multispace1), builder.as_parser(), endobj),
+            )
+                .map(Self::from),
+        )
+        .parse_next(input)
+    }
+}
+
+impl<T> Serialize for Indirect<T>
+where
+    T: Serialize,
+{
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.id.serialize(writer)?;
+        writer.write_all(b" obj\n")?;
+        self.inner.serialize(writer)?;
+        writer.write_all(b"\nendobj")
     }
 }
 
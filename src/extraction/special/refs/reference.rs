@@ -1,4 +1,7 @@
-use std::marker::PhantomData;
+use std::{
+    io::{self, Write},
+    marker::PhantomData,
+};
 
 use winnow::{
     combinator::{alt, terminated, trace},
@@ -9,6 +12,7 @@ use super::ReferenceId;
 use crate::{
     extraction::{extract, Extract},
     follow_refs::{Build, Builder, BuilderParser},
+    serialization::Serialize,
 };
 
 /// A PDF reference to an *indirect object*.
@@ -55,6 +59,28 @@ impl<T> Extract<'_> for Reference<T> {
     }
 }
 
+impl<T> Serialize for Reference<T> {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.id.serialize(writer)?;
+        writer.write_all(b" R")
+    }
+}
+
+/// References are written as in PDF syntax, e.g. `"12 0 R"`.
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Reference<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let ReferenceId { object, generation } = self.id;
+        serializer.collect_str(&format_args!("{object} {generation} R"))
+    }
+}
+
 impl<T> From<ReferenceId> for Reference<T> {
     fn from(id: ReferenceId) -> Self {
         Self {
@@ -76,6 +102,7 @@ impl<T> From<(usize, u16)> for Reference<T> {
 /// Many properties in the PDF specification are optionally represented through an indirect object,
 /// making this type extremely valuable.
 #[derive(Debug, Clone, PartialEq, Eq, Copy, Extract)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(untagged))]
 pub enum OptRef<T> {
     Ref(Reference<T>),
     Direct(T),
@@ -109,6 +136,21 @@ impl<T> Build for Reference<T> {
     }
 }
 
+impl<T> Serialize for OptRef<T>
+where
+    T: Serialize,
+{
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Self::Ref(reference) => reference.serialize(writer),
+            Self::Direct(value) => value.serialize(writer),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fmt::Debug;
@@ -1,7 +1,10 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    io::{self, Write},
+};
 
 use winnow::{
-    ascii::{line_ending, multispace0},
+    ascii::{line_ending, multispace0, space0},
     combinator::{delimited, trace},
     error::{ContextError, ErrMode},
     token::take,
@@ -9,9 +12,11 @@ use winnow::{
 };
 
 use crate::{
+    error::Error,
     extraction::{extract, Extract, FromRawDict},
     filtering::{Filter, Filtering},
-    follow_refs::{Build, BuildFromRawDict, Builder, BuilderParser},
+    follow_refs::{recover, Build, BuildFromRawDict, Builder, BuilderParser},
+    serialization::{DictWriter, Serialize, SerializeDict},
 };
 
 use super::{MaybeArray, Nil, RawDict};
@@ -23,28 +28,105 @@ use super::{MaybeArray, Nil, RawDict};
 ///
 /// Since `StreamConfig` is needed to extract the content of a stream, Livre implements [`Parser`]
 /// for it.
-#[derive(Debug, PartialEq, Eq, FromRawDict, BuildFromRawDict)]
+#[derive(Debug, PartialEq, Eq, FromRawDict)]
 pub struct StreamConfig {
-    length: usize,
+    /// The number of bytes of the encoded content. Although required by the specification,
+    /// a lenient builder can do without.
+    length: Option<usize>,
     #[livre(from = MaybeArray<Filter>, default)]
     filter: Vec<Filter>,
 }
 
-impl Parser<&BStr, Vec<u8>, ErrMode<ContextError>> for StreamConfig {
-    fn parse_next(&mut self, input: &mut &BStr) -> ModalResult<Vec<u8>> {
-        let content = trace(
-            "livre-stream-content",
+/// `StreamConfig` is built manually, since the `Length` key may be an indirect object: producers
+/// often write the stream content before they know its length.
+impl BuildFromRawDict for StreamConfig {
+    fn build_from_raw_dict<B>(dict: &mut RawDict<'_>, builder: &B) -> ModalResult<Self>
+    where
+        B: Builder,
+    {
+        // A dangling reference resolves to `None`, just like a missing key.
+        let length = dict
+            .pop_and_build::<Option<usize>, _>(&"Length".into(), builder)?
+            .flatten();
+
+        let filter = dict
+            .pop_and_build::<MaybeArray<Filter>, _>(&"Filter".into(), builder)?
+            .map(Vec::from)
+            .unwrap_or_default();
+
+        Ok(Self { length, filter })
+    }
+}
+
+impl StreamConfig {
+    /// Read and decode the stream content, trusting the `Length` key.
+    ///
+    /// If the builder is [lenient](crate::follow_refs::Strictness::Lenient), a missing or wrong
+    /// `Length` is recovered from by searching for the `endstream` keyword instead.
+    fn read<'de, B>(&self, input: &mut &'de BStr, builder: &B) -> ModalResult<Vec<u8>>
+    where
+        B: Builder,
+    {
+        let length = self.length;
+
+        let strict = move |i: &mut &'de BStr| {
+            let length = length.ok_or(ErrMode::Backtrack(ContextError::new()))?;
+
             delimited(
                 (multispace0, b"stream", line_ending),
-                take(self.length),
+                take(length),
                 (multispace0, b"endstream"),
+            )
+            .parse_next(i)
+        };
+
+        let content = trace(
+            "livre-stream-content",
+            recover(
+                builder,
+                "Searched for `endstream`: the stream `Length` is missing or wrong",
+                strict,
+                search_endstream,
             ),
         )
         .parse_next(input)?;
 
-        self.filter
-            .decode(content)
-            .map_err(|_| ErrMode::Cut(ContextError::new()))
+        builder.check_deadline()?;
+
+        let limit = builder.options().limits.max_stream_size;
+        self.filter.decode(content, limit).map_err(Error::cut)
+    }
+}
+
+/// Recover the content of a stream whose length is unknown, by looking for the `endstream`
+/// keyword. Also tolerates spaces between the `stream` keyword and the end-of-line marker.
+fn search_endstream<'de>(input: &mut &'de BStr) -> ModalResult<&'de [u8]> {
+    const TAG: &[u8] = b"endstream";
+
+    (multispace0, b"stream", space0, line_ending).parse_next(input)?;
+
+    let end = input
+        .windows(TAG.len())
+        .position(|w| w == TAG)
+        .ok_or(ErrMode::Backtrack(ContextError::new()))?;
+
+    let content = take(end).parse_next(input)?;
+    take(TAG.len()).parse_next(input)?;
+
+    // The end-of-line marker that precedes `endstream` is not part of the content.
+    let content = content
+        .strip_suffix(b"\r\n")
+        .or_else(|| content.strip_suffix(b"\n"))
+        .or_else(|| content.strip_suffix(b"\r"))
+        .unwrap_or(content);
+
+    Ok(content)
+}
+
+impl Parser<&BStr, Vec<u8>, ErrMode<ContextError>> for StreamConfig {
+    fn parse_next(&mut self, input: &mut &BStr) -> ModalResult<Vec<u8>> {
+        // Without a builder, the parser is strict.
+        self.read(input, &())
     }
 }
 
@@ -75,13 +157,77 @@ struct StreamDict<T> {
 /// extracted stream only contains what you need, that is:
 ///
 /// - the structured data, if any
+/// - the filters that were used to encode the content, such that it can be written back
 /// - the actual, decoded content
 #[derive(PartialEq, Clone)]
 pub struct Stream<T> {
     pub structured: T,
+    pub filters: Vec<Filter>,
     pub content: Vec<u8>,
 }
 
+#[cfg(feature = "serde")]
+impl Stream<super::map::Map<super::object::Object>> {
+    /// Serialize the stream as its dictionary, its filters and the length of its decoded
+    /// content, along with the content itself, encoded in base64, if `data` is set.
+    ///
+    /// Streams that were kept encoded are flagged with `encoded`, and their length and
+    /// content are the encoded ones.
+    pub(crate) fn serialize_with_data<S>(
+        &self,
+        data: bool,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use base64::Engine;
+        use serde::ser::SerializeMap;
+
+        use super::object::Object;
+
+        struct Dict<'a>(&'a super::map::Map<super::object::Object>);
+
+        impl serde::Serialize for Dict<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                super::map::serialize_sorted(self.0, serializer)
+            }
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("dict", &Dict(&self.structured))?;
+        match self.structured.get(&"Filter".into()) {
+            // A stream that Livre could not decode, whose filters are only listed in its
+            // dictionary.
+            Some(filter) if self.filters.is_empty() => {
+                let names: Vec<String> = match filter {
+                    Object::Name(name) => vec![name.clone().into()],
+                    Object::Array(array) => array
+                        .iter()
+                        .filter_map(|filter| match filter {
+                            Object::Name(name) => Some(name.clone().into()),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                map.serialize_entry("filters", &names)?;
+                map.serialize_entry("encoded", &true)?;
+            }
+            _ => map.serialize_entry("filters", &self.filters)?,
+        }
+        map.serialize_entry("length", &self.content.len())?;
+        if data {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(&self.content);
+            map.serialize_entry("data", &encoded)?;
+        }
+        map.end()
+    }
+}
+
 impl<T> Debug for Stream<T>
 where
     T: Debug,
@@ -91,6 +237,7 @@ where
 
         f.debug_struct("Stream")
             .field("structured", &self.structured)
+            .field("filters", &self.filters)
             .field("content", &utfish)
             .finish()
     }
@@ -103,15 +250,13 @@ where
     fn extract(input: &mut &'de BStr) -> ModalResult<Self> {
         trace("livre-stream", move |i: &mut &'de BStr| {
             let mut dict: RawDict = extract(i)?;
-            let StreamDict {
-                mut config,
-                structured,
-            } = StreamDict::from_raw_dict(&mut dict)?;
+            let StreamDict { config, structured } = StreamDict::from_raw_dict(&mut dict)?;
 
-            let content = config.parse_next(i)?;
+            let content = config.read(i, &())?;
 
             Ok(Self {
                 structured,
+                filters: config.filter,
                 content,
             })
         })
@@ -129,15 +274,14 @@ where
     {
         trace("livre-stream", move |i: &mut &BStr| {
             let mut dict: RawDict = extract(i)?;
-            let StreamDict {
-                mut config,
-                structured,
-            } = StreamDict::build_from_raw_dict(&mut dict, builder)?;
+            let StreamDict { config, structured } =
+                StreamDict::build_from_raw_dict(&mut dict, builder)?;
 
-            let content = config.parse_next(i)?;
+            let content = config.read(i, builder)?;
 
             Ok(Self {
                 structured,
+                filters: config.filter,
                 content,
             })
         })
@@ -149,11 +293,13 @@ impl Extract<'_> for Stream<()> {
     fn extract(input: &mut &'_ BStr) -> ModalResult<Self> {
         let Stream {
             structured: Nil,
+            filters,
             content,
         } = extract(input)?;
 
         Ok(Self {
             structured: (),
+            filters,
             content,
         })
     }
@@ -166,16 +312,108 @@ impl Build for Stream<()> {
     {
         let Stream {
             structured: Nil,
+            filters,
             content,
         } = builder.as_parser().parse_next(input)?;
 
         Ok(Self {
             structured: (),
+            filters,
             content,
         })
     }
 }
 
+impl<T> Serialize for Stream<T>
+where
+    T: SerializeDict,
+{
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write_stream(&self.structured, &self.filters, &self.content, writer)
+    }
+}
+
+impl Serialize for Stream<()> {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write_stream(&Nil, &self.filters, &self.content, writer)
+    }
+}
+
+/// Write a stream. The content is encoded through the `filters`, and the `Length` and `Filter`
+/// keys are written alongside the structured data.
+fn write_stream<S, W>(
+    structured: &S,
+    filters: &[Filter],
+    content: &[u8],
+    writer: &mut W,
+) -> io::Result<()>
+where
+    S: SerializeDict,
+    W: Write,
+{
+    let encoded = filters.to_vec().encode(content)?;
+
+    let mut dict = DictWriter::new(writer)?;
+    structured.serialize_entries(&mut dict)?;
+    dict.entry(b"Length", &encoded.len())?;
+    if !filters.is_empty() {
+        dict.entry(b"Filter", &MaybeArray(filters.to_vec()))?;
+    }
+    dict.finish()?;
+
+    writer.write_all(b"\nstream\n")?;
+    writer.write_all(&encoded)?;
+    writer.write_all(b"\nendstream")
+}
+
+/// A stream whose content is kept encoded. Its `Filter` and `DecodeParms` entries remain
+/// part of the structured dictionary, such that it can be written back verbatim, or decoded
+/// by other means, e.g. a JPEG image.
+///
+/// Used as a fallback for the streams whose filters Livre does not support.
+pub(crate) struct EncodedStream<T>(pub(crate) Stream<T>);
+
+impl<T> Build for EncodedStream<T>
+where
+    T: BuildFromRawDict,
+{
+    fn build<B>(input: &mut &BStr, builder: &B) -> ModalResult<Self>
+    where
+        B: Builder,
+    {
+        trace("livre-encoded-stream", move |i: &mut &BStr| {
+            let mut dict: RawDict = extract(i)?;
+
+            let length = dict
+                .pop_and_build::<Option<usize>, _>(&"Length".into(), builder)?
+                .flatten()
+                .ok_or(ErrMode::Backtrack(ContextError::new()))?;
+
+            let structured = T::build_from_raw_dict(&mut dict, builder)?;
+
+            let content = delimited(
+                (multispace0, b"stream", line_ending),
+                take(length),
+                (multispace0, b"endstream"),
+            )
+            .parse_next(i)?;
+
+            Ok(Self(Stream {
+                structured,
+                filters: vec![],
+                content: content.to_vec(),
+            }))
+        })
+        .parse_next(input)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -184,14 +422,14 @@ mod tests {
     use indoc::indoc;
     use rstest::rstest;
 
-    use crate::extraction::extract;
+    use crate::{extraction::extract, follow_refs::Options};
 
     use super::*;
 
     #[rstest]
-    #[case(b"<</Length 2/SomeOtherKey/Test>>", StreamConfig{length: 2, filter: vec![]})]
-    #[case(b"<</Length 42>>", StreamConfig{length: 42, filter: vec![]})]
-    #[case(b"<<  /SomeRandomKey (some text...)/Length 42>>", StreamConfig{length: 42, filter: vec![]})]
+    #[case(b"<</Length 2/SomeOtherKey/Test>>", StreamConfig{length: Some(2), filter: vec![]})]
+    #[case(b"<</Length 42>>", StreamConfig{length: Some(42), filter: vec![]})]
+    #[case(b"<<  /SomeRandomKey (some text...)/Length 42>>", StreamConfig{length: Some(42), filter: vec![]})]
     fn stream_config(#[case] input: &[u8], #[case] expected: StreamConfig) {
         let result = extract(&mut input.as_ref()).unwrap();
         assert_eq!(expected, result);
@@ -252,9 +490,34 @@ mod tests {
         let Stream {
             content,
             structured,
+            ..
         } = extract(&mut input.as_ref()).unwrap();
 
         assert_eq!(expected_stream, content);
         assert_eq!(expected_structured, structured);
     }
+
+    #[rstest]
+    #[case(b"<</Length 10>>stream\n0123456789\nendstream", Some(b"0123456789".as_slice()))]
+    #[case(b"<</Length 4>>stream\n0123456789\nendstream", None)]
+    #[case(b"<</Length 42>>stream\n0123456789\nendstream", None)]
+    #[case(b"<</Length 3 0 R>>stream\n0123456789\nendstream", None)]
+    #[case(b"<<>>stream\n0123456789\nendstream", None)]
+    #[case(b"<</Length 10>>stream \r\n0123456789endstream", None)]
+    fn strict(#[case] input: &[u8], #[case] expected: Option<&[u8]>) {
+        let result: Option<Stream<Nil>> = ().build(&mut input.as_ref()).ok();
+        assert_eq!(expected, result.as_ref().map(|s| s.content.as_slice()));
+    }
+
+    #[rstest]
+    #[case(b"<</Length 10>>stream\n0123456789\nendstream")]
+    #[case(b"<</Length 4>>stream\n0123456789\nendstream")]
+    #[case(b"<</Length 42>>stream\n0123456789\r\nendstream")]
+    #[case(b"<<>>stream\n0123456789\nendstream")]
+    #[case(b"<</Length 10>>stream \r\n0123456789endstream")]
+    fn lenient(#[case] input: &[u8]) {
+        let Stream { content, .. }: Stream<Nil> =
+            Options::lenient().build(&mut input.as_ref()).unwrap();
+        assert_eq!(content, b"0123456789");
+    }
 }
@@ -1,4 +1,7 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    io::{self, Write},
+};
 
 use winnow::{
     ascii::hex_uint,
@@ -9,7 +12,7 @@ use winnow::{
     BStr, ModalResult, Parser,
 };
 
-use crate::extraction::Extract;
+use crate::{extraction::Extract, serialization::Serialize};
 
 /// A PDF Hexadecimal String.
 ///
@@ -44,12 +47,27 @@ impl Extract<'_> for HexadecimalString {
     fn extract(input: &mut &BStr) -> ModalResult<Self> {
         trace(
             "livre-hexadecimal-string",
-            delimited(b'<', repeat(1.., parse_hexadecimal_bigram), b'>').map(Self),
+            delimited(b'<', repeat(0.., parse_hexadecimal_bigram), b'>').map(Self),
         )
         .parse_next(input)
     }
 }
 
+impl Serialize for HexadecimalString {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(b"<")?;
+
+        for b in &self.0 {
+            write!(writer, "{b:02X}")?;
+        }
+
+        writer.write_all(b">")
+    }
+}
+
 impl<T> From<T> for HexadecimalString
 where
     T: Into<Vec<u8>>,
@@ -96,6 +114,7 @@ mod tests {
     #[rstest]
     #[case(b"<901FA3>", &[144, 31, 163])]
     #[case(b"<901FA>", &[144, 31, 160])]
+    #[case(b"<>", &[])]
     fn hex_string(#[case] input: &[u8], #[case] expected: &[u8]) {
         let HexadecimalString(bytes) = extract(&mut input.as_ref()).unwrap();
         assert_eq!(bytes, expected);
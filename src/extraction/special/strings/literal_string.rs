@@ -1,4 +1,8 @@
-use std::{borrow::Cow, fmt::Debug};
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    io::{self, Write},
+};
 
 use winnow::{
     combinator::{fail, peek, trace},
@@ -8,10 +12,13 @@ use winnow::{
     BStr, ModalResult, Parser,
 };
 
-use crate::extraction::{
-    extract,
-    utilities::{escaped_sequence, Parentheses},
-    Extract,
+use crate::{
+    extraction::{
+        extract,
+        utilities::{escaped_sequence, Parentheses},
+        Extract,
+    },
+    serialization::Serialize,
 };
 
 /// Struct that represent a PDF "literal string", ie one represented within
@@ -91,12 +98,50 @@ impl Extract<'_> for LiteralString {
     }
 }
 
+impl LiteralString {
+    /// Write `bytes` as a PDF literal string.
+    ///
+    /// Parentheses and backslashes are always escaped, which lets us ignore balancing.
+    /// Non-printable characters use the dedicated escape sequences where they exist, and the
+    /// octal notation otherwise.
+    pub(crate) fn write<W>(bytes: &[u8], writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(b"(")?;
+
+        for &b in bytes {
+            match b {
+                b'(' | b')' | b'\\' => writer.write_all(&[b'\\', b])?,
+                b'\n' => writer.write_all(b"\\n")?,
+                b'\r' => writer.write_all(b"\\r")?,
+                b'\t' => writer.write_all(b"\\t")?,
+                0x08 => writer.write_all(b"\\b")?,
+                0x0C => writer.write_all(b"\\f")?,
+                0x20..=0x7E => writer.write_all(&[b])?,
+                _ => write!(writer, "\\{b:03o}")?,
+            }
+        }
+
+        writer.write_all(b")")
+    }
+}
+
+impl Serialize for LiteralString {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        Self::write(&self.0, writer)
+    }
+}
+
 static EMPTY: &[u8] = b"";
 static NEWLINE: &[u8] = b"\n";
 static RETURN: &[u8] = b"\r";
 static TAB: &[u8] = b"\t";
-static B: &[u8] = &[33, 161];
-static F: &[u8] = &[35, 43];
+static BACKSPACE: &[u8] = b"\x08";
+static FORM_FEED: &[u8] = b"\x0c";
 static LEFT_PAR: &[u8] = b"(";
 static RIGHT_PAR: &[u8] = b")";
 static BACKSLASH: &[u8] = b"\\";
@@ -107,9 +152,9 @@ fn escape_string<'de>(input: &mut &'de BStr) -> Result<Cow<'de, [u8]>, ContextEr
         b'\n' => any.value(Cow::Borrowed(EMPTY)),
         b'n' => any.value(Cow::Borrowed(NEWLINE)),
         b'r' => any.value(Cow::Borrowed(RETURN)),
-        b'\t' => any.value(Cow::Borrowed(TAB)),
-        b'b' => any.value(Cow::Borrowed(B)),
-        b'f' => any.value(Cow::Borrowed(F)),
+        b't' => any.value(Cow::Borrowed(TAB)),
+        b'b' => any.value(Cow::Borrowed(BACKSPACE)),
+        b'f' => any.value(Cow::Borrowed(FORM_FEED)),
         b'(' => any.value(Cow::Borrowed(LEFT_PAR)),
         b')' => any.value(Cow::Borrowed(RIGHT_PAR)),
         b'\\' => any.value(Cow::Borrowed(BACKSLASH)),
@@ -132,13 +177,10 @@ fn parse_octal(input: &mut &BStr) -> Result<u8, ContextError> {
         let s = unsafe { std::str::from_utf8_unchecked(num) };
 
         // NOTE: three octal digits may produce an overflow. For instance,
-        // `777` is *not* a valid u8 number. In practice, the PDF specs state
-        // that "high-order overflow shall be ignored".
-        // Besides, a PDF that displays this kind of failure case is probably
-        // not worth parsing...
-        let n =
-            u8::from_str_radix(s, 8).expect("by construction, valid number. should not overflow.");
-        Ok(n)
+        // `777` is *not* a valid u8 number. The PDF specs state that
+        // "high-order overflow shall be ignored", hence the truncation.
+        let n = u16::from_str_radix(s, 8).expect("by construction, at most three octal digits");
+        Ok(n as u8)
     })
     .parse_next(input)
 }
@@ -168,6 +210,7 @@ mod tests {
     #[case(b"(te\\\\st)", b"te\\st")]
     #[case(b"(te\\\nst)", b"test")]
     #[case(b"(!3\\(.!530)", b"!3(.!530")]
+    #[case(b"(\\t\\b\\f)", b"\t\x08\x0c")]
     fn literal_string(#[case] input: &[u8], #[case] expected: &[u8]) {
         let LiteralString(inner) = extract(&mut input.as_ref()).unwrap();
         assert_eq!(expected, &inner);
@@ -180,4 +223,13 @@ mod tests {
         let result = input.into();
         assert_eq!(format!("{result:?}"), expected);
     }
+
+    #[rstest]
+    #[case(b"abcd", b"(abcd)")]
+    #[case(b"f(x)", b"(f\\(x\\))")]
+    #[case(b"a\\b", b"(a\\\\b)")]
+    #[case(b"\n\x00\xe9", b"(\\n\\000\\351)")]
+    fn serialize(#[case] input: &[u8], #[case] expected: &[u8]) {
+        assert_eq!(LiteralString(input.to_vec()).to_bytes(), expected);
+    }
 }
@@ -14,14 +14,17 @@
 mod hex_string;
 mod literal_string;
 
-use std::fmt::{Debug, Display};
+use std::{
+    fmt::{Debug, Display},
+    io::{self, Write},
+};
 
 pub use hex_string::HexadecimalString;
 pub use literal_string::LiteralString;
 use winnow::combinator::{alt, trace};
 use winnow::Parser;
 
-use crate::extraction::Extract;
+use crate::{extraction::Extract, serialization::Serialize};
 
 #[derive(Clone, PartialEq)]
 pub struct PDFString(pub Vec<u8>);
@@ -109,3 +112,13 @@ impl Extract<'_> for PDFString {
         .parse_next(input)
     }
 }
+
+/// PDF strings are written as literal strings, which are more compact for text.
+impl Serialize for PDFString {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        LiteralString::write(&self.0, writer)
+    }
+}
@@ -45,10 +45,8 @@ impl<'a> Parser<&'a BStr, &'a [u8], ContextError> for WithinBalancedParser {
         // (here, `u8`), Winnow makes this quite easy
         self.opening.parse_next(input)?;
 
-        // A `u8` counter would probably be amply sufficient already...
-        // It seems unlikely to go beyond a few level of nesting, let alone 256.
-        // Let us remain on the side of caution for now.
-        // TODO: evaluate the need for a `u16` here.
+        // Legitimate input hardly goes beyond a few levels of nesting. Deeper input is
+        // rejected rather than overflowing the counter.
         let mut counter: u16 = 1;
         let mut skip = false;
 
@@ -61,7 +59,10 @@ impl<'a> Parser<&'a BStr, &'a [u8], ContextError> for WithinBalancedParser {
             } else if byte == self.closing {
                 counter -= 1;
             } else if byte == self.opening {
-                counter += 1;
+                let Some(incremented) = counter.checked_add(1) else {
+                    return fail(input);
+                };
+                counter = incremented;
             } else {
                 continue;
             }
@@ -1,18 +1,16 @@
 //! Filters for PDF stream objects.
 
 use enum_dispatch::enum_dispatch;
-use std::io::Read;
-use winnow::{
-    combinator::fail,
-    error::{ContextError, ErrMode, StrContext},
-    BStr, ModalResult, Parser,
-};
+use std::io::{self, Read, Write};
+use winnow::{BStr, ModalResult};
 
-use flate2::read::ZlibDecoder;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
 
 use crate::{
+    error::{Error, ErrorKind, Result},
     extraction::{extract, Extract, Name},
     follow_refs::{Build, Builder},
+    serialization::Serialize,
 };
 
 /// Main filter objects, that represents any kind of PDF filter.
@@ -38,7 +36,11 @@ pub enum Filter {
 /// (if any), the encryption scheme, etc.
 #[enum_dispatch]
 pub trait Filtering {
-    fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, ()>;
+    /// Decode the bytes, failing with [`ErrorKind::StreamTooLarge`] if the output would exceed
+    /// `limit` bytes.
+    fn decode(&self, bytes: &[u8], limit: usize) -> Result<Vec<u8>>;
+    /// The inverse of [`decode`](Filtering::decode), used when writing streams.
+    fn encode(&self, bytes: &[u8]) -> io::Result<Vec<u8>>;
 }
 
 impl Extract<'_> for Filter {
@@ -47,11 +49,23 @@ impl Extract<'_> for Filter {
 
         match value.as_slice() {
             b"FlateDecode" => Ok(Self::FlateDecode(FlateDecode)),
-            b"ASCII85Decode" | b"ASCIIHexDecode" | b"LZWDecode" | b"RunLengthDecode"
-            | b"CCITTFaxDecode" | b"JBIG2Decode" | b"DCTDecode" | b"JPXDecode" | b"Crypt" => fail
-                .context(StrContext::Label("unsupported filter"))
-                .parse_next(input),
-            _ => Err(ErrMode::Backtrack(ContextError::new())),
+            name => {
+                let name = String::from_utf8_lossy(name).into_owned();
+                Err(Error::new(ErrorKind::UnsupportedFilter(name)).cut())
+            }
+        }
+    }
+}
+
+/// Filters are written as their name, e.g. `"FlateDecode"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Filter {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::FlateDecode(_) => serializer.serialize_str("FlateDecode"),
         }
     }
 }
@@ -65,26 +79,61 @@ impl Build for Filter {
     }
 }
 
+impl Serialize for Filter {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let name: &[u8] = match self {
+            Self::FlateDecode(_) => b"FlateDecode",
+        };
+        Name::write(name, writer)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FlateDecode;
 
 impl Filtering for FlateDecode {
-    fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, ()> {
-        let mut d = ZlibDecoder::new(bytes);
+    fn decode(&self, bytes: &[u8], limit: usize) -> Result<Vec<u8>> {
+        // Reading one byte past the limit tells a stream that fits exactly from a larger one,
+        // without inflating the rest of a decompression bomb.
+        let mut d = ZlibDecoder::new(bytes).take((limit as u64).saturating_add(1));
         let mut buf = Vec::new();
-        d.read_to_end(&mut buf).map_err(|_| ())?;
+        d.read_to_end(&mut buf)
+            .map_err(|e| Error::new(ErrorKind::Decompression(e.to_string())))?;
+
+        if buf.len() > limit {
+            return Err(Error::new(ErrorKind::StreamTooLarge(limit)));
+        }
+
         Ok(buf)
     }
+
+    fn encode(&self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        let mut e = ZlibEncoder::new(Vec::new(), Compression::default());
+        e.write_all(bytes)?;
+        e.finish()
+    }
 }
 
 impl<T> Filtering for Vec<T>
 where
     T: Filtering,
 {
-    fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, ()> {
+    fn decode(&self, bytes: &[u8], limit: usize) -> Result<Vec<u8>> {
         let mut result = bytes.to_vec();
         for decoder in self {
-            result = decoder.decode(&result)?;
+            result = decoder.decode(&result, limit)?;
+        }
+        Ok(result)
+    }
+
+    /// Filters are applied in reverse order when encoding.
+    fn encode(&self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        let mut result = bytes.to_vec();
+        for encoder in self.iter().rev() {
+            result = encoder.encode(&result)?;
         }
         Ok(result)
     }
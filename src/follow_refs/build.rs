@@ -9,10 +9,11 @@ use winnow::{
 };
 
 use crate::extraction::{
-    extract, HexadecimalString, Id, LiteralString, MaybeArray, Name, Object, Rectangle,
+    extract, malformed_number, whole_number, HexadecimalString, Id, LiteralString, MaybeArray,
+    Name, OptRef, Rectangle,
 };
 
-use super::{Builder, BuilderParser, Built};
+use super::{recover, Builder, BuilderParser, Built};
 
 /// Generalisation on the [`Extract`](crate::extraction::Extract) trait, which allows the
 /// extraction logic to follow references.
@@ -48,15 +49,39 @@ macro_rules! impl_build_for_primitive {
     };
 }
 
-impl_build_for_primitive!(
+/// Numbers can be recovered by a [lenient](super::Strictness::Lenient) builder if malformed.
+macro_rules! impl_build_for_number {
+    ($($t:ty)+) => {
+        $(
+            impl Build for $t {
+                fn build<B>(input: &mut &BStr, builder: &B) -> ModalResult<Self>
+                where
+                    B: Builder,
+                {
+                    recover(
+                        builder,
+                        "Recovered a malformed number",
+                        whole_number,
+                        malformed_number,
+                    )
+                    .parse_next(input)
+                }
+            }
+        )+
+    };
+}
+
+impl_build_for_number!(
   i8 i16 i32 i64 i128 isize
   u8 u16 u32 u64 u128 usize
   f32 f64
+);
+
+impl_build_for_primitive!(
   bool
   LiteralString HexadecimalString
   Id
   Name
-  Object
   Rectangle
 );
 
@@ -68,10 +93,22 @@ where
     where
         B: Builder,
     {
-        alt((
-            builder.as_parser().map(|Built(value)| Some(value)),
-            b"null".map(|_| None),
-        ))
+        trace(
+            "livre-option",
+            alt((
+                // Trying `null` first lets errors from the reference resolution bubble up.
+                b"null".map(|_| None),
+                move |i: &mut &BStr| {
+                    let optref: OptRef<T> = builder.as_parser().parse_next(i)?;
+
+                    match optref {
+                        OptRef::Direct(value) => Ok(Some(value)),
+                        // A reference to an undefined object is a reference to the null object.
+                        OptRef::Ref(reference) => builder.resolve_reference(reference),
+                    }
+                },
+            )),
+        )
         .parse_next(input)
     }
 }
@@ -3,9 +3,12 @@ use winnow::{
     BStr, ModalResult, Parser,
 };
 
-use crate::extraction::Reference;
+use crate::{
+    error::{Error, ErrorKind},
+    extraction::Reference,
+};
 
-use super::Build;
+use super::{Build, Options};
 
 /// Trait that can follow references.
 ///
@@ -22,35 +25,59 @@ pub trait Builder: Sized {
         T::build(input, self)
     }
 
+    /// Follow a reference and extract the indirect object it points to, if any.
+    ///
+    /// From the specification:
+    ///
+    /// > An indirect reference to an undefined object shall not be considered an error by a PDF
+    /// > processor; it shall be treated as a reference to the null object.
+    ///
+    /// Hence, this method returns `Ok(None)` if the reference points to a free or missing
+    /// object, or to an object with another generation number. Errors are reserved to
+    /// objects that are defined, but cannot be parsed.
+    fn resolve_reference<T>(&self, reference: Reference<T>) -> ModalResult<Option<T>>
+    where
+        T: Build;
+
     /// Follow a reference and extract it directly.
     ///
     /// This method checks that the reference is known to the builder, and returns a parsing error
     /// if that is not the case. It includes the mechanism to extract a *indirect object*.
     ///
-    /// This method is usually the one that is used in practice.
+    /// This method is usually the one that is used in practice, for required objects.
+    /// Use [`resolve_reference`](Builder::resolve_reference) when a dangling reference should
+    /// be treated as null.
     fn build_reference<T>(&self, reference: Reference<T>) -> ModalResult<T>
     where
-        T: Build;
-    //{
-    //    let input = &mut self
-    //        .follow_reference(id)
-    //        .ok_or(ErrMode::Cut(ContextError::new()))?;
-    //
-    //    let Indirect {
-    //        id: reference_id,
-    //        inner,
-    //    } = Indirect::parse(input, self.as_parser())?;
-    //
-    //    debug_assert_eq!(reference_id, id);
-    //
-    //    Ok(inner)
-    //}
+        T: Build,
+    {
+        let id = reference.id;
+        self.resolve_reference(reference)?
+            .ok_or_else(|| Error::new(ErrorKind::UnresolvableReference(id)).backtrack())
+    }
+
+    /// Configuration of the builder, which parsers may use to adapt their behaviour, e.g.
+    /// to recover from malformed input (see [`Strictness`](super::Strictness)).
+    ///
+    /// Defaults to a strict builder.
+    fn options(&self) -> Options {
+        Options::default()
+    }
+
+    /// Fail with [`ErrorKind::TimeLimitExceeded`] once the
+    /// [time limit](super::Limits::timeout) is reached, such that long-running work can
+    /// be interrupted.
+    ///
+    /// Defaults to a builder without a time limit.
+    fn check_deadline(&self) -> ModalResult<()> {
+        Ok(())
+    }
 }
 
 /// The unit type is a context-less builder, making `().as_parser` somewhat equivalent to
 /// `extact`: it will simply error if there is any reference to instantiate.
 impl Builder for () {
-    fn build_reference<T>(&self, _: Reference<T>) -> ModalResult<T>
+    fn resolve_reference<T>(&self, _: Reference<T>) -> ModalResult<Option<T>>
     where
         T: Build,
     {
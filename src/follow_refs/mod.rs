@@ -54,9 +54,12 @@
 mod build;
 mod builder;
 mod from_raw_dict;
+mod options;
 mod primitive;
 
 pub use build::Build;
 pub use builder::{Builder, BuilderParser, LivreBuilder};
 pub use from_raw_dict::BuildFromRawDict;
+pub(crate) use options::recover;
+pub use options::{Limits, Options, Strictness};
 pub use primitive::Built;
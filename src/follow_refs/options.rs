@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use winnow::{
+    error::{ContextError, ErrMode},
+    stream::Stream,
+    BStr, ModalResult, Parser,
+};
+
+use crate::extraction::Reference;
+
+use super::{Build, Builder};
+
+/// How closely the parser should follow the specification.
+///
+/// Real-world PDF files are often malformed: producers may write a wrong `Length` for a stream,
+/// forget an `endobj` tag, or write numbers such as `--5`. A strict parser rejects such files,
+/// while a lenient parser tries to recover and logs a [`tracing`] warning for each fix it makes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Strictness {
+    /// Reject any deviation from the specification.
+    #[default]
+    Strict,
+    /// Recover from common producer bugs.
+    Lenient,
+}
+
+impl Strictness {
+    pub fn is_lenient(&self) -> bool {
+        matches!(self, Self::Lenient)
+    }
+}
+
+/// Configuration of a [`Builder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    pub strictness: Strictness,
+    /// Maximum number of links to follow in a chain of objects: nesting of the page tree,
+    /// `Extends` chain of object streams, `Prev` chain of cross-reference sections, and
+    /// references that are resolved while building another object.
+    ///
+    /// Malicious or corrupted files may nest objects deep enough to exhaust the stack.
+    pub max_depth: usize,
+    pub limits: Limits,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            strictness: Strictness::default(),
+            max_depth: 256,
+            limits: Limits::default(),
+        }
+    }
+}
+
+/// Bounds on the resources spent processing a document, for untrusted input.
+///
+/// Hitting a limit produces a dedicated [`ErrorKind`](crate::error::ErrorKind). The defaults
+/// are generous enough for legitimate documents, and only stop hostile ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum size of a decoded stream, in bytes. Guards against decompression bombs.
+    pub max_stream_size: usize,
+    /// Maximum nesting of arrays and dictionaries within a single object.
+    pub max_nesting: usize,
+    /// Maximum number of entries across the cross-reference sections of a document.
+    pub max_xref_entries: usize,
+    /// Maximum number of operators in the content of a page, counting those of the forms it
+    /// draws when the page is interpreted.
+    pub max_operators: usize,
+    /// Maximum number of pixels of a rasterized page or of a decoded image, which bounds
+    /// the memory spent by the `render` feature.
+    pub max_pixels: usize,
+    /// Maximum time spent on a document, counting from the moment it starts loading.
+    ///
+    /// Checked when resolving indirect objects, before decoding a stream and before each
+    /// content operator, which bounds text extraction and rendering as well. A single
+    /// stream is decoded in full, within [`max_stream_size`](Self::max_stream_size), and
+    /// the layout of the text of a page is not interrupted.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_stream_size: 256 << 20,
+            max_nesting: 128,
+            // The implementation limit suggested by the specification for indirect objects.
+            max_xref_entries: 8_388_607,
+            max_operators: 10_000_000,
+            // A 1 GB RGBA buffer.
+            max_pixels: 1 << 28,
+            timeout: None,
+        }
+    }
+}
+
+impl Options {
+    /// Options for a lenient builder.
+    pub fn lenient() -> Self {
+        Self {
+            strictness: Strictness::Lenient,
+            ..Self::default()
+        }
+    }
+
+    /// Set the maximum depth of object chains.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set the resource limits.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// Like the unit type, `Options` is a context-less builder: it cannot follow references, but
+/// lets you parse objects with a given configuration.
+impl Builder for Options {
+    fn resolve_reference<T>(&self, reference: Reference<T>) -> ModalResult<Option<T>>
+    where
+        T: Build,
+    {
+        ().resolve_reference(reference)
+    }
+
+    fn options(&self) -> Options {
+        *self
+    }
+}
+
+/// Run the strict `parser`. If it fails and the `builder` is lenient, rewind the input and try
+/// the `recovery` parser instead, logging the supplied `fix` as a warning on success.
+///
+/// The error from the strict parser is returned if the recovery fails as well.
+pub(crate) fn recover<'de, B, O, P, R>(
+    builder: &B,
+    fix: &'static str,
+    mut parser: P,
+    mut recovery: R,
+) -> impl Parser<&'de BStr, O, ErrMode<ContextError>>
+where
+    B: Builder,
+    P: Parser<&'de BStr, O, ErrMode<ContextError>>,
+    R: Parser<&'de BStr, O, ErrMode<ContextError>>,
+{
+    let lenient = builder.options().strictness.is_lenient();
+
+    move |input: &mut &'de BStr| {
+        let checkpoint = input.checkpoint();
+
+        match parser.parse_next(input) {
+            Err(err) if lenient => {
+                input.reset(&checkpoint);
+                let output = recovery.parse_next(input).map_err(|_| err)?;
+                tracing::warn!("{fix}");
+                Ok(output)
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(b"42", Some(42.0), Some(42.0))]
+    #[case(b"--5", None, Some(-5.0))]
+    #[case(b"0.5.1", None, Some(0.5))]
+    fn numbers(#[case] input: &[u8], #[case] strict: Option<f32>, #[case] lenient: Option<f32>) {
+        let result: Option<f32> = Options::default().build(&mut input.as_ref()).ok();
+        assert_eq!(result, strict);
+
+        let result: Option<f32> = Options::lenient().build(&mut input.as_ref()).ok();
+        assert_eq!(result, lenient);
+    }
+}
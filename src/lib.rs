@@ -2,11 +2,14 @@
 //! the French word for book) aims to provide a set of type-safe tools to read PDF content.
 
 pub mod content;
+pub mod error;
 pub mod extraction;
+pub mod filtering;
 pub mod follow_refs;
+pub mod serialization;
 pub mod structure;
 
 mod document;
-mod filtering;
 
 pub use document::InMemoryDocument;
+pub use error::{Error, ErrorKind};
@@ -0,0 +1,332 @@
+//! Page-level manipulation: making a new document out of pages taken from other documents.
+//!
+//! A [`Composition`] collects a selection of pages, possibly from several source documents,
+//! in any order. Each page is copied along with every object it references, the latter being
+//! deduplicated per source document. The result is written through a [`Rewrite`], hence
+//! merging, splitting, reordering and rotating pages all come down to a composition.
+//!
+//! The page tree of the output is flat: inheritable page attributes are copied into each page,
+//! since the original ancestors are not. Document-level structures that are not reachable from
+//! the pages (outlines, named destinations, the information dictionary...) are dropped.
+
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::{
+    error::{self, Error, ErrorKind},
+    extraction::{Map, Name, Object, Reference, ReferenceId},
+    structure::{RotationAngle, Trailer},
+    InMemoryDocument,
+};
+
+use super::rewrite::{version, Body, Renumbering, Rewrite};
+
+/// Page attributes that may be inherited from an ancestor in the page tree.
+const INHERITABLE: [&str; 4] = ["Resources", "MediaBox", "CropBox", "Rotate"];
+
+/// Object numbers reserved for the catalog and the root of the page tree, respectively.
+const CATALOG: usize = 1;
+const PAGES: usize = 2;
+
+/// Identifies an original object: the index of its source, its identifier within the source,
+/// and the copy number. Only pages that are selected more than once have several copies.
+type Key = (usize, ReferenceId, usize);
+
+/// A page of a source document.
+#[derive(Debug, Clone)]
+struct SourcePage {
+    id: ReferenceId,
+    /// Entries inherited from the ancestors of the page.
+    inherited: Map<Object>,
+}
+
+struct Source<'a> {
+    document: &'a InMemoryDocument,
+    pages: Vec<SourcePage>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Selection {
+    source: usize,
+    page: usize,
+    rotate: Option<RotationAngle>,
+}
+
+/// A new document made of a selection of pages, taken from one or more source documents.
+#[derive(Default)]
+pub struct Composition<'a> {
+    sources: Vec<Source<'a>>,
+    selections: Vec<Selection>,
+}
+
+impl<'a> Composition<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of pages of the composition.
+    pub fn len(&self) -> usize {
+        self.selections.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selections.is_empty()
+    }
+
+    /// The index of the `document` among the sources, adding it if need be.
+    fn source(&mut self, document: &'a InMemoryDocument) -> error::Result<usize> {
+        if let Some(index) = self
+            .sources
+            .iter()
+            .position(|source| std::ptr::eq(source.document, document))
+        {
+            return Ok(index);
+        }
+
+        self.sources.push(Source {
+            document,
+            pages: list_pages(document)?,
+        });
+
+        Ok(self.sources.len() - 1)
+    }
+
+    /// Append the page at `index` (starting from zero) of the supplied document.
+    pub fn add_page(&mut self, document: &'a InMemoryDocument, index: usize) -> error::Result<()> {
+        self.add_pages(document, [index])
+    }
+
+    /// Append the pages of the supplied document, in the order of the `indices`. A page may
+    /// be selected more than once, in which case it is copied.
+    pub fn add_pages<I>(&mut self, document: &'a InMemoryDocument, indices: I) -> error::Result<()>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let source = self.source(document)?;
+        let count = self.sources[source].pages.len();
+
+        for page in indices {
+            if page >= count {
+                return Err(Error::new(ErrorKind::PageNotFound(page)));
+            }
+
+            self.selections.push(Selection {
+                source,
+                page,
+                rotate: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Append every page of the supplied document, e.g. to merge documents.
+    pub fn add_document(&mut self, document: &'a InMemoryDocument) -> error::Result<()> {
+        let source = self.source(document)?;
+        let count = self.sources[source].pages.len();
+        self.add_pages(document, 0..count)
+    }
+
+    /// Set the rotation of the page at `index` within the composition, overriding the
+    /// original `Rotate` attribute.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the composition has no page at `index`.
+    pub fn set_rotation(&mut self, index: usize, rotate: RotationAngle) {
+        self.selections[index].rotate = Some(rotate);
+    }
+
+    /// Copy the selected pages and every object they reference, and prepare the new document
+    /// for writing.
+    pub fn finish(&self) -> error::Result<Rewrite> {
+        let mut renumbering = Renumbering::<Key>::starting_at(PAGES + 1);
+
+        // Selected pages are numbered first, such that they are copied even if some other
+        // object references them.
+        let mut selected = HashMap::new();
+        let mut copies = HashMap::new();
+
+        let kids: Vec<Object> = self
+            .selections
+            .iter()
+            .map(|selection| {
+                let id = self.sources[selection.source].pages[selection.page].id;
+                let copy = copies.entry((selection.source, id)).or_insert(0);
+                let key = (selection.source, id, *copy);
+                *copy += 1;
+
+                selected.insert(key, selection);
+                Object::from(Reference::from(renumbering.renumber(key)))
+            })
+            .collect();
+
+        let catalog: Object = [
+            ("Type", Object::Name("Catalog".into())),
+            ("Pages", Reference::from(ReferenceId::first(PAGES)).into()),
+        ]
+        .into_iter()
+        .collect();
+
+        let pages: Object = [
+            ("Type", Object::Name("Pages".into())),
+            ("Count", Object::Integer(kids.len() as i32)),
+            ("Kids", Object::Array(kids)),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut objects = vec![catalog, pages];
+
+        while let Some(key) = renumbering.queue.pop_front() {
+            let (source, id, _) = key;
+            let Source { document, pages } = &self.sources[source];
+
+            let mut object = document.object(id)?.unwrap_or(Object::Null);
+
+            match (selected.get(&key), &mut object) {
+                (Some(selection), Object::Dictionary(page)) => {
+                    // The original parent is not copied.
+                    page.remove(&"Parent".into());
+
+                    for (name, value) in &pages[selection.page].inherited {
+                        page.entry(name.clone()).or_insert_with(|| value.clone());
+                    }
+
+                    if let Some(rotate) = selection.rotate {
+                        let degrees = Object::Integer(rotate.degrees().into());
+                        page.insert("Rotate".into(), degrees);
+                    }
+                }
+                // Other pages and page tree nodes are dropped, along with the references
+                // to them (e.g. from link annotations).
+                (None, Object::Dictionary(dict)) if is_page_tree(dict) => object = Object::Null,
+                _ => {}
+            }
+
+            renumbering.rewrite(&mut object, |id| (source, id, 0));
+
+            if let (Some(_), Object::Dictionary(page)) = (selected.get(&key), &mut object) {
+                let parent = Reference::from(ReferenceId::first(PAGES));
+                page.insert("Parent".into(), parent.into());
+            }
+
+            objects.push(object);
+        }
+
+        let version = self
+            .sources
+            .iter()
+            .map(|source| version(source.document.builder.input()))
+            .max()
+            .unwrap_or_else(|| "1.7".to_string());
+
+        let trailer = Trailer {
+            size: objects.len() + 1,
+            prev: None,
+            root: ReferenceId::first(CATALOG).into(),
+            xref_stm: None,
+            encrypt: None,
+            info: None,
+            id: None,
+        };
+
+        let objects = objects.into_iter().map(Body::Object).collect();
+
+        Ok(Rewrite::from_parts(version, objects, trailer))
+    }
+
+    /// Split the document into `parts` documents of consecutive pages, whose page counts
+    /// differ by one at most. There are fewer parts if the document has fewer pages.
+    pub fn split(document: &'a InMemoryDocument, parts: usize) -> error::Result<Vec<Rewrite>> {
+        // The page tree is walked once, and each part only gets its own slice of the pages.
+        let pages = list_pages(document)?;
+        let count = pages.len();
+        let parts = parts.min(count);
+
+        let mut start = 0;
+
+        (0..parts)
+            .map(|part| {
+                // The first parts get an extra page, if the division is not exact.
+                let len = count / parts + usize::from(part < count % parts);
+
+                let composition = Self {
+                    sources: vec![Source {
+                        document,
+                        pages: pages[start..start + len].to_vec(),
+                    }],
+                    selections: (0..len)
+                        .map(|page| Selection {
+                            source: 0,
+                            page,
+                            rotate: None,
+                        })
+                        .collect(),
+                };
+                start += len;
+
+                composition.finish()
+            })
+            .collect()
+    }
+}
+
+/// Whether the dictionary is a page or an intermediate node of the page tree.
+fn is_page_tree(dict: &Map<Object>) -> bool {
+    matches!(
+        dict.get(&"Type".into()),
+        Some(Object::Name(Name(name))) if name == b"Page" || name == b"Pages"
+    )
+}
+
+/// List the pages of the document, along with the attributes they inherit.
+///
+/// The page tree is walked by [`PagesIter`](crate::structure::PagesIter), but inherited
+/// attributes are read from the untyped nodes, such that no entry is lost in the copy.
+fn list_pages(document: &InMemoryDocument) -> error::Result<Vec<SourcePage>> {
+    let root = document.history.latest().trailer.root;
+
+    let catalog = document.object(root.id)?;
+    let Some(Object::Dictionary(catalog)) = catalog else {
+        return Err(Error::new(ErrorKind::UnresolvableReference(root.id)));
+    };
+    let root = match catalog.get(&"Pages".into()) {
+        Some(Object::Indirect(node)) => Some(node.id),
+        _ => None,
+    };
+
+    // Inheritable attributes of the intermediate nodes, which are shared by their pages.
+    let mut nodes: HashMap<ReferenceId, Map<Object>> = HashMap::new();
+    let mut pages = Vec::new();
+    let mut iter = document.pages_iter();
+
+    while let Some(result) = iter.next_with_ids() {
+        let (_, id, ancestors) = result?;
+        let mut inherited = Map::new();
+
+        // Nearer ancestors take precedence.
+        for node in root.into_iter().chain(ancestors) {
+            let attributes = match nodes.entry(node) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(inheritable(document, node)?),
+            };
+            inherited.extend(attributes.clone());
+        }
+
+        pages.push(SourcePage { id, inherited });
+    }
+
+    Ok(pages)
+}
+
+/// The inheritable attributes of the page tree node `id`.
+fn inheritable(document: &InMemoryDocument, id: ReferenceId) -> error::Result<Map<Object>> {
+    let Some(Object::Dictionary(node)) = document.object(id)? else {
+        return Ok(Map::new());
+    };
+
+    Ok(INHERITABLE
+        .into_iter()
+        .filter_map(|name| Some((name.into(), node.get(&name.into())?.clone())))
+        .collect())
+}
@@ -0,0 +1,206 @@
+//! JSON export of the object graph, behind the `serde` feature.
+//!
+//! The [`ObjectGraph`] of a document lists every indirect object that is in use, by
+//! reference, along with the reference to the document catalog:
+//!
+//! ```json
+//! {
+//!   "root": "1 0 R",
+//!   "objects": {
+//!     "1 0 R": { "/Pages": "2 0 R", "/Type": "/Catalog" },
+//!     "4 0 R": { "dict": {}, "filters": ["FlateDecode"], "length": 56 },
+//!     "5 0 R": {
+//!       "dict": { "/Filter": "/DCTDecode" },
+//!       "filters": ["DCTDecode"],
+//!       "encoded": true,
+//!       "length": 2048
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Streams whose filters Livre cannot decode, such as JPEG images, are flagged with
+//! `encoded`: their length, and their content if requested, are the encoded ones.
+//!
+//! Objects are written as described in the [`Object`] implementation of
+//! [`serde::Serialize`]. Objects are sorted by number and dictionary keys by name, such that
+//! the output of two documents can be diffed.
+
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+use crate::{
+    extraction::{Map, Object, Reference, Stream},
+    InMemoryDocument,
+};
+
+/// How to write the content of streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamData {
+    /// Only write the length of the decoded content.
+    #[default]
+    Summary,
+    /// Write the decoded content as well, encoded in base64.
+    Base64,
+}
+
+/// The indirect objects of a document, which serializes as described in the
+/// [module documentation](self).
+///
+/// Objects that cannot be read are logged and written as `null`.
+#[derive(Clone, Copy)]
+pub struct ObjectGraph<'a> {
+    document: &'a InMemoryDocument,
+    streams: StreamData,
+}
+
+impl<'a> ObjectGraph<'a> {
+    pub fn new(document: &'a InMemoryDocument, streams: StreamData) -> Self {
+        Self { document, streams }
+    }
+}
+
+/// The objects of the graph, by reference.
+struct Objects<'a>(ObjectGraph<'a>);
+
+impl Serialize for Objects<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let ObjectGraph { document, streams } = self.0;
+
+        let mut ids: Vec<_> = document.builder.xrefs.in_use().map(|(id, _)| id).collect();
+        ids.sort_by_key(|id| id.object);
+
+        let mut map = serializer.serialize_map(Some(ids.len()))?;
+
+        for id in ids {
+            let reference = Reference::<Object>::from(id);
+            let object = document
+                .object(id)
+                .unwrap_or_else(|err| {
+                    tracing::warn!("Could not read object {id:?}: {err:?}");
+                    None
+                })
+                .unwrap_or(Object::Null);
+
+            map.serialize_key(&reference)?;
+            match &object {
+                Object::Stream(stream) if streams == StreamData::Base64 => {
+                    map.serialize_value(&Base64(stream))?
+                }
+                object => map.serialize_value(object)?,
+            }
+        }
+
+        map.end()
+    }
+}
+
+/// A stream, written along with its content.
+struct Base64<'a>(&'a Stream<Map<Object>>);
+
+impl Serialize for Base64<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize_with_data(true, serializer)
+    }
+}
+
+impl Serialize for ObjectGraph<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("root", &self.document.history.latest().trailer.root)?;
+        map.serialize_entry("objects", &Objects(*self))?;
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::extraction::{extract, Name};
+
+    use super::*;
+
+    #[rstest]
+    #[case(b"null", json!(null))]
+    #[case(b"true", json!(true))]
+    #[case(b"42", json!(42))]
+    #[case(b"-1.5", json!(-1.5))]
+    #[case(b"(Hello)", json!("u:Hello"))]
+    #[case(b"<FEFF00E9>", json!("u:é"))]
+    #[case(b"/Type", json!("/Type"))]
+    #[case(b"12 0 R", json!("12 0 R"))]
+    #[case(b"[1 /Name (text)]", json!([1, "/Name", "u:text"]))]
+    #[case(
+        b"<</Type /Page /Parent 1 0 R /Count 2>>",
+        json!({"/Count": 2, "/Parent": "1 0 R", "/Type": "/Page"})
+    )]
+    fn objects(#[case] input: &[u8], #[case] expected: serde_json::Value) {
+        let object: Object = extract(&mut input.as_ref()).unwrap();
+        assert_eq!(serde_json::to_value(&object).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(StreamData::Summary, json!({"dict": {"/Length": 5}, "filters": [], "length": 5}))]
+    #[case(
+        StreamData::Base64,
+        json!({"dict": {"/Length": 5}, "filters": [], "length": 5, "data": "aGVsbG8="})
+    )]
+    fn stream(#[case] streams: StreamData, #[case] expected: serde_json::Value) {
+        let stream = Stream {
+            structured: [(Name::from("Length"), Object::Integer(5))]
+                .into_iter()
+                .collect(),
+            filters: Vec::new(),
+            content: b"hello".to_vec(),
+        };
+        let object = Object::Stream(stream.clone());
+
+        let value = match streams {
+            StreamData::Summary => serde_json::to_value(&object),
+            StreamData::Base64 => serde_json::to_value(Base64(&stream)),
+        };
+        assert_eq!(value.unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(Object::Name("DCTDecode".into()), json!(["DCTDecode"]))]
+    #[case(
+        Object::Array(vec![Object::Name("ASCIIHexDecode".into()), Object::Name("DCTDecode".into())]),
+        json!(["ASCIIHexDecode", "DCTDecode"])
+    )]
+    fn encoded_stream(#[case] filter: Object, #[case] filters: serde_json::Value) {
+        let stream = Stream {
+            structured: [(Name::from("Filter"), filter.clone())]
+                .into_iter()
+                .collect(),
+            filters: Vec::new(),
+            content: vec![0xff, 0xd8],
+        };
+
+        assert_eq!(
+            serde_json::to_value(Object::Stream(stream)).unwrap(),
+            json!({"dict": {"/Filter": filter}, "filters": filters, "encoded": true, "length": 2})
+        );
+    }
+
+    #[test]
+    fn name_keys() {
+        let map: Map<i32> = [(Name::from("B"), 2), (Name::from("A"), 1)]
+            .into_iter()
+            .collect();
+        let mut output = Vec::new();
+        crate::extraction::serialize_sorted(&map, &mut serde_json::Serializer::new(&mut output))
+            .unwrap();
+        assert_eq!(output, br#"{"/A":1,"/B":2}"#);
+    }
+}
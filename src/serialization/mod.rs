@@ -0,0 +1,227 @@
+//! Definition of the serialization logic, the inverse of [extraction](crate::extraction).
+//!
+//! The [`Serialize`] trait writes a type as PDF syntax, such that extracting the written bytes
+//! yields the original value back. Like [`Extract`](crate::extraction::Extract), it is
+//! implemented for every "primitive" type, as well as for PDF-specific types such as
+//! [`Name`](crate::extraction::Name) or [`Stream`](crate::extraction::Stream).
+//!
+//! ## PDF dictionaries
+//!
+//! Structured types are written as dictionaries through the [`SerializeDict`] trait, which
+//! mirrors [`FromRawDict`](crate::extraction::FromRawDict): a type only needs to write its
+//! entries using a [`DictWriter`], and becomes [`Serialize`] through a blanket implementation.
+//!
+//! ## Derivability
+//!
+//! Like [`FromRawDict`](crate::extraction::FromRawDict), [`SerializeDict`] can be derived
+//! through the [`livre_derive`] helper crate. The derive macro understands the same
+//! `#[livre(...)]` attributes, such that a derived type can be read and written alike.
+//!
+//! ## JSON
+//!
+//! With the `serde` feature, the main types implement [`serde::Serialize`], and the
+//! [`ObjectGraph`] of a document can be exported to JSON, e.g. to diff two documents.
+
+mod composition;
+#[cfg(feature = "serde")]
+mod json;
+mod primitives;
+mod rewrite;
+mod serialize;
+mod serialize_dict;
+mod update;
+mod xref;
+
+pub use composition::Composition;
+#[cfg(feature = "serde")]
+pub use json::{ObjectGraph, StreamData};
+pub use rewrite::Rewrite;
+pub use serialize::Serialize;
+pub use serialize_dict::{DictWriter, SerializeDict};
+pub use update::{IncrementalUpdate, XRefFormat};
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use rstest::rstest;
+
+    use crate::{
+        extraction::{
+            extract, Date, Extract, HexadecimalString, Indirect, LiteralString, Map, MaybeArray,
+            Name, Object, OptRef, PDFString, Rectangle, Reference, ReferenceId, Stream,
+        },
+        filtering::{Filter, FlateDecode},
+    };
+
+    use super::*;
+
+    fn round_trip<T>(value: T)
+    where
+        T: Serialize + for<'de> Extract<'de> + Debug + PartialEq,
+    {
+        let bytes = value.to_bytes();
+        let result: T = extract(&mut bytes.as_slice().as_ref())
+            .unwrap_or_else(|_| panic!("could not extract {}", String::from_utf8_lossy(&bytes)));
+        assert_eq!(value, result);
+    }
+
+    #[rstest]
+    #[case(true)]
+    #[case(false)]
+    fn boolean(#[case] value: bool) {
+        round_trip(value);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(-42)]
+    #[case(i32::MAX)]
+    #[case(i32::MIN)]
+    fn integer(#[case] value: i32) {
+        round_trip(value);
+        round_trip(value as i64);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(42)]
+    #[case(u8::MAX)]
+    fn unsigned(#[case] value: u8) {
+        round_trip(value);
+        round_trip(value as u16);
+        round_trip(value as usize);
+    }
+
+    #[rstest]
+    #[case(0.0)]
+    #[case(-0.5)]
+    #[case(3.25)]
+    #[case(1e-7)]
+    #[case(1e20)]
+    fn real(#[case] value: f32) {
+        round_trip(value);
+        round_trip(value as f64);
+    }
+
+    #[test]
+    fn non_finite() {
+        assert!(f32::NAN.serialize(&mut Vec::new()).is_err());
+        assert!(f64::INFINITY.serialize(&mut Vec::new()).is_err());
+    }
+
+    #[rstest]
+    #[case("Type")]
+    #[case("Lime Green")]
+    #[case("paired()parentheses")]
+    #[case("The_Key_of_F#_Minor")]
+    #[case("A/B<C>[D]{E}%")]
+    #[case("é\n")]
+    fn name(#[case] value: &str) {
+        round_trip(Name::from(value));
+    }
+
+    #[rstest]
+    #[case(b"")]
+    #[case(b"abcd")]
+    #[case(b"test (with inner parenthesis)")]
+    #[case(b"unbalanced ) (")]
+    #[case(b"back\\slash")]
+    #[case(b"\n\r\t\x08\x0c")]
+    #[case(b"\x00\x01\xff")]
+    fn strings(#[case] value: &[u8]) {
+        round_trip(LiteralString(value.to_vec()));
+        round_trip(HexadecimalString(value.to_vec()));
+        round_trip(PDFString(value.to_vec()));
+    }
+
+    #[test]
+    fn collections() {
+        round_trip(vec![1, 2, 3]);
+        round_trip(Vec::<i32>::new());
+        round_trip([1.5f32, 2.0, -3.0]);
+        round_trip(vec![vec![Name::from("A")], vec![]]);
+        round_trip((1, true, Name::from("N")));
+        round_trip(Some(42));
+        round_trip(MaybeArray(vec![1]));
+        round_trip(MaybeArray(vec![1, 2]));
+    }
+
+    #[test]
+    fn map() {
+        let map: Map<i32> = [("A".into(), 1), ("B".into(), -2)].into_iter().collect();
+        round_trip(map);
+        round_trip(Map::<bool>::new());
+    }
+
+    #[test]
+    fn references() {
+        let id = ReferenceId::new(12, 3);
+        round_trip(id);
+        round_trip(Reference::<Object>::from(id));
+        round_trip(OptRef::<i32>::Ref(id.into()));
+        round_trip(OptRef::Direct(42));
+        round_trip(Indirect {
+            id,
+            inner: Name::from("Inner"),
+        });
+    }
+
+    #[test]
+    fn rectangle() {
+        round_trip(Rectangle::from((0.0, -1.5, 612.0, 792.0)));
+    }
+
+    #[rstest]
+    #[case(Date { year: 2024, month: None, day: None, hour: None, minute: None, second: None, offset: None })]
+    #[case(Date { year: 1998, month: Some(12), day: Some(23), hour: Some(19), minute: Some(52), second: Some(7), offset: Some(-480) })]
+    #[case(Date { year: 2001, month: Some(1), day: Some(2), hour: Some(3), minute: Some(4), second: Some(5), offset: Some(0) })]
+    #[case(Date { year: 2001, month: Some(1), day: None, hour: None, minute: None, second: None, offset: Some(90) })]
+    fn date(#[case] value: Date) {
+        round_trip(value);
+    }
+
+    #[rstest]
+    #[case(Object::Null)]
+    #[case(Object::Boolean(true))]
+    #[case(Object::Integer(-7))]
+    #[case(Object::Real(2.0))]
+    #[case(Object::Real(-0.25))]
+    #[case(Object::String(b"(nested)".to_vec()))]
+    #[case(Object::Name("Name".into()))]
+    #[case(Object::Array(vec![Object::Integer(1), Object::Null, Object::Name("A".into())]))]
+    #[case(Object::Dictionary([("Key".into(), Object::Real(1.5))].into_iter().collect()))]
+    #[case(Object::Indirect(ReferenceId::new(1, 0).into()))]
+    #[case(Object::Stream(Stream {
+        structured: [("Type".into(), Object::Name("XObject".into()))].into_iter().collect(),
+        filters: vec![],
+        content: b"raw content".to_vec(),
+    }))]
+    fn object(#[case] value: Object) {
+        round_trip(value);
+    }
+
+    #[rstest]
+    #[case(vec![])]
+    #[case(vec![Filter::FlateDecode(FlateDecode)])]
+    fn stream(#[case] filters: Vec<Filter>) {
+        let content = b"BT /F1 12 Tf (Hello) Tj ET".repeat(10);
+        round_trip(Stream {
+            structured: Map::from([("Key".into(), true)]),
+            filters,
+            content,
+        });
+    }
+
+    #[test]
+    fn dictionary() {
+        let map: Map<Object> = [
+            ("Type".into(), Object::Name("Page".into())),
+            ("Rotate".into(), Object::Integer(90)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(map.to_bytes(), b"<</Rotate 90 /Type /Page>>");
+    }
+}
@@ -0,0 +1,199 @@
+//! Implementation of the [`Serialize`] trait for primitive types: `bool`,
+//! (un-)signed integers, floating point numbers, unit type, vectors, arrays,
+//! optionals & tuple.
+
+use std::io::{self, Write};
+
+use paste::paste;
+
+use super::Serialize;
+
+impl Serialize for bool {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write!(writer, "{self}")
+    }
+}
+
+macro_rules! integer {
+    ($($name:ident)+) => {
+        $(
+            impl Serialize for $name {
+                fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+                where
+                    W: Write,
+                {
+                    write!(writer, "{self}")
+                }
+            }
+        )+
+    };
+}
+
+integer! {
+    u8
+    u16
+    u32
+    u64
+    u128
+    usize
+    i8
+    i16
+    i32
+    i64
+    i128
+    isize
+}
+
+/// PDF real numbers have no exponent notation, which Rust's `Display` implementation for
+/// floating point numbers never uses. Non-finite values cannot be represented at all.
+macro_rules! real {
+    ($($name:ident)+) => {
+        $(
+            impl Serialize for $name {
+                fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+                where
+                    W: Write,
+                {
+                    if !self.is_finite() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("{self} cannot be represented in a PDF"),
+                        ));
+                    }
+
+                    write!(writer, "{self}")
+                }
+            }
+        )+
+    };
+}
+
+real! {
+    f32
+    f64
+}
+
+/// The unit type is represented by the `null` object.
+impl Serialize for () {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(b"null")
+    }
+}
+
+impl<T> Serialize for Option<T>
+where
+    T: Serialize,
+{
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Some(value) => value.serialize(writer),
+            None => ().serialize(writer),
+        }
+    }
+}
+
+impl<T> Serialize for [T]
+where
+    T: Serialize,
+{
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(b"[")?;
+
+        for (i, item) in self.iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b" ")?;
+            }
+            item.serialize(writer)?;
+        }
+
+        writer.write_all(b"]")
+    }
+}
+
+impl<T> Serialize for Vec<T>
+where
+    T: Serialize,
+{
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.as_slice().serialize(writer)
+    }
+}
+
+impl<T, const N: usize> Serialize for [T; N]
+where
+    T: Serialize,
+{
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.as_slice().serialize(writer)
+    }
+}
+
+/// Tuples are written as a sequence of space-separated values, without delimiters.
+macro_rules! impl_tuple {
+    ($first:ident, $($ty:ident),+) => {
+        paste! {
+            impl<$first, $($ty),+> Serialize for ($first, $($ty),+)
+            where
+                $first: Serialize,
+                $( $ty: Serialize),+
+            {
+                fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+                where
+                    W: Write,
+                {
+                    let ([<$first:lower>], $([<$ty:lower>]),+) = self;
+
+                    [<$first:lower>].serialize(writer)?;
+                    $(
+                        writer.write_all(b" ")?;
+                        [<$ty:lower>].serialize(writer)?;
+                    )+
+
+                    Ok(())
+                }
+            }
+        }
+    };
+}
+
+impl_tuple!(T1, T2);
+impl_tuple!(T1, T2, T3);
+impl_tuple!(T1, T2, T3, T4);
+impl_tuple!(T1, T2, T3, T4, T5);
+impl_tuple!(T1, T2, T3, T4, T5, T6);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+
+impl<T> Serialize for (T,)
+where
+    T: Serialize,
+{
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.0.serialize(writer)
+    }
+}
@@ -0,0 +1,617 @@
+//! Full-document rewrites, which produce a compact copy of the document.
+//!
+//! Unlike an [incremental update](super::IncrementalUpdate), a rewrite only keeps the objects
+//! that are reachable from the trailer, i.e. from the document catalog and the information
+//! dictionary. Everything else (unreachable objects, previous revisions, cross-reference
+//! sections) is dropped, and the remaining objects are renumbered densely.
+//!
+//! The rewrite can optionally:
+//!
+//! - pack non-stream objects into object streams, along with a cross-reference stream
+//!   (PDF 1.5)
+//! - compress streams that were stored without any filter
+//!
+//! Objects other than streams are copied verbatim, save for their references, such that
+//! numbers keep their precision even if they do not fit an [`Object`].
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    hash::Hash,
+    io::{self, Write},
+};
+
+use winnow::{BStr, ModalResult, Parser};
+
+use crate::{
+    error,
+    extraction::{Indirect, Map, Name, Object, Reference, ReferenceId, Stream},
+    filtering::{Filter, FlateDecode},
+    follow_refs::{Build, Builder},
+    structure::Trailer,
+    InMemoryDocument,
+};
+
+use super::{
+    xref::{write_table, xref_stream, XRef},
+    Serialize,
+};
+
+/// Maximum number of objects packed within a single object stream.
+const OBJECTS_PER_STREAM: usize = 100;
+
+/// PDF whitespace and delimiters, which end regular tokens such as numbers.
+const WHITESPACE: &[u8] = b" \t\r\n\x0C\0";
+const DELIMITERS: &[u8] = b"()<>[]{}/%";
+
+/// The body of an indirect object of the rewrite.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Body {
+    Object(Object),
+    /// An object other than a stream, in its original syntax.
+    Raw(Vec<u8>),
+}
+
+/// Streams are parsed, such that they can be compressed. Other objects are kept verbatim.
+impl Build for Body {
+    fn build<B>(input: &mut &BStr, builder: &B) -> ModalResult<Self>
+    where
+        B: Builder,
+    {
+        let (object, raw) = (|i: &mut &BStr| Object::build(i, builder))
+            .with_taken()
+            .parse_next(input)?;
+
+        match object {
+            Object::Stream(_) => Ok(Self::Object(object)),
+            _ => Ok(Self::Raw(raw.to_vec())),
+        }
+    }
+}
+
+impl Serialize for Body {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Self::Object(object) => object.serialize(writer),
+            Self::Raw(raw) => writer.write_all(raw),
+        }
+    }
+}
+
+/// A rewrite of an entire document, garbage-collected and renumbered.
+///
+/// Use [`InMemoryDocument::rewrite`] to start one. Reachable objects are collected upfront,
+/// such that writing the rewrite cannot fail on account of the original document.
+#[derive(Debug, Clone)]
+pub struct Rewrite {
+    /// The version of the original document, e.g. `1.7`.
+    version: String,
+    /// Reachable objects, in the order they were discovered: object `n` is at index `n - 1`.
+    objects: Vec<Body>,
+    /// The trailer of the rewritten document, whose references are already renumbered.
+    trailer: Trailer,
+    object_streams: bool,
+    compression: bool,
+}
+
+impl Rewrite {
+    /// Collect every object that is reachable from the trailer of the supplied document.
+    ///
+    /// References to undefined objects are rewritten as references to the null object.
+    pub fn new(document: &InMemoryDocument) -> error::Result<Self> {
+        let latest = &document.history.latest().trailer;
+
+        let mut renumbering = Renumbering::default();
+
+        let root = renumbering.renumber(latest.root.id);
+        let info = latest.info.map(|info| renumbering.renumber(info.id));
+
+        let mut objects = Vec::new();
+
+        while let Some(id) = renumbering.queue.pop_front() {
+            let body = match document.resolve_reference::<Body>(id.into()) {
+                Ok(body) => body,
+                // Streams whose filters are not supported are copied encoded.
+                Err(_) => document.object(id)?.map(Body::Object),
+            };
+
+            let body = match body {
+                Some(Body::Object(mut object)) => {
+                    renumbering.rewrite(&mut object, |id| id);
+                    Body::Object(object)
+                }
+                Some(Body::Raw(raw)) => Body::Raw(renumbering.rewrite_raw(&raw, |id| id)),
+                None => Body::Object(Object::Null),
+            };
+
+            objects.push(body);
+        }
+
+        let trailer = Trailer {
+            size: objects.len() + 1,
+            prev: None,
+            root: root.into(),
+            xref_stm: None,
+            encrypt: None,
+            info: info.map(Reference::from),
+            id: latest.id.clone(),
+        };
+
+        Ok(Self::from_parts(
+            version(document.builder.input()),
+            objects,
+            trailer,
+        ))
+    }
+
+    /// Assemble a rewrite from densely numbered objects, starting with object `1`.
+    pub(super) fn from_parts(version: String, objects: Vec<Body>, trailer: Trailer) -> Self {
+        Self {
+            version,
+            objects,
+            trailer,
+            object_streams: false,
+            compression: false,
+        }
+    }
+
+    /// Pack non-stream objects into object streams, and write a cross-reference stream.
+    ///
+    /// This bumps the version of the document to PDF 1.5 if needed.
+    pub fn with_object_streams(mut self, object_streams: bool) -> Self {
+        self.object_streams = object_streams;
+        self
+    }
+
+    /// Compress the streams that were stored without any filter.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// The number of indirect objects kept by the rewrite, excluding the ones created to
+    /// write it, such as object streams.
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Whether the rewrite has no objects at all, which cannot happen for a valid document.
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Write the rewritten document.
+    pub fn write<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut output = Vec::new();
+
+        let version = if self.object_streams && self.version.as_str() < "1.5" {
+            "1.5"
+        } else {
+            &self.version
+        };
+
+        // The comment line flags the file as binary, as recommended by the specification.
+        writeln!(output, "%PDF-{version}")?;
+        output.write_all(b"%\xE2\xE3\xCF\xD3\n")?;
+
+        let mut xrefs = BTreeMap::new();
+        xrefs.insert(0, XRef::Free { generation: 65535 });
+
+        let mut packed = Vec::new();
+
+        for (object, body) in (1..).zip(&self.objects) {
+            if self.object_streams && !matches!(body, Body::Object(Object::Stream(_))) {
+                packed.push((object, body));
+                continue;
+            }
+
+            let body = match body {
+                Body::Object(Object::Stream(stream)) if self.compression => {
+                    &Body::Object(Object::Stream(compress(stream)))
+                }
+                body => body,
+            };
+
+            xrefs.insert(
+                object,
+                XRef::InUse {
+                    offset: output.len(),
+                    generation: 0,
+                },
+            );
+            write_object(ReferenceId::first(object), body, &mut output)?;
+        }
+
+        let mut trailer = self.trailer.clone();
+
+        for chunk in packed.chunks(OBJECTS_PER_STREAM) {
+            let stream_id = trailer.size;
+            trailer.size += 1;
+
+            for (index, &(object, _)) in chunk.iter().enumerate() {
+                xrefs.insert(object, XRef::Compressed { stream_id, index });
+            }
+
+            xrefs.insert(
+                stream_id,
+                XRef::InUse {
+                    offset: output.len(),
+                    generation: 0,
+                },
+            );
+            let stream = object_stream(chunk)?;
+            write_object(ReferenceId::first(stream_id), &stream, &mut output)?;
+        }
+
+        let start = output.len();
+
+        if self.object_streams {
+            // The cross-reference stream is an indirect object itself, and shall be
+            // part of the section.
+            let id = ReferenceId::first(trailer.size);
+            trailer.size += 1;
+            xrefs.insert(
+                id.object,
+                XRef::InUse {
+                    offset: start,
+                    generation: 0,
+                },
+            );
+
+            Indirect {
+                id,
+                inner: xref_stream(&xrefs, trailer),
+            }
+            .serialize(&mut output)?;
+        } else {
+            write_table(&xrefs, &mut output)?;
+            output.write_all(b"trailer\n")?;
+            trailer.serialize(&mut output)?;
+        }
+
+        write!(output, "\nstartxref\n{start}\n%%EOF\n")?;
+
+        writer.write_all(&output)
+    }
+
+    /// Write the rewritten document to a byte vector.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer)
+            .expect("writing to a vector cannot fail");
+        buffer
+    }
+}
+
+/// Keeps track of the new object numbers, assigned densely in the order of discovery.
+///
+/// Original objects are identified by a key, which lets a [`Composition`](super::Composition)
+/// tell apart objects that come from different documents.
+#[derive(Debug)]
+pub(super) struct Renumbering<K> {
+    /// The first number to assign, lower object numbers being reserved.
+    first: usize,
+    numbers: HashMap<K, usize>,
+    /// Objects that were assigned a number, but are yet to be visited.
+    pub(super) queue: VecDeque<K>,
+}
+
+impl<K> Renumbering<K> {
+    pub(super) fn starting_at(first: usize) -> Self {
+        Self {
+            first,
+            numbers: HashMap::new(),
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl<K> Default for Renumbering<K> {
+    fn default() -> Self {
+        Self::starting_at(1)
+    }
+}
+
+impl<K> Renumbering<K>
+where
+    K: Hash + Eq + Copy,
+{
+    /// The new identifier of the original object `key`.
+    pub(super) fn renumber(&mut self, key: K) -> ReferenceId {
+        let next = self.first + self.numbers.len();
+        let object = *self.numbers.entry(key).or_insert_with(|| {
+            self.queue.push_back(key);
+            next
+        });
+        ReferenceId::first(object)
+    }
+
+    /// Renumber every reference within the object, recursively. The `key` function identifies
+    /// the original object a reference points to.
+    pub(super) fn rewrite<F>(&mut self, object: &mut Object, key: F)
+    where
+        F: Fn(ReferenceId) -> K + Copy,
+    {
+        match object {
+            Object::Array(array) => array.iter_mut().for_each(|item| self.rewrite(item, key)),
+            Object::Dictionary(map) => self.rewrite_entries(map, key),
+            Object::Stream(stream) => self.rewrite_entries(&mut stream.structured, key),
+            Object::Indirect(reference) => *reference = self.renumber(key(reference.id)).into(),
+            _ => {}
+        }
+    }
+
+    /// Renumber every reference within the raw syntax of an object, in the order they appear.
+    /// Everything else is copied verbatim.
+    pub(super) fn rewrite_raw<F>(&mut self, raw: &[u8], key: F) -> Vec<u8>
+    where
+        F: Fn(ReferenceId) -> K,
+    {
+        let mut output = Vec::with_capacity(raw.len());
+        let mut i = 0;
+
+        while i < raw.len() {
+            let start = i;
+
+            match raw[i] {
+                b'%' => i = find(raw, i, |b| b"\r\n".contains(&b)),
+                b'(' => i = literal_string_end(raw, i),
+                b'<' if raw.get(i + 1) == Some(&b'<') => i += 2,
+                b'<' => i = find(raw, i, |b| b == b'>') + 1,
+                // Names are skipped whole, since they may look like numbers.
+                b'/' => i = token_end(raw, i + 1),
+                b if WHITESPACE.contains(&b) || DELIMITERS.contains(&b) => i += 1,
+                _ => {
+                    if let Some((id, end)) = reference(raw, i) {
+                        let id = self.renumber(key(id));
+                        write!(output, "{} {} R", id.object, id.generation)
+                            .expect("writing to a vector cannot fail");
+                        i = end;
+                        continue;
+                    }
+                    i = token_end(raw, i);
+                }
+            }
+
+            output.extend_from_slice(&raw[start..i.min(raw.len())]);
+        }
+
+        output
+    }
+
+    /// Entries are visited in key order, such that the numbering is deterministic.
+    fn rewrite_entries<F>(&mut self, map: &mut Map<Object>, key: F)
+    where
+        F: Fn(ReferenceId) -> K + Copy,
+    {
+        let mut entries: Vec<_> = map.iter_mut().collect();
+        entries.sort_unstable_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+
+        for (_, value) in entries {
+            self.rewrite(value, key);
+        }
+    }
+}
+
+/// The index of the first byte after `start` that matches the predicate, or the length of the
+/// input if there is none.
+fn find<P>(raw: &[u8], start: usize, predicate: P) -> usize
+where
+    P: Fn(u8) -> bool,
+{
+    raw[start + 1..]
+        .iter()
+        .position(|&b| predicate(b))
+        .map_or(raw.len(), |position| start + 1 + position)
+}
+
+/// The end of the regular token that starts at `start`.
+fn token_end(raw: &[u8], start: usize) -> usize {
+    raw[start..]
+        .iter()
+        .position(|b| WHITESPACE.contains(b) || DELIMITERS.contains(b))
+        .map_or(raw.len(), |position| start + position)
+}
+
+/// The end of the literal string that starts at `start`, accounting for balanced parentheses
+/// and escaped bytes.
+fn literal_string_end(raw: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+
+    while i < raw.len() {
+        match raw[i] {
+            b'\\' => i += 1,
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    raw.len()
+}
+
+/// Recognize the reference `n g R` at `start`, returning its identifier and end.
+fn reference(raw: &[u8], start: usize) -> Option<(ReferenceId, usize)> {
+    let integer = |i: usize| -> Option<(&[u8], usize)> {
+        let end = token_end(raw, i);
+        let token = &raw[i..end];
+        (!token.is_empty() && token.iter().all(u8::is_ascii_digit)).then_some((token, end))
+    };
+    let whitespace = |i: usize| -> Option<usize> {
+        let end = raw[i..]
+            .iter()
+            .position(|b| !WHITESPACE.contains(b))
+            .map_or(raw.len(), |position| i + position);
+        (end > i).then_some(end)
+    };
+
+    let (object, i) = integer(start)?;
+    let (generation, i) = integer(whitespace(i)?)?;
+    let i = whitespace(i)?;
+
+    if token_end(raw, i) != i + 1 || raw[i] != b'R' {
+        return None;
+    }
+
+    // Both integers are made of ASCII digits.
+    let object = std::str::from_utf8(object).ok()?.parse().ok()?;
+    let generation = std::str::from_utf8(generation).ok()?.parse().ok()?;
+    let id = ReferenceId::new(object, generation);
+
+    Some((id, i + 1))
+}
+
+/// Compress a stream that has no filter. Encoded streams are left untouched.
+fn compress(stream: &Stream<Map<Object>>) -> Stream<Map<Object>> {
+    let mut stream = stream.clone();
+
+    if stream.filters.is_empty() && !stream.structured.contains_key(&"Filter".into()) {
+        stream.filters = vec![Filter::FlateDecode(FlateDecode)];
+    }
+
+    stream
+}
+
+/// Pack the supplied objects into an object stream.
+fn object_stream(objects: &[(usize, &Body)]) -> io::Result<Object> {
+    let mut header = Vec::new();
+    let mut bodies = Vec::new();
+
+    for &(object, body) in objects {
+        write!(header, "{object} {} ", bodies.len())?;
+        body.serialize(&mut bodies)?;
+        bodies.push(b'\n');
+    }
+
+    header.push(b'\n');
+
+    let structured: Map<Object> = [
+        (Name::from("Type"), Object::Name("ObjStm".into())),
+        (Name::from("N"), Object::Integer(objects.len() as i32)),
+        (Name::from("First"), Object::Integer(header.len() as i32)),
+    ]
+    .into_iter()
+    .collect();
+
+    header.extend(bodies);
+
+    Ok(Object::Stream(Stream {
+        structured,
+        filters: vec![Filter::FlateDecode(FlateDecode)],
+        content: header,
+    }))
+}
+
+/// Write an indirect object, followed by an end-of-line marker. Contrary to [`Indirect`], the
+/// object is borrowed.
+fn write_object<T, W>(id: ReferenceId, body: &T, writer: &mut W) -> io::Result<()>
+where
+    T: Serialize,
+    W: Write,
+{
+    id.serialize(writer)?;
+    writer.write_all(b" obj\n")?;
+    body.serialize(writer)?;
+    writer.write_all(b"\nendobj\n")
+}
+
+/// The version of the document, read from its header. Defaults to PDF 1.7.
+pub(super) fn version(input: &[u8]) -> String {
+    input
+        .strip_prefix(b"%PDF-")
+        .and_then(|rest| {
+            let end = rest
+                .iter()
+                .position(|b| !(b.is_ascii_digit() || *b == b'.'))?;
+            std::str::from_utf8(&rest[..end]).ok()
+        })
+        .filter(|version| !version.is_empty())
+        .unwrap_or("1.7")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(b"%PDF-1.4\n%...", "1.4")]
+    #[case(b"%PDF-2.0\r\n", "2.0")]
+    #[case(b"garbage", "1.7")]
+    fn header(#[case] input: &[u8], #[case] expected: &str) {
+        assert_eq!(version(input), expected);
+    }
+
+    #[test]
+    fn renumbering() {
+        let mut renumbering = Renumbering::default();
+
+        let mut object: Object = vec![
+            Object::from(Reference::from(ReferenceId::first(12))),
+            Object::from(Reference::from(ReferenceId::new(7, 2))),
+            Object::from(Reference::from(ReferenceId::first(12))),
+        ]
+        .into();
+
+        renumbering.rewrite(&mut object, |id| id);
+
+        assert_eq!(
+            object,
+            Object::from(vec![
+                Object::from(Reference::from(ReferenceId::first(1))),
+                Object::from(Reference::from(ReferenceId::first(2))),
+                Object::from(Reference::from(ReferenceId::first(1))),
+            ])
+        );
+        assert_eq!(
+            renumbering.queue,
+            [ReferenceId::first(12), ReferenceId::new(7, 2)]
+        );
+    }
+
+    #[rstest]
+    #[case(b"[12 0 R 7 2 R 12 0 R]", b"[1 0 R 2 0 R 1 0 R]")]
+    #[case(b"<</A 12 0 R/B[1 2 7 2 R]>>", b"<</A 1 0 R/B[1 2 2 0 R]>>")]
+    #[case(
+        b"[0.123456789 3000000000 -1 12 0 R]",
+        b"[0.123456789 3000000000 -1 1 0 R]"
+    )]
+    #[case(b"[(12 0 R) <3132> /12 0 R]", b"[(12 0 R) <3132> /12 0 R]")]
+    #[case(b"[(a\\) (12 0 R)) 12 0 R]", b"[(a\\) (12 0 R)) 1 0 R]")]
+    #[case(b"[12 0 R % 7 2 R\n12\n0\nR]", b"[1 0 R % 7 2 R\n1 0 R]")]
+    #[case(b"[12 0 Rx 12 0R]", b"[12 0 Rx 12 0R]")]
+    fn raw_renumbering(#[case] raw: &[u8], #[case] expected: &[u8]) {
+        let mut renumbering = Renumbering::default();
+        let output = renumbering.rewrite_raw(raw, |id| id);
+        assert_eq!(BStr::new(&output), BStr::new(expected));
+    }
+
+    #[test]
+    fn packing() {
+        let objects = [
+            Body::Object(Object::Integer(42)),
+            Body::Raw(b"/Name".to_vec()),
+        ];
+        let Object::Stream(stream) = object_stream(&[(3, &objects[0]), (5, &objects[1])]).unwrap()
+        else {
+            panic!("object streams are streams");
+        };
+
+        assert_eq!(stream.content, b"3 0 5 3 \n42\n/Name\n");
+        assert_eq!(stream.structured[&"First".into()], Object::Integer(9));
+    }
+}
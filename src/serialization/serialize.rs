@@ -0,0 +1,17 @@
+use std::io::{self, Write};
+
+/// The [`Serialize`] trait marks a type as writable as PDF syntax. It is the inverse of
+/// [`Extract`](crate::extraction::Extract).
+pub trait Serialize {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write;
+
+    /// Serialize the value to a byte vector.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)
+            .expect("writing to a vector cannot fail");
+        buffer
+    }
+}
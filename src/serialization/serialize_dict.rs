@@ -0,0 +1,121 @@
+pub use livre_derive::SerializeDict;
+
+use std::io::{self, Write};
+
+use crate::extraction::Name;
+
+use super::Serialize;
+
+/// Write a type as the entries of a PDF dictionary, the inverse of
+/// [`FromRawDict`](crate::extraction::FromRawDict).
+///
+/// Contrary to [`Serialize`], implementors do not write the dictionary delimiters: this
+/// allows a type to be flattened into another dictionary, such as a stream dictionary.
+///
+/// This type can be derived using the [`livre_derive`] helper crate.
+pub trait SerializeDict {
+    fn serialize_entries<W>(&self, dict: &mut DictWriter<'_, W>) -> io::Result<()>
+    where
+        W: Write;
+}
+
+/// Any type that is [`SerializeDict`] is trivially [`Serialize`]: its entries are written
+/// within a dictionary.
+impl<T> Serialize for T
+where
+    T: SerializeDict,
+{
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut dict = DictWriter::new(writer)?;
+        self.serialize_entries(&mut dict)?;
+        dict.finish()
+    }
+}
+
+/// Helper that writes the entries of a PDF dictionary, e.g. `<</Type /Page /Rotate 90>>`.
+pub struct DictWriter<'w, W> {
+    writer: &'w mut W,
+    empty: bool,
+}
+
+impl<'w, W> DictWriter<'w, W>
+where
+    W: Write,
+{
+    /// Open a new dictionary.
+    pub fn new(writer: &'w mut W) -> io::Result<Self> {
+        writer.write_all(b"<<")?;
+        Ok(Self {
+            writer,
+            empty: true,
+        })
+    }
+
+    /// Write a `/Key value` entry.
+    pub fn entry<T>(&mut self, key: &[u8], value: &T) -> io::Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        if !self.empty {
+            self.writer.write_all(b" ")?;
+        }
+        self.empty = false;
+
+        Name::write(key, self.writer)?;
+        self.writer.write_all(b" ")?;
+        value.serialize(self.writer)
+    }
+
+    /// Close the dictionary.
+    pub fn finish(self) -> io::Result<()> {
+        self.writer.write_all(b">>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::extraction::{extract, FromRawDict, MaybeArray, Name};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, FromRawDict, SerializeDict)]
+    struct Inner {
+        #[livre(rename = "Kind")]
+        name: Name,
+    }
+
+    #[derive(Debug, PartialEq, FromRawDict, SerializeDict)]
+    struct Outer {
+        #[livre(flatten)]
+        inner: Inner,
+        optional: Option<i32>,
+        #[livre(default = 1.0)]
+        scale: f32,
+        #[livre(default = || -1)]
+        closure: i32,
+        #[livre(default, from = MaybeArray<i32>)]
+        values: Vec<i32>,
+    }
+
+    #[rstest]
+    #[case(b"<</Kind /A>>", b"<</Kind /A>>")]
+    #[case(b"<</Kind/A/Scale 1.0/Closure -1/Values[]>>", b"<</Kind /A>>")]
+    #[case(
+        b"<</Kind/A/Optional 2/Scale 2/Closure 0/Values 3>>",
+        b"<</Kind /A /Optional 2 /Scale 2 /Closure 0 /Values 3>>"
+    )]
+    #[case(b"<</Kind/A/Values[1 2]>>", b"<</Kind /A /Values [1 2]>>")]
+    fn derive(#[case] input: &[u8], #[case] expected: &[u8]) {
+        let outer: Outer = extract(&mut input.as_ref()).unwrap();
+        let bytes = outer.to_bytes();
+        assert_eq!(bytes, expected);
+
+        let result: Outer = extract(&mut bytes.as_slice().as_ref()).unwrap();
+        assert_eq!(outer, result);
+    }
+}
@@ -0,0 +1,227 @@
+//! Incremental updates, which let us save modifications without rewriting the document.
+//!
+//! From the specification:
+//!
+//! > The contents of a PDF file can be updated incrementally without rewriting the entire file.
+//! > When updating a PDF file incrementally, changes shall be appended to the end of the file,
+//! > leaving its original contents intact.
+//!
+//! Since the original bytes are left untouched, an incremental update preserves existing digital
+//! signatures. See the [`History`](crate::structure::History) type for the reading side.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use crate::{
+    extraction::{Indirect, Reference, ReferenceId},
+    structure::Trailer,
+    InMemoryDocument,
+};
+
+use super::{
+    xref::{write_table, xref_stream, XRef},
+    Serialize,
+};
+
+/// How the cross-reference section of an update is written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum XRefFormat {
+    /// A classic cross-reference table, followed by the `trailer` keyword and dictionary.
+    #[default]
+    Table,
+    /// (PDF 1.5) A compressed cross-reference stream, whose dictionary doubles as the trailer.
+    Stream,
+}
+
+/// An entry of the cross-reference section written by the update.
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    /// A new or modified object, stored as its serialized body.
+    InUse { generation: u16, body: Vec<u8> },
+    /// A deleted object. The generation number is the one to use if the object number is reused.
+    Free { generation: u16 },
+}
+
+/// An incremental update to an existing document.
+///
+/// The update collects new and modified indirect objects, and appends them to the original
+/// document along with a new cross-reference section and a trailer whose `Prev` key points
+/// to the previous section. Use [`InMemoryDocument::update`] to start one.
+#[derive(Debug, Clone)]
+pub struct IncrementalUpdate<'a> {
+    /// The bytes of the original document, left untouched.
+    original: &'a [u8],
+    /// Byte offset of the latest cross-reference section of the original document.
+    prev: usize,
+    /// The trailer of the update, initialised from the latest revision.
+    trailer: Trailer,
+    /// New, modified and deleted objects, by object number.
+    entries: BTreeMap<usize, Entry>,
+    format: XRefFormat,
+}
+
+impl<'a> IncrementalUpdate<'a> {
+    /// Start an update of the supplied document.
+    pub fn new(document: &'a InMemoryDocument) -> Self {
+        let latest = document.history.latest();
+
+        Self {
+            original: document.builder.input(),
+            prev: latest.offset,
+            trailer: latest.trailer.clone(),
+            entries: BTreeMap::new(),
+            format: XRefFormat::default(),
+        }
+    }
+
+    /// Choose how the cross-reference section is written.
+    pub fn with_format(mut self, format: XRefFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// The trailer of the update, e.g. to point to a new `Root` or `Info` dictionary.
+    ///
+    /// The `Size`, `Prev` and `XRefStm` keys are computed when the update is written.
+    pub fn trailer_mut(&mut self) -> &mut Trailer {
+        &mut self.trailer
+    }
+
+    /// Set the indirect object `id`, overwriting its previous definition if there is one.
+    pub fn insert<T>(&mut self, id: ReferenceId, object: &T) -> io::Result<()>
+    where
+        T: Serialize,
+    {
+        let mut body = Vec::new();
+        object.serialize(&mut body)?;
+
+        self.entries.insert(
+            id.object,
+            Entry::InUse {
+                generation: id.generation,
+                body,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Add a new indirect object to the document, and return a reference to it.
+    pub fn add<T>(&mut self, object: &T) -> io::Result<Reference<T>>
+    where
+        T: Serialize,
+    {
+        let id = ReferenceId::first(self.size());
+        self.insert(id, object)?;
+        Ok(id.into())
+    }
+
+    /// Delete the indirect object `id`: references to it will resolve to the null object.
+    pub fn delete(&mut self, id: ReferenceId) {
+        let generation = id.generation.saturating_add(1);
+        self.entries.insert(id.object, Entry::Free { generation });
+    }
+
+    /// One greater than the highest object number of the updated document.
+    fn size(&self) -> usize {
+        let last = self
+            .entries
+            .keys()
+            .next_back()
+            .map_or(0, |&object| object + 1);
+        self.trailer.size.max(last)
+    }
+
+    /// Write the updated document, i.e. the original bytes followed by the update.
+    pub fn write<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(self.original)?;
+        self.write_update(writer)
+    }
+
+    /// Write the update alone, to be appended to the original document.
+    pub fn write_update<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut output = Vec::new();
+
+        if !self.original.ends_with(b"\n") && !self.original.ends_with(b"\r") {
+            output.push(b'\n');
+        }
+
+        let mut xrefs = BTreeMap::new();
+
+        for (&object, entry) in &self.entries {
+            let offset = self.original.len() + output.len();
+
+            let xref = match *entry {
+                Entry::InUse {
+                    generation,
+                    ref body,
+                } => {
+                    ReferenceId::new(object, generation).serialize(&mut output)?;
+                    output.write_all(b" obj\n")?;
+                    output.write_all(body)?;
+                    output.write_all(b"\nendobj\n")?;
+                    XRef::InUse { offset, generation }
+                }
+                Entry::Free { generation } => XRef::Free { generation },
+            };
+
+            xrefs.insert(object, xref);
+        }
+
+        let mut trailer = Trailer {
+            size: self.size(),
+            prev: Some(self.prev),
+            xref_stm: None,
+            ..self.trailer.clone()
+        };
+
+        let start = self.original.len() + output.len();
+
+        match self.format {
+            XRefFormat::Table => {
+                write_table(&xrefs, &mut output)?;
+                output.write_all(b"trailer\n")?;
+                trailer.serialize(&mut output)?;
+            }
+            XRefFormat::Stream => {
+                // The cross-reference stream is an indirect object itself, and shall be
+                // part of the section.
+                let id = ReferenceId::first(trailer.size);
+                trailer.size += 1;
+                xrefs.insert(
+                    id.object,
+                    XRef::InUse {
+                        offset: start,
+                        generation: 0,
+                    },
+                );
+
+                Indirect {
+                    id,
+                    inner: xref_stream(&xrefs, trailer),
+                }
+                .serialize(&mut output)?;
+            }
+        }
+
+        write!(output, "\nstartxref\n{start}\n%%EOF\n")?;
+
+        writer.write_all(&output)
+    }
+
+    /// Write the updated document to a byte vector.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer)
+            .expect("writing to a vector cannot fail");
+        buffer
+    }
+}
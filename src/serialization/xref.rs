@@ -0,0 +1,221 @@
+//! Writing of cross-reference sections, be it a classic table or a cross-reference stream.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use crate::{
+    extraction::{Name, Stream},
+    filtering::{Filter, FlateDecode},
+    structure::Trailer,
+};
+
+use super::SerializeDict;
+
+/// The location of an object within the written document, by object number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum XRef {
+    InUse {
+        offset: usize,
+        generation: u16,
+    },
+    Free {
+        generation: u16,
+    },
+    /// An object stored within an object stream. Only cross-reference streams can represent it.
+    Compressed {
+        stream_id: usize,
+        index: usize,
+    },
+}
+
+/// Group the entries into subsections of consecutive object numbers.
+fn subsections(xrefs: &BTreeMap<usize, XRef>) -> Vec<(usize, Vec<XRef>)> {
+    let mut subsections: Vec<(usize, Vec<XRef>)> = Vec::new();
+
+    for (&object, &xref) in xrefs {
+        match subsections.last_mut() {
+            Some((start, entries)) if *start + entries.len() == object => entries.push(xref),
+            _ => subsections.push((object, vec![xref])),
+        }
+    }
+
+    subsections
+}
+
+/// Write a classic cross-reference table. Each entry is exactly 20 bytes long.
+///
+/// # Panics
+///
+/// Compressed objects cannot be represented in a classic table, and trigger a panic.
+pub(crate) fn write_table<W>(xrefs: &BTreeMap<usize, XRef>, writer: &mut W) -> io::Result<()>
+where
+    W: Write,
+{
+    writer.write_all(b"xref\n")?;
+
+    for (start, entries) in subsections(xrefs) {
+        writeln!(writer, "{start} {}", entries.len())?;
+
+        for xref in entries {
+            match xref {
+                XRef::InUse { offset, generation } => {
+                    write!(writer, "{offset:010} {generation:05} n\r\n")?
+                }
+                XRef::Free { generation } => write!(writer, "{:010} {generation:05} f\r\n", 0)?,
+                XRef::Compressed { .. } => {
+                    unreachable!("compressed objects require a cross-reference stream")
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The dictionary of a cross-reference stream, which holds the trailer entries.
+#[derive(Debug, SerializeDict)]
+pub(crate) struct XRefStreamDict {
+    #[livre(rename = "Type")]
+    kind: Name,
+    /// Flattened pairs of first object number and number of entries.
+    index: Vec<usize>,
+    w: [usize; 3],
+    #[livre(flatten)]
+    trailer: Trailer,
+}
+
+/// Build the cross-reference stream for the supplied entries.
+pub(crate) fn xref_stream(
+    xrefs: &BTreeMap<usize, XRef>,
+    trailer: Trailer,
+) -> Stream<XRefStreamDict> {
+    let max_field = xrefs
+        .values()
+        .map(|xref| match *xref {
+            XRef::InUse { offset, .. } => offset,
+            XRef::Free { .. } => 0,
+            XRef::Compressed { stream_id, .. } => stream_id,
+        })
+        .max()
+        .unwrap_or(0);
+
+    // Number of bytes needed to represent the largest field.
+    let field_len = (usize::BITS - max_field.leading_zeros()).div_ceil(8).max(1) as usize;
+    let w = [1, field_len, 2];
+
+    let mut index = Vec::new();
+    let mut content = Vec::new();
+
+    for (start, entries) in subsections(xrefs) {
+        index.extend([start, entries.len()]);
+
+        for xref in entries {
+            let (kind, field, last) = match xref {
+                XRef::Free { generation } => (0u8, 0, generation),
+                XRef::InUse { offset, generation } => (1u8, offset, generation),
+                XRef::Compressed { stream_id, index } => {
+                    let index = u16::try_from(index).expect("object streams are kept small");
+                    (2u8, stream_id, index)
+                }
+            };
+
+            content.push(kind);
+            content.extend_from_slice(&field.to_be_bytes()[size_of::<usize>() - field_len..]);
+            content.extend_from_slice(&last.to_be_bytes());
+        }
+    }
+
+    Stream {
+        structured: XRefStreamDict {
+            kind: "XRef".into(),
+            index,
+            w,
+            trailer,
+        },
+        filters: vec![Filter::FlateDecode(FlateDecode)],
+        content,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::extraction::ReferenceId;
+
+    use super::*;
+
+    fn in_use(offset: usize) -> XRef {
+        XRef::InUse {
+            offset,
+            generation: 0,
+        }
+    }
+
+    #[rstest]
+    #[case(&[], &[])]
+    #[case(&[1, 2, 3], &[(1, 3)])]
+    #[case(&[1, 3, 4, 7], &[(1, 1), (3, 2), (7, 1)])]
+    fn grouping(#[case] objects: &[usize], #[case] expected: &[(usize, usize)]) {
+        let xrefs = objects.iter().map(|&o| (o, in_use(o))).collect();
+        let result: Vec<_> = subsections(&xrefs)
+            .into_iter()
+            .map(|(start, entries)| (start, entries.len()))
+            .collect();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn table() {
+        let xrefs = [(3, in_use(42)), (4, XRef::Free { generation: 1 })]
+            .into_iter()
+            .collect();
+
+        let mut output = Vec::new();
+        write_table(&xrefs, &mut output).unwrap();
+
+        assert_eq!(
+            output,
+            b"xref\n3 2\n0000000042 00000 n\r\n0000000000 00001 f\r\n"
+        );
+    }
+
+    #[test]
+    fn stream() {
+        let xrefs = [
+            (1, in_use(300)),
+            (2, XRef::Free { generation: 1 }),
+            (
+                3,
+                XRef::Compressed {
+                    stream_id: 1,
+                    index: 4,
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let stream = xref_stream(
+            &xrefs,
+            Trailer {
+                size: 4,
+                prev: None,
+                root: ReferenceId::first(1).into(),
+                xref_stm: None,
+                encrypt: None,
+                info: None,
+                id: None,
+            },
+        );
+
+        assert_eq!(stream.structured.w, [1, 2, 2]);
+        assert_eq!(stream.structured.index, [1, 3]);
+        assert_eq!(
+            stream.content,
+            [1, 1, 44, 0, 0, 0, 0, 0, 0, 1, 2, 0, 1, 0, 4]
+        );
+    }
+}
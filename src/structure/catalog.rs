@@ -12,6 +12,7 @@ use crate::{
 use super::pages::PageTreeNode;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PageLayout {
     ///Display one page at a time
     #[default]
@@ -54,6 +55,7 @@ impl Extract<'_> for PageLayout {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PageMode {
     /// Neither document outline nor thumbnail images visible
     #[default]
@@ -94,6 +96,7 @@ impl Extract<'_> for PageMode {
 /// The document catalog contains references to other objects defining the content of the
 /// document's content.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Catalog {
     /// The root [page tree node](PageTreeNode).
     pub pages: PageTreeNode,
@@ -129,10 +132,9 @@ impl Build for Catalog {
         let pages: Reference<PageTreeNode> = dict
             .pop(&"Pages".into())
             .ok_or(ErrMode::Cut(ContextError::new()))?
-            .extract()
-            .unwrap();
+            .extract()?;
 
-        let pages = builder.build_reference(pages).unwrap();
+        let pages = builder.build_reference(pages)?;
 
         Ok(Self {
             page_mode,
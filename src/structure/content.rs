@@ -1,6 +1,13 @@
-use winnow::{combinator::trace, BStr, ModalResult, Parser};
+use std::vec;
 
-use crate::extraction::{extract, Extract, Stream};
+use winnow::{combinator::trace, error::ErrMode, BStr, ModalResult, Parser};
+
+use crate::{
+    content::operators::{skip_malformed, Operator},
+    error::{Error, ErrorKind},
+    extraction::{extract, multicomment0, Extract, Reference, Stream},
+    follow_refs::Builder,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ContentStream(Vec<u8>);
@@ -11,9 +18,148 @@ impl Extract<'_> for ContentStream {
             let Stream {
                 structured: (),
                 content,
+                ..
             } = extract(i)?;
             Ok(Self(content))
         })
         .parse_next(input)
     }
 }
+
+/// A lazy iterator over the operators of a page, see
+/// [`Page::operators_iter`](super::Page::operators_iter).
+///
+/// Content streams are decoded one at a time, when the previous one is exhausted, such that
+/// only one of them is held in memory. From the specification, they behave as if they were
+/// concatenated with a white-space character in between: an operator may start in a stream
+/// and end in the next one, e.g. with its operands in the first stream and its keyword in the
+/// second.
+///
+/// Malformed tokens are skipped with a warning, and the iterator stops after the first error.
+/// The next stream is only decoded when the buffer ends in the middle of an operator.
+pub struct OperatorsIter<'b, B> {
+    builder: &'b B,
+    /// Content streams that remain to be decoded.
+    streams: vec::IntoIter<Reference<Stream<()>>>,
+    /// Decoded content that remains to be parsed: the end of the previous stream, which did not
+    /// make up a complete operator, followed by the current stream.
+    buffer: Vec<u8>,
+    /// Position of the next operator within the buffer.
+    position: usize,
+    /// Number of operators parsed so far, which may not exceed
+    /// [`Limits::max_operators`](crate::follow_refs::Limits).
+    count: usize,
+    done: bool,
+}
+
+impl<'b, B> OperatorsIter<'b, B> {
+    pub(crate) fn new(builder: &'b B, streams: Vec<Reference<Stream<()>>>) -> Self {
+        Self {
+            builder,
+            streams: streams.into_iter(),
+            buffer: Vec::new(),
+            position: 0,
+            count: 0,
+            done: false,
+        }
+    }
+
+    /// Iterate over the operators of content that is already decoded, e.g. a form XObject.
+    pub(crate) fn from_content(builder: &'b B, content: Vec<u8>) -> Self {
+        Self {
+            buffer: content,
+            ..Self::new(builder, Vec::new())
+        }
+    }
+}
+
+impl<B> OperatorsIter<'_, B>
+where
+    B: Builder,
+{
+    /// Decode the next content stream, and append it to what remains of the buffer. Returns
+    /// `false` if every stream was decoded already.
+    fn load_next(&mut self) -> ModalResult<bool> {
+        let Some(reference) = self.streams.next() else {
+            return Ok(false);
+        };
+
+        // A dangling content stream is a reference to the null object, i.e. empty.
+        let content = self
+            .builder
+            .resolve_reference(reference)?
+            .map(|stream| stream.content)
+            .unwrap_or_default();
+
+        self.buffer.drain(..self.position);
+        self.buffer.push(b'\n');
+        self.buffer.extend(content);
+        self.position = 0;
+
+        Ok(true)
+    }
+
+    fn parse_next(&mut self) -> ModalResult<Option<Operator>> {
+        loop {
+            let mut input = BStr::new(&self.buffer[self.position..]);
+            multicomment0.parse_next(&mut input)?;
+            self.position = self.buffer.len() - input.len();
+
+            match Operator::extract(&mut input) {
+                Ok(operator) => {
+                    self.position = self.buffer.len() - input.len();
+                    return Ok(Some(operator));
+                }
+                Err(ErrMode::Backtrack(_)) => {
+                    let mut input = BStr::new(&self.buffer[self.position..]);
+                    if skip_malformed(&mut input) {
+                        tracing::warn!("Skipping a malformed token in a content stream");
+                        self.position = self.buffer.len() - input.len();
+                    } else if !self.load_next()? {
+                        // The operator may continue in the next stream, if any.
+                        return Ok(None);
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl<B> Iterator for OperatorsIter<'_, B>
+where
+    B: Builder,
+{
+    type Item = ModalResult<Operator>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let limit = self.builder.options().limits.max_operators;
+
+        let result = match self
+            .builder
+            .check_deadline()
+            .and_then(|()| self.parse_next())
+        {
+            Ok(Some(_)) if self.count == limit => {
+                Err(Error::new(ErrorKind::TooManyOperators(limit)).cut())
+            }
+            Ok(Some(operator)) => {
+                self.count += 1;
+                return Some(Ok(operator));
+            }
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Err(err) => Err(err),
+        };
+
+        self.done = true;
+        self.buffer = Vec::new();
+        Some(result)
+    }
+}
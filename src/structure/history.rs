@@ -0,0 +1,406 @@
+//! Document history, as described by the chain of incremental updates.
+//!
+//! From the specification:
+//!
+//! > The contents of a PDF file can be updated incrementally without rewriting the entire file.
+//! > When updating a PDF file incrementally, changes shall be appended to the end of the file,
+//! > leaving its original contents intact.
+//!
+//! Each update appends a new cross-reference section and a new trailer, which points to the
+//! previous section through its `Prev` key. Livre models that chain as an ordered list of
+//! [`Revision`]s, which lets us resolve the cross-reference table of the document as of any
+//! revision.
+
+use std::collections::HashSet;
+
+use winnow::{
+    error::{ContextError, ErrMode},
+    BStr, ModalResult,
+};
+
+use crate::{
+    error::{Error, ErrorKind},
+    extraction::{extract, ReferenceId},
+    follow_refs::Options,
+};
+
+use super::{RefLocation, StartXRef, Trailer, XRefTable, XRefTrailerBlock};
+
+/// A single revision of the document, i.e. one cross-reference section and its trailer.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Revision {
+    /// Byte offset of the cross-reference section (be it a table or a stream) that introduced
+    /// this revision.
+    pub offset: usize,
+    /// The trailer of the revision.
+    pub trailer: Trailer,
+    /// Entries defined by this revision's cross-reference section, including free entries.
+    pub xrefs: Vec<(ReferenceId, RefLocation)>,
+}
+
+/// The ordered list of [`Revision`]s of a document, from the original document to the latest
+/// incremental update.
+#[derive(Debug, PartialEq, Clone)]
+pub struct History {
+    /// Revisions, oldest first.
+    revisions: Vec<Revision>,
+}
+
+impl History {
+    /// Extract the document history by following the `Prev` chain, starting from the last
+    /// `startxref` tag of the input.
+    pub fn extract(input: &BStr) -> ModalResult<Self> {
+        Self::extract_with(input, Options::default())
+    }
+
+    /// Like [`extract`](Self::extract), with the supplied [`Options`]: the `Prev` chain may
+    /// not be longer than [`Options::max_depth`], nor loop back on itself, and the sections
+    /// may not hold more than [`Limits::max_xref_entries`](crate::follow_refs::Limits) entries
+    /// altogether.
+    pub fn extract_with(input: &BStr, options: Options) -> ModalResult<Self> {
+        let StartXRef(start) = StartXRef::find(input)?;
+
+        let mut revisions = Vec::new();
+        let mut visited = HashSet::new();
+        let mut offset = Some(start);
+        let mut entries = 0;
+
+        while let Some(start) = offset {
+            if !visited.insert(start) {
+                return Err(Error::new(ErrorKind::Cycle).at(start).cut());
+            }
+            if revisions.len() == options.max_depth {
+                let limit = options.max_depth;
+                return Err(Error::new(ErrorKind::DepthLimitExceeded(limit))
+                    .at(start)
+                    .cut());
+            }
+
+            let XRefTrailerBlock { trailer, mut xrefs } = extract_section(input, start)?;
+            offset = trailer.prev;
+
+            if let Some(xref_stm) = trailer.xref_stm {
+                // Only the entries of the hidden stream are relevant: its trailer dictionary
+                // (including its own `Prev` key, if any) is ignored.
+                let XRefTrailerBlock { xrefs: hidden, .. } = extract_section(input, xref_stm)?;
+                xrefs = merge_hybrid_section(xrefs, hidden);
+            }
+
+            entries += xrefs.len();
+            if entries > options.limits.max_xref_entries {
+                let limit = options.limits.max_xref_entries;
+                return Err(Error::new(ErrorKind::TooManyXRefEntries(limit))
+                    .at(start)
+                    .cut());
+            }
+
+            revisions.push(Revision {
+                offset: start,
+                trailer,
+                xrefs,
+            });
+        }
+
+        // The chain is walked from the latest update backwards.
+        revisions.reverse();
+
+        Ok(Self { revisions })
+    }
+
+    /// All revisions, oldest first.
+    pub fn revisions(&self) -> &[Revision] {
+        &self.revisions
+    }
+
+    /// Number of revisions. A document without incremental update has a single revision.
+    pub fn len(&self) -> usize {
+        self.revisions.len()
+    }
+
+    /// Always false: a document has at least one revision.
+    pub fn is_empty(&self) -> bool {
+        self.revisions.is_empty()
+    }
+
+    /// The latest revision, which describes the current state of the document.
+    pub fn latest(&self) -> &Revision {
+        self.revisions
+            .last()
+            .expect("a history contains at least one revision")
+    }
+
+    /// Resolve the full cross-reference table of the current document.
+    pub fn cross_references(&self) -> XRefTable {
+        self.cross_references_at(self.len() - 1)
+            .expect("the latest revision exists")
+    }
+
+    /// Resolve the full cross-reference table of the document as of the supplied revision
+    /// (`0` being the original document). Returns `None` if the revision does not exist.
+    ///
+    /// Newer entries shadow older ones, regardless of their generation number. Free entries
+    /// are kept in the table, such that references to deleted objects resolve to null.
+    pub fn cross_references_at(&self, revision: usize) -> Option<XRefTable> {
+        let revisions = self.revisions.get(..=revision)?;
+
+        let table = revisions
+            .iter()
+            .flat_map(|Revision { xrefs, .. }| xrefs.iter().copied())
+            .collect();
+
+        Some(table)
+    }
+}
+
+/// Extract the cross-reference section (and its trailer) that starts at `offset`.
+///
+/// Any failure is unrecoverable, and reported at the offset of the section.
+fn extract_section(input: &BStr, offset: usize) -> ModalResult<XRefTrailerBlock> {
+    let bad_section = || Error::new(ErrorKind::BadXRefEntry).at(offset).cut();
+
+    let i = &mut input.get(offset..).ok_or_else(bad_section)?.as_ref();
+
+    extract(i).map_err(|err: ErrMode<ContextError>| match Error::from(err) {
+        error if error.kind() == &ErrorKind::Parse => bad_section(),
+        error => error.at(offset).cut(),
+    })
+}
+
+/// Merge the entries of a hybrid-reference section, i.e. a cross-reference table whose trailer
+/// points to a cross-reference stream through the `XRefStm` key.
+///
+/// The specification (section 7.5.8.4) requires PDF readers to look an object up in the
+/// cross-reference table first, then in the cross-reference stream pointed to by `XRefStm`, and
+/// only then to follow the `Prev` key.
+///
+/// Hybrid files are designed to remain readable by PDF 1.4 processors, which cannot read object
+/// streams: compressed objects are therefore either absent from the table or marked as free.
+/// Hence, in-use entries from the table take precedence, while the stream's entries take
+/// precedence over the table's free entries.
+fn merge_hybrid_section(
+    table: Vec<(ReferenceId, RefLocation)>,
+    stream: Vec<(ReferenceId, RefLocation)>,
+) -> Vec<(ReferenceId, RefLocation)> {
+    let in_table: HashSet<usize> = table
+        .iter()
+        .filter(|(_, location)| location.is_in_use())
+        .map(|(id, _)| id.object)
+        .collect();
+
+    let hidden: Vec<_> = stream
+        .into_iter()
+        .filter(|(id, _)| !in_table.contains(&id.object))
+        .collect();
+
+    let in_stream: HashSet<usize> = hidden.iter().map(|(id, _)| id.object).collect();
+
+    table
+        .into_iter()
+        .filter(|(id, location)| location.is_in_use() || !in_stream.contains(&id.object))
+        .chain(hidden)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use indoc::indoc;
+
+    use crate::follow_refs::Limits;
+
+    use super::*;
+
+    fn in_use(table: &XRefTable) -> HashMap<ReferenceId, RefLocation> {
+        table.in_use().collect()
+    }
+
+    /// A document with an original section and one incremental update that modifies object 1
+    /// (with a new generation number), deletes object 2 and adds object 4.
+    fn updated() -> Vec<u8> {
+        let original = indoc! {b"
+            xref
+            0 4
+            0000000000 65535 f\r
+            0000000010 00000 n\r
+            0000000020 00000 n\r
+            0000000030 00000 n\r
+            trailer
+            <</Size 4/Root 1 0 R>>
+        "};
+
+        let update = indoc! {b"
+            xref
+            0 3
+            0000000002 65535 f\r
+            0000000100 00001 n\r
+            0000000000 00001 f\r
+            4 1
+            0000000200 00000 n\r
+            trailer
+            <</Size 5/Root 1 1 R/Prev 0>>
+        "};
+
+        let mut input = original.to_vec();
+        input.extend_from_slice(update);
+        input.extend_from_slice(format!("startxref\n{}\n%%EOF\n", original.len()).as_bytes());
+        input
+    }
+
+    #[test]
+    fn revisions() {
+        let history = History::extract(updated().as_slice().as_ref()).unwrap();
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.revisions()[0].offset, 0);
+        assert_eq!(history.latest().offset, 120);
+        assert_eq!(history.latest().trailer.prev, Some(0));
+    }
+
+    #[test]
+    fn newest_shadows_oldest() {
+        let history = History::extract(updated().as_slice().as_ref()).unwrap();
+        let table = history.cross_references();
+
+        assert_eq!(table.get(&(1, 0).into()), None);
+        assert_eq!(table.get(&(2, 0).into()), None);
+        assert_eq!(table.entry(2), Some(((2, 1).into(), RefLocation::Free)));
+
+        let expected: HashMap<ReferenceId, RefLocation> = vec![
+            ((1, 1).into(), RefLocation::Plain(100)),
+            ((3, 0).into(), RefLocation::Plain(30)),
+            ((4, 0).into(), RefLocation::Plain(200)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(in_use(&table), expected);
+    }
+
+    #[test]
+    fn earlier_revision() {
+        let history = History::extract(updated().as_slice().as_ref()).unwrap();
+        let table = history.cross_references_at(0).unwrap();
+
+        let expected: HashMap<ReferenceId, RefLocation> = vec![
+            ((1, 0).into(), RefLocation::Plain(10)),
+            ((2, 0).into(), RefLocation::Plain(20)),
+            ((3, 0).into(), RefLocation::Plain(30)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(in_use(&table), expected);
+        assert!(history.cross_references_at(2).is_none());
+    }
+
+    #[test]
+    fn prev_cycle() {
+        // The section points to itself.
+        let mut input = indoc! {b"
+            xref
+            0 1
+            0000000000 65535 f\r
+            trailer
+            <</Size 1/Root 1 0 R/Prev 0>>
+        "}
+        .to_vec();
+        input.extend_from_slice(b"startxref\n0\n%%EOF\n");
+
+        let err = History::extract(input.as_slice().as_ref()).unwrap_err();
+        let error = Error::from(err);
+        assert_eq!(error.kind(), &ErrorKind::Cycle);
+        assert_eq!(error.offset(), Some(0));
+    }
+
+    #[test]
+    fn prev_depth() {
+        let input = updated();
+        let options = Options::default().with_max_depth(1);
+
+        let err = History::extract_with(input.as_slice().as_ref(), options).unwrap_err();
+        assert_eq!(Error::from(err).kind(), &ErrorKind::DepthLimitExceeded(1));
+
+        let options = Options::default().with_max_depth(2);
+        assert!(History::extract_with(input.as_slice().as_ref(), options).is_ok());
+    }
+
+    #[test]
+    fn xref_entries() {
+        let input = updated();
+        let total: usize = History::extract(input.as_slice().as_ref())
+            .unwrap()
+            .revisions()
+            .iter()
+            .map(|revision| revision.xrefs.len())
+            .sum();
+
+        let limits = |max_xref_entries| Limits {
+            max_xref_entries,
+            ..Limits::default()
+        };
+
+        let options = Options::default().with_limits(limits(total - 1));
+        let err = History::extract_with(input.as_slice().as_ref(), options).unwrap_err();
+        assert_eq!(
+            Error::from(err).kind(),
+            &ErrorKind::TooManyXRefEntries(total - 1)
+        );
+
+        let options = Options::default().with_limits(limits(total));
+        assert!(History::extract_with(input.as_slice().as_ref(), options).is_ok());
+    }
+
+    /// A hybrid-reference file: the table defines objects 1 and 3 (the latter being marked as
+    /// free), while the hidden cross-reference stream defines objects 1, 2 and 3.
+    fn hybrid() -> Vec<u8> {
+        let mut input =
+            b"9 0 obj\n<</Type/XRef/Size 4/Index[1 3]/W[1 2 1]/Root 1 0 R/Length 12>>stream\n"
+                .to_vec();
+        input.extend_from_slice(&[
+            0x01, 0x00, 0x50, 0x00, // object 1, shadowed by the table
+            0x01, 0x00, 0x60, 0x00, // object 2, plain
+            0x02, 0x00, 0x09, 0x00, // object 3, compressed
+        ]);
+        input.extend_from_slice(b"\nendstream\nendobj\n");
+
+        let start = input.len();
+
+        input.extend_from_slice(indoc! {b"
+            xref
+            0 4
+            0000000000 65535 f\r
+            0000000010 00000 n\r
+            0000000000 65535 f\r
+            0000000000 65535 f\r
+            trailer
+            <</Size 4/Root 1 0 R/XRefStm 0>>
+        "});
+        input.extend_from_slice(format!("startxref\n{start}\n%%EOF\n").as_bytes());
+        input
+    }
+
+    #[test]
+    fn hybrid_reference() {
+        let history = History::extract(hybrid().as_slice().as_ref()).unwrap();
+        assert_eq!(history.len(), 1);
+
+        let table = history.cross_references();
+
+        let expected: HashMap<ReferenceId, RefLocation> = vec![
+            ((1, 0).into(), RefLocation::Plain(10)),
+            ((2, 0).into(), RefLocation::Plain(0x60)),
+            (
+                (3, 0).into(),
+                RefLocation::Compressed {
+                    stream_id: 9,
+                    index: 0,
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(in_use(&table), expected);
+    }
+}
@@ -39,18 +39,23 @@
 //!    2. That table may contain a link to the previous cross-reference dictionary - if it does,
 //!       follow along and continue your way up the document until you have collected the full
 //!       cross-reference table.
+//!    3. Merge the collected sections, from the oldest to the newest: each update shadows the
+//!       entries of its predecessors (see [`History`]).
 //! 2. Iterate through the Pages dictionary.
 
 mod catalog;
 mod content;
+mod history;
 mod object_stream;
 mod pages;
 mod trailer_block;
 
 pub use catalog::{Catalog, PageLayout, PageMode};
-pub use content::ContentStream;
+pub use content::{ContentStream, OperatorsIter};
+pub use history::{History, Revision};
 pub use object_stream::ObjectStream;
 pub use pages::{
-    IndividualPageProperties, InheritablePageProperties, Page, PageTreeNode, Resources,
+    IndividualPageProperties, InheritablePageProperties, Page, PageTreeNode, PagesIter, Resources,
+    RotationAngle,
 };
-pub use trailer_block::{RefLocation, StartXRef, Trailer, XRefTrailerBlock};
+pub use trailer_block::{RefLocation, StartXRef, Trailer, XRefTable, XRefTrailerBlock};
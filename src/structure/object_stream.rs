@@ -1,6 +1,6 @@
 //! Definition of object streams.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::iter::Iterator;
 
 use winnow::{
@@ -11,7 +11,8 @@ use winnow::{
 };
 
 use crate::{
-    extraction::{extract, Reference, ReferenceId, Stream},
+    error::{Error, ErrorKind},
+    extraction::{extract, RawDict, Reference, ReferenceId, Stream},
     follow_refs::{Build, BuildFromRawDict, Builder, BuilderParser},
 };
 
@@ -49,7 +50,8 @@ impl ObjectStream {
     fn get_data(&self, reference: &ReferenceId) -> Option<&BStr> {
         self.map
             .get(reference)
-            .map(|&offset| self.content[offset..].as_ref())
+            .and_then(|&offset| self.content.get(offset..))
+            .map(BStr::new)
     }
 }
 
@@ -73,21 +75,44 @@ impl ObjectStream {
     ///
     /// This is only available to owned types because while following the linked list, we
     /// instantiate transient objects that cannot be referenced into.
+    ///
+    /// The chain may not loop back on itself, nor be longer than the
+    /// [maximum depth](crate::follow_refs::Options::max_depth).
     pub fn build_owned_object<B, T>(&self, reference: &ReferenceId, builder: &B) -> ModalResult<T>
     where
         T: Build,
         B: Builder,
     {
         if let Some(mut input) = self.get_data(reference) {
-            builder.as_parser().parse_next(&mut input)
-        } else if let Some(extends) = self.extends {
+            return builder.as_parser().parse_next(&mut input);
+        }
+
+        let limit = builder.options().max_depth;
+        let mut visited = HashSet::new();
+        let mut extends = self.extends;
+
+        while let Some(next) = extends {
             tracing::debug!("Reference not found in this stream. Checking the extended stream.");
 
-            let stream = builder.build_reference(extends)?;
-            stream.build_object(reference, builder)
-        } else {
-            Err(ErrMode::Backtrack(ContextError::new()))
+            if !visited.insert(next.id) {
+                return Err(Error::new(ErrorKind::Cycle).object(next.id).cut());
+            }
+            if visited.len() > limit {
+                return Err(Error::new(ErrorKind::DepthLimitExceeded(limit))
+                    .object(next.id)
+                    .cut());
+            }
+
+            let stream = builder.build_reference(next)?;
+
+            if let Some(mut input) = stream.get_data(reference) {
+                return builder.as_parser().parse_next(&mut input);
+            }
+
+            extends = stream.extends;
         }
+
+        Err(ErrMode::Backtrack(ContextError::new()))
     }
 }
 
@@ -100,8 +125,13 @@ impl Build for ObjectStream {
             let Stream {
                 structured: ObjectStreamDict { n, first, extends },
                 content,
+                ..
             } = builder.as_parser().parse_next(i)?;
 
+            if first > content.len() {
+                return Err(ErrMode::Backtrack(ContextError::new()));
+            }
+
             let (i, content) = content.split_at(first);
             let content = content.to_vec();
 
@@ -126,7 +156,6 @@ impl Build for ObjectStream {
 
 /// Transient object that represents the structured data associated with the [`Stream`], containing
 /// necessary information for the extraction of the [`ObjectStream`].
-#[derive(BuildFromRawDict)]
 struct ObjectStreamDict {
     /// Number of elements within the object stream
     pub n: usize,
@@ -136,11 +165,31 @@ struct ObjectStreamDict {
     pub extends: Option<Reference<ObjectStream>>,
 }
 
+impl BuildFromRawDict for ObjectStreamDict {
+    fn build_from_raw_dict<B>(dict: &mut RawDict<'_>, builder: &B) -> ModalResult<Self>
+    where
+        B: Builder,
+    {
+        let n = dict
+            .pop_and_build(&"N".into(), builder)?
+            .ok_or(ErrMode::Backtrack(ContextError::new()))?;
+        let first = dict
+            .pop_and_build(&"First".into(), builder)?
+            .ok_or(ErrMode::Backtrack(ContextError::new()))?;
+
+        // The reference is kept as is: building an `Option<Reference<_>>` would try to resolve it.
+        let extends = dict.pop_and_build(&"Extends".into(), builder)?;
+
+        Ok(Self { n, first, extends })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use indoc::indoc;
+    use rstest::rstest;
 
     #[test]
     fn object_stream() {
@@ -177,4 +226,43 @@ mod tests {
 
         assert_eq!(stream, expected);
     }
+    /// An object stream that holds the single object `object`, and extends `extends`.
+    fn indirect(id: usize, object: usize, extends: usize) -> Vec<u8> {
+        let header = format!("{object} 0\n");
+        let content = format!("{header}{object}");
+        format!(
+            "{id} 0 obj\n<</Type/ObjStm/N 1/First {}/Extends {extends} 0 R/Length {}>>stream\n{content}\nendstream\nendobj",
+            header.len(),
+            content.len()
+        )
+        .into_bytes()
+    }
+
+    #[rstest]
+    #[case(2, Ok(2))]
+    #[case(3, Ok(3))]
+    #[case(4, Err(ErrorKind::Cycle))]
+    fn extends(#[case] object: usize, #[case] expected: Result<usize, ErrorKind>) {
+        // Streams 20, 21 and 22 form a loop.
+        let streams = [
+            (20, indirect(20, 1, 21)),
+            (21, indirect(21, 2, 22)),
+            (22, indirect(22, 3, 20)),
+        ];
+
+        let builder: HashMap<ReferenceId, &BStr> = streams
+            .iter()
+            .map(|(id, input)| (ReferenceId::first(*id), BStr::new(input)))
+            .collect();
+
+        let stream: ObjectStream = builder
+            .build_reference(ReferenceId::first(20).into())
+            .unwrap();
+
+        let result: ModalResult<usize> =
+            stream.build_owned_object(&ReferenceId::first(object), &builder);
+        let result = result.map_err(|err| Error::from(err).kind().clone());
+
+        assert_eq!(result, expected);
+    }
 }
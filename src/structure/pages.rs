@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
 
 use winnow::{
     error::{ContextError, ErrMode},
@@ -6,18 +9,127 @@ use winnow::{
 };
 
 use crate::{
+    content::{
+        font::Font,
+        interpreter::{ContentInterpreter, Device},
+        layout::{find_tables, Painted, Table, TextPage, Viewport},
+        operators::{spanned_operators, Operator, SpannedOperator},
+        svg::SvgDevice,
+        Matrix,
+    },
+    error::{Error, ErrorKind},
     extraction::{
-        extract, Date, Extract, FromRawDict, Id, Map, MaybeArray, Name, OptRef, RawDict, Rectangle,
-        Reference, Stream, Todo,
+        extract, Date, Extract, FromRawDict, Id, Map, MaybeArray, Name, Object, OptRef, RawDict,
+        Rectangle, Reference, ReferenceId, Stream, Todo,
     },
-    follow_refs::{Build, Builder},
+    follow_refs::{Build, BuildFromRawDict, Builder},
+    serialization::{DictWriter, Serialize, SerializeDict},
 };
 
+#[cfg(feature = "render")]
+use crate::content::render::{Pixmap, Renderer};
+
+use super::OperatorsIter;
+
 /// Page resources.
-#[derive(Debug, PartialEq, Clone, FromRawDict)]
+///
+/// Each sub-dictionary may be an indirect object, which only a [`Builder`] can resolve.
+/// Entries that cannot be read are left empty, such that the others remain usable.
+#[derive(Debug, Default, PartialEq, Clone, SerializeDict)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Resources {
     /// Font dictionary.
+    #[livre(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::extraction::serialize_sorted")
+    )]
     pub font: HashMap<Name, Reference<()>>,
+    /// External objects, i.e. images and form XObjects, painted by the `Do` operator.
+    #[livre(rename = "XObject", default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::extraction::serialize_sorted")
+    )]
+    pub xobject: HashMap<Name, Reference<()>>,
+    /// Graphics state parameter dictionaries, set by the `gs` operator.
+    #[livre(rename = "ExtGState", default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::extraction::serialize_sorted")
+    )]
+    pub ext_gstate: Map<Object>,
+    /// Named colour spaces, set by the `CS` and `cs` operators.
+    #[livre(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::extraction::serialize_sorted")
+    )]
+    pub color_space: Map<Object>,
+    /// Named property lists of marked content, referred to by the `BDC` and `DP` operators.
+    #[livre(default)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::extraction::serialize_sorted")
+    )]
+    pub properties: Map<Object>,
+}
+
+impl FromRawDict<'_> for Resources {
+    fn from_raw_dict(dict: &mut RawDict<'_>) -> ModalResult<Self> {
+        fn entry<'de, T>(dict: &mut RawDict<'de>, key: &str) -> T
+        where
+            T: Extract<'de> + Default,
+        {
+            dict.pop_and_extract(&key.into())
+                .and_then(Result::ok)
+                .unwrap_or_default()
+        }
+
+        Ok(Self {
+            font: entry(dict, "Font"),
+            xobject: entry(dict, "XObject"),
+            ext_gstate: entry(dict, "ExtGState"),
+            color_space: entry(dict, "ColorSpace"),
+            properties: entry(dict, "Properties"),
+        })
+    }
+}
+
+impl BuildFromRawDict for Resources {
+    fn build_from_raw_dict<B>(dict: &mut RawDict<'_>, builder: &B) -> ModalResult<Self>
+    where
+        B: Builder,
+    {
+        fn entry<T, B>(dict: &mut RawDict<'_>, key: &str, builder: &B) -> T
+        where
+            T: Build + Default,
+            B: Builder,
+        {
+            dict.pop_and_build::<Option<T>, _>(&key.into(), builder)
+                .ok()
+                .flatten()
+                .flatten()
+                .unwrap_or_default()
+        }
+
+        Ok(Self {
+            font: entry(dict, "Font", builder),
+            xobject: entry(dict, "XObject", builder),
+            ext_gstate: entry(dict, "ExtGState", builder),
+            color_space: entry(dict, "ColorSpace", builder),
+            properties: entry(dict, "Properties", builder),
+        })
+    }
+}
+
+/// Build the `Resources` entry of a page tree element, whose sub-dictionaries may be
+/// indirect objects.
+fn build_resources<B>(dict: &mut RawDict<'_>, builder: &B) -> ModalResult<Option<OptRef<Resources>>>
+where
+    B: Builder,
+{
+    dict.pop_and_build(&"Resources".into(), builder)
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -43,6 +155,38 @@ impl Extract<'_> for RotationAngle {
     }
 }
 
+impl RotationAngle {
+    /// The clockwise rotation, in degrees.
+    pub fn degrees(self) -> u16 {
+        match self {
+            Self::Zero => 0,
+            Self::Quarter => 90,
+            Self::Full => 180,
+            Self::ThreeQuarters => 270,
+        }
+    }
+}
+
+/// Rotations are written in degrees.
+#[cfg(feature = "serde")]
+impl serde::Serialize for RotationAngle {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u16(self.degrees())
+    }
+}
+
+impl Serialize for RotationAngle {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.degrees().serialize(writer)
+    }
+}
+
 impl Build for RotationAngle {
     fn build<B>(input: &mut &BStr, _builder: &B) -> ModalResult<Self>
     where
@@ -154,7 +298,8 @@ pub struct IndividualPageProperties {
 ///
 /// Due to their inheritable nature, every field is wrapped in an [`Option`], whether
 /// they are optional or not in the final properties dictionary.
-#[derive(Debug, PartialEq, Clone, FromRawDict)]
+#[derive(Debug, PartialEq, Clone, FromRawDict, SerializeDict)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct InheritablePageProperties {
     /// A dictionary containing any resources required by the page contents.
     pub resources: Option<OptRef<Resources>>,
@@ -173,6 +318,9 @@ pub struct InheritablePageProperties {
 
 impl InheritablePageProperties {
     pub fn merge_with_parent(&mut self, parent_props: &InheritablePageProperties) {
+        if let Some(resources) = &parent_props.resources {
+            self.resources.get_or_insert_with(|| resources.clone());
+        }
         if let Some(media_box) = parent_props.media_box {
             self.media_box.get_or_insert(media_box);
         }
@@ -196,7 +344,8 @@ impl InheritablePageProperties {
 ///
 /// In Livre, the page tree is immediately transformed into a vector of [`Page`]s for simplicity.
 /// Hence this type, along with other low-level types, are made public for reference only.
-#[derive(Debug, FromRawDict, Clone, PartialEq)]
+#[derive(Debug, FromRawDict, SerializeDict, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PageTreeNode {
     /// Properties that can be passed down to `Kids` pages.
     #[livre(flatten)]
@@ -204,14 +353,20 @@ pub struct PageTreeNode {
     /// Array of indirect references other pages.
     #[livre(from = MaybeArray<Reference<PageElement>>)]
     kids: Vec<Reference<PageElement>>,
+    /// The number of leaf nodes (page objects) that are descendants of this node within
+    /// the page tree. Although required by the specification, some producers omit it.
+    count: Option<usize>,
 }
 
-impl Build for PageTreeNode {
-    fn build<B>(input: &mut &BStr, _: &B) -> ModalResult<Self>
+impl BuildFromRawDict for PageTreeNode {
+    fn build_from_raw_dict<B>(dict: &mut RawDict<'_>, builder: &B) -> ModalResult<Self>
     where
         B: Builder,
     {
-        extract(input)
+        let resources = build_resources(dict, builder)?;
+        let mut node = Self::from_raw_dict(dict)?;
+        node.props.resources = resources;
+        Ok(node)
     }
 }
 
@@ -220,25 +375,230 @@ impl PageTreeNode {
     where
         B: Builder,
     {
-        let Self { props, kids, .. } = self;
+        self.pages_iter(builder).collect()
+    }
+
+    /// Iterate over the pages that descend from this node, building them one at a time.
+    /// Inheritable properties are merged on the way down.
+    pub fn pages_iter<'b, B>(&self, builder: &'b B) -> PagesIter<'b, B>
+    where
+        B: Builder,
+    {
+        PagesIter {
+            builder,
+            stack: vec![(None, self.props.clone(), self.kids.clone().into_iter())],
+            visited: HashSet::new(),
+        }
+    }
+
+    /// The number of pages that descend from this node.
+    ///
+    /// Relies on the `Count` entry, and only walks the tree if it is missing.
+    pub fn page_count<B>(&self, builder: &B) -> ModalResult<usize>
+    where
+        B: Builder,
+    {
+        self.guarded_page_count(builder, &mut HashSet::new(), 1)
+    }
+
+    fn guarded_page_count<B>(
+        &self,
+        builder: &B,
+        visited: &mut HashSet<ReferenceId>,
+        depth: usize,
+    ) -> ModalResult<usize>
+    where
+        B: Builder,
+    {
+        if let Some(count) = self.count {
+            return Ok(count);
+        }
 
-        let mut result = Vec::new();
+        let mut count = 0;
 
-        for &kid in kids {
-            let mut element = builder.build_reference(kid)?;
+        for &kid in &self.kids {
+            match builder.resolve_reference(kid)? {
+                Some(PageElement::Page(_)) => count += 1,
+                Some(PageElement::Node(node)) => {
+                    enter_node(builder, visited, kid.id, depth)?;
+                    count += node.guarded_page_count(builder, visited, depth + 1)?;
+                }
+                None => {}
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Build the page at `index` (starting from zero), if it exists. Pages and subtrees that
+    /// come before it are skipped according to their `Type` and `Count`, without building them.
+    pub fn page<B>(&self, index: usize, builder: &B) -> ModalResult<Option<Page>>
+    where
+        B: Builder,
+    {
+        self.guarded_page(index, builder, &mut HashSet::new(), 1)
+    }
+
+    fn guarded_page<B>(
+        &self,
+        mut index: usize,
+        builder: &B,
+        visited: &mut HashSet<ReferenceId>,
+        depth: usize,
+    ) -> ModalResult<Option<Page>>
+    where
+        B: Builder,
+    {
+        for &kid in &self.kids {
+            let Some(summary) =
+                builder.resolve_reference(Reference::<ElementSummary>::from(kid.id))?
+            else {
+                tracing::warn!("Skipping dangling page tree element {:?}", kid.id);
+                continue;
+            };
+
+            // Kids that come before the page are skipped from their summary alone.
+            let skipped = match (summary.kind.0.as_slice(), summary.count) {
+                (b"Page", _) => (index > 0).then_some(1),
+                (b"Pages", Some(count)) => (index >= count).then_some(count),
+                _ => None,
+            };
+            if let Some(skipped) = skipped {
+                index -= skipped;
+                continue;
+            }
+
+            let Some(mut element) = builder.resolve_reference(kid)? else {
+                continue;
+            };
+            element.merge_props(&self.props);
+
+            match element {
+                PageElement::Page(page) => return Ok(Some(page)),
+                PageElement::Node(node) => {
+                    let count = node.page_count(builder)?;
+                    if index < count {
+                        enter_node(builder, visited, kid.id, depth)?;
+                        return node.guarded_page(index, builder, visited, depth + 1);
+                    }
+                    index -= count;
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Check that the page tree node `id`, which sits at the supplied `depth` below the root,
+/// was not visited already and is not nested too deep.
+///
+/// Without this guard, a node that is its own ancestor would make the walk loop forever.
+fn enter_node<B>(
+    builder: &B,
+    visited: &mut HashSet<ReferenceId>,
+    id: ReferenceId,
+    depth: usize,
+) -> ModalResult<()>
+where
+    B: Builder,
+{
+    let limit = builder.options().max_depth;
+
+    if !visited.insert(id) {
+        Err(Error::new(ErrorKind::Cycle).object(id).cut())
+    } else if depth >= limit {
+        Err(Error::new(ErrorKind::DepthLimitExceeded(limit))
+            .object(id)
+            .cut())
+    } else {
+        Ok(())
+    }
+}
+
+/// A lazy iterator over the pages of a page tree, see [`PageTreeNode::pages_iter`].
+///
+/// The iterator stops after the first error.
+pub struct PagesIter<'b, B> {
+    builder: &'b B,
+    /// For each level of the tree being walked, the node (unknown for the root), the
+    /// properties to pass down and the remaining kids.
+    stack: Vec<(
+        Option<ReferenceId>,
+        InheritablePageProperties,
+        std::vec::IntoIter<Reference<PageElement>>,
+    )>,
+    /// Intermediate nodes that were walked through.
+    visited: HashSet<ReferenceId>,
+}
+
+impl<B> PagesIter<'_, B>
+where
+    B: Builder,
+{
+    /// Like [`next`](Iterator::next), but also returns the reference to the page, along with
+    /// those of the intermediate nodes it descends from, from the top down. The root node is
+    /// left out, since the iterator does not know its reference.
+    pub(crate) fn next_with_ids(
+        &mut self,
+    ) -> Option<ModalResult<(Page, ReferenceId, Vec<ReferenceId>)>> {
+        loop {
+            let depth = self.stack.len();
+            let (_, props, kids) = self.stack.last_mut()?;
+
+            let Some(kid) = kids.next() else {
+                self.stack.pop();
+                continue;
+            };
+
+            let element = self.builder.resolve_reference(kid).and_then(|element| {
+                if let Some(PageElement::Node(_)) = element {
+                    enter_node(self.builder, &mut self.visited, kid.id, depth)?;
+                }
+                Ok(element)
+            });
+
+            let mut element = match element {
+                Ok(Some(element)) => element,
+                Ok(None) => {
+                    tracing::warn!("Skipping dangling page tree element {:?}", kid.id);
+                    continue;
+                }
+                Err(err) => {
+                    self.stack.clear();
+                    return Some(Err(err));
+                }
+            };
             element.merge_props(props);
 
             match element {
-                PageElement::Page(p) => result.push(p),
-                PageElement::Node(n) => result.extend(n.list_pages(builder)?),
+                PageElement::Page(page) => {
+                    let ancestors = self.stack.iter().filter_map(|(id, ..)| *id).collect();
+                    return Some(Ok((page, kid.id, ancestors)));
+                }
+                PageElement::Node(node) => {
+                    self.stack
+                        .push((Some(kid.id), node.props, node.kids.into_iter()))
+                }
             }
         }
+    }
+}
 
-        Ok(result)
+impl<B> Iterator for PagesIter<'_, B>
+where
+    B: Builder,
+{
+    type Item = ModalResult<Page>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_ids()
+            .map(|result| result.map(|(page, ..)| page))
     }
 }
 
-#[derive(Debug, FromRawDict, Clone, PartialEq)]
+#[derive(Debug, FromRawDict, SerializeDict, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Page {
     #[livre(flatten)]
     pub inheritable_props: InheritablePageProperties,
@@ -271,6 +631,8 @@ pub struct Page {
 }
 
 impl Page {
+    /// The decoded content of the page. Content streams are concatenated with a line break in
+    /// between, as required by the specification.
     pub fn build_content<B>(&self, builder: &B) -> ModalResult<Vec<u8>>
     where
         B: Builder,
@@ -280,13 +642,168 @@ impl Page {
             .iter()
             .copied()
             .map(|reference| {
-                let stream = builder.build_reference(reference)?;
-                Ok::<Vec<u8>, ErrMode<ContextError>>(stream.content)
+                // A dangling content stream is a reference to the null object, i.e. empty.
+                let content = builder
+                    .resolve_reference(reference)?
+                    .map(|stream| stream.content)
+                    .unwrap_or_default();
+                Ok::<Vec<u8>, ErrMode<ContextError>>(content)
             })
             .collect();
 
-        let content = contents?.into_iter().flatten().collect();
-        Ok(content)
+        Ok(contents?.join(&b'\n'))
+    }
+
+    /// Parse the operators of the page content, of which there may not be more than
+    /// [`Limits::max_operators`](crate::follow_refs::Limits). Malformed tokens are skipped,
+    /// with a warning.
+    pub fn operators<B>(&self, builder: &B) -> ModalResult<Vec<Operator>>
+    where
+        B: Builder,
+    {
+        self.operators_iter(builder).collect()
+    }
+
+    /// Iterate over the operators of the page content, decoding the content streams one at a
+    /// time (see [`OperatorsIter`]).
+    pub fn operators_iter<'b, B>(&self, builder: &'b B) -> OperatorsIter<'b, B>
+    where
+        B: Builder,
+    {
+        OperatorsIter::new(builder, self.contents.clone())
+    }
+
+    /// The decoded page content, along with its operators and their position within it (see
+    /// [`spanned_operators`]).
+    pub fn spanned_operators<B>(&self, builder: &B) -> ModalResult<(Vec<u8>, Vec<SpannedOperator>)>
+    where
+        B: Builder,
+    {
+        let content = self.build_content(builder)?;
+        let operators = spanned_operators(&content, builder.options().limits.max_operators)?;
+
+        Ok((content, operators))
+    }
+
+    /// The resources of the page, which are empty if the page does not define any.
+    pub fn resources<B>(&self, builder: &B) -> ModalResult<Resources>
+    where
+        B: Builder,
+    {
+        let resources = match &self.inheritable_props.resources {
+            Some(OptRef::Direct(resources)) => Some(resources.clone()),
+            Some(OptRef::Ref(reference)) => builder.resolve_reference(*reference)?,
+            None => None,
+        };
+
+        Ok(resources.unwrap_or_default())
+    }
+
+    /// Run the operators of the page, and report what they paint to the `device` (see the
+    /// [`interpreter`](crate::content::interpreter) module). The matrix `ctm` maps user space
+    /// to device space.
+    pub fn interpret<B, D>(&self, builder: &B, ctm: Matrix, device: &mut D) -> ModalResult<()>
+    where
+        B: Builder,
+        D: Device,
+    {
+        let mut interpreter = ContentInterpreter::new(builder, self.resources(builder)?, ctm);
+        interpreter.run(self.operators_iter(builder), device)
+    }
+
+    /// The fonts of the page resources, by resource name. Fonts that cannot be read are left
+    /// out, and replaced with a default font during text extraction.
+    pub fn fonts<B>(&self, builder: &B) -> ModalResult<HashMap<Name, Font>>
+    where
+        B: Builder,
+    {
+        let mut fonts = HashMap::new();
+
+        for (name, reference) in self.resources(builder)?.font {
+            match builder.resolve_reference(Reference::<Font>::from(reference.id)) {
+                Ok(Some(font)) => {
+                    fonts.insert(name, font);
+                }
+                Ok(None) => {}
+                Err(err) => tracing::warn!("Skipping unreadable font {:?}: {err:?}", reference.id),
+            }
+        }
+
+        Ok(fonts)
+    }
+
+    /// Position every glyph of the page, and group them into blocks of text in reading order
+    /// (see the [`layout`](crate::content::layout) module).
+    pub fn text_page<B>(&self, builder: &B) -> ModalResult<TextPage>
+    where
+        B: Builder,
+    {
+        let viewport = Viewport::of(self);
+        let mut painted = Painted::default();
+        self.interpret(builder, viewport.matrix(), &mut painted)?;
+
+        Ok(TextPage::new(painted.glyphs, viewport))
+    }
+
+    /// The plain text of the page, in reading order. Words are separated by a space, lines by
+    /// a line break and blocks by an empty line.
+    pub fn extract_text<B>(&self, builder: &B) -> ModalResult<String>
+    where
+        B: Builder,
+    {
+        Ok(self.text_page(builder)?.to_string())
+    }
+
+    /// The tables of the page, ruled or laid out with whitespace, from top to bottom.
+    pub fn tables<B>(&self, builder: &B) -> ModalResult<Vec<Table>>
+    where
+        B: Builder,
+    {
+        let viewport = Viewport::of(self);
+        let mut painted = Painted::default();
+        self.interpret(builder, viewport.matrix(), &mut painted)?;
+
+        Ok(find_tables(&painted.glyphs, &painted.segments, viewport))
+    }
+
+    /// An SVG preview of the page, as it is displayed (see the [`svg`](crate::content::svg)
+    /// module).
+    pub fn to_svg<B>(&self, builder: &B) -> ModalResult<String>
+    where
+        B: Builder,
+    {
+        let viewport = Viewport::of(self);
+        let mut device = SvgDevice::new(viewport);
+        self.interpret(builder, viewport.matrix(), &mut device)?;
+
+        Ok(device.finish())
+    }
+
+    /// The page rasterized at `dpi` dots per inch, as it is displayed (see the
+    /// [`render`](crate::content::render) module). Fails if the page holds more than
+    /// [`Limits::max_pixels`](crate::follow_refs::Limits) pixels at this resolution.
+    #[cfg(feature = "render")]
+    pub fn render<B>(&self, builder: &B, dpi: f32) -> ModalResult<Pixmap>
+    where
+        B: Builder,
+    {
+        let viewport = Viewport::of(self);
+        let scale = dpi / 72.0;
+        let width = (viewport.width() * scale).ceil().max(1.0) as u32;
+        let height = (viewport.height() * scale).ceil().max(1.0) as u32;
+
+        let limit = builder.options().limits.max_pixels;
+        if width as u64 * height as u64 > limit as u64 {
+            return Err(Error::new(ErrorKind::TooManyPixels(limit)).cut());
+        }
+
+        let ctm = viewport
+            .matrix()
+            .then(&Matrix::from([scale, 0.0, 0.0, scale, 0.0, 0.0]));
+        let mut renderer = Renderer::new(builder, width, height);
+        self.interpret(builder, ctm, &mut renderer)?;
+
+        Ok(renderer.finish())
     }
 }
 
@@ -310,6 +827,34 @@ impl PageElement {
     }
 }
 
+/// The entries of a page tree element that are needed to skip it, without building the
+/// rest of it.
+#[derive(Debug, BuildFromRawDict)]
+struct ElementSummary {
+    #[livre(rename = "Type")]
+    kind: Name,
+    count: Option<usize>,
+}
+
+/// The `Type` key, which is consumed by [`PageElement`] during extraction, is written back.
+impl SerializeDict for PageElement {
+    fn serialize_entries<W>(&self, dict: &mut DictWriter<'_, W>) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            Self::Page(page) => {
+                dict.entry(b"Type", &Name::from("Page"))?;
+                page.serialize_entries(dict)
+            }
+            Self::Node(node) => {
+                dict.entry(b"Type", &Name::from("Pages"))?;
+                node.serialize_entries(dict)
+            }
+        }
+    }
+}
+
 impl FromRawDict<'_> for PageElement {
     fn from_raw_dict(dict: &mut RawDict<'_>) -> ModalResult<Self> {
         let Name(page_type) = dict
@@ -326,22 +871,32 @@ impl FromRawDict<'_> for PageElement {
     }
 }
 
-impl Build for PageElement {
-    fn build<B>(input: &mut &BStr, _builder: &B) -> ModalResult<Self>
+impl BuildFromRawDict for PageElement {
+    fn build_from_raw_dict<B>(dict: &mut RawDict<'_>, builder: &B) -> ModalResult<Self>
     where
         B: Builder,
     {
-        extract(input)
+        let resources = build_resources(dict, builder)?;
+
+        let mut element = Self::from_raw_dict(dict)?;
+        match &mut element {
+            Self::Page(page) => page.inheritable_props.resources = resources,
+            Self::Node(node) => node.props.resources = resources,
+        }
+
+        Ok(element)
     }
 }
 
 #[cfg(test)]
 mod tests {
 
+    use std::{cell::RefCell, collections::HashMap};
+
     use indoc::indoc;
     use rstest::rstest;
 
-    use crate::extraction::Extract;
+    use crate::extraction::{Extract, Indirect, ReferenceId};
 
     use super::*;
 
@@ -432,4 +987,276 @@ mod tests {
         let InheritablePageProperties { media_box, .. } = page.inheritable_props;
         assert_eq!(media_box, Some(expected));
     }
+
+    #[test]
+    fn dangling_references() {
+        let kids =
+            BStr::new(b"2 0 obj\n<</Type/Page/MediaBox[0 0 612 792]/Contents[4 0 R]>>\nendobj");
+
+        let mut builder = HashMap::new();
+        builder.insert(ReferenceId::first(2), kids);
+
+        // Objects 3 and 4 are undefined, hence references to the null object.
+        let node =
+            PageTreeNode::extract(&mut BStr::new(b"<</Type/Pages/Kids[2 0 R 3 0 R]>>")).unwrap();
+
+        let pages = node.list_pages(&builder).unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].build_content(&builder).unwrap(), b"");
+    }
+
+    #[test]
+    fn indirect_resources() {
+        let objects: [(usize, &[u8]); 4] = [
+            (
+                2,
+                b"2 0 obj\n<</Type/Page/Resources<</ExtGState 6 0 R/XObject 7 0 R/Font 8 0 R/ColorSpace 9 0 R>>>>\nendobj",
+            ),
+            (6, b"6 0 obj\n<</GS1 10 0 R>>\nendobj"),
+            (7, b"7 0 obj\n<</Im1 11 0 R>>\nendobj"),
+            (8, b"8 0 obj\n<</F1 5 0 R>>\nendobj"),
+        ];
+
+        let builder: HashMap<ReferenceId, &BStr> = objects
+            .into_iter()
+            .map(|(object, input)| (ReferenceId::first(object), BStr::new(input)))
+            .collect();
+
+        let node = PageTreeNode::extract(&mut BStr::new(b"<</Type/Pages/Kids[2 0 R]>>")).unwrap();
+        assert_eq!(node.page_count(&builder).unwrap(), 1);
+
+        let pages = node.list_pages(&builder).unwrap();
+        let resources = pages[0].resources(&builder).unwrap();
+
+        assert_eq!(resources.font.len(), 1);
+        assert_eq!(resources.xobject.len(), 1);
+        assert_eq!(resources.ext_gstate.len(), 1);
+        // Object 9 is undefined: the colour spaces are empty rather than the page unreadable.
+        assert!(resources.color_space.is_empty());
+    }
+
+    /// A page tree with two subtrees. The page in the first subtree cannot be built, since
+    /// its rotation is not a multiple of 90.
+    fn tree() -> (PageTreeNode, HashMap<ReferenceId, &'static BStr>) {
+        let objects: [(usize, &[u8]); 5] = [
+            (2, b"2 0 obj\n<</Type/Pages/Kids[4 0 R]/Count 1>>\nendobj"),
+            (
+                3,
+                b"3 0 obj\n<</Type/Pages/Kids[5 0 R 6 0 R]/Count 2/Rotate 90>>\nendobj",
+            ),
+            (4, b"4 0 obj\n<</Type/Page/Rotate 45>>\nendobj"),
+            (5, b"5 0 obj\n<</Type/Page/Contents 7 0 R>>\nendobj"),
+            (
+                6,
+                b"6 0 obj\n<</Type/Page/Rotate 0/Contents 8 0 R>>\nendobj",
+            ),
+        ];
+
+        let builder = objects
+            .into_iter()
+            .map(|(object, input)| (ReferenceId::first(object), BStr::new(input)))
+            .collect();
+
+        let node = PageTreeNode::extract(&mut BStr::new(
+            b"<</Type/Pages/Kids[2 0 R 3 0 R]/Count 3/MediaBox[0 0 612 792]>>",
+        ))
+        .unwrap();
+
+        (node, builder)
+    }
+
+    #[test]
+    fn lazy_access() {
+        let (node, builder) = tree();
+
+        assert_eq!(node.page_count(&builder).unwrap(), 3);
+
+        // Building every page fails...
+        assert!(node.list_pages(&builder).is_err());
+
+        // ...but the pages of the second subtree can be reached by skipping the first one.
+        let page = node.page(1, &builder).unwrap().unwrap();
+        assert_eq!(page.contents, vec![ReferenceId::first(7).into()]);
+        assert_eq!(page.inheritable_props.rotate, Some(RotationAngle::Quarter));
+        assert_eq!(
+            page.inheritable_props.media_box,
+            Some(Rectangle::from((0.0, 0.0, 612.0, 792.0)))
+        );
+
+        let page = node.page(2, &builder).unwrap().unwrap();
+        assert_eq!(page.inheritable_props.rotate, Some(RotationAngle::Zero));
+
+        assert_eq!(node.page(3, &builder).unwrap(), None);
+    }
+
+    /// Records the objects it resolves.
+    struct CountingBuilder(
+        HashMap<ReferenceId, &'static BStr>,
+        RefCell<Vec<ReferenceId>>,
+    );
+
+    impl Builder for CountingBuilder {
+        fn resolve_reference<T>(&self, reference: Reference<T>) -> ModalResult<Option<T>>
+        where
+            T: Build,
+        {
+            self.1.borrow_mut().push(reference.id);
+
+            let Some(&(mut input)) = self.0.get(&reference.id) else {
+                return Ok(None);
+            };
+            let Indirect { inner, .. } = Indirect::build(&mut input, self)?;
+
+            Ok(Some(inner))
+        }
+    }
+
+    #[test]
+    fn skipped_pages_are_not_built() {
+        // Every page but the last one has an invalid rotation, and cannot be built.
+        let objects: HashMap<ReferenceId, &'static BStr> = (10..1010)
+            .map(|id| {
+                let rotate = if id == 1009 { 0 } else { 45 };
+                let page = format!("{id} 0 obj\n<</Type/Page/Rotate {rotate}>>\nendobj");
+                (ReferenceId::first(id), BStr::new(page.leak()))
+            })
+            .collect();
+
+        let kids: Vec<String> = (10..1010).map(|id| format!("{id} 0 R")).collect();
+        let node = PageTreeNode::extract(&mut BStr::new(&format!(
+            "<</Type/Pages/Kids[{}]/Count 1000>>",
+            kids.join(" ")
+        )))
+        .unwrap();
+
+        let builder = CountingBuilder(objects, RefCell::new(Vec::new()));
+
+        let page = node.page(999, &builder).unwrap().unwrap();
+        assert_eq!(page.inheritable_props.rotate, Some(RotationAngle::Zero));
+
+        // Each kid is read once, and the target page once more to build it.
+        assert_eq!(builder.1.borrow().len(), 1001);
+    }
+
+    #[test]
+    fn missing_count() {
+        let (_, builder) = tree();
+
+        let node =
+            PageTreeNode::extract(&mut BStr::new(b"<</Type/Pages/Kids[3 0 R 9 0 R]>>")).unwrap();
+
+        assert_eq!(node.page_count(&builder).unwrap(), 2);
+
+        let pages: Vec<_> = node
+            .pages_iter(&builder)
+            .collect::<ModalResult<_>>()
+            .unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(node.page(1, &builder).unwrap(), Some(pages[1].clone()));
+    }
+
+    #[test]
+    fn cycle() {
+        // Node 3 lists its own parent among its kids.
+        let objects: [(usize, &[u8]); 3] = [
+            (2, b"2 0 obj\n<</Type/Pages/Kids[3 0 R]>>\nendobj"),
+            (3, b"3 0 obj\n<</Type/Pages/Kids[4 0 R 2 0 R]>>\nendobj"),
+            (4, b"4 0 obj\n<</Type/Page>>\nendobj"),
+        ];
+
+        let builder: HashMap<ReferenceId, &BStr> = objects
+            .into_iter()
+            .map(|(object, input)| (ReferenceId::first(object), BStr::new(input)))
+            .collect();
+
+        let node = PageTreeNode::extract(&mut BStr::new(b"<</Type/Pages/Kids[2 0 R]>>")).unwrap();
+
+        let kind = |err| Error::from(err).kind().clone();
+
+        assert_eq!(
+            node.page_count(&builder).map_err(kind),
+            Err(ErrorKind::Cycle)
+        );
+        assert_eq!(node.page(0, &builder).map_err(kind), Err(ErrorKind::Cycle));
+
+        let mut pages = node.pages_iter(&builder);
+        assert!(pages.next().unwrap().is_ok());
+        assert_eq!(pages.next().unwrap().map_err(kind), Err(ErrorKind::Cycle));
+        assert!(pages.next().is_none());
+    }
+
+    #[rstest]
+    #[case(b"<</Type/Page/MediaBox[0 0 612 792]/Contents 4 0 R>>")]
+    #[case(b"<</Type/Page/Rotate 90/Contents[4 0 R 5 0 R]/UserUnit 2.5>>")]
+    #[case(b"<</Type/Page/Resources<</Font<</F1 7 0 R>>>>>>")]
+    #[case(b"<</Type/Page/Resources<</ExtGState 6 0 R/Font<</F1 5 0 R>>>>>>")]
+    #[case(b"<</Type/Pages/Kids[2 0 R 3 0 R]/CropBox[0 0 10 10]>>")]
+    #[case(b"<</Type/Pages/Kids[2 0 R]/Resources 5 0 R>>")]
+    fn round_trip(#[case] input: &[u8]) {
+        let element = PageElement::extract(&mut input.as_ref()).unwrap();
+        let bytes = element.to_bytes();
+        let result = PageElement::extract(&mut bytes.as_slice().as_ref()).unwrap();
+        assert_eq!(element, result);
+    }
+
+    #[test]
+    fn defaults() {
+        let page = Page::extract(&mut BStr::new(b"<</Type/Page/UserUnit 1.0>>")).unwrap();
+        assert_eq!(page.to_bytes(), b"<<>>");
+
+        let element = PageElement::Page(page);
+        assert_eq!(element.to_bytes(), b"<</Type /Page>>");
+    }
+
+    /// A builder holding one content stream per object, starting from object 7.
+    fn streams(contents: &[&str]) -> HashMap<ReferenceId, &'static BStr> {
+        contents
+            .iter()
+            .enumerate()
+            .map(|(i, content)| {
+                let object = format!(
+                    "{} 0 obj\n<</Length {}>>stream\n{content}\nendstream\nendobj",
+                    i + 7,
+                    content.len()
+                );
+                let object: &'static [u8] = object.into_bytes().leak();
+                (ReferenceId::first(i + 7), BStr::new(object))
+            })
+            .collect()
+    }
+
+    #[rstest]
+    // The operands of `Tf` lie in the first stream, its keyword in the second.
+    #[case(&["BT /F1", "12 Tf (Hi) Tj", "ET"], 4)]
+    // Streams are separated by white-space, hence `q` and `Q` do not run together.
+    #[case(&["1 0 0 1 0 0 cm q", "Q"], 3)]
+    #[case(&["q", "", "Q"], 2)]
+    // A stray delimiter is skipped, rather than ending the content.
+    #[case(&["q ) Q", "BT ET"], 4)]
+    #[case(&["q 1 2 ] Q", "BT ET"], 4)]
+    // Inline image data is not parsed as operators.
+    #[case(&["q BI /W 1 /H 1 /BPC 8 /CS /G ID (\u{7f} EI Q", "BT /F1 12 Tf (x) Tj ET"], 7)]
+    // An inline image may continue in the next stream.
+    #[case(&["q BI /W 1 /H 1 /BPC 8 /CS /G ID (", "EI Q"], 3)]
+    fn split_streams(#[case] contents: &[&str], #[case] expected: usize) {
+        let builder = streams(contents);
+        let mut page = Page::extract(&mut BStr::new(b"<</Type/Page>>")).unwrap();
+        page.contents = (0..contents.len())
+            .map(|i| ReferenceId::first(i + 7).into())
+            .collect();
+
+        let operators = page.operators(&builder).unwrap();
+        assert_eq!(operators.len(), expected);
+        assert!(!operators
+            .iter()
+            .any(|operator| matches!(operator, Operator::NotImplemented(_))));
+
+        // Concatenating the streams yields the same operators.
+        let content = page.build_content(&builder).unwrap();
+        assert_eq!(content, contents.join("\n").as_bytes());
+
+        let spanned = spanned_operators(&content, usize::MAX).unwrap();
+        let concatenated: Vec<_> = spanned.into_iter().map(|op| op.operator).collect();
+        assert_eq!(operators, concatenated);
+    }
 }
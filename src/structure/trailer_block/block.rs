@@ -41,10 +41,11 @@ use super::{plain, stream, RefLocation};
 #[derive(Debug, PartialEq)]
 pub struct XRefTrailerBlock {
     /// The [PDF trailer](Trailer). Only the last one (i.e., the first one that is extracted) is
-    /// useful.
+    /// needed to understand the current document.
     pub trailer: Trailer,
-    /// The collected cross-reference. The full cross-reference table is the concatenation of all
-    /// tables.
+    /// The collected cross-reference entries, including free entries. The full cross-reference
+    /// table is obtained by merging all sections, newer entries shadowing older ones
+    /// (see [`History`](crate::structure::History)).
     pub xrefs: Vec<(ReferenceId, RefLocation)>,
 }
 
@@ -4,8 +4,10 @@ mod ref_location;
 mod startxref;
 mod stream;
 mod trailer;
+mod xref_table;
 
 pub use block::XRefTrailerBlock;
 pub use ref_location::RefLocation;
 pub use startxref::StartXRef;
 pub use trailer::Trailer;
+pub use xref_table::XRefTable;
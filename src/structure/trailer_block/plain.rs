@@ -1,7 +1,7 @@
 //! Describe how to extract "plain" references, i.e. indirect objects that are not compressed
 //! within an PDF [stream](crate::extraction::Stream).
 
-use std::{fmt::Debug, str::FromStr};
+use std::{num::ParseIntError, str::FromStr};
 
 use winnow::{
     ascii::{line_ending, multispace0, multispace1},
@@ -11,22 +11,24 @@ use winnow::{
     BStr, ModalParser, ModalResult, Parser,
 };
 
-use crate::extraction::{extract, Extract, ReferenceId};
+use crate::{
+    error::{Error, ErrorKind},
+    extraction::{extract, Extract, ReferenceId},
+};
 
 use super::{RefLocation, XRefTrailerBlock};
 
 /// Decimal number, with fixed number of characters.
-fn dec_num<'de, T, E>(count: usize) -> impl ModalParser<&'de BStr, T, ContextError>
+fn dec_num<'de, T>(count: usize) -> impl ModalParser<&'de BStr, T, ContextError>
 where
-    T: FromStr<Err = E>,
-    E: Debug,
+    T: FromStr<Err = ParseIntError>,
 {
     move |input: &mut &'de BStr| {
-        let num = take_while(count, b'0'..=b'9').parse_next(input)?;
-        let num = unsafe { std::str::from_utf8_unchecked(num) };
-        let n = num.parse().expect("correct by construction");
-
-        Ok(n)
+        take_while(count, b'0'..=b'9')
+            // SAFETY: the slice only contains ASCII digits. Parsing may still fail on overflow,
+            // e.g. for a generation number above 65535.
+            .try_map(|num| unsafe { std::str::from_utf8_unchecked(num) }.parse::<T>())
+            .parse_next(input)
     }
 }
 
@@ -66,21 +68,29 @@ fn xref_entry(input: &mut &BStr) -> ModalResult<(usize, u16, bool)> {
 /// 0000000003 65535 f   -> Cross-reference entry
 /// 0000000017 00000 n   -> Another Xref entry
 /// ```
-fn xref_subsection(input: &mut &BStr) -> ModalResult<Vec<(ReferenceId, usize)>> {
+///
+/// Free entries are kept, since they shadow definitions from previous revisions.
+fn xref_subsection(input: &mut &BStr) -> ModalResult<Vec<(ReferenceId, RefLocation)>> {
     let (initial, n) = separated_pair(usize::extract, b' ', usize::extract).parse_next(input)?;
 
     line_ending(input)?;
 
+    if initial.checked_add(n).is_none() {
+        return Err(Error::new(ErrorKind::BadXRefEntry).cut());
+    }
+
     let entries: Vec<(usize, u16, bool)> = repeat(n, xref_entry).parse_next(input)?;
 
     let res = entries
         .into_iter()
         .enumerate()
-        .filter_map(|(i, (offset, gen, in_use))| {
-            if !in_use {
-                return None;
-            }
-            Some((ReferenceId::new(initial + i, gen), offset))
+        .map(|(i, (offset, gen, in_use))| {
+            let location = if in_use {
+                RefLocation::Plain(offset)
+            } else {
+                RefLocation::Free
+            };
+            (ReferenceId::new(initial + i, gen), location)
         })
         .collect();
 
@@ -92,10 +102,7 @@ pub fn xref(input: &mut &BStr) -> ModalResult<Vec<(ReferenceId, RefLocation)>> {
     (b"xref", multispace1).parse_next(input)?;
 
     let mut it = iterator(&mut *input, terminated(xref_subsection, multispace0));
-    let res = it
-        .flatten()
-        .map(|(r, loc)| (r, RefLocation::Plain(loc)))
-        .collect();
+    let res = it.flatten().collect();
     it.finish()?;
 
     Ok(res)
@@ -135,7 +142,9 @@ mod tests {
             0 1
             0000000000 65535 f 
         "},
-        Vec::new()
+        vec![
+            ((0, 65535).into(), RefLocation::Free),
+        ]
     )]
     #[case(
         indoc!{b"
@@ -144,7 +153,8 @@ mod tests {
             0000000035 00000 n 
         "},
         vec![
-            ((11, 0).into(), 35),
+            ((10, 1).into(), RefLocation::Free),
+            ((11, 0).into(), RefLocation::Plain(35)),
         ]
     )]
     #[case(
@@ -155,12 +165,12 @@ mod tests {
             0000050020 03482 n 
         "},
         vec![
-            ((110, 20).into(), 10001),
-            ((111, 0).into(), 10020),
-            ((112, 3482).into(), 50020),
+            ((110, 20).into(), RefLocation::Plain(10001)),
+            ((111, 0).into(), RefLocation::Plain(10020)),
+            ((112, 3482).into(), RefLocation::Plain(50020)),
         ]
     )]
-    fn subsection(#[case] input: &[u8], #[case] expected: Vec<(ReferenceId, usize)>) {
+    fn subsection(#[case] input: &[u8], #[case] expected: Vec<(ReferenceId, RefLocation)>) {
         let res = xref_subsection(&mut input.as_ref()).unwrap();
         assert_eq!(expected, res)
     }
@@ -178,8 +188,10 @@ mod tests {
             0000000300 00000 n\r
         "},
         vec![
+            ((0, 65535).into(), RefLocation::Free),
             ((1, 0).into(), RefLocation::Plain(200)),
             ((2, 1).into(), RefLocation::Plain(220)),
+            ((3, 1).into(), RefLocation::Free),
             ((4, 0).into(), RefLocation::Plain(300)),
         ]
     )]
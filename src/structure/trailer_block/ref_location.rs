@@ -6,6 +6,9 @@ use std::fmt::Debug;
 /// The PDF specification allows indirect objects to be included within an object stream.
 /// Livre encodes that fact within the `RefLocation` object directly.
 ///
+/// Cross-reference sections may also mark an object number as *free*, meaning that the object
+/// was deleted by that revision. Such entries shadow any previous definition of the object.
+///
 /// ## Side note: performance implication
 ///
 /// We might be better off using two dedicated data structures instead of a single one with this
@@ -15,4 +18,12 @@ use std::fmt::Debug;
 pub enum RefLocation {
     Plain(usize),
     Compressed { stream_id: usize, index: usize },
+    Free,
+}
+
+impl RefLocation {
+    /// Whether the entry points to an object that is in use.
+    pub fn is_in_use(&self) -> bool {
+        !matches!(self, Self::Free)
+    }
 }
@@ -1,12 +1,11 @@
 use std::fmt::Debug;
 
-use winnow::{
-    ascii::multispace1,
-    error::{ContextError, ErrMode},
-    BStr, ModalResult, Parser,
-};
+use winnow::{ascii::multispace1, BStr, ModalResult, Parser};
 
-use crate::extraction::{extract, Extract};
+use crate::{
+    error::{Error, ErrorKind},
+    extraction::{extract, Extract},
+};
 
 /// Extractor type for the `startxref` tag in a PDF document.
 #[derive(Debug, Clone, Copy)]
@@ -34,9 +33,10 @@ impl StartXRef {
         let pos = window
             .windows(TAG.len())
             .rposition(|w| w == TAG)
-            .ok_or(ErrMode::Cut(ContextError::new()))?;
+            .ok_or_else(|| Error::new(ErrorKind::MissingStartXRef).cut())?;
 
         Self::extract(&mut window[pos..].into())
+            .map_err(|_| Error::new(ErrorKind::MissingStartXRef).cut())
     }
 }
 
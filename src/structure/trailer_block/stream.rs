@@ -8,6 +8,7 @@ use winnow::{
 };
 
 use crate::{
+    error::{Error, ErrorKind},
     extraction::{extract, Extract, FromRawDict, Indirect, ReferenceId, Stream},
     structure::Trailer,
 };
@@ -47,20 +48,19 @@ struct FieldSize {
 impl Extract<'_> for FieldSize {
     fn extract(input: &mut &BStr) -> ModalResult<Self> {
         let [f1, f2, f3] = extract(input)?;
-        Ok(Self {
-            f1,
-            f2: NonZeroU8::new(f2).expect("guaranteed by the specification"),
-            f3,
-        })
+
+        // The specification requires the second field, which points to the object.
+        let f2 = NonZeroU8::new(f2).ok_or_else(|| Error::new(ErrorKind::BadXRefEntry).cut())?;
+
+        Ok(Self { f1, f2, f3 })
     }
 }
 
+/// Big-endian number. Fields wider than a `usize` overflow, their high-order bytes being
+/// discarded.
 fn parse_num(num: &[u8]) -> usize {
-    let mut res = 0;
-    for (i, &digit) in num.iter().rev().enumerate() {
-        res += (digit as usize) * 256usize.pow(i as u32);
-    }
-    res
+    num.iter()
+        .fold(0, |res: usize, &digit| (res << 8) | digit as usize)
 }
 
 /// Different possible types of xref entry.
@@ -73,20 +73,23 @@ fn parse_num(num: &[u8]) -> usize {
 enum EntryType {
     /// Type `0` entries define the linked list of free (unused) objects.
     ///
-    /// Livre simply discards those, since browsing a document's history is not one of Livre's
-    /// goal.
-    Type0,
+    /// Livre keeps track of those, since a free entry shadows the definition of the same object
+    /// in a previous revision of the document.
+    Type0 {
+        /// Number of bytes occupied by the next free object number, which Livre does not need.
+        next_free_len: NonZeroU8,
+        /// Number of bytes occupied by the generation number to use if the object number is
+        /// used again.
+        generation_number_len: u8,
+    },
     /// Type `1` entries define objects that are in use but uncompressed, i.e. stored as
     /// free-standing indirect objct in the PDF body.
     Type1 {
         /// Number of bytes handling the byte offset of the indirect object.
         byte_offset_len: NonZeroU8,
-        /// Number of bytes occupied by the generation number.
-        ///
-        /// Livre does not keep track of the generation number for the cross-reference table...
-        /// Since Livre is only intrested in the layout of the *current document*, we can safely
-        /// disregard the generation number and focus on the ID.
-        _generation_number_len: u8,
+        /// Number of bytes occupied by the generation number, which defaults to zero if
+        /// the field is absent.
+        generation_number_len: u8,
     },
     /// Type `2` entries define objects that are in use and compressed, i.e. stored within
     /// an object stream.
@@ -104,22 +107,43 @@ enum EntryType {
         index_len: u8,
     },
     /// The `Unknown` variant is used to handle additional types "gracefully".
-    Unknown,
+    Unknown {
+        /// Number of bytes occupied by the remaining fields.
+        remaining_len: usize,
+    },
 }
 
-impl Parser<&BStr, Option<RefLocation>, ContextError> for EntryType {
-    fn parse_next(&mut self, input: &mut &BStr) -> Result<Option<RefLocation>, ContextError> {
+/// Parsed entries are paired with their generation number. Objects stored within an object
+/// stream always have a generation number of zero.
+impl Parser<&BStr, Option<(u16, RefLocation)>, ContextError> for EntryType {
+    fn parse_next(
+        &mut self,
+        input: &mut &BStr,
+    ) -> Result<Option<(u16, RefLocation)>, ContextError> {
         match self {
-            Self::Type0 => Ok(None),
+            Self::Type0 {
+                next_free_len,
+                generation_number_len,
+            } => {
+                take(next_free_len.get()).parse_next(input)?;
+                let generation = take(*generation_number_len)
+                    .map(parse_num)
+                    .parse_next(input)?;
+
+                Ok(Some((generation as u16, RefLocation::Free)))
+            }
             Self::Type1 {
                 byte_offset_len,
-                _generation_number_len: _,
+                generation_number_len,
             } => {
                 let byte_offset = take(byte_offset_len.get())
                     .map(parse_num)
                     .parse_next(input)?;
+                let generation = take(*generation_number_len)
+                    .map(parse_num)
+                    .parse_next(input)?;
 
-                Ok(Some(RefLocation::Plain(byte_offset)))
+                Ok(Some((generation as u16, RefLocation::Plain(byte_offset))))
             }
             Self::Type2 {
                 stream_id_len,
@@ -128,32 +152,48 @@ impl Parser<&BStr, Option<RefLocation>, ContextError> for EntryType {
                 let stream_id = take(stream_id_len.get()).map(parse_num).parse_next(input)?;
                 let index = take(*index_len).map(parse_num).parse_next(input)?;
 
-                Ok(Some(RefLocation::Compressed { stream_id, index }))
+                Ok(Some((0, RefLocation::Compressed { stream_id, index })))
+            }
+            Self::Unknown { remaining_len } => {
+                take(*remaining_len).parse_next(input)?;
+                Ok(None)
             }
-            Self::Unknown => Ok(None),
         }
     }
 }
 
 impl FieldSize {
     /// Extract the reference entry type.
+    ///
+    /// From the specification:
+    ///
+    /// > If the first element is zero, the type field shall not be present, and shall default
+    /// > to type 1.
     fn parse_ref_type(&self, input: &mut &BStr) -> Result<EntryType, ContextError> {
+        let remaining_len = self.f2.get() as usize + self.f3 as usize;
+
         if self.f1 == 0 {
-            Ok(EntryType::Type0)
+            Ok(EntryType::Type1 {
+                byte_offset_len: self.f2,
+                generation_number_len: self.f3,
+            })
         } else {
-            let num = take(self.f1).parse_next(input)?;
-            debug_assert_eq!(num.len(), 1, "f1 should only ever span one byte.");
-            let entry_type = match num[0] {
-                0 => EntryType::Type0,
+            // The type usually spans a single byte, but nothing prevents a wider field.
+            let num = take(self.f1).map(parse_num).parse_next(input)?;
+            let entry_type = match num {
+                0 => EntryType::Type0 {
+                    next_free_len: self.f2,
+                    generation_number_len: self.f3,
+                },
                 1 => EntryType::Type1 {
                     byte_offset_len: self.f2,
-                    _generation_number_len: self.f3,
+                    generation_number_len: self.f3,
                 },
                 2 => EntryType::Type2 {
                     stream_id_len: self.f2,
                     index_len: self.f3,
                 },
-                _ => EntryType::Unknown,
+                _ => EntryType::Unknown { remaining_len },
             };
 
             Ok(entry_type)
@@ -161,8 +201,11 @@ impl FieldSize {
     }
 }
 
-impl Parser<&BStr, Option<RefLocation>, ContextError> for FieldSize {
-    fn parse_next(&mut self, input: &mut &BStr) -> Result<Option<RefLocation>, ContextError> {
+impl Parser<&BStr, Option<(u16, RefLocation)>, ContextError> for FieldSize {
+    fn parse_next(
+        &mut self,
+        input: &mut &BStr,
+    ) -> Result<Option<(u16, RefLocation)>, ContextError> {
         let mut ref_type = self.parse_ref_type(input)?;
         ref_type.parse_next(input)
     }
@@ -186,6 +229,7 @@ impl Extract<'_> for XRefStreamBlock {
         let Stream {
             content,
             structured: XRefStreamConfig { index, w, trailer },
+            ..
         } = extract(input)?;
 
         let index = index.unwrap_or(vec![SubSection {
@@ -198,13 +242,16 @@ impl Extract<'_> for XRefStreamBlock {
         let mut xrefs = Vec::new();
 
         for SubSection { start, n } in index {
-            let subsection: Vec<Option<RefLocation>> =
+            if start.checked_add(n).is_none() {
+                return Err(Error::new(ErrorKind::BadXRefEntry).cut());
+            }
+
+            let subsection: Vec<Option<(u16, RefLocation)>> =
                 repeat(n, w).parse_next(input).map_err(ErrMode::Backtrack)?;
 
-            let iter = subsection
-                .into_iter()
-                .enumerate()
-                .filter_map(|(i, loc)| loc.map(|offset| (ReferenceId::first(start + i), offset)));
+            let iter = subsection.into_iter().enumerate().filter_map(|(i, entry)| {
+                entry.map(|(generation, loc)| (ReferenceId::new(start + i, generation), loc))
+            });
 
             xrefs.extend(iter);
         }
@@ -233,9 +280,11 @@ mod tests {
     use super::*;
 
     #[rstest]
-    #[case(&[0x00, 0x0e, 0x8a, 0x00], None)]
-    #[case(&[0x00, 0x11, 0x00, 0x00], None)]
+    #[case(&[0x00, 0x0e, 0x8a, 0x00], Some(RefLocation::Free))]
+    #[case(&[0x00, 0x11, 0x00, 0x00], Some(RefLocation::Free))]
+    #[case(&[0x03, 0x11, 0x00, 0x00], None)]
     #[case(&[0x01, 0x0e, 0x8a, 0x00], Some(RefLocation::Plain(3722)))]
+    #[case(&[0x01, 0x0e, 0x8a, 0x02], Some(RefLocation::Plain(3722)))]
     #[case(&[0x02, 0x00, 0x02, 0x00], Some(RefLocation::Compressed { stream_id: 2, index: 0}))]
     #[case(&[0x02, 0x00, 0x02, 0x01], Some(RefLocation::Compressed { stream_id: 2, index: 1}))]
     #[case(&[0x02, 0x00, 0x02, 0x02], Some(RefLocation::Compressed { stream_id: 2, index: 2}))]
@@ -251,7 +300,39 @@ mod tests {
             f2: NonZeroU8::new(2).unwrap(),
             f3: 1,
         };
-        let result = w.parse_next(&mut input.as_ref()).unwrap();
+        let input = &mut input.as_ref();
+        let result = w.parse_next(input).unwrap().map(|(_, loc)| loc);
+        assert_eq!(expected, result);
+        assert!(input.is_empty(), "every field should be consumed");
+    }
+
+    #[rstest]
+    #[case(&[0x0e, 0x8a, 0x00], Some(RefLocation::Plain(3722)))]
+    #[case(&[0x13, 0x23, 0x01], Some(RefLocation::Plain(4899)))]
+    fn default_entry_type(#[case] input: &[u8], #[case] expected: Option<RefLocation>) {
+        let mut w = FieldSize {
+            f1: 0,
+            f2: NonZeroU8::new(2).unwrap(),
+            f3: 1,
+        };
+        let input = &mut input.as_ref();
+        let result = w.parse_next(input).unwrap().map(|(_, loc)| loc);
         assert_eq!(expected, result);
+        assert!(input.is_empty(), "every field should be consumed");
+    }
+
+    #[rstest]
+    #[case(&[0x00, 0x00, 0x00, 0x01], 1)]
+    #[case(&[0x01, 0x0e, 0x8a, 0x00], 0)]
+    #[case(&[0x01, 0x0e, 0x8a, 0x02], 2)]
+    #[case(&[0x02, 0x00, 0x02, 0x03], 0)]
+    fn generation(#[case] input: &[u8], #[case] expected: u16) {
+        let mut w = FieldSize {
+            f1: 1,
+            f2: NonZeroU8::new(2).unwrap(),
+            f3: 1,
+        };
+        let (generation, _) = w.parse_next(&mut input.as_ref()).unwrap().unwrap();
+        assert_eq!(expected, generation);
     }
 }
@@ -1,5 +1,6 @@
 use crate::{
-    extraction::{FromRawDict, Id, Reference},
+    extraction::{FromRawDict, Id, Object, Reference},
+    serialization::SerializeDict,
     structure::Catalog,
 };
 
@@ -9,7 +10,7 @@ use crate::{
 /// table and certain special objects. In the case of an updated PDF, the full trailer is
 /// repeated (be it modified or not), hence only the last trailer of the document is necessary
 /// for comprehension.
-#[derive(Debug, Clone, PartialEq, FromRawDict)]
+#[derive(Debug, Clone, PartialEq, FromRawDict, SerializeDict)]
 pub struct Trailer {
     /// From the specs:
     ///
@@ -29,10 +30,20 @@ pub struct Trailer {
     /// Reference to the PDF catalog.
     pub root: Reference<Catalog>,
 
-    // pub encrypt: Encrypt,
-    // The PDF file’s [information dictionary](Info).
-    // Not required.
-    // pub info: Option<Reference<Info>>,
+    /// (PDF 1.5, hybrid-reference files only) The byte offset of a cross-reference stream
+    /// holding the entries that are hidden from the cross-reference table, typically objects
+    /// stored within object streams. See section 7.5.8.4 of the specification.
+    #[livre(rename = "XRefStm")]
+    pub xref_stm: Option<usize>,
+
+    /// The document's encryption dictionary, if the document is encrypted. Livre does not
+    /// support encryption yet, hence the dictionary is kept as-is.
+    pub encrypt: Option<Object>,
+
+    /// The PDF file’s information dictionary, which holds metadata such as the title or the
+    /// producer of the document. Livre does not type it yet.
+    pub info: Option<Reference<Object>>,
+
     /// The PDF identifier.
     ///
     /// Interestingly, the specification gives a few clarifications on the exact format of the PDF
@@ -53,7 +64,10 @@ mod tests {
     use indoc::indoc;
     use rstest::rstest;
 
-    use crate::extraction::{extract, Extract};
+    use crate::{
+        extraction::{extract, Extract},
+        serialization::Serialize,
+    };
 
     use super::*;
 
@@ -75,7 +89,23 @@ mod tests {
             id: Some([[0x2b, 0x55, 0x2b, 0x55], [0x0a, 0x12, 0x2b, 0x55]].into()),
             prev: Some(116),
             root: Reference::from((90794, 0)),
-            //info: TypedReference::new(90792, 0), 
+            xref_stm: None,
+            encrypt: None,
+            info: Some(Reference::from((90792, 0))),
+        }
+    )]
+    #[case(
+        indoc!{b"
+            <</Size 194/Root 1 0 R/Info 36 0 R/Prev 160714/XRefStm 160062>>
+        "},
+        Trailer {
+            size: 194,
+            id: None,
+            prev: Some(160714),
+            root: Reference::from((1, 0)),
+            xref_stm: Some(160062),
+            encrypt: None,
+            info: Some(Reference::from((36, 0))),
         }
     )]
     fn extraction<'de, T>(#[case] input: &'de [u8], #[case] expected: T)
@@ -85,4 +115,37 @@ mod tests {
         let result = extract(&mut input.as_ref()).unwrap();
         assert_eq!(expected, result);
     }
+
+    #[rstest]
+    #[case(
+        Trailer {
+            size: 194,
+            id: None,
+            prev: Some(160714),
+            root: Reference::from((1, 0)),
+            xref_stm: Some(160062),
+            encrypt: None,
+            info: Some(Reference::from((36, 0))),
+        },
+        b"<</Size 194 /Prev 160714 /Root 1 0 R /XRefStm 160062 /Info 36 0 R>>",
+    )]
+    #[case(
+        Trailer {
+            size: 3,
+            id: Some([[0x2b, 0x55], [0x0a, 0x12]].into()),
+            prev: None,
+            root: Reference::from((2, 0)),
+            xref_stm: None,
+            encrypt: None,
+            info: None,
+        },
+        b"<</Size 3 /Root 2 0 R /ID [<2B55> <0A12>]>>",
+    )]
+    fn serialization(#[case] trailer: Trailer, #[case] expected: &[u8]) {
+        let bytes = trailer.to_bytes();
+        assert_eq!(bytes, expected);
+
+        let result: Trailer = extract(&mut bytes.as_slice().as_ref()).unwrap();
+        assert_eq!(trailer, result);
+    }
 }
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use crate::extraction::ReferenceId;
+
+use super::RefLocation;
+
+/// The resolved cross-reference table of a document, i.e. the merge of every cross-reference
+/// section up to a given revision (see [`History`](crate::structure::History)).
+///
+/// The table holds at most one entry per object number, including free entries. Looking up a
+/// reference checks the generation number: from the specification, a reference to a free or
+/// missing object is not an error, but shall be treated as a reference to the null object.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct XRefTable {
+    entries: HashMap<usize, (u16, RefLocation)>,
+}
+
+impl XRefTable {
+    /// Insert an entry, shadowing any previous entry for the same object number.
+    pub fn insert(&mut self, ReferenceId { object, generation }: ReferenceId, loc: RefLocation) {
+        self.entries.insert(object, (generation, loc));
+    }
+
+    /// Get the location of an object that is in use, provided the generation numbers match.
+    ///
+    /// Returns `None` if the object is free, missing from the table, or if the reference
+    /// points to another generation of the object.
+    pub fn get(&self, id: &ReferenceId) -> Option<RefLocation> {
+        let Some(&(generation, location)) = self.entries.get(&id.object) else {
+            tracing::debug!("Object {id:?} is missing from the cross-reference table");
+            return None;
+        };
+
+        if !location.is_in_use() {
+            tracing::debug!("Object {id:?} is marked as free");
+            return None;
+        }
+
+        if generation != id.generation {
+            tracing::warn!(
+                "Generation mismatch for object {}: referenced {}, found {generation}",
+                id.object,
+                id.generation
+            );
+            return None;
+        }
+
+        Some(location)
+    }
+
+    /// Get the raw entry associated with an object number, regardless of its status.
+    pub fn entry(&self, object: usize) -> Option<(ReferenceId, RefLocation)> {
+        self.entries
+            .get(&object)
+            .map(|&(generation, location)| (ReferenceId::new(object, generation), location))
+    }
+
+    /// Iterate over every entry of the table, including free entries, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (ReferenceId, RefLocation)> + '_ {
+        self.entries
+            .iter()
+            .map(|(&object, &(generation, location))| {
+                (ReferenceId::new(object, generation), location)
+            })
+    }
+
+    /// Iterate over the objects that are in use, in no particular order.
+    pub fn in_use(&self) -> impl Iterator<Item = (ReferenceId, RefLocation)> + '_ {
+        self.iter().filter(|(_, location)| location.is_in_use())
+    }
+
+    /// Number of entries in the table, including free entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl FromIterator<(ReferenceId, RefLocation)> for XRefTable {
+    fn from_iter<T: IntoIterator<Item = (ReferenceId, RefLocation)>>(iter: T) -> Self {
+        let mut table = Self::default();
+        for (id, location) in iter {
+            table.insert(id, location);
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case((1, 0), Some(RefLocation::Plain(10)))]
+    #[case((1, 1), None)]
+    #[case((2, 0), None)]
+    #[case((3, 0), None)]
+    fn lookup(#[case] id: (usize, u16), #[case] expected: Option<RefLocation>) {
+        let table: XRefTable = vec![
+            ((1, 0).into(), RefLocation::Plain(10)),
+            ((2, 0).into(), RefLocation::Free),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(table.get(&id.into()), expected);
+    }
+}
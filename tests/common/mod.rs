@@ -0,0 +1,29 @@
+/// Build a minimal document made of the supplied objects, with a valid cross-reference table.
+pub fn document(objects: &[&[u8]], trailer: &str) -> Vec<u8> {
+    let mut input = b"%PDF-1.7\n".to_vec();
+    let mut offsets = Vec::new();
+
+    for object in objects {
+        offsets.push(input.len());
+        input.extend_from_slice(object);
+        input.push(b'\n');
+    }
+
+    let start = input.len();
+
+    input.extend_from_slice(format!("xref\n0 {}\n", objects.len() + 1).as_bytes());
+    input.extend_from_slice(b"0000000000 65535 f\r\n");
+    for offset in offsets {
+        input.extend_from_slice(format!("{offset:010} 00000 n\r\n").as_bytes());
+    }
+
+    input.extend_from_slice(
+        format!(
+            "trailer\n<</Size {}/Root 1 0 R{trailer}>>\nstartxref\n{start}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .as_bytes(),
+    );
+
+    input
+}
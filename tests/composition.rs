@@ -0,0 +1,267 @@
+use livre::{
+    error::ErrorKind,
+    extraction::{Object, Rectangle},
+    follow_refs::{Builder, Options},
+    serialization::Composition,
+    structure::RotationAngle,
+    InMemoryDocument,
+};
+use rstest::rstest;
+
+mod common;
+
+use common::document;
+
+/// A document with three pages, whose media box and resources are inherited from an
+/// intermediate node of the page tree.
+fn three_pages() -> InMemoryDocument {
+    let input = document(
+        &[
+            b"1 0 obj\n<</Type/Catalog/Pages 2 0 R>>\nendobj",
+            b"2 0 obj\n<</Type/Pages/Kids[3 0 R 4 0 R]/Count 3/MediaBox[0 0 612 792]>>\nendobj",
+            b"3 0 obj\n<</Type/Pages/Parent 2 0 R/Kids[5 0 R 6 0 R]/Count 2/Resources<</Font<</F1 10 0 R>>>>>>\nendobj",
+            b"4 0 obj\n<</Type/Page/Parent 2 0 R/Contents 9 0 R/Resources<</Font<<>>>>/Rotate 90>>\nendobj",
+            b"5 0 obj\n<</Type/Page/Parent 3 0 R/Contents 7 0 R>>\nendobj",
+            b"6 0 obj\n<</Type/Page/Parent 3 0 R/Contents 8 0 R/Annots[11 0 R]>>\nendobj",
+            b"7 0 obj\n<</Length 5>>stream\npage1\nendstream\nendobj",
+            b"8 0 obj\n<</Length 5>>stream\npage2\nendstream\nendobj",
+            b"9 0 obj\n<</Length 5>>stream\npage3\nendstream\nendobj",
+            b"10 0 obj\n<</Type/Font/Subtype/Type1/BaseFont/Helvetica>>\nendobj",
+            // A link to the first page.
+            b"11 0 obj\n<</Type/Annot/Subtype/Link/Dest[5 0 R/Fit]>>\nendobj",
+        ],
+        "",
+    );
+
+    InMemoryDocument::from_bytes(&input).unwrap()
+}
+
+/// A document with a single page.
+fn single_page() -> InMemoryDocument {
+    let input = document(
+        &[
+            b"1 0 obj\n<</Type/Catalog/Pages 2 0 R>>\nendobj",
+            b"2 0 obj\n<</Type/Pages/Kids[3 0 R]/Count 1>>\nendobj",
+            b"3 0 obj\n<</Type/Page/Parent 2 0 R/MediaBox[0 0 100 100]/Resources<</Font<<>>>>/Contents 4 0 R>>\nendobj",
+            b"4 0 obj\n<</Length 5>>stream\nother\nendstream\nendobj",
+        ],
+        "",
+    );
+
+    InMemoryDocument::from_bytes(&input).unwrap()
+}
+
+fn contents(doc: &InMemoryDocument) -> Vec<Vec<u8>> {
+    doc.pages()
+        .unwrap()
+        .iter()
+        .map(|page| page.build_content(doc).unwrap())
+        .collect()
+}
+
+fn load(composition: &Composition) -> InMemoryDocument {
+    let output = composition.finish().unwrap().to_bytes();
+    InMemoryDocument::from_bytes(&output).unwrap()
+}
+
+#[test]
+fn merge() {
+    let first = three_pages();
+    let second = single_page();
+
+    let mut composition = Composition::new();
+    composition.add_document(&first).unwrap();
+    composition.add_document(&second).unwrap();
+    assert_eq!(composition.len(), 4);
+
+    let merged = load(&composition);
+
+    assert_eq!(
+        contents(&merged),
+        [
+            b"page1".to_vec(),
+            b"page2".to_vec(),
+            b"page3".to_vec(),
+            b"other".to_vec()
+        ]
+    );
+
+    // Inherited attributes are flattened into each page.
+    let pages = merged.pages().unwrap();
+    assert_eq!(
+        pages[0].inheritable_props.media_box,
+        Some(Rectangle::from((0.0, 0.0, 612.0, 792.0)))
+    );
+    assert!(pages[0].inheritable_props.resources.is_some());
+    assert_eq!(
+        pages[3].inheritable_props.media_box,
+        Some(Rectangle::from((0.0, 0.0, 100.0, 100.0)))
+    );
+}
+
+#[test]
+fn reorder_and_rotate() {
+    let doc = three_pages();
+
+    let mut composition = Composition::new();
+    composition.add_pages(&doc, [2, 0, 0]).unwrap();
+    composition.set_rotation(1, RotationAngle::Full);
+
+    let reordered = load(&composition);
+
+    assert_eq!(
+        contents(&reordered),
+        [b"page3".to_vec(), b"page1".to_vec(), b"page1".to_vec()]
+    );
+
+    let rotations: Vec<_> = reordered
+        .pages()
+        .unwrap()
+        .iter()
+        .map(|page| page.inheritable_props.rotate)
+        .collect();
+    assert_eq!(
+        rotations,
+        [
+            Some(RotationAngle::Quarter),
+            Some(RotationAngle::Full),
+            None
+        ]
+    );
+}
+
+#[test]
+fn deduplication() {
+    let doc = three_pages();
+
+    let mut composition = Composition::new();
+    composition.add_pages(&doc, [0, 1]).unwrap();
+
+    let rewrite = composition.finish().unwrap();
+
+    // Catalog, page tree, two pages, two content streams, the annotation, and the font
+    // shared by both pages. The third page is not copied, and neither is the intermediate node.
+    assert_eq!(rewrite.len(), 8);
+
+    let output = rewrite.to_bytes();
+    let doc = InMemoryDocument::from_bytes(&output).unwrap();
+    assert_eq!(contents(&doc), [b"page1".to_vec(), b"page2".to_vec()]);
+}
+
+#[test]
+fn dropped_link_target() {
+    let doc = three_pages();
+
+    let mut composition = Composition::new();
+    composition.add_page(&doc, 1).unwrap();
+
+    let output = composition.finish().unwrap().to_bytes();
+    let doc = InMemoryDocument::from_bytes(&output).unwrap();
+
+    // The annotation links to a page that was not selected, which becomes the null object.
+    let Object::Dictionary(page) = doc.build_reference((3, 0).into()).unwrap() else {
+        panic!("the page should be a dictionary");
+    };
+    let Object::Array(annots) = &page[&"Annots".into()] else {
+        panic!("the annotations should be an array");
+    };
+    let Object::Indirect(annotation) = annots[0] else {
+        panic!("the annotation should be an indirect object");
+    };
+    let Object::Dictionary(annotation) = doc.build_reference(annotation).unwrap() else {
+        panic!("the annotation should be a dictionary");
+    };
+    let Object::Array(dest) = &annotation[&"Dest".into()] else {
+        panic!("the destination should be an array");
+    };
+    let Object::Indirect(target) = dest[0] else {
+        panic!("the destination should point to a page");
+    };
+    let target: Option<Object> = doc.resolve_reference(target).unwrap();
+    assert_eq!(target, Some(Object::Null));
+}
+
+#[test]
+fn missing_page() {
+    let doc = three_pages();
+
+    let mut composition = Composition::new();
+    let err = composition.add_page(&doc, 3).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::PageNotFound(3));
+}
+
+#[rstest]
+#[case(1, &[3])]
+#[case(2, &[2, 1])]
+#[case(3, &[1, 1, 1])]
+#[case(5, &[1, 1, 1])]
+fn split(#[case] parts: usize, #[case] expected: &[usize]) {
+    let doc = three_pages();
+
+    let documents: Vec<_> = doc
+        .split(parts)
+        .unwrap()
+        .into_iter()
+        .map(|rewrite| InMemoryDocument::from_bytes(&rewrite.to_bytes()).unwrap())
+        .collect();
+
+    let counts: Vec<_> = documents
+        .iter()
+        .map(|doc| doc.pages().unwrap().len())
+        .collect();
+    assert_eq!(counts, expected);
+
+    let all: Vec<_> = documents.iter().flat_map(contents).collect();
+    assert_eq!(all, contents(&doc));
+}
+
+#[test]
+fn cyclic_page_tree() {
+    let input = document(
+        &[
+            b"1 0 obj\n<</Type/Catalog/Pages 2 0 R>>\nendobj",
+            b"2 0 obj\n<</Type/Pages/Kids[3 0 R]/Count 1>>\nendobj",
+            // The intermediate node lists the root among its kids.
+            b"3 0 obj\n<</Type/Pages/Parent 2 0 R/Kids[2 0 R]/Count 1>>\nendobj",
+        ],
+        "",
+    );
+    let doc = InMemoryDocument::from_bytes(&input).unwrap();
+
+    let mut composition = Composition::new();
+    let err = composition.add_document(&doc).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Cycle);
+}
+
+/// The composition walks the page tree like the document does, with the same limits.
+#[rstest]
+#[case(3, Ok(1))]
+#[case(2, Err(ErrorKind::DepthLimitExceeded(2)))]
+fn page_tree_walk(#[case] max_depth: usize, #[case] expected: Result<usize, ErrorKind>) {
+    let input = document(
+        &[
+            b"1 0 obj\n<</Type/Catalog/Pages 2 0 R>>\nendobj",
+            // The second kid is dangling.
+            b"2 0 obj\n<</Type/Pages/Kids[3 0 R 9 0 R]/MediaBox[0 0 612 792]>>\nendobj",
+            b"3 0 obj\n<</Type/Pages/Parent 2 0 R/Kids[4 0 R]>>\nendobj",
+            b"4 0 obj\n<</Type/Pages/Parent 3 0 R/Kids[5 0 R]>>\nendobj",
+            b"5 0 obj\n<</Type/Page/Parent 4 0 R>>\nendobj",
+        ],
+        "",
+    );
+    let options = Options::default().with_max_depth(max_depth);
+    let doc = InMemoryDocument::from_bytes_with(&input, options).unwrap();
+
+    let pages = doc
+        .pages()
+        .map(|pages| pages.len())
+        .map_err(|err| livre::Error::from(err).kind().clone());
+    assert_eq!(pages, expected);
+
+    let mut composition = Composition::new();
+    let result = composition
+        .add_document(&doc)
+        .map(|()| composition.len())
+        .map_err(|err| err.kind().clone());
+    assert_eq!(result, expected);
+}
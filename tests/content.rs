@@ -1,6 +1,13 @@
 use std::{fs::File, io::Read};
 
-use livre::{content::operators::Operator, extraction::Extract, InMemoryDocument};
+use livre::{
+    content::{
+        layout::{write_alto, write_hocr, TextPage},
+        operators::Operator,
+    },
+    extraction::Extract,
+    InMemoryDocument,
+};
 use rstest::rstest;
 use winnow::{
     ascii::multispace0,
@@ -41,3 +48,62 @@ fn test_content(#[case] path: &str) {
             .unwrap();
     }
 }
+
+#[rstest]
+#[case("tests/resources/letter.pdf")]
+#[case("tests/resources/text.pdf")]
+fn lazy_pages(#[case] path: &str) {
+    let doc = read_document(path);
+    let pages = doc.pages().unwrap();
+
+    assert_eq!(doc.page_count().unwrap(), pages.len());
+
+    for (index, page) in doc.pages_iter().enumerate() {
+        let page = page.unwrap();
+        assert_eq!(page, pages[index]);
+        assert_eq!(doc.page(index).unwrap(), Some(page));
+    }
+
+    assert_eq!(doc.page(pages.len()).unwrap(), None);
+}
+
+#[rstest]
+#[case(
+    "tests/resources/letter.pdf",
+    "Groupe Hospitalier Henri Mondor\n51, Avenue du Mal de Lattre de"
+)]
+#[case(
+    "tests/resources/letter.pdf",
+    "Comité Scientifique et Ethique de l’Entrepôt de"
+)]
+#[case(
+    "tests/resources/text.pdf",
+    "Longtemps, je me suis couché de bonne heure. Parfois, à peine ma bougie éteinte, mes yeux\nse fermaient"
+)]
+fn extract_text(#[case] path: &str, #[case] expected: &str) {
+    let doc = read_document(path);
+    let text = doc.extract_text().unwrap();
+
+    assert!(
+        text.contains(expected),
+        "{expected:?} not found in {text:?}"
+    );
+}
+
+#[rstest]
+#[case(write_hocr, r#"class="ocrx_word""#)]
+#[case(write_alto, r#"<String ID="P1_ST1""#)]
+fn layout_export(
+    #[case] write: fn(&[TextPage], &mut Vec<u8>) -> std::io::Result<()>,
+    #[case] element: &str,
+) {
+    let doc = read_document("tests/resources/text.pdf");
+    let pages = doc.text_pages().unwrap();
+
+    let mut output = Vec::new();
+    write(&pages, &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.contains(element));
+    assert!(output.contains(">Longtemps,<") || output.contains(r#"CONTENT="Longtemps,""#));
+}
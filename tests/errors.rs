@@ -0,0 +1,87 @@
+use livre::{extraction::ReferenceId, ErrorKind, InMemoryDocument};
+use rstest::rstest;
+
+mod common;
+
+use common::document;
+
+const CATALOG: &[u8] = b"1 0 obj\n<</Type/Catalog/Pages 2 0 R>>\nendobj";
+const PAGES: &[u8] = b"2 0 obj\n<</Type/Pages/Kids[3 0 R]/Count 1>>\nendobj";
+
+#[test]
+fn valid() {
+    let page = b"3 0 obj\n<</Type/Page/MediaBox[0 0 612 792]>>\nendobj";
+    let doc = InMemoryDocument::from_bytes(&document(&[CATALOG, PAGES, page], "")).unwrap();
+    assert_eq!(doc.pages().unwrap().len(), 1);
+}
+
+#[rstest]
+#[case(b"%PDF-1.7\nnothing to see here", ErrorKind::MissingStartXRef)]
+#[case(b"%PDF-1.7\nstartxref\n5000\n%%EOF", ErrorKind::BadXRefEntry)]
+#[case(b"%PDF-1.7\nstartxref\n3\n%%EOF", ErrorKind::BadXRefEntry)]
+fn structure(#[case] input: &[u8], #[case] expected: ErrorKind) {
+    let error = InMemoryDocument::from_bytes(input).err().unwrap();
+    assert_eq!(error.kind(), &expected);
+}
+
+#[test]
+fn encrypted() {
+    let doc = document(&[CATALOG, PAGES], "/Encrypt<</Filter/Standard>>");
+    let error = InMemoryDocument::from_bytes(&doc).err().unwrap();
+    assert_eq!(error.kind(), &ErrorKind::EncryptionRequired);
+}
+
+#[test]
+fn unresolvable_root() {
+    // The catalog is missing from the document, hence the root resolves to null.
+    let doc = document(&[b"1 0 obj\nnull\nendobj"], "");
+    let doc = String::from_utf8(doc)
+        .unwrap()
+        .replace("1 0 R>>", "4 0 R>>");
+
+    let error = InMemoryDocument::from_bytes(doc.as_bytes()).err().unwrap();
+    assert_eq!(
+        error.kind(),
+        &ErrorKind::UnresolvableReference(ReferenceId::first(4))
+    );
+}
+
+#[test]
+fn type_mismatch() {
+    let pages = b"2 0 obj\n[1 2 3]\nendobj";
+    let doc = document(&[CATALOG, pages], "");
+
+    let error = InMemoryDocument::from_bytes(&doc).err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::TypeMismatch { .. }));
+    // The pages object directly follows the header and the catalog.
+    assert_eq!(
+        error.offset(),
+        Some(b"%PDF-1.7\n".len() + CATALOG.len() + 1)
+    );
+    assert_eq!(
+        error.objects(),
+        &[ReferenceId::first(2), ReferenceId::first(1)]
+    );
+}
+
+#[rstest]
+#[case(
+    b"3 0 obj\n<</Length 3/Filter/LZWDecode>>stream\nabc\nendstream\nendobj",
+    ErrorKind::UnsupportedFilter("LZWDecode".into())
+)]
+#[case(
+    b"3 0 obj\n<</Length 3/Filter/FlateDecode>>stream\nabc\nendstream\nendobj",
+    ErrorKind::Decompression("corrupt deflate stream".into())
+)]
+fn filters(#[case] content: &[u8], #[case] expected: ErrorKind) {
+    let page = b"4 0 obj\n<</Type/Page/Contents 3 0 R>>\nendobj";
+    let pages = b"2 0 obj\n<</Type/Pages/Kids[4 0 R]/Count 1>>\nendobj";
+    let doc = document(&[CATALOG, pages, content, page], "");
+
+    let doc = InMemoryDocument::from_bytes(&doc).unwrap();
+    let page = &doc.pages().unwrap()[0];
+
+    let error = livre::Error::from(page.build_content(&doc).err().unwrap());
+    assert_eq!(error.kind(), &expected);
+    assert_eq!(error.objects(), &[ReferenceId::first(3)]);
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "serde")]
+
+use std::{fs::File, io::Read};
+
+use livre::{extraction::Extract, serialization::StreamData, InMemoryDocument};
+use rstest::rstest;
+use serde_json::Value;
+
+fn read_document(path: &str) -> InMemoryDocument {
+    let mut file = File::open(path).unwrap();
+    let mut buffer: Vec<u8> = Vec::new();
+    file.read_to_end(&mut buffer).unwrap();
+
+    InMemoryDocument::extract(&mut buffer.as_slice().as_ref()).unwrap()
+}
+
+#[rstest]
+#[case(StreamData::Summary)]
+#[case(StreamData::Base64)]
+fn object_graph(#[case] streams: StreamData) {
+    let doc = read_document("tests/resources/text.pdf");
+    let json: Value = serde_json::from_str(&doc.to_json(streams).unwrap()).unwrap();
+
+    let root = json["root"].as_str().unwrap();
+    assert_eq!(json["objects"][root]["/Type"], "/Catalog");
+
+    let streams_with_data = json["objects"]
+        .as_object()
+        .unwrap()
+        .values()
+        .filter(|object| object.get("data").is_some())
+        .count();
+    assert_eq!(streams_with_data > 0, streams == StreamData::Base64);
+}
+
+#[test]
+fn page() {
+    let doc = read_document("tests/resources/text.pdf");
+    let page = doc.page(0).unwrap().unwrap();
+    let json = serde_json::to_value(&page).unwrap();
+
+    assert!(json["inheritable_props"]["media_box"]["xmax"].is_number());
+    assert!(json["contents"][0].as_str().unwrap().ends_with(" R"));
+}
+
+#[test]
+fn encoded_streams() {
+    let doc = read_document("tests/resources/letter.pdf");
+    let json: Value = serde_json::from_str(&doc.to_json(StreamData::Summary).unwrap()).unwrap();
+
+    let objects = json["objects"].as_object().unwrap();
+    assert!(objects.values().all(|object| !object.is_null()));
+
+    let image = objects
+        .values()
+        .find(|object| object["dict"]["/Filter"] == "/DCTDecode")
+        .unwrap();
+    assert_eq!(image["filters"], serde_json::json!(["DCTDecode"]));
+    assert_eq!(image["encoded"], true);
+    assert!(image["length"].as_u64().unwrap() > 0);
+}
@@ -0,0 +1,39 @@
+use livre::{follow_refs::Options, InMemoryDocument};
+
+mod common;
+
+use common::document;
+
+/// A document riddled with common producer bugs:
+///
+/// - the pages object lacks its `endobj` tag
+/// - stray bytes precede the page object, hence its offset is wrong
+/// - the content stream has an indirect, wrong `Length`
+fn malformed() -> Vec<u8> {
+    document(
+        &[
+            b"1 0 obj\n<</Type/Catalog/Pages 2 0 R>>\nendobj",
+            b"2 0 obj\n<</Type/Pages/Kids[3 0 R]/Count 1>>",
+            b"garbage\n3 0 obj\n<</Type/Page/Contents 4 0 R>>\nendobj",
+            b"4 0 obj\n<</Length 5 0 R>>stream\nBT /F1 12 Tf (Hello) Tj ET\nendstream\nendobj",
+            b"5 0 obj\n3\nendobj",
+        ],
+        "",
+    )
+}
+
+#[test]
+fn strict() {
+    assert!(InMemoryDocument::from_bytes(&malformed()).is_err());
+}
+
+#[test]
+fn lenient() {
+    let doc = InMemoryDocument::from_bytes_with(&malformed(), Options::lenient()).unwrap();
+
+    let pages = doc.pages().unwrap();
+    assert_eq!(pages.len(), 1);
+
+    let content = pages[0].build_content(&doc).unwrap();
+    assert_eq!(content, b"BT /F1 12 Tf (Hello) Tj ET");
+}
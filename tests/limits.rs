@@ -0,0 +1,287 @@
+use std::{thread, time::Duration};
+
+use livre::{
+    content::{interpreter::Device, Matrix},
+    error::{Error, ErrorKind},
+    extraction::{Extract, Object},
+    filtering::{Filtering, FlateDecode},
+    follow_refs::{Builder, Limits, Options},
+    InMemoryDocument,
+};
+use rstest::rstest;
+use winnow::BStr;
+
+mod common;
+
+use common::document;
+
+/// A single-page document, whose content stream is compressed.
+fn single_page(content: &[u8]) -> Vec<u8> {
+    let encoded = FlateDecode.encode(content).unwrap();
+
+    let mut stream = format!(
+        "4 0 obj\n<</Length {}/Filter/FlateDecode>>stream\n",
+        encoded.len()
+    )
+    .into_bytes();
+    stream.extend_from_slice(&encoded);
+    stream.extend_from_slice(b"\nendstream\nendobj");
+
+    document(
+        &[
+            b"1 0 obj\n<</Type/Catalog/Pages 2 0 R>>\nendobj",
+            b"2 0 obj\n<</Type/Pages/Kids[3 0 R]/Count 1>>\nendobj",
+            b"3 0 obj\n<</Type/Page/Parent 2 0 R/MediaBox[0 0 612 792]/Resources<</Font<<>>>>/Contents 4 0 R>>\nendobj",
+            &stream,
+        ],
+        "",
+    )
+}
+
+fn with_limits(limits: Limits) -> Options {
+    Options::default().with_limits(limits)
+}
+
+#[rstest]
+#[case(999, Err(ErrorKind::StreamTooLarge(999)))]
+#[case(1000, Ok(1000))]
+#[case::unlimited(usize::MAX, Ok(1000))]
+fn stream_size(#[case] max_stream_size: usize, #[case] expected: Result<usize, ErrorKind>) {
+    let input = single_page(&[b' '; 1000]);
+
+    let options = with_limits(Limits {
+        max_stream_size,
+        ..Limits::default()
+    });
+    let doc = InMemoryDocument::from_bytes_with(&input, options).unwrap();
+
+    let page = doc.page(0).unwrap().unwrap();
+    let result = page
+        .build_content(&doc)
+        .map(|content| content.len())
+        .map_err(|err| Error::from(err).kind().clone());
+
+    assert_eq!(result, expected);
+}
+
+#[rstest]
+#[case(3, Err(ErrorKind::TooManyOperators(3)))]
+#[case(4, Ok(4))]
+fn operators(#[case] max_operators: usize, #[case] expected: Result<usize, ErrorKind>) {
+    let input = single_page(b"BT ET\nBT ET");
+
+    let options = with_limits(Limits {
+        max_operators,
+        ..Limits::default()
+    });
+    let doc = InMemoryDocument::from_bytes_with(&input, options).unwrap();
+
+    let page = doc.page(0).unwrap().unwrap();
+    let result = page
+        .operators(&doc)
+        .map(|operators| operators.len())
+        .map_err(|err| Error::from(err).kind().clone());
+
+    assert_eq!(result, expected);
+}
+
+#[cfg(feature = "render")]
+#[rstest]
+#[case(612 * 792 - 1, Err(ErrorKind::TooManyPixels(612 * 792 - 1)))]
+#[case(612 * 792, Ok((612, 792)))]
+fn pixels(#[case] max_pixels: usize, #[case] expected: Result<(u32, u32), ErrorKind>) {
+    let input = single_page(b"0 0 612 792 re f");
+
+    let options = with_limits(Limits {
+        max_pixels,
+        ..Limits::default()
+    });
+    let doc = InMemoryDocument::from_bytes_with(&input, options).unwrap();
+
+    let page = doc.page(0).unwrap().unwrap();
+    let result = page
+        .render(&doc, 72.0)
+        .map(|pixmap| (pixmap.width, pixmap.height))
+        .map_err(|err| Error::from(err).kind().clone());
+
+    assert_eq!(result, expected);
+}
+
+#[rstest]
+#[case(b"[[[1]]]", 2, false)]
+#[case(b"[[[1]]]", 3, true)]
+#[case(b"<</A<</B[1]>>>>", 2, false)]
+#[case(b"<</A<</B[1]>>>>", 3, true)]
+fn nesting(#[case] input: &[u8], #[case] max_nesting: usize, #[case] ok: bool) {
+    let options = with_limits(Limits {
+        max_nesting,
+        ..Limits::default()
+    });
+
+    let result: Result<Object, _> = options.build(&mut BStr::new(input));
+
+    match result {
+        Ok(_) => assert!(ok),
+        Err(err) => {
+            assert!(!ok);
+            assert_eq!(
+                Error::from(err).kind(),
+                &ErrorKind::NestingLimitExceeded(max_nesting)
+            );
+        }
+    }
+}
+
+#[test]
+fn deep_nesting() {
+    // Deep enough to overflow the stack, were it not for the nesting limit.
+    let input = [b"[".repeat(100_000), b"]".repeat(100_000)].concat();
+
+    let err = Object::extract(&mut BStr::new(&input)).unwrap_err();
+    assert_eq!(
+        Error::from(err).kind(),
+        &ErrorKind::NestingLimitExceeded(Limits::default().max_nesting)
+    );
+}
+
+#[rstest]
+#[case(b"(")]
+#[case(b"<<")]
+fn deep_delimiters(#[case] opening: &[u8]) {
+    // More levels than a 16-bit counter can hold.
+    let input = opening.repeat(70_000);
+    assert!(Object::extract(&mut BStr::new(&input)).is_err());
+}
+
+/// A page that draws form 5 once, each form drawing the next one ten times: 311 operators
+/// in all, but fewer than 100 per content stream.
+fn nested_forms() -> Vec<u8> {
+    let form = |id: usize, content: &str| {
+        format!(
+            "{id} 0 obj\n<</Subtype/Form/Resources<</XObject<</Fm {} 0 R>>>>/Length {}>>stream\n{content}\nendstream\nendobj",
+            id + 1,
+            content.len()
+        )
+        .into_bytes()
+    };
+    let repeat = "/Fm Do ".repeat(10);
+
+    document(
+        &[
+            b"1 0 obj\n<</Type/Catalog/Pages 2 0 R>>\nendobj",
+            b"2 0 obj\n<</Type/Pages/Kids[3 0 R]/Count 1>>\nendobj",
+            b"3 0 obj\n<</Type/Page/Parent 2 0 R/MediaBox[0 0 612 792]/Resources<</XObject<</Fm 5 0 R>>>>/Contents 4 0 R>>\nendobj",
+            b"4 0 obj\n<</Length 6>>stream\n/Fm Do\nendstream\nendobj",
+            &form(5, &repeat),
+            &form(6, &repeat),
+            &form(7, "0 0 1 1 re f"),
+        ],
+        "",
+    )
+}
+
+#[rstest]
+#[case(100, Err(ErrorKind::TooManyOperators(100)))]
+#[case(311, Ok(()))]
+fn operators_in_forms(#[case] max_operators: usize, #[case] expected: Result<(), ErrorKind>) {
+    struct Nothing;
+    impl Device for Nothing {}
+
+    let options = with_limits(Limits {
+        max_operators,
+        ..Limits::default()
+    });
+    let doc = InMemoryDocument::from_bytes_with(&nested_forms(), options).unwrap();
+
+    let page = doc.page(0).unwrap().unwrap();
+    let result = page
+        .interpret(&doc, Matrix::default(), &mut Nothing)
+        .map_err(|err| Error::from(err).kind().clone());
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn timeout() {
+    let input = single_page(b"BT ET");
+
+    let options = with_limits(Limits {
+        timeout: Some(Duration::ZERO),
+        ..Limits::default()
+    });
+
+    // Loading the document may already take too long.
+    let err = match InMemoryDocument::from_bytes_with(&input, options) {
+        Err(err) => err,
+        Ok(doc) => {
+            thread::sleep(Duration::from_millis(1));
+            Error::from(doc.page(0).unwrap_err())
+        }
+    };
+
+    assert_eq!(err.kind(), &ErrorKind::TimeLimitExceeded);
+}
+
+#[test]
+fn timeout_while_parsing_operators() {
+    let input = single_page(b"BT ET");
+
+    let options = with_limits(Limits {
+        timeout: Some(Duration::from_secs(1)),
+        ..Limits::default()
+    });
+    let doc = InMemoryDocument::from_bytes_with(&input, options).unwrap();
+    let page = doc.page(0).unwrap().unwrap();
+
+    let mut operators = page.operators_iter(&doc);
+    assert!(operators.next().unwrap().is_ok());
+
+    // The content stream is decoded already: only the operator iterator sees the deadline.
+    thread::sleep(Duration::from_millis(1100));
+    let err = Error::from(operators.next().unwrap().unwrap_err());
+    assert_eq!(err.kind(), &ErrorKind::TimeLimitExceeded);
+}
+
+/// Object streams that are said to be stored in an object stream, which used to overflow
+/// the stack.
+#[rstest]
+// Object 1 is stored in object stream 1, which is the cross-reference stream itself.
+#[case::self_contained(
+    b"1 0 obj\n<</Type/XRef/Size 2/W[1 1 1]/Root 1 0 R/Length 6>>stream\n\x00\x00\x00\x02\x01\x00\nendstream\nendobj\nstartxref\n0\n%%EOF".to_vec(),
+    ErrorKind::BadXRefEntry
+)]
+// The `Length` of object stream 2 is stored in object stream 2.
+#[case::length_within(object_stream_cycle(), ErrorKind::Cycle)]
+fn compressed_object_streams(#[case] input: Vec<u8>, #[case] expected: ErrorKind) {
+    let err = InMemoryDocument::from_bytes(&input).err().unwrap();
+    assert_eq!(err.kind(), &expected);
+}
+
+fn object_stream_cycle() -> Vec<u8> {
+    let mut input =
+        b"2 0 obj\n<</Type/ObjStm/N 1/First 4/Length 3 0 R>>stream\n1 0 <<>>\nendstream\nendobj\n"
+            .to_vec();
+    let offset = input.len() as u8;
+
+    input
+        .extend_from_slice(b"4 0 obj\n<</Type/XRef/Size 5/W[1 1 1]/Root 1 0 R/Length 15>>stream\n");
+    input.extend_from_slice(&[0, 0, 0, 2, 2, 0, 1, 0, 0, 2, 2, 1, 1, offset, 0]);
+    input.extend_from_slice(format!("\nendstream\nendobj\nstartxref\n{offset}\n%%EOF").as_bytes());
+
+    input
+}
+
+/// Inputs that used to make the parser panic.
+#[rstest]
+#[case::octal_overflow(b"(\\777)")]
+#[case::integer_overflow(b"99999999999999999999")]
+#[case::xref_generation(
+    b"xref\n0 1\n0000000000 99999 f\r\ntrailer\n<</Size 1/Root 1 0 R>>\nstartxref\n0\n%%EOF"
+)]
+#[case::xref_stream_widths(b"1 0 obj\n<</Type/XRef/Size 1/W[1 0 1]/Root 1 0 R/Length 2>>stream\n\x01\x00\nendstream\nendobj\nstartxref\n0\n%%EOF")]
+#[case::xref_stream_type(b"1 0 obj\n<</Type/XRef/Size 1/W[2 1 1]/Root 1 0 R/Length 4>>stream\n\x01\x00\x00\x00\nendstream\nendobj\nstartxref\n0\n%%EOF")]
+#[case::xref_subsection(b"xref\n18446744073709551615 1\n0000000000 65535 f\r\ntrailer\n<</Size 1/Root 1 0 R>>\nstartxref\n0\n%%EOF")]
+fn hostile(#[case] input: &[u8]) {
+    let _ = Object::extract(&mut BStr::new(input));
+    assert!(InMemoryDocument::from_bytes(input).is_err());
+}
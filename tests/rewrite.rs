@@ -0,0 +1,143 @@
+use livre::{
+    extraction::{Map, Object},
+    follow_refs::Builder,
+    InMemoryDocument,
+};
+use rstest::rstest;
+
+mod common;
+
+use common::document;
+
+/// A document with an unreachable object, an indirect stream length, a stream whose filter
+/// Livre does not support, and an incremental update that stamps an information dictionary.
+fn original() -> Vec<u8> {
+    let input = document(
+        &[
+            b"1 0 obj\n<</Type/Catalog/Pages 2 0 R>>\nendobj",
+            b"2 0 obj\n<</Type/Pages/Kids[3 0 R]/Count 1>>\nendobj",
+            b"3 0 obj\n<</Type/Page/Contents 4 0 R/Resources<</Font<<>>/XObject<</Im0 7 0 R>>>>>>\nendobj",
+            b"4 0 obj\n<</Length 6 0 R>>stream\nBT /F1 12 Tf (Hello) Tj ET\nendstream\nendobj",
+            b"5 0 obj\n(unreachable)\nendobj",
+            b"6 0 obj\n26\nendobj",
+            b"7 0 obj\n<</Type/XObject/Subtype/Image/Filter/DCTDecode/Length 8>>stream\n\xFF\xD8JPEG\xFF\xD9\nendstream\nendobj",
+        ],
+        "",
+    );
+
+    let doc = InMemoryDocument::from_bytes(&input).unwrap();
+
+    let mut update = doc.update();
+    let info: Map<Object> = [("Producer".into(), Object::from("Livre"))]
+        .into_iter()
+        .collect();
+    let info = update.add(&Object::Dictionary(info)).unwrap();
+    update.trailer_mut().info = Some(info);
+
+    update.to_bytes()
+}
+
+#[rstest]
+fn rewrite(#[values(false, true)] object_streams: bool, #[values(false, true)] compression: bool) {
+    let input = original();
+    let doc = InMemoryDocument::from_bytes(&input).unwrap();
+
+    let rewrite = doc
+        .rewrite()
+        .unwrap()
+        .with_object_streams(object_streams)
+        .with_compression(compression);
+
+    // The unreachable object and the stream length are gone.
+    assert_eq!(rewrite.len(), 6);
+
+    let output = rewrite.to_bytes();
+
+    assert!(output.starts_with(b"%PDF-1.7\n"));
+
+    let rewritten = InMemoryDocument::from_bytes(&output).unwrap();
+    assert_eq!(rewritten.history.len(), 1);
+
+    let trailer = &rewritten.history.latest().trailer;
+    assert_eq!(trailer.root.id.object, 1);
+    assert_eq!(trailer.prev, None);
+
+    let Object::Dictionary(info) = rewritten.build_reference(trailer.info.unwrap()).unwrap() else {
+        panic!("the information dictionary should be a dictionary");
+    };
+    assert_eq!(info[&"Producer".into()], Object::from("Livre"));
+
+    let pages = rewritten.pages().unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(
+        pages[0].build_content(&rewritten).unwrap(),
+        b"BT /F1 12 Tf (Hello) Tj ET"
+    );
+
+    // The image is copied verbatim.
+    assert!(output
+        .windows(8)
+        .any(|window| window == b"\xFF\xD8JPEG\xFF\xD9"));
+    assert!(output
+        .windows(b"/Filter /DCTDecode".len())
+        .any(|window| window == b"/Filter /DCTDecode"));
+
+    // Text content is only readable when left uncompressed.
+    let uncompressed = output
+        .windows(b"(Hello) Tj".len())
+        .any(|window| window == b"(Hello) Tj");
+    assert_eq!(uncompressed, !compression);
+}
+
+#[rstest]
+#[case("tests/resources/letter.pdf")]
+#[case("tests/resources/text.pdf")]
+fn rewrite_resources(#[case] path: &str, #[values(false, true)] object_streams: bool) {
+    let input = std::fs::read(path).unwrap();
+    let doc = InMemoryDocument::from_bytes(&input).unwrap();
+
+    let output = doc
+        .rewrite()
+        .unwrap()
+        .with_object_streams(object_streams)
+        .with_compression(true)
+        .to_bytes();
+
+    let rewritten = InMemoryDocument::from_bytes(&output).unwrap();
+
+    let pages = doc.pages().unwrap();
+    let rewritten_pages = rewritten.pages().unwrap();
+    assert_eq!(pages.len(), rewritten_pages.len());
+
+    for (page, rewritten_page) in pages.iter().zip(&rewritten_pages) {
+        assert_eq!(
+            page.build_content(&doc).unwrap(),
+            rewritten_page.build_content(&rewritten).unwrap()
+        );
+    }
+}
+
+#[test]
+fn verbatim() {
+    let input = document(
+        &[
+            b"1 0 obj\n<</Type/Catalog/Pages 2 0 R/Numbers 4 0 R>>\nendobj",
+            b"2 0 obj\n<</Type/Pages/Kids[]/Count 0>>\nendobj",
+            b"3 0 obj\n(unreachable)\nendobj",
+            b"4 0 obj\n<</Real 0.123456789/Integer 3000000000/Catalog 1 0 R>>\nendobj",
+        ],
+        "",
+    );
+    let doc = InMemoryDocument::from_bytes(&input).unwrap();
+
+    let output = doc.rewrite().unwrap().to_bytes();
+
+    // Numbers that do not fit an `Object` keep their precision, and only references change.
+    let expected = b"3 0 obj\n<</Real 0.123456789/Integer 3000000000/Catalog 1 0 R>>\nendobj";
+    assert!(output
+        .windows(expected.len())
+        .any(|window| window == expected));
+
+    let rewritten = InMemoryDocument::from_bytes(&output).unwrap();
+    assert_eq!(rewritten.page_count().unwrap(), 0);
+}
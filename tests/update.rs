@@ -0,0 +1,109 @@
+use livre::{
+    error::{Error, ErrorKind},
+    extraction::{extract, Map, Object, ReferenceId, Stream},
+    follow_refs::Builder,
+    serialization::XRefFormat,
+    InMemoryDocument,
+};
+use rstest::rstest;
+
+mod common;
+
+use common::document;
+
+fn original() -> Vec<u8> {
+    document(
+        &[
+            b"1 0 obj\n<</Type/Catalog/Pages 2 0 R>>\nendobj",
+            b"2 0 obj\n<</Type/Pages/Kids[3 0 R]/Count 1>>\nendobj",
+            b"3 0 obj\n<</Type/Page/Contents 4 0 R>>\nendobj",
+            b"4 0 obj\n<</Length 26>>stream\nBT /F1 12 Tf (Hello) Tj ET\nendstream\nendobj",
+        ],
+        "",
+    )
+}
+
+#[rstest]
+fn update(#[values(XRefFormat::Table, XRefFormat::Stream)] format: XRefFormat) {
+    let input = original();
+    let doc = InMemoryDocument::from_bytes(&input).unwrap();
+
+    let mut update = doc.update().with_format(format);
+
+    // Stamp metadata.
+    let info: Map<Object> = [("Producer".into(), Object::from("Livre"))]
+        .into_iter()
+        .collect();
+    let info = update.add(&Object::Dictionary(info)).unwrap();
+    update.trailer_mut().info = Some(info);
+
+    // Replace the content of the page, and delete the original stream.
+    let content = Object::Stream(Stream {
+        structured: Map::new(),
+        filters: vec![],
+        content: b"BT /F1 12 Tf (Updated) Tj ET".to_vec(),
+    });
+    let content = update.add(&content).unwrap();
+    let page = format!("<</Type/Page/Contents {} 0 R>>", content.id.object);
+    let page: Object = extract(&mut page.as_bytes().as_ref()).unwrap();
+    update.insert(ReferenceId::first(3), &page).unwrap();
+    update.delete(ReferenceId::first(4));
+
+    let output = update.to_bytes();
+
+    // The original bytes are left untouched.
+    assert!(output.starts_with(&input));
+
+    let updated = InMemoryDocument::from_bytes(&output).unwrap();
+    assert_eq!(updated.history.len(), 2);
+
+    let trailer = &updated.history.latest().trailer;
+    assert_eq!(trailer.prev, Some(doc.history.latest().offset));
+
+    let Object::Dictionary(info) = updated.build_reference(trailer.info.unwrap()).unwrap() else {
+        panic!("the information dictionary should be a dictionary");
+    };
+    assert_eq!(info[&"Producer".into()], Object::from("Livre"));
+
+    let pages = updated.pages().unwrap();
+    assert_eq!(
+        pages[0].build_content(&updated).unwrap(),
+        b"BT /F1 12 Tf (Updated) Tj ET"
+    );
+
+    let deleted: Option<Object> = updated
+        .resolve_reference(ReferenceId::first(4).into())
+        .unwrap();
+    assert_eq!(deleted, None);
+
+    // The previous revision is still available.
+    let previous = updated.at_revision(0).unwrap();
+    let pages = previous.pages().unwrap();
+    assert_eq!(
+        pages[0].build_content(&previous).unwrap(),
+        b"BT /F1 12 Tf (Hello) Tj ET"
+    );
+
+    let err = updated.at_revision(2).map(|_| ()).map_err(Error::from);
+    assert_eq!(
+        err.unwrap_err().kind(),
+        &ErrorKind::RevisionNotFound {
+            requested: 2,
+            available: 2
+        }
+    );
+}
+
+#[test]
+fn write_update() {
+    let input = original();
+    let doc = InMemoryDocument::from_bytes(&input).unwrap();
+
+    let mut update = doc.update();
+    update.add(&Object::Integer(42)).unwrap();
+
+    let mut output = input.clone();
+    update.write_update(&mut output).unwrap();
+
+    assert_eq!(output, update.to_bytes());
+}